@@ -17,14 +17,32 @@ use proc_macro2::{Literal, Span};
 use proc_macro_crate::{crate_name, FoundCrate};
 use quote::quote;
 use syn::{
-    parse_macro_input, parse_quote, AttributeArgs, Ident, ItemStruct, Lit, LitStr, Meta,
-    NestedMeta, Path,
+    parse_macro_input, parse_quote, Attribute, AttributeArgs, Ident, Item, ItemStruct, Lit, LitStr,
+    Meta, NestedMeta, Path,
 };
 
+mod manifest;
+
+/// Whether to generate the smallest code, without the Debug derives the macro adds, the
+/// `_mut` variants of callbacks unless asked for and diagnostics.
+const MINIMAL: bool = cfg!(feature = "Minimal");
+
+/// Whether to keep the `///` docs of components as runtime metadata.
+const DOCS: bool = cfg!(feature = "Docs");
+
 fn expand_crate_ref(name: &str, path: Path) -> syn::Path {
-    let found_crate = crate_name(name).expect(&format!("{} is present in `Cargo.toml`", name));
+    let found_crate =
+        crate_name(name).unwrap_or_else(|_| panic!("{} is present in `Cargo.toml`", name));
 
     match found_crate {
+        // The examples of the crate itself are separate crates that use it by name.
+        FoundCrate::Itself
+            if std::env::var("CARGO_CRATE_NAME").ok().as_deref()
+                != Some(&name.replace('-', "_")) =>
+        {
+            let ident = Ident::new(&name.replace('-', "_"), Span::call_site());
+            parse_quote!( #ident::#path )
+        }
         FoundCrate::Itself => parse_quote!( crate::#path ),
         FoundCrate::Name(name) => {
             let ident = Ident::new(&name, Span::call_site());
@@ -36,20 +54,124 @@ fn expand_crate_ref(name: &str, path: Path) -> syn::Path {
 struct AttributeConfig {
     class_name: Literal,
     element_name: Literal,
-    observed_attributes: Literal,
-    observed_events: Literal,
-    base_class: Literal,
+    attribute_names: Vec<String>,
+    attr_types: Vec<(String, syn::Type)>,
+    attribute_aliases: Vec<(String, String)>,
+    emits: Vec<String>,
+    event_types: Vec<String>,
+    base_class: String,
+    stateless: bool,
+    pool_size: Literal,
+    snapshot: bool,
+    persisted_attributes: Vec<String>,
+    persist_storage: String,
+    url_attributes: Vec<String>,
+    observed_dataset: Vec<String>,
+    data_src: bool,
+    live: bool,
+    role: String,
+    aria: Vec<(String, serde_json::Value)>,
+    dialog: bool,
+    form_control: Option<&'static str>,
+    view_transition: bool,
+    animate_in: bool,
+    media_queries: Vec<String>,
+    has_media_queries: bool,
+    content_visibility: String,
+    has_content_visibility: bool,
+    themed: bool,
+    parts: Vec<String>,
+    scoped_style: Option<(String, String)>,
+    light_dom: bool,
+    version: Option<String>,
+    version_alias: bool,
+    disabled_feature_names: Vec<String>,
+    constructor: Option<Ident>,
+    concrete: Option<syn::Type>,
+    mutability: Mutability,
+    listen_on: ListenOn,
+    drag_source: bool,
+    drop_target: bool,
+    gestures: bool,
+    track_focus: bool,
+    files: bool,
+    persist_idb: Option<String>,
+    localized: bool,
+    children_ready: bool,
+    shortcuts: Vec<(String, Ident)>,
+    global_shortcuts: bool,
+    form_associated: bool,
+    label_target: String,
+    behaviors: Vec<syn::Path>,
+    delegates: Vec<Ident>,
+}
+
+/// Where the generated class listens for the observed events.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ListenOn {
+    /// The shadow root if the element has one when it's constructed, otherwise the host.
+    Auto,
+    Host,
+    Shadow,
+    Both,
 }
 
 fn get_class_and_element_names(
     args: Vec<NestedMeta>,
     struct_name: &Ident,
-) -> AttributeConfig {
+) -> syn::Result<AttributeConfig> {
     let mut class_name = None;
     let mut element_name = None;
     let mut observed_attributes = None;
+    let mut attribute_aliases = None;
     let mut observed_events = None;
     let mut base_class = None;
+    let mut stateless = false;
+    let mut pool_size = None;
+    let mut snapshot = false;
+    let mut persisted_attributes = None;
+    let mut persist_storage = None;
+    let mut url_attributes = None;
+    let mut observed_dataset = None;
+    let mut data_src = false;
+    let mut live = false;
+    let mut role = None;
+    let mut aria = None;
+    let mut view_transition = false;
+    let mut animate_in = false;
+    let mut media_queries = None;
+    let mut content_visibility = None;
+    let mut themed = false;
+    let mut parts = Vec::new();
+    let mut scoped_style = None;
+    let mut light_dom = false;
+    let mut version = None;
+    let mut version_alias = false;
+    let mut disabled_features = None;
+    let mut constructor = None;
+    let mut concrete = None;
+    let mut mutability = if MINIMAL {
+        Mutability::Ref
+    } else {
+        Mutability::Both
+    };
+    let mut listen_on = ListenOn::Auto;
+    let mut drag_source = false;
+    let mut drop_target = false;
+    let mut gestures = false;
+    let mut track_focus = false;
+    let mut files = false;
+    let mut persist_idb = None;
+    let mut localized = false;
+    let mut children_ready = false;
+    let mut shortcuts = Vec::new();
+    let mut global_shortcuts = false;
+    let mut form_associated = false;
+    let mut label_target = None;
+    let mut behaviors = Vec::new();
+    let mut attr_types = Vec::new();
+    let mut emits = Vec::new();
+    let mut delegates = Vec::new();
     for arg in args {
         if let NestedMeta::Meta(Meta::NameValue(nv)) = arg {
             if nv.path.is_ident("class_name") {
@@ -62,8 +184,22 @@ fn get_class_and_element_names(
                 }
             } else if nv.path.is_ident("observed_attrs") {
                 if let Lit::Str(nm) = nv.lit {
+                    check_names(&parse_js_string_array(&nm.value()), &nm)?;
                     observed_attributes = Some(nm);
                 }
+            } else if nv.path.is_ident("attr_types") {
+                if let Lit::Str(nm) = nv.lit {
+                    attr_types = parse_attr_types(&nm)?;
+                }
+            } else if nv.path.is_ident("aliases") {
+                if let Lit::Str(nm) = nv.lit {
+                    attribute_aliases = Some(nm);
+                }
+            } else if nv.path.is_ident("emits") {
+                if let Lit::Str(nm) = nv.lit {
+                    emits = parse_js_string_array(&nm.value());
+                    check_names(&emits, &nm)?;
+                }
             } else if nv.path.is_ident("observed_events") {
                 if let Lit::Str(nm) = nv.lit {
                     observed_events = Some(nm);
@@ -72,281 +208,2576 @@ fn get_class_and_element_names(
                 if let Lit::Str(nm) = nv.lit {
                     base_class = Some(nm);
                 }
+            } else if nv.path.is_ident("stateless") {
+                if let Lit::Bool(b) = nv.lit {
+                    stateless = b.value;
+                }
+            } else if nv.path.is_ident("pool_size") {
+                if let Lit::Int(n) = nv.lit {
+                    pool_size = Some(n);
+                }
+            } else if nv.path.is_ident("snapshot") {
+                if let Lit::Bool(b) = nv.lit {
+                    snapshot = b.value;
+                }
+            } else if nv.path.is_ident("persist_attrs") {
+                if let Lit::Str(nm) = nv.lit {
+                    persisted_attributes = Some(nm);
+                }
+            } else if nv.path.is_ident("persist") {
+                if let Lit::Str(nm) = nv.lit {
+                    persist_storage = match nm.value().as_str() {
+                        "local" => Some(LitStr::new("localStorage", nm.span())),
+                        "session" => Some(LitStr::new("sessionStorage", nm.span())),
+                        storage => {
+                            return Err(syn::Error::new(
+                                nm.span(),
+                                format!("persist must be \"local\" or \"session\" not {}", storage),
+                            ))
+                        }
+                    };
+                }
+            } else if nv.path.is_ident("observed_dataset") {
+                if let Lit::Str(nm) = nv.lit {
+                    observed_dataset = Some(nm);
+                }
+            } else if nv.path.is_ident("url_attrs") {
+                if let Lit::Str(nm) = nv.lit {
+                    url_attributes = Some(nm);
+                }
+            } else if nv.path.is_ident("data_src") {
+                if let Lit::Bool(b) = nv.lit {
+                    data_src = b.value;
+                }
+            } else if nv.path.is_ident("live") {
+                if let Lit::Bool(b) = nv.lit {
+                    live = b.value;
+                }
+            } else if nv.path.is_ident("role") {
+                if let Lit::Str(nm) = nv.lit {
+                    role = Some(nm);
+                }
+            } else if nv.path.is_ident("aria") {
+                if let Lit::Str(nm) = nv.lit {
+                    aria = Some(nm);
+                }
+            } else if nv.path.is_ident("view_transition") {
+                if let Lit::Bool(b) = nv.lit {
+                    view_transition = b.value;
+                }
+            } else if nv.path.is_ident("animate_in") {
+                if let Lit::Bool(b) = nv.lit {
+                    animate_in = b.value;
+                }
+            } else if nv.path.is_ident("media_queries") {
+                if let Lit::Str(nm) = nv.lit {
+                    media_queries = Some(nm);
+                }
+            } else if nv.path.is_ident("content_visibility") {
+                match nv.lit {
+                    Lit::Bool(b) if b.value => {
+                        content_visibility = Some(LitStr::new("auto 500px", b.span))
+                    }
+                    Lit::Str(nm) => content_visibility = Some(nm),
+                    _ => {}
+                }
+            } else if nv.path.is_ident("themed") {
+                if let Lit::Bool(b) = nv.lit {
+                    themed = b.value;
+                }
+            } else if nv.path.is_ident("parts") {
+                if let Lit::Str(nm) = nv.lit {
+                    parts = parse_js_string_array(&nm.value());
+                }
+            } else if nv.path.is_ident("scoped_style") {
+                if let Lit::Str(nm) = nv.lit {
+                    scoped_style = Some(nm.value());
+                }
+            } else if nv.path.is_ident("light_dom") {
+                if let Lit::Bool(b) = nv.lit {
+                    light_dom = b.value;
+                }
+            } else if nv.path.is_ident("version") {
+                match nv.lit {
+                    Lit::Str(v) => version = Some(v.value()),
+                    Lit::Int(v) => version = Some(v.base10_digits().to_owned()),
+                    _ => (),
+                }
+            } else if nv.path.is_ident("version_alias") {
+                if let Lit::Bool(b) = nv.lit {
+                    version_alias = b.value;
+                }
+            } else if nv.path.is_ident("disabled_features") {
+                if let Lit::Str(nm) = nv.lit {
+                    disabled_features = Some(nm);
+                }
+            } else if nv.path.is_ident("constructor") {
+                if let Lit::Str(nm) = nv.lit {
+                    constructor = Some(nm.parse().map_err(|_| {
+                        syn::Error::new(nm.span(), "constructor must be the name of a method")
+                    })?);
+                }
+            } else if nv.path.is_ident("mutability") {
+                if let Lit::Str(nm) = nv.lit {
+                    mutability = match nm.value().as_str() {
+                        "mut" => Mutability::Mut,
+                        "ref" => Mutability::Ref,
+                        "both" => Mutability::Both,
+                        mutability => {
+                            return Err(syn::Error::new(
+                                nm.span(),
+                                format!(
+                                    "mutability must be \"mut\", \"ref\" or \"both\" not {}",
+                                    mutability
+                                ),
+                            ))
+                        }
+                    };
+                }
+            } else if nv.path.is_ident("listen_on") {
+                if let Lit::Str(nm) = nv.lit {
+                    listen_on = match nm.value().as_str() {
+                        "host" => ListenOn::Host,
+                        "shadow" => ListenOn::Shadow,
+                        "both" => ListenOn::Both,
+                        "auto" => ListenOn::Auto,
+                        listen_on => {
+                            return Err(syn::Error::new(
+                                nm.span(),
+                                format!(
+                                    "listen_on must be \"auto\", \"host\", \"shadow\" or \"both\" not {}",
+                                    listen_on
+                                ),
+                            ))
+                        }
+                    };
+                }
+            } else if nv.path.is_ident("drag_source") {
+                if let Lit::Bool(b) = nv.lit {
+                    drag_source = b.value;
+                }
+            } else if nv.path.is_ident("drop_target") {
+                if let Lit::Bool(b) = nv.lit {
+                    drop_target = b.value;
+                }
+            } else if nv.path.is_ident("gestures") {
+                if let Lit::Bool(b) = nv.lit {
+                    gestures = b.value;
+                }
+            } else if nv.path.is_ident("track_focus") {
+                if let Lit::Bool(b) = nv.lit {
+                    track_focus = b.value;
+                }
+            } else if nv.path.is_ident("files") {
+                if let Lit::Bool(b) = nv.lit {
+                    files = b.value;
+                }
+            } else if nv.path.is_ident("persist_idb") {
+                if let Lit::Str(nm) = nv.lit {
+                    persist_idb = Some(nm.value());
+                }
+            } else if nv.path.is_ident("localized") {
+                if let Lit::Bool(b) = nv.lit {
+                    localized = b.value;
+                }
+            } else if nv.path.is_ident("children_ready") {
+                if let Lit::Bool(b) = nv.lit {
+                    children_ready = b.value;
+                }
+            } else if nv.path.is_ident("shortcuts") {
+                if let Lit::Str(nm) = nv.lit {
+                    shortcuts = parse_shortcuts(&nm)?;
+                }
+            } else if nv.path.is_ident("shortcuts_scope") {
+                if let Lit::Str(nm) = nv.lit {
+                    global_shortcuts = match nm.value().as_str() {
+                        "global" => true,
+                        "element" => false,
+                        scope => {
+                            return Err(syn::Error::new(
+                                nm.span(),
+                                format!(
+                                    "shortcuts_scope must be \"element\" or \"global\" not {}",
+                                    scope
+                                ),
+                            ))
+                        }
+                    };
+                }
+            } else if nv.path.is_ident("form_associated") {
+                if let Lit::Bool(b) = nv.lit {
+                    form_associated = b.value;
+                }
+            } else if nv.path.is_ident("label_target") {
+                if let Lit::Str(nm) = nv.lit {
+                    label_target = Some(nm);
+                }
+            } else if nv.path.is_ident("behaviors") {
+                if let Lit::Str(nm) = nv.lit {
+                    behaviors = parse_rust_path_list(&nm)?;
+                }
+            } else if nv.path.is_ident("delegates") {
+                if let Lit::Str(nm) = nv.lit {
+                    delegates = parse_js_string_array(&nm.value())
+                        .iter()
+                        .map(|field| {
+                            syn::parse_str::<Ident>(field).map_err(|_| {
+                                syn::Error::new(
+                                    nm.span(),
+                                    format!("{} in delegates must be the name of a field", field),
+                                )
+                            })
+                        })
+                        .collect::<syn::Result<_>>()?;
+                }
+            } else if nv.path.is_ident("concrete") {
+                if let Lit::Str(nm) = nv.lit {
+                    concrete = Some(
+                        nm.parse()
+                            .map_err(|_| syn::Error::new(nm.span(), "concrete must be a type"))?,
+                    );
+                }
+            }
+        }
+    }
+
+    let class_name = class_name.map(|n| n.token()).unwrap_or_else(|| {
+        LitStr::new(struct_name.to_string().as_ref(), Span::call_site()).token()
+    });
+
+    let element_name = match element_name.map(|n| n.token()) {
+        Some(n) => n,
+        None => {
+            let class_kebab = class_name.to_string().to_kebab_case().to_lowercase();
+            LitStr::new(&class_kebab, Span::call_site()).token()
+        }
+    };
+    let scoped_style = scoped_style.map(|css| {
+        let scope = style_scope(&element_name.to_string());
+        (scope_css(&css, &scope), scope)
+    });
+    let base_class = base_class
+        .map(|n| n.value())
+        .unwrap_or_else(|| "HTMLElement".to_owned());
+    let dialog = base_class == "HTMLDialogElement";
+    let form_control = match base_class.as_str() {
+        "HTMLInputElement" => Some("input"),
+        "HTMLSelectElement" => Some("select"),
+        "HTMLTextAreaElement" => Some("textarea"),
+        _ => None,
+    };
+
+    let string_array = |array: Option<LitStr>| {
+        array
+            .map(|n| parse_js_string_array(&n.value()))
+            .unwrap_or_default()
+    };
+    let attribute_names = string_array(observed_attributes);
+    let attribute_aliases = match attribute_aliases {
+        Some(nm) => parse_js_object(&nm.value())
+            .and_then(|aliases| {
+                aliases
+                    .into_iter()
+                    .map(|(name, alias)| Some((name, alias.as_str()?.to_owned())))
+                    .collect()
+            })
+            .ok_or_else(|| {
+                syn::Error::new(
+                    nm.span(),
+                    "aliases must be a javascript object of attribute names",
+                )
+            })?,
+        None => Vec::new(),
+    };
+    let event_types = string_array(observed_events);
+    let persisted_attributes = string_array(persisted_attributes);
+    let persist_storage = persist_storage
+        .map(|n| n.value())
+        .unwrap_or_else(|| "localStorage".to_owned());
+    let url_attributes = string_array(url_attributes);
+    let observed_dataset = string_array(observed_dataset)
+        .iter()
+        .map(|key| dataset_attribute(key))
+        .collect();
+    let role = role.map(|n| n.value()).unwrap_or_default();
+    let aria = match aria {
+        Some(nm) => parse_js_object(&nm.value())
+            .ok_or_else(|| syn::Error::new(nm.span(), "aria must be a javascript object"))?,
+        None => Vec::new(),
+    };
+    let label_target = label_target
+        .map(|n| n.value())
+        .unwrap_or_else(|| "input, select, textarea, button, [tabindex]".to_owned());
+    let has_media_queries = media_queries.is_some();
+    let media_queries = string_array(media_queries);
+    // The contain-intrinsic-size hint, empty if content visibility isn't managed.
+    let content_visibility = content_visibility.map(|n| n.value()).unwrap_or_default();
+    let has_content_visibility = !content_visibility.is_empty();
+    let disabled_feature_names = string_array(disabled_features);
+    let pool_size = pool_size
+        .map(|n| n.token())
+        .unwrap_or_else(|| Literal::usize_unsuffixed(0));
+    Ok(AttributeConfig {
+        class_name,
+        element_name,
+        attribute_names,
+        attr_types,
+        attribute_aliases,
+        emits,
+        event_types,
+        base_class,
+        stateless,
+        pool_size,
+        snapshot,
+        persisted_attributes,
+        persist_storage,
+        url_attributes,
+        observed_dataset,
+        data_src,
+        live,
+        role,
+        aria,
+        dialog,
+        form_control,
+        view_transition,
+        animate_in,
+        media_queries,
+        has_media_queries,
+        content_visibility,
+        has_content_visibility,
+        themed,
+        parts,
+        scoped_style,
+        light_dom,
+        version,
+        version_alias,
+        disabled_feature_names,
+        constructor,
+        concrete,
+        mutability,
+        listen_on,
+        drag_source,
+        drop_target,
+        gestures,
+        track_focus,
+        files,
+        persist_idb,
+        localized,
+        children_ready,
+        shortcuts,
+        global_shortcuts,
+        form_associated,
+        label_target,
+        behaviors,
+        delegates,
+    })
+}
+
+/// Returns the class name suffix scoping the styles of the element. It is a hash of
+/// the element name so it is stable across builds.
+fn style_scope(element_name: &str) -> String {
+    // FNV-1a
+    let mut hash: u32 = 0x811c9dc5;
+    for b in element_name.trim_matches('"').bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    format!("s{:06x}", hash & 0xffffff)
+}
+
+/// Copies a comment or string starting at `i` to `out` and returns the index after it.
+fn copy_opaque(chars: &[char], mut i: usize, out: &mut String) -> usize {
+    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+        out.push_str("/*");
+        i += 2;
+        while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+            out.push(chars[i]);
+            i += 1;
+        }
+        if i < chars.len() {
+            out.push_str("*/");
+            i += 2;
+        }
+        return i;
+    }
+    let quote = chars[i];
+    out.push(quote);
+    i += 1;
+    while i < chars.len() && chars[i] != quote {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            out.push(chars[i]);
+            i += 1;
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    if i < chars.len() {
+        out.push(quote);
+        i += 1;
+    }
+    i
+}
+
+fn is_opaque_start(chars: &[char], i: usize) -> bool {
+    chars[i] == '"' || chars[i] == '\'' || (chars[i] == '/' && chars.get(i + 1) == Some(&'*'))
+}
+
+/// Adds the scope to every class in a selector list.
+fn scope_selector(selector: &str, scope: &str) -> String {
+    let chars: Vec<char> = selector.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if is_opaque_start(&chars, i) {
+            i = copy_opaque(&chars, i, &mut out);
+        } else if chars[i] == '[' {
+            // Attribute selectors are left alone.
+            while i < chars.len() && chars[i] != ']' {
+                if is_opaque_start(&chars, i) {
+                    i = copy_opaque(&chars, i, &mut out);
+                } else {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+            }
+        } else if chars[i] == '.'
+            && chars
+                .get(i + 1)
+                .map(|c| c.is_alphabetic() || *c == '_' || *c == '-')
+                .unwrap_or(false)
+        {
+            out.push('.');
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-')
+            {
+                out.push(chars[i]);
+                i += 1;
+            }
+            out.push('-');
+            out.push_str(scope);
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Rewrites the class selectors of a stylesheet to add the scope to every class.
+/// Rules nested in conditional at-rules get rewritten too.
+fn scope_css(css: &str, scope: &str) -> String {
+    let chars: Vec<char> = css.chars().collect();
+    let mut out = String::new();
+    let mut prelude = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if is_opaque_start(&chars, i) {
+            i = copy_opaque(&chars, i, &mut prelude);
+            continue;
+        }
+        match chars[i] {
+            '{' => {
+                let at_rule = prelude.trim_start().strip_prefix('@').map(|rule| {
+                    rule.split(|c: char| !c.is_alphanumeric() && c != '-')
+                        .next()
+                        .unwrap_or("")
+                        .to_owned()
+                });
+                i += 1;
+                match at_rule.as_deref() {
+                    Some("media") | Some("supports") | Some("layer") | Some("container") => {
+                        // These contain rules so keep rewriting inside of them.
+                        out.push_str(&prelude);
+                        out.push('{');
+                    }
+                    _ => {
+                        if at_rule.is_some() {
+                            out.push_str(&prelude);
+                        } else {
+                            out.push_str(&scope_selector(&prelude, scope));
+                        }
+                        out.push('{');
+                        let mut depth = 1;
+                        while i < chars.len() && depth > 0 {
+                            if is_opaque_start(&chars, i) {
+                                i = copy_opaque(&chars, i, &mut out);
+                                continue;
+                            }
+                            match chars[i] {
+                                '{' => depth += 1,
+                                '}' => depth -= 1,
+                                _ => (),
+                            }
+                            out.push(chars[i]);
+                            i += 1;
+                        }
+                    }
+                }
+                prelude.clear();
+            }
+            '}' | ';' => {
+                out.push_str(&prelude);
+                out.push(chars[i]);
+                prelude.clear();
+                i += 1;
+            }
+            c => {
+                prelude.push(c);
+                i += 1;
+            }
+        }
+    }
+    out.push_str(&prelude);
+    out
+}
+
+/// Splits javascript source at the commas that aren't inside of quotes or brackets.
+fn split_js_list(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[' | '{' | '(') => depth += 1,
+            (None, ']' | '}' | ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                items.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&list[start..]);
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Strips the quotes from a javascript string literal.
+fn unquote(text: &str) -> &str {
+    text.trim().trim_matches(|c| c == '\'' || c == '"')
+}
+
+/// Parses a javascript array literal of strings like `['label', "icon"]`.
+fn parse_js_string_array(array: &str) -> Vec<String> {
+    let array = array.trim();
+    let array = array.strip_prefix('[').unwrap_or(array);
+    let array = array.strip_suffix(']').unwrap_or(array);
+    split_js_list(array)
+        .into_iter()
+        .map(|item| unquote(item).to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Parses a javascript object literal like `{ariaLevel: 2, 'old-name': "name"}` with
+/// string, number, boolean or null values. None if it isn't one.
+fn parse_js_object(object: &str) -> Option<Vec<(String, serde_json::Value)>> {
+    let object = object.trim().strip_prefix('{')?.strip_suffix('}')?;
+    split_js_list(object)
+        .into_iter()
+        .map(|entry| {
+            let key_end = match entry.chars().next()? {
+                q @ ('\'' | '"') => entry[1..].find(q)? + 2,
+                _ => entry.find(':')?,
+            };
+            let (key, value) = entry.split_at(key_end);
+            let value = value.trim_start().strip_prefix(':')?.trim();
+            let value = if value.len() > 1 && (value.starts_with('\'') || value.starts_with('"')) {
+                serde_json::Value::String(value[1..value.len() - 1].to_owned())
+            } else {
+                match serde_json::from_str(value).ok()? {
+                    value @ (serde_json::Value::Number(_)
+                    | serde_json::Value::Bool(_)
+                    | serde_json::Value::Null) => value,
+                    _ => return None,
+                }
+            };
+            Some((unquote(key).to_owned(), value))
+        })
+        .collect()
+}
+
+/// Converts a `dataset` key like `userId` to its attribute name `data-user-id`.
+fn dataset_attribute(key: &str) -> String {
+    let mut name = String::from("data-");
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('-');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Parses a map of attribute names to Rust types like `"{open: bool, 'page-size': u32}"`.
+fn parse_attr_types(map: &LitStr) -> syn::Result<Vec<(String, syn::Type)>> {
+    let value = map.value();
+    let inner = value.trim().trim_start_matches('{').trim_end_matches('}');
+    // Commas inside of generic arguments don't separate entries.
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    let mut entry = String::new();
+    for c in inner.chars() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(std::mem::take(&mut entry));
+                continue;
+            }
+            _ => {}
+        }
+        entry.push(c);
+    }
+    entries.push(entry);
+    let types = entries
+        .iter()
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (name, ty) = entry.split_once(':').ok_or_else(|| {
+                syn::Error::new(
+                    map.span(),
+                    format!("{} in attr_types must be `name: Type`", entry.trim()),
+                )
+            })?;
+            let name = name
+                .trim()
+                .trim_matches(|c| c == '\'' || c == '"')
+                .to_owned();
+            let ty = syn::parse_str(ty.trim()).map_err(|_| {
+                syn::Error::new(
+                    map.span(),
+                    format!("{} in attr_types must be a type", ty.trim()),
+                )
+            })?;
+            Ok((name, ty))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    check_names(types.iter().map(|(name, _)| name), map)?;
+    Ok(types)
+}
+
+/// Returns the identifier for a javascript name. Characters that can't be part of an
+/// identifier become `_`, a leading digit gets a `_` prefix and keywords become raw
+/// identifiers like `r#type`, or get a `_` suffix if they can't be raw like `self`. None if
+/// the name has no letters or digits.
+fn rust_ident(name: &str) -> Option<Ident> {
+    if !name.chars().any(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if matches!(ident.as_str(), "self" | "Self" | "super" | "crate") {
+        ident.push('_');
+    }
+    Some(
+        syn::parse_str::<Ident>(&ident)
+            .unwrap_or_else(|_| Ident::new_raw(&ident, Span::call_site())),
+    )
+}
+
+/// Fails with an error pointing at the option if one of its names can't be turned into a
+/// method name.
+fn check_names<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    option: &LitStr,
+) -> syn::Result<()> {
+    match names.into_iter().find(|name| rust_ident(name).is_none()) {
+        Some(name) => Err(syn::Error::new(
+            option.span(),
+            format!("{:?} can't be turned into a method name", name),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn expand_element_ref(
+    struct_name: &Ident,
+    vis: &syn::Visibility,
+    config: &AttributeConfig,
+) -> proc_macro2::TokenStream {
+    let ref_name = Ident::new(&format!("{}Ref", struct_name), Span::call_site());
+    let get_attr_path = expand_crate_ref("wasm-web-component", parse_quote!(get_attr));
+    let set_attr_path = expand_crate_ref("wasm-web-component", parse_quote!(set_attr));
+    let subscription_path = expand_crate_ref("wasm-web-component", parse_quote!(EventSubscription));
+    let check_path = expand_crate_ref("wasm-web-component", parse_quote!(check_element_ref));
+    let mut names = config.attribute_names.clone();
+    for (name, _) in &config.attr_types {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    let accessors = names.iter().map(|name| {
+        let ty: syn::Type = config
+            .attr_types
+            .iter()
+            .find(|(typed, _)| typed == name)
+            .map(|(_, ty)| ty.clone())
+            .unwrap_or_else(|| parse_quote!(String));
+        let snake = name.to_snake_case();
+        let getter = rust_ident(&snake).expect("checked attribute name");
+        let setter = rust_ident(&format!("set_{}", snake)).expect("checked attribute name");
+        let get_doc = format!("The parsed value of the `{}` attribute.", name);
+        let set_doc = format!("Sets the `{}` attribute to the value.", name);
+        quote! {
+            #[doc = #get_doc]
+            pub fn #getter(&self) -> Option<#ty> {
+                #get_attr_path::<#ty>(&self.0, #name)
+            }
+
+            #[doc = #set_doc]
+            pub fn #setter(&self, value: &#ty) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #set_attr_path(&self.0, #name, value)
+            }
+        }
+    });
+    let events = config.emits.iter().map(|event| {
+        let method = rust_ident(&format!("on_{}", event.to_snake_case())).expect("checked event name");
+        let doc = format!(
+            "Calls the callback with the `{}` events of the element until the subscription is dropped.",
+            event
+        );
+        quote! {
+            #[doc = #doc]
+            pub fn #method<F: FnMut(web_sys::Event) + 'static>(&self, callback: F) -> std::result::Result<#subscription_path, ::wasm_bindgen::JsValue> {
+                #subscription_path::listen(&self.0, #event, callback)
+            }
+        }
+    });
+    let doc = format!("A typed handle to an element of [{}].", struct_name);
+    let conversions =
+        expand_element_ref_conversions(&ref_name, quote!(#check_path::<#struct_name>));
+    let debug = if MINIMAL { quote!() } else { quote!(Debug,) };
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone, #debug PartialEq, Eq)]
+        #vis struct #ref_name(web_sys::HtmlElement);
+
+        impl #ref_name {
+            #[doc = "The element."]
+            pub fn element(&self) -> &web_sys::HtmlElement {
+                &self.0
+            }
+
+            #(#accessors)*
+
+            #(#events)*
+        }
+
+        #conversions
+    }
+}
+
+/// The trait impls of a typed element wrapper. The check is called with the `Element`
+/// to wrap and returns the `HtmlElement` or a `WrongElementError`.
+fn expand_element_ref_conversions(
+    ref_name: &Ident,
+    check: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let error_path = expand_crate_ref("wasm-web-component", parse_quote!(WrongElementError));
+    quote! {
+        impl std::ops::Deref for #ref_name {
+            type Target = web_sys::HtmlElement;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl AsRef<web_sys::HtmlElement> for #ref_name {
+            fn as_ref(&self) -> &web_sys::HtmlElement {
+                &self.0
+            }
+        }
+
+        impl From<#ref_name> for web_sys::HtmlElement {
+            fn from(element: #ref_name) -> Self {
+                element.0
+            }
+        }
+
+        impl std::convert::TryFrom<web_sys::Element> for #ref_name {
+            type Error = #error_path;
+
+            fn try_from(element: web_sys::Element) -> std::result::Result<Self, Self::Error> {
+                (#check)(element).map(Self)
+            }
+        }
+
+        impl std::convert::TryFrom<web_sys::HtmlElement> for #ref_name {
+            type Error = #error_path;
+
+            fn try_from(element: web_sys::HtmlElement) -> std::result::Result<Self, Self::Error> {
+                (#check)(element.into()).map(Self)
+            }
+        }
+    }
+}
+
+/// Parses a javascript array of shortcuts like `"['Ctrl+K => open_search', 'Escape => close']"`
+/// into their key combinations and method names. Key combinations can contain commas,
+/// e.g. `Ctrl+,`, so only the quotes delimit the items.
+fn parse_shortcuts(array: &LitStr) -> syn::Result<Vec<(String, Ident)>> {
+    let value = array.value();
+    let mut items = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\'' || c == '"' {
+            items.push(
+                chars
+                    .by_ref()
+                    .take_while(|next| *next != c)
+                    .collect::<String>(),
+            );
+        }
+    }
+    items
+        .iter()
+        .map(|item| {
+            let (combo, method) = item.rsplit_once("=>").ok_or_else(|| {
+                syn::Error::new(
+                    array.span(),
+                    format!("{} in shortcuts must be `Keys => method`", item),
+                )
+            })?;
+            let combo = combo.trim();
+            let modifiers = match combo.strip_suffix("++") {
+                Some(modifiers) => modifiers,
+                None => combo
+                    .rsplit_once('+')
+                    .map(|(modifiers, _)| modifiers)
+                    .unwrap_or(""),
+            };
+            for modifier in modifiers
+                .split('+')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+            {
+                if !matches!(
+                    modifier.to_lowercase().as_str(),
+                    "ctrl"
+                        | "control"
+                        | "alt"
+                        | "option"
+                        | "shift"
+                        | "meta"
+                        | "cmd"
+                        | "command"
+                        | "mod"
+                ) {
+                    return Err(syn::Error::new(
+                        array.span(),
+                        format!("{} in shortcuts isn't a modifier key", modifier),
+                    ));
+                }
+            }
+            let method = syn::parse_str::<Ident>(method.trim()).map_err(|_| {
+                syn::Error::new(
+                    array.span(),
+                    format!(
+                        "{} in shortcuts must be the name of a method",
+                        method.trim()
+                    ),
+                )
+            })?;
+            Ok((combo.to_owned(), method))
+        })
+        .collect()
+}
+
+/// Parses a list of Rust paths like `"[Tooltip, behaviors::Draggable]"`.
+fn parse_rust_path_list(list: &LitStr) -> syn::Result<Vec<syn::Path>> {
+    list.value()
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            syn::parse_str(item).map_err(|_| {
+                syn::Error::new(list.span(), format!("{} in behaviors must be a path", item))
+            })
+        })
+        .collect()
+}
+
+fn expand_component_def(
+    struct_name: &Ident,
+    config: &AttributeConfig,
+    docs: &StructDocs,
+) -> syn::ItemImpl {
+    let prefixed_element_name_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(prefixed_element_name));
+    let AttributeConfig {
+        class_name,
+        element_name,
+        pool_size,
+        dialog,
+        form_control,
+        parts,
+        scoped_style,
+        light_dom,
+        event_types,
+        emits,
+        version,
+        version_alias,
+        disabled_feature_names,
+        behaviors,
+        ..
+    } = config;
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
+    let extends = match (*dialog, form_control) {
+        (true, _) => quote! {
+            fn extends() -> Option<&'static str> {
+                Some("dialog")
+            }
+        },
+        (false, Some(tag)) => quote! {
+            fn extends() -> Option<&'static str> {
+                Some(#tag)
+            }
+        },
+        (false, None) => quote!(),
+    };
+    let parts = if parts.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn parts() -> &'static [&'static str] {
+                &[#(#parts),*]
+            }
+        }
+    };
+    let scoped_style = match scoped_style {
+        Some((css, scope)) => quote! {
+            fn scoped_style() -> Option<&'static str> {
+                Some(#css)
+            }
+
+            fn style_scope() -> Option<&'static str> {
+                Some(#scope)
+            }
+        },
+        None => quote!(),
+    };
+    let event_types = if event_types.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn observed_events() -> &'static [&'static str] {
+                &[#(#event_types),*]
+            }
+        }
+    };
+    let emitted_events = if emits.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn emitted_events() -> &'static [&'static str] {
+                &[#(#emits),*]
+            }
+        }
+    };
+    let disabled_features = if disabled_feature_names.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn disabled_features() -> &'static [&'static str] {
+                &[#(#disabled_feature_names),*]
+            }
+        }
+    };
+    let behaviors = if behaviors.is_empty() {
+        quote!()
+    } else {
+        let hooks_path = expand_crate_ref("wasm-web-component", parse_quote!(BehaviorHooks));
+        quote! {
+            fn behaviors() -> &'static [#hooks_path] {
+                const BEHAVIORS: &[#hooks_path] = &[#(#hooks_path::of::<#behaviors>()),*];
+                BEHAVIORS
+            }
+        }
+    };
+    let version = match version {
+        Some(version) => quote! {
+            fn version() -> Option<&'static str> {
+                Some(#version)
+            }
+        },
+        None => quote!(),
+    };
+    let version_alias = if *version_alias {
+        quote! {
+            fn version_alias() -> bool {
+                true
+            }
+        }
+    } else {
+        quote!()
+    };
+    let docs = expand_docs(docs);
+    let light_dom = if *light_dom {
+        quote! {
+            fn light_dom() -> bool {
+                true
+            }
+        }
+    } else {
+        quote!()
+    };
+    parse_quote! {
+        impl #trait_path for #struct_name {
+            fn element_name() -> &'static str {
+                #prefixed_element_name_path(#element_name)
+            }
+
+            fn class_name() -> &'static str {
+                #class_name
+            }
+
+            fn pool_size() -> usize {
+                #pool_size
+            }
+
+            #extends
+
+            #parts
+
+            #scoped_style
+
+            #light_dom
+
+            #event_types
+
+            #emitted_events
+
+            #version
+
+            #version_alias
+
+            #disabled_features
+
+            #behaviors
+
+            #docs
+        }
+    }
+}
+
+/// The `docs` method returning the struct's docs if they are kept.
+fn expand_docs(docs: &StructDocs) -> proc_macro2::TokenStream {
+    if !DOCS {
+        return quote!();
+    }
+    let docs_path = expand_crate_ref("wasm-web-component", parse_quote!(ComponentDocs));
+    let description = &docs.description;
+    let attributes = docs
+        .attributes
+        .iter()
+        .map(|(name, doc)| quote!((#name, #doc)));
+    let events = docs.events.iter().map(|(name, doc)| quote!((#name, #doc)));
+    quote! {
+        fn docs() -> #docs_path {
+            #docs_path {
+                description: #description,
+                attributes: &[#(#attributes),*],
+                events: &[#(#events),*],
+            }
+        }
+    }
+}
+
+/// The javascript shared by every web component, see [component_runtime]. The parts for
+/// crate features that are off are dropped and the rest gets minified before it is
+/// embedded.
+///
+/// The syntax stays within ES2017, which every engine with WebAssembly supports, so
+/// it also parses on the older engines that only have custom elements from the
+/// polyfill. That rules out optional chaining, class fields, `for await` and
+/// `globalThis`.
+const RUNTIME: &str = include_str!("runtime.js");
+
+/// The features of the runtime that are only there with the crate feature for them.
+const RUNTIME_FEATURES: &[&str] = &[
+    "data_src",
+    "diagnostics",
+    "dialog",
+    "drag_drop",
+    "files",
+    "gestures",
+    "live",
+    "persist_idb",
+    "polyfill",
+    "shortcuts",
+];
+
+/// Drops the parts of the javascript for the features that are off so they don't end
+/// up in the binary. The lines between `// #if feature` and its `// #endif` are dropped
+/// when the feature is off. The markers can be nested.
+fn select_features(js: &str, enabled: &[&str]) -> String {
+    let mut selected = Vec::new();
+    // The depth of the markers and the depth of the outermost one that is off.
+    let mut depth = 0;
+    let mut dropped_at = None;
+    for line in js.lines() {
+        let trimmed = line.trim();
+        if let Some(feature) = trimmed.strip_prefix("// #if ") {
+            depth += 1;
+            if dropped_at.is_none() && !enabled.contains(&feature) {
+                dropped_at = Some(depth);
+            }
+        } else if trimmed == "// #endif" {
+            if dropped_at == Some(depth) {
+                dropped_at = None;
+            }
+            depth -= 1;
+        } else if dropped_at.is_none() {
+            selected.push(line);
+        }
+    }
+    selected.join("\n")
+}
+
+/// Strips the indentation, blank lines and comment lines from javascript to keep the
+/// whitespace out of the binary.
+fn minify_js(js: &str) -> String {
+    js.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn expand_wc_struct_trait_shim(
+    struct_name: &Ident,
+    once_name: &Ident,
+    config: AttributeConfig,
+) -> syn::ItemImpl {
+    let AttributeConfig {
+        class_name: _,
+        element_name: _,
+        attribute_names,
+        attr_types: _,
+        attribute_aliases,
+        emits: _,
+        event_types,
+        base_class,
+        stateless,
+        pool_size: _,
+        snapshot: _,
+        persisted_attributes,
+        persist_storage,
+        url_attributes,
+        observed_dataset,
+        data_src,
+        live,
+        role,
+        aria,
+        dialog,
+        form_control,
+        view_transition,
+        animate_in,
+        media_queries,
+        has_media_queries: _,
+        content_visibility,
+        has_content_visibility: _,
+        themed,
+        parts: _,
+        scoped_style: _,
+        light_dom: _,
+        version: _,
+        version_alias: _,
+        disabled_feature_names,
+        constructor: _,
+        concrete: _,
+        mutability: _,
+        listen_on,
+        drag_source,
+        drop_target,
+        gestures,
+        track_focus,
+        files,
+        persist_idb,
+        localized,
+        children_ready,
+        shortcuts,
+        global_shortcuts,
+        form_associated,
+        label_target,
+        behaviors: _,
+        delegates: _,
+    } = config;
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
+    let handle_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentHandle));
+    let builder_path = expand_crate_ref("wasm-web-component", parse_quote!(ElementBuilder));
+    let inject_style_path = expand_crate_ref("wasm-web-component", parse_quote!(inject_style));
+    let from_config_path = expand_crate_ref("wasm-web-component", parse_quote!(FromConfig));
+    let listen_path = expand_crate_ref("wasm-web-component", parse_quote!(listen));
+    let unlisten_path = expand_crate_ref("wasm-web-component", parse_quote!(unlisten));
+    let listen_options_path = expand_crate_ref("wasm-web-component", parse_quote!(ListenOptions));
+    let query_path = expand_crate_ref("wasm-web-component", parse_quote!(query));
+    let query_all_path = expand_crate_ref("wasm-web-component", parse_quote!(query_all));
+    let upgrade_all_path = expand_crate_ref("wasm-web-component", parse_quote!(upgrade_all));
+    let ref_name = Ident::new(&format!("{}Ref", struct_name), Span::call_site());
+    let query_error_path = expand_crate_ref("wasm-web-component", parse_quote!(QueryError));
+    let resolve_define_name_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(resolve_define_name));
+    let define_window_path = expand_crate_ref("wasm-web-component", parse_quote!(define_window));
+    let define_component_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(define_component));
+    let record_definition_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(record_definition));
+    let custom_elements_supported_path = expand_crate_ref(
+        "wasm-web-component",
+        parse_quote!(custom_elements_supported),
+    );
+    let apply_fallback_path = expand_crate_ref("wasm-web-component", parse_quote!(apply_fallback));
+    // The settings of the component's class in the shared runtime. The runtime adds the
+    // names, the behaviors and everything else that is only known once it is defined.
+    let listen_on = match listen_on {
+        ListenOn::Auto => "auto",
+        ListenOn::Host => "host",
+        ListenOn::Shadow => "shadow",
+        ListenOn::Both => "both",
+    };
+    let class_config = serde_json::json!({
+        "observedAttributes": attribute_names,
+        "aliases": attribute_aliases
+            .into_iter()
+            .map(|(name, alias)| (name, serde_json::Value::from(alias)))
+            .collect::<serde_json::Map<_, _>>(),
+        "observedEvents": event_types,
+        "baseClass": base_class,
+        "stateless": stateless,
+        "persistedAttributes": persisted_attributes,
+        "persistStorage": persist_storage,
+        "urlAttributes": url_attributes,
+        "observedDataset": observed_dataset,
+        "dataSrc": data_src,
+        "live": live,
+        "role": role,
+        "aria": aria.into_iter().collect::<serde_json::Map<_, _>>(),
+        "dialog": dialog,
+        "formControl": form_control.is_some(),
+        "viewTransition": view_transition,
+        "animateIn": animate_in,
+        "mediaQueries": media_queries,
+        "contentVisibility": content_visibility,
+        "themed": themed,
+        "disabledFeatures": disabled_feature_names,
+        "listenOn": listen_on,
+        "dragSource": drag_source,
+        "dropTarget": drop_target,
+        "gestures": gestures,
+        "trackFocus": track_focus,
+        "files": files,
+        "persistIdb": persist_idb,
+        "localized": localized,
+        "childrenReady": children_ready,
+        "shortcuts": !shortcuts.is_empty(),
+        "globalShortcuts": global_shortcuts,
+        "formAssociated": form_associated,
+        "labelTarget": label_target,
+    })
+    .to_string();
+    parse_quote! {
+        impl #struct_name {
+            pub fn element_name() -> &'static str {
+                <Self as #trait_path>::element_name()
+            }
+
+            pub fn class_name() -> &'static str {
+                <Self as #trait_path>::class_name()
+            }
+
+            #[doc = "The element name with the version appended if this web component has a version."]
+            pub fn element_name_versioned() -> &'static str {
+                <Self as #trait_path>::element_name_versioned()
+            }
+
+            #[doc = "The name this web component element actually got defined under."]
+            pub fn tag_name() -> String {
+                <Self as #trait_path>::tag_name()
+            }
+
+            #[doc = "Delivers events of the type to the handle_event callbacks until unlisten gets called or the element gets disconnected."]
+            pub fn listen(&self, element: &web_sys::HtmlElement, event_type: &str, options: #listen_options_path) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #listen_path(element, event_type, options)
+            }
+
+            #[doc = "Removes a listener added with listen."]
+            pub fn unlisten(&self, element: &web_sys::HtmlElement, event_type: &str) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #unlisten_path(element, event_type)
+            }
+
+            #[doc = "Returns the first element matching the selector in the element's shadow root, or the element itself without one, cast to the type."]
+            pub fn query<E: ::wasm_bindgen::JsCast>(&self, element: &web_sys::HtmlElement, selector: &str) -> std::result::Result<E, #query_error_path> {
+                #query_path::<Self, E>(element, selector)
+            }
+
+            #[doc = "Returns every element matching the selector in the element's shadow root, or the element itself without one, cast to the type."]
+            pub fn query_all<E: ::wasm_bindgen::JsCast>(&self, element: &web_sys::HtmlElement, selector: &str) -> std::result::Result<Vec<E>, #query_error_path> {
+                #query_all_path::<Self, E>(element, selector)
+            }
+
+            #[doc = "Upgrades the elements under the root, e.g. after inserting server rendered or cloned template HTML, and returns the upgraded elements of this web component under it. The element has to be defined."]
+            pub fn upgrade_all(root: &web_sys::Node) -> std::result::Result<Vec<#ref_name>, ::wasm_bindgen::JsValue> {
+                Ok(#upgrade_all_path::<Self>(root)?.into_iter().map(#ref_name).collect())
+            }
+
+            #[doc = "Starts a builder for a new instance of this web component element."]
+            pub fn builder() -> #builder_path {
+                <Self as #trait_path>::builder()
+            }
+
+            #[doc = "Removes an instance of this web component element after its exit animations."]
+            pub fn remove(element: &web_sys::Element) -> js_sys::Promise {
+                <Self as #trait_path>::remove(element)
+            }
+
+            #[doc = "Defines this web component element exactly once. Subsequent calls are noops."]
+            pub fn define_once() {
+                #once_name.call_once(|| {
+                    let _ = Self::define();
+                });
+            }
+
+            #[doc = "Defines this web component element if not defined already otherwise returns an error."]
+            pub fn define() -> std::result::Result<#handle_path, ::wasm_bindgen::JsValue> {
+                Self::define_with_factory(Self::new)
+            }
+
+            #[doc = "Defines this web component element if custom elements are supported. Otherwise calls the fallback with every element of the component in the document and returns None."]
+            pub fn define_or_fallback<F: Fn(&web_sys::Element)>(fallback: F) -> std::result::Result<Option<#handle_path>, ::wasm_bindgen::JsValue> {
+                if #custom_elements_supported_path() {
+                    Self::define().map(Some)
+                } else {
+                    #apply_fallback_path::<Self, F>(fallback)?;
+                    Ok(None)
+                }
+            }
+
+            #[doc = "Defines this web component element with every instance constructed from the shared config if not defined already otherwise returns an error."]
+            pub fn define_with<C: 'static>(config: C) -> std::result::Result<#handle_path, ::wasm_bindgen::JsValue>
+            where
+                Self: #from_config_path<C>,
+            {
+                let config = std::rc::Rc::new(config);
+                Self::define_with_factory(move || <Self as #from_config_path<C>>::from_config(config.clone()))
+            }
+
+            #[doc = "Defines this web component element with every instance constructed by the factory if not defined already otherwise returns an error."]
+            pub fn define_with_factory<F: Fn() -> Self + 'static>(factory: F) -> std::result::Result<#handle_path, ::wasm_bindgen::JsValue> {
+                use ::wasm_bindgen::JsCast;
+                use web_sys::{Element, HtmlElement};
+                #define_window_path::<Self>()?;
+                let tag_name = #resolve_define_name_path::<Self>()?;
+                let f: Box<dyn FnMut() -> Self> = Box::new(move || {
+                    let obj = factory();
+                    obj
+                });
+                // NOTE(jwall): The constructor has to live as long as the element class which,
+                // since custom elements can't be undefined, is the life of the page.
+                let constructor_handle = ::wasm_bindgen::prelude::Closure::wrap(f).into_js_value().unchecked_into::<js_sys::Function>();
+                let element = #define_component_path::<Self>(#class_config, &tag_name, &constructor_handle)?
+                    .dyn_into()?;
+                if let Some(style) = <Self as #trait_path>::scoped_style() {
+                    #inject_style_path(Self::element_name(), style)?;
+                }
+                #record_definition_path::<Self>(&tag_name);
+                Ok(#handle_path {
+                    element_constructor: element,
+                })
+            }
+        }
+    }
+}
+
+/// Which variants of the lifecycle callbacks the generated shims call.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mutability {
+    Both,
+    Ref,
+    Mut,
+}
+
+/// Calls the variants of a lifecycle callback for the mutability. When both get called
+/// the non-mut variant goes first.
+fn dispatch(
+    mutability: Mutability,
+    call: proc_macro2::TokenStream,
+    call_mut: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match mutability {
+        Mutability::Both => quote!(#call #call_mut),
+        Mutability::Ref => call,
+        Mutability::Mut => call_mut,
+    }
+}
+
+/// Dispatches a lifecycle callback to the component and then to its delegates in order.
+/// With `reverse` the delegates get it first in reverse order, so teardown mirrors
+/// setup.
+fn dispatch_delegated(
+    mutability: Mutability,
+    delegates: &[Ident],
+    reverse: bool,
+    calls: impl Fn(proc_macro2::TokenStream) -> (proc_macro2::TokenStream, proc_macro2::TokenStream),
+) -> proc_macro2::TokenStream {
+    let mut receivers = vec![quote!(self)];
+    receivers.extend(delegates.iter().map(|delegate| quote!(self.#delegate)));
+    if reverse {
+        receivers.reverse();
+    }
+    receivers
+        .into_iter()
+        .map(|this| {
+            let (call, call_mut) = calls(this);
+            dispatch(mutability, call, call_mut)
+        })
+        .collect()
+}
+
+/// The expression constructing the Rust side of a new element. Only components
+/// without a `constructor` have to implement Default.
+fn expand_constructor(config: &AttributeConfig) -> proc_macro2::TokenStream {
+    let def_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
+    match (&config.constructor, &config.concrete) {
+        (Some(constructor), Some(concrete)) => quote!(Self {
+            component: <#concrete>::#constructor()
+        }),
+        (None, Some(concrete)) => quote!(Self {
+            component: <#concrete>::default()
+        }),
+        (Some(constructor), None) => quote!(Self::#constructor()),
+        (None, None) => quote!(<Self as #def_path>::new()),
+    }
+}
+
+/// The exports the class calls into. The ones for optional features are only there
+/// when the component uses the feature so the class never calls one that's missing.
+fn expand_wasm_shim(
+    struct_name: &Ident,
+    config: &AttributeConfig,
+    constructor: proc_macro2::TokenStream,
+) -> syn::ItemImpl {
+    let mutability = config.mutability;
+    let delegates = &config.delegates;
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let event_span_path = expand_crate_ref("wasm-web-component", parse_quote!(event_span));
+    let run_broadcast_path = expand_crate_ref("wasm-web-component", parse_quote!(run_broadcast));
+    let attribute_changes_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(attribute_changes));
+    let attribute_span_path = expand_crate_ref("wasm-web-component", parse_quote!(attribute_span));
+    let measure_phase_path = expand_crate_ref("wasm-web-component", parse_quote!(measure_phase));
+    let check_attach_shadow_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(check_attach_shadow));
+    let check_observed_event_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(check_observed_event));
+    let (check_attach_shadow, check_observed_event) = if MINIMAL {
+        (quote!(), quote!())
+    } else {
+        (
+            quote!(#check_attach_shadow_path::<Self>(element);),
+            quote!(#check_observed_event_path::<Self>(element, event);),
+        )
+    };
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentBinding));
+    let render_shadow_path = expand_crate_ref("wasm-web-component", parse_quote!(render_shadow));
+    let init_context_path = expand_crate_ref("wasm-web-component", parse_quote!(InitContext));
+    let event_context_path = expand_crate_ref("wasm-web-component", parse_quote!(EventContext));
+    let value_source_path = expand_crate_ref("wasm-web-component", parse_quote!(ValueSource));
+    let def_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
+    let disconnect_hooks_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(run_disconnect_hooks));
+    let track_connected_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(track_connected));
+    let behaviors_connected_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(behaviors_connected));
+    let behaviors_disconnected_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(behaviors_disconnected));
+    let behaviors_attribute_changed_path = expand_crate_ref(
+        "wasm-web-component",
+        parse_quote!(behaviors_attribute_changed),
+    );
+    let behaviors_handle_event_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(behaviors_handle_event));
+    let track_disconnected_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(track_disconnected));
+    let init_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.init(element);),
+            quote!(#this.init_mut(element);),
+        )
+    });
+    let init_with_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.init_with(element, &context);),
+            quote!(#this.init_with_mut(element, &context);),
+        )
+    });
+    let connected_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.connected(element);),
+            quote!(#this.connected_mut(element);),
+        )
+    });
+    let disconnected_calls = dispatch_delegated(mutability, delegates, true, |this| {
+        (
+            quote!(#this.disconnected(element);),
+            quote!(#this.disconnected_mut(element);),
+        )
+    });
+    let media_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.media_changed(element, query, matches);),
+            quote!(#this.media_changed_mut(element, query, matches);),
+        )
+    });
+    let content_visibility_changed_calls =
+        dispatch_delegated(mutability, delegates, false, |this| {
+            (
+                quote!(#this.content_visibility_changed(element, skipped);),
+                quote!(#this.content_visibility_changed_mut(element, skipped);),
+            )
+        });
+    let theme_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.theme_changed(element);),
+            quote!(#this.theme_changed_mut(element);),
+        )
+    });
+    let children_ready_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.children_ready(element);),
+            quote!(#this.children_ready_mut(element);),
+        )
+    });
+    let locale_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.locale_changed(element, locale);),
+            quote!(#this.locale_changed_mut(element, locale);),
+        )
+    });
+    let animate_in_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.animate_in(element);),
+            quote!(#this.animate_in_mut(element);),
+        )
+    });
+    let reset_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.reset(element);),
+            quote!(#this.reset_mut(element);),
+        )
+    });
+    let invalidated_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.invalidated(element);),
+            quote!(#this.invalidated_mut(element);),
+        )
+    });
+    let adopted_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.adopted(element);),
+            quote!(#this.adopted_mut(element);),
+        )
+    });
+    let attribute_changed_calls = match (mutability, delegates.is_empty()) {
+        (Mutability::Both, true) => quote! {
+            self.attribute_changed(element, name.clone(), old_value.clone(), new_value.clone());
+            self.attribute_changed_mut(element, name, old_value, new_value);
+        },
+        (Mutability::Ref, true) => {
+            quote!(self.attribute_changed(element, name, old_value, new_value);)
+        }
+        (Mutability::Mut, true) => {
+            quote!(self.attribute_changed_mut(element, name, old_value, new_value);)
+        }
+        // Every delegate gets its own copy of the values.
+        (_, false) => dispatch_delegated(mutability, delegates, false, |this| {
+            (
+                quote!(#this.attribute_changed(element, name.clone(), old_value.clone(), new_value.clone());),
+                quote!(#this.attribute_changed_mut(element, name.clone(), old_value.clone(), new_value.clone());),
+            )
+        }),
+    };
+    let attributes_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.attributes_changed(element, &changes);),
+            quote!(#this.attributes_changed_mut(element, &changes);),
+        )
+    });
+    let dialog_closed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.dialog_closed(element, &return_value);),
+            quote!(#this.dialog_closed_mut(element, &return_value);),
+        )
+    });
+    let dialog_cancel_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.dialog_cancel(element, event);),
+            quote!(#this.dialog_cancel_mut(element, event);),
+        )
+    });
+    let focus_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.focus_changed(element, has_focus);),
+            quote!(#this.focus_changed_mut(element, has_focus);),
+        )
+    });
+    let value_changed_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.value_changed(element, &old_value, &new_value, source);),
+            quote!(#this.value_changed_mut(element, &old_value, &new_value, source);),
+        )
+    });
+    let handle_event_calls = dispatch_delegated(mutability, delegates, false, |this| {
+        (
+            quote!(#this.handle_event_with(element, event, &context);),
+            quote!(#this.handle_event_with_mut(element, event, &context);),
+        )
+    });
+    // The callbacks returning a value return the first value the component or one of
+    // its delegates returns.
+    let before_remove_calls = delegates.iter().fold(
+        quote!(self.before_remove(element)),
+        |calls, delegate| quote!(#calls.or_else(|| self.#delegate.before_remove(element))),
+    );
+    let validate_calls = delegates.iter().fold(
+        quote!(self.validate(element)),
+        |calls, delegate| quote!(#calls.or_else(|| self.#delegate.validate(element))),
+    );
+    let media_changed_export = if config.has_media_queries {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn media_changed_impl(&mut self, element: &web_sys::HtmlElement, query: &str, matches: bool) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("media_changed", element);
+                #media_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let content_visibility_changed_export = if config.has_content_visibility {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn content_visibility_changed_impl(&mut self, element: &web_sys::HtmlElement, skipped: bool) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("content_visibility_changed", element);
+                #content_visibility_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let theme_changed_export = if config.themed {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn theme_changed_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("theme_changed", element);
+                #theme_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let locale_changed_export = if config.localized {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn locale_changed_impl(&mut self, element: &web_sys::HtmlElement, locale: &str) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("locale_changed", element);
+                #locale_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let children_ready_export = if config.children_ready {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn children_ready_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("children_ready", element);
+                #children_ready_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let animate_in_export = if config.animate_in {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn animate_in_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("animate_in", element);
+                #animate_in_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let dialog_exports = if config.dialog {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn dialog_closed_impl(&mut self, element: &web_sys::HtmlElement, return_value: String) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("dialog_closed", element);
+                #dialog_closed_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn dialog_cancel_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("dialog_cancel", element);
+                #dialog_cancel_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let focus_changed_export = if config.track_focus {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn focus_changed_impl(&mut self, element: &web_sys::HtmlElement, has_focus: bool) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("focus_changed", element);
+                #focus_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    let value_changed_export = if config.form_control.is_some() {
+        quote! {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn value_changed_impl(
+                &mut self,
+                element: &web_sys::HtmlElement,
+                old_value: String,
+                new_value: String,
+                source: String,
+            ) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("value_changed", element);
+                let source = #value_source_path::from_name(&source);
+                #value_changed_calls
+            }
+        }
+    } else {
+        quote!()
+    };
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen(constructor)]
+            pub fn new() -> Self {
+                #constructor
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            #[doc = "Attach an open shadowroot to our element."]
+            pub fn attach_shadow(&self, element: &web_sys::HtmlElement, root: &str) {
+                self.attach_shadow_with_mode(element, root, web_sys::ShadowRootMode::Open);
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            #[doc = "Attach a shadowroot with the given mode to our element."]
+            pub fn attach_shadow_with_mode(&self, element: &web_sys::HtmlElement, root: &str, mode: web_sys::ShadowRootMode) {
+                #check_attach_shadow
+                #render_shadow_path::<Self>(element, root, mode).unwrap();
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn init_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("init", element);
+                let context = #init_context_path::new(element);
+                #init_calls
+                #init_with_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn connected_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("connected", element);
+                let _measure = #measure_phase_path::<Self>("connected");
+                #track_connected_path(<Self as #def_path>::element_name(), element);
+                #behaviors_connected_path::<Self>(element);
+                #connected_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn disconnected_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("disconnected", element);
+                #disconnected_calls
+                #behaviors_disconnected_path::<Self>(element);
+                #track_disconnected_path(<Self as #def_path>::element_name(), element);
+                #disconnect_hooks_path(element);
+            }
+
+            #media_changed_export
+            #content_visibility_changed_export
+            #theme_changed_export
+            #locale_changed_export
+            #children_ready_export
+            #animate_in_export
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn before_remove_impl(&self, element: &web_sys::HtmlElement) -> Option<js_sys::Promise> {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("before_remove", element);
+                #before_remove_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn validate_impl(&self, element: &web_sys::HtmlElement) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("validate", element);
+                match #validate_calls {
+                    Some(validity) => validity.apply(element),
+                    None => Ok(()),
+                }
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn reset_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("reset", element);
+                #reset_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn invalidated_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("invalidated", element);
+                #invalidated_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn broadcast_impl(&mut self, element: &web_sys::HtmlElement, id: u32) {
+                let _span = #lifecycle_span_path::<Self>("broadcast", element);
+                #run_broadcast_path(self, element, id);
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn adopted_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("adopted", element);
+                #adopted_calls
+            }
+
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn attribute_changed_impl(
+                &mut self,
+                element: &web_sys::HtmlElement,
+                name: ::wasm_bindgen::JsValue,
+                old_value: ::wasm_bindgen::JsValue,
+                new_value: ::wasm_bindgen::JsValue,
+            ) {
+                use #trait_path;
+                let _span = #attribute_span_path::<Self>(element, &name, &old_value, &new_value);
+                let _measure = #measure_phase_path::<Self>("attribute_changed");
+                #behaviors_attribute_changed_path::<Self>(element, &name, &old_value, &new_value);
+                #attribute_changed_calls
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn attributes_changed_impl(&mut self, element: &web_sys::HtmlElement, changes: ::wasm_bindgen::JsValue) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("attributes_changed", element);
+                let _measure = #measure_phase_path::<Self>("attribute_changed");
+                let changes = #attribute_changes_path(&changes);
+                for change in &changes {
+                    let (name, old_value, new_value) = change.to_js();
+                    #behaviors_attribute_changed_path::<Self>(element, &name, &old_value, &new_value);
+                }
+                #attributes_changed_calls
+            }
+
+            #dialog_exports
+            #focus_changed_export
+            #value_changed_export
+
+            pub fn handle_component_event_impl(
+                &mut self,
+                element: &web_sys::HtmlElement,
+                event: &web_sys::Event,
+                current_target: ::wasm_bindgen::JsValue,
+                composed_path: ::wasm_bindgen::JsValue,
+            ) {
+                use #trait_path;
+                let _span = #event_span_path::<Self>(element, event);
+                #check_observed_event
+                #behaviors_handle_event_path::<Self>(element, event);
+                let context = #event_context_path::from_dispatch(element, event, current_target, composed_path);
+                #handle_event_calls
+            }
+        }
+    }
+}
+
+fn expand_snapshot_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentSnapshot));
+    let encode_path = expand_crate_ref("wasm-web-component", parse_quote!(snapshot_encode));
+    let decode_path = expand_crate_ref("wasm-web-component", parse_quote!(snapshot_decode));
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn snapshot_impl(&self) -> std::result::Result<String, ::wasm_bindgen::JsValue> {
+                use #trait_path;
+                #encode_path(&self.snapshot())
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn restore_impl(
+                &mut self,
+                element: &web_sys::HtmlElement,
+                state: &str,
+            ) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("restore", element);
+                self.restore(element, #decode_path(state)?);
+                Ok(())
             }
         }
     }
+}
 
-    let class_name = class_name.map(|n| n.token()).unwrap_or_else(|| {
-        LitStr::new(struct_name.to_string().as_ref(), Span::call_site()).token()
-    });
+fn expand_data_source_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(DataSource));
+    let decode_path = expand_crate_ref("wasm-web-component", parse_quote!(data_source_decode));
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn data_loaded_impl(&mut self, element: &web_sys::HtmlElement, data: &str) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("data_loaded", element);
+                match #decode_path(data) {
+                    Ok(data) => self.data_loaded(element, data),
+                    Err(error) => self.data_error(element, error),
+                }
+            }
 
-    let element_name = match element_name.map(|n| n.token()) {
-        Some(n) => n,
-        None => {
-            let class_kebab = class_name.to_string().to_kebab_case().to_lowercase();
-            LitStr::new(&class_kebab, Span::call_site()).token()
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn data_error_impl(&mut self, element: &web_sys::HtmlElement, error: ::wasm_bindgen::JsValue) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("data_error", element);
+                self.data_error(element, error);
+            }
         }
-    };
-    let base_class = base_class.unwrap_or_else(|| LitStr::new("HTMLElement", Span::call_site())).token();
-
-    let observed_attributes = observed_attributes
-        .map(|n| n.token())
-        .unwrap_or_else(|| LitStr::new("[]", Span::call_site()).token());
-    let observed_events = observed_events
-        .map(|n| n.token())
-        .unwrap_or_else(|| LitStr::new("[]", Span::call_site()).token());
-    AttributeConfig {
-        class_name,
-        element_name,
-        observed_attributes,
-        observed_events,
-        base_class,
     }
 }
 
-fn expand_component_def(
-    struct_name: &Ident,
-    class_name: &Literal,
-    element_name: &Literal,
-) -> syn::ItemImpl {
-    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
+fn expand_live_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(LiveComponent));
+    let decode_path = expand_crate_ref("wasm-web-component", parse_quote!(live_decode));
     parse_quote! {
-        impl #trait_path for #struct_name {
-            fn element_name() -> &'static str {
-                #element_name
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn live_url_impl(&self, element: &web_sys::HtmlElement) -> Option<String> {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("live_url", element);
+                self.socket_url(element)
             }
 
-            fn class_name() -> &'static str {
-                #class_name
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn live_message_impl(&mut self, element: &web_sys::HtmlElement, message: &str) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("live_message", element);
+                match #decode_path(message) {
+                    Ok(message) => self.message_received(element, message),
+                    Err(error) => self.message_error(element, error),
+                }
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn live_opened_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("live_opened", element);
+                self.socket_opened(element);
             }
 
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn live_closed_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("live_closed", element);
+                self.socket_closed(element);
+            }
         }
     }
 }
 
-fn expand_wc_struct_trait_shim(
-    struct_name: &Ident,
-    once_name: &Ident,
-    config: AttributeConfig,
-) -> syn::ItemImpl {
-    let AttributeConfig {
-        class_name: _,
-        element_name: _,
-        observed_attributes,
-        observed_events,
-        base_class,
-    } = config;
-    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentDef));
-    let handle_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentHandle));
+fn expand_drag_source_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(DragSource));
+    let drag_data_path = expand_crate_ref("wasm-web-component", parse_quote!(DragData));
     parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
         impl #struct_name {
-            pub fn element_name() -> &'static str {
-                <Self as #trait_path>::element_name()
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn drag_started_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("drag_started", element);
+                self.drag_started(element, &#drag_data_path::from_event(event));
             }
 
-            pub fn class_name() -> &'static str {
-                <Self as #trait_path>::class_name()
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn drag_ended_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("drag_ended", element);
+                self.drag_ended(element, &#drag_data_path::from_event(event));
             }
+        }
+    }
+}
 
-            #[doc = "Defines this web component element exactly once. Subsequent calls are noops."]
-            pub fn define_once() {
-                #once_name.call_once(|| {
-                    let _ = Self::define();
-                });
+fn expand_drop_target_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(DropTarget));
+    let drag_data_path = expand_crate_ref("wasm-web-component", parse_quote!(DragData));
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn dragged_over_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) -> bool {
+                use #trait_path;
+                let data = #drag_data_path::from_event(event);
+                if !self.accepts(element, &data) {
+                    return false;
+                }
+                let _span = #lifecycle_span_path::<Self>("dragged_over", element);
+                self.dragged_over(element, &data);
+                true
             }
 
-            #[doc = "Defines this web component element if not defined already otherwise returns an error."]
-            pub fn define() -> std::result::Result<#handle_path, ::wasm_bindgen::JsValue> {
-                use ::wasm_bindgen::JsCast;
-                use web_sys::{Element, HtmlElement};
-                let registry = web_sys::window().unwrap().custom_elements();
-                let maybe_element = registry.get(Self::element_name());
-                if maybe_element.is_truthy() {
-                    return Err("Custom Element has already been defined".into());
-                }
-                let body = format!(
-                "class {name} extends {base_class} {{
-    constructor() {{
-        super();
-        this._impl = impl();
-        this._impl.init_impl(this);
-        var self = this;
-        if (self.shadowRoot) {{
-            for (const t of this.observedEvents()) {{
-                self.shadowRoot.addEventListener(t, function(evt) {{ self.handleComponentEvent(evt); }} );
-            }}
-        }} else {{
-            for (const t of self.observedEvents()) {{
-                self.addEventListener(t, function(evt) {{ self.handleComponentEvent(evt); }} );
-            }}
-        }}
-    }}
-
-    connectedCallback() {{
-        this._impl.connected_impl(this);
-    }}
-    
-    disconnectedCallback() {{
-        this._impl.disconnected_impl(this);
-    }}
-
-    static get observedAttributes() {{
-        return {observed_attributes};
-    }}
-
-    observedEvents() {{
-        return {observed_events};
-    }}
-
-    adoptedCallback() {{
-        this._impl.adopted_impl(this);
-    }}
-    
-    attributeChangedCallback(name, oldValue, newValue) {{
-        this._impl.attribute_changed_impl(this, name, oldValue, newValue);
-    }}
-
-    handleComponentEvent(evt) {{
-        this._impl.handle_component_event_impl(this, evt);
-    }}
-}}
-customElements.define(\"{element_name}\", {name});
-var element = customElements.get(\"{element_name}\");
-return element;",
-                    name = Self::class_name(),
-                    element_name = Self::element_name(),
-                    observed_attributes = #observed_attributes,
-                    observed_events = #observed_events,
-                    base_class = #base_class,
-                );
-                let fun = js_sys::Function::new_with_args("impl", &body);
-                let f: Box<dyn FnMut() -> Self> = Box::new(|| {
-                    let obj = Self::new();
-                    obj
-                });
-                let constructor_handle = ::wasm_bindgen::prelude::Closure::wrap(f).into_js_value().unchecked_into::<js_sys::Function>();
-                let element = fun
-                    .call1(
-                        &web_sys::window().unwrap(),
-                        constructor_handle.as_ref(),
-                    )?
-                    .dyn_into()?;
-                Ok(#handle_path {
-                    element_constructor: element,
-                })
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn drag_left_impl(&mut self, element: &web_sys::HtmlElement) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("drag_left", element);
+                self.drag_left(element);
+            }
+
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn dropped_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                use #trait_path;
+                let _span = #lifecycle_span_path::<Self>("dropped", element);
+                self.dropped(element, #drag_data_path::from_event(event));
             }
         }
     }
 }
 
-fn expand_wasm_shim(struct_name: &Ident) -> syn::ItemImpl {
-    let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponentBinding));
+fn expand_gestures_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let recognize_gesture_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(recognize_gesture));
+    let recognize_long_press_path =
+        expand_crate_ref("wasm-web-component", parse_quote!(recognize_long_press));
     parse_quote! {
         #[::wasm_bindgen::prelude::wasm_bindgen]
         impl #struct_name {
-            #[::wasm_bindgen::prelude::wasm_bindgen(constructor)]
-            pub fn new() -> Self {
-                Self::default()
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn gesture_pointer_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                #recognize_gesture_path(self, element, event);
             }
 
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            #[doc = "Attach an open shadowroot to our element."]
-            pub fn attach_shadow(&self, element: &web_sys::HtmlElement, root: &str) {
-                self.attach_shadow_with_mode(element, root, web_sys::ShadowRootMode::Open);
+            pub fn gesture_timeout_impl(&mut self, element: &web_sys::HtmlElement, press: u32) {
+                #recognize_long_press_path(self, element, press);
             }
+        }
+    }
+}
 
+fn expand_shortcuts_shim(struct_name: &Ident, shortcuts: &[(String, Ident)]) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let shortcut_event_path = expand_crate_ref("wasm-web-component", parse_quote!(shortcut_event));
+    let event_ext_path = expand_crate_ref("wasm-web-component", parse_quote!(EventExt));
+    // The first matching shortcut wins.
+    let dispatch = shortcuts.iter().map(|(combo, method)| {
+        quote! {
+            if event.matches_key(#combo) {
+                event.prevent_default();
+                self.#method(element, &event);
+                return;
+            }
+        }
+    });
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            #[doc = "Attach a shadowroot with the given mode to our element."]
-            pub fn attach_shadow_with_mode(&self, element: &web_sys::HtmlElement, root: &str, mode: web_sys::ShadowRootMode) {
-                let shadow_root = element.attach_shadow(&web_sys::ShadowRootInit::new(mode)).unwrap();
-                shadow_root.set_inner_html(root);
+            pub fn shortcut_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
+                use #event_ext_path;
+                let event = match #shortcut_event_path(event) {
+                    Some(event) => event,
+                    None => return,
+                };
+                let _span = #lifecycle_span_path::<Self>("shortcut", element);
+                #(#dispatch)*
+            }
+        }
+    }
+}
+
+fn expand_files_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let file_info_path = expand_crate_ref("wasm-web-component", parse_quote!(FileInfo));
+    let started_path = expand_crate_ref("wasm-web-component", parse_quote!(file_started));
+    let chunk_path = expand_crate_ref("wasm-web-component", parse_quote!(file_chunk));
+    let read_path = expand_crate_ref("wasm-web-component", parse_quote!(file_read));
+    let error_path = expand_crate_ref("wasm-web-component", parse_quote!(file_error));
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn file_started_impl(&mut self, element: &web_sys::HtmlElement, id: u32, name: String, path: String, mime: String, size: f64) {
+                #started_path(element, id, #file_info_path { name, path, mime, size });
             }
 
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            pub fn init_impl(&mut self, element: &web_sys::HtmlElement) {
-                use #trait_path;
-                self.init(element);
-                self.init_mut(element);
+            pub fn file_chunk_impl(&mut self, element: &web_sys::HtmlElement, id: u32, chunk: &[u8]) {
+                let _span = #lifecycle_span_path::<Self>("file_chunk", element);
+                #chunk_path(self, element, id, chunk);
             }
 
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            pub fn connected_impl(&mut self, element: &web_sys::HtmlElement) {
-                use #trait_path;
-                self.connected(element);
-                self.connected_mut(element);
+            pub fn file_read_impl(&mut self, element: &web_sys::HtmlElement, id: u32) {
+                let _span = #lifecycle_span_path::<Self>("file_read", element);
+                #read_path(self, element, id);
             }
 
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            pub fn disconnected_impl(&mut self, element: &web_sys::HtmlElement) {
-                use #trait_path;
-                self.disconnected(element);
-                self.disconnected_mut(element);
+            pub fn file_error_impl(&mut self, element: &web_sys::HtmlElement, id: u32, error: ::wasm_bindgen::JsValue) {
+                let _span = #lifecycle_span_path::<Self>("file_error", element);
+                #error_path(self, element, id, error);
             }
+        }
+    }
+}
 
+fn expand_idb_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let lifecycle_span_path = expand_crate_ref("wasm-web-component", parse_quote!(lifecycle_span));
+    let encode_path = expand_crate_ref("wasm-web-component", parse_quote!(idb_encode));
+    let load_path = expand_crate_ref("wasm-web-component", parse_quote!(idb_load));
+    let state_path = expand_crate_ref("wasm-web-component", parse_quote!(IdbState));
+    parse_quote! {
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        impl #struct_name {
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            pub fn adopted_impl(&mut self, element: &web_sys::HtmlElement) {
-                use #trait_path;
-                self.adopted(element);
-                self.adopted_mut(element);
+            pub fn idb_save_impl(&self, element: &web_sys::HtmlElement) -> std::result::Result<String, ::wasm_bindgen::JsValue> {
+                let _span = #lifecycle_span_path::<Self>("save_state", element);
+                #encode_path(self)
             }
 
+            #[::wasm_bindgen::prelude::wasm_bindgen]
+            pub fn idb_load_impl(&mut self, element: &web_sys::HtmlElement, stored: &str) {
+                let _span = #lifecycle_span_path::<Self>("load_state", element);
+                #load_path(self, element, stored);
+            }
 
             #[::wasm_bindgen::prelude::wasm_bindgen]
-            pub fn attribute_changed_impl(
-                &mut self,
-                element: &web_sys::HtmlElement,
-                name: ::wasm_bindgen::JsValue,
-                old_value: ::wasm_bindgen::JsValue,
-                new_value: ::wasm_bindgen::JsValue,
-            ) {
-                use #trait_path;
-                self.attribute_changed(element, name.clone(), old_value.clone(), new_value.clone());
-                self.attribute_changed_mut(element, name, old_value, new_value);
+            pub fn idb_error_impl(&mut self, element: &web_sys::HtmlElement, error: ::wasm_bindgen::JsValue) {
+                <Self as #state_path>::state_error(self, element, error);
             }
+        }
+    }
+}
 
-            pub fn handle_component_event_impl(&mut self, element: &web_sys::HtmlElement, event: &web_sys::Event) {
-                use #trait_path;
-                self.handle_event(element, event);
-                self.handle_event_mut(element, event);
+fn expand_dialog_shim(struct_name: &Ident) -> syn::ItemImpl {
+    let show_modal_path = expand_crate_ref("wasm-web-component", parse_quote!(show_modal));
+    let close_path = expand_crate_ref("wasm-web-component", parse_quote!(close_dialog));
+    parse_quote! {
+        impl #struct_name {
+            #[doc = "Shows the dialog element as a modal."]
+            pub fn show_modal(element: &web_sys::Element) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #show_modal_path(element)
+            }
+
+            #[doc = "Closes the dialog element, optionally setting its return value."]
+            pub fn close(element: &web_sys::Element, return_value: Option<&str>) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #close_path(element, return_value)
             }
         }
     }
 }
 
-fn expand_binding(struct_name: &Ident) -> syn::ItemImpl {
+fn expand_binding(struct_type: &syn::Type) -> syn::ItemImpl {
     let trait_path = expand_crate_ref("wasm-web-component", parse_quote!(WebComponent));
     parse_quote!(
-        impl #trait_path for #struct_name {}
+        impl #trait_path for #struct_type {}
     )
 }
 
+/// Generic structs can't be exported to javascript so the concrete instantiation of
+/// the struct gets wrapped in a struct that can. The wrapper derefs to the
+/// instantiation so the shims reach its WebComponentBinding implementation.
+fn expand_concrete_wrapper(wrapper_name: &Ident, concrete: &syn::Type) -> proc_macro2::TokenStream {
+    let from_config_path = expand_crate_ref("wasm-web-component", parse_quote!(FromConfig));
+    let doc = format!(" The web component for `{}`.", quote!(#concrete));
+    let derive_debug = if MINIMAL {
+        quote!()
+    } else {
+        quote!(#[derive(Debug)])
+    };
+    quote! {
+        #[doc = #doc]
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        #derive_debug
+        pub struct #wrapper_name {
+            component: #concrete,
+        }
+
+        impl std::ops::Deref for #wrapper_name {
+            type Target = #concrete;
+
+            fn deref(&self) -> &Self::Target {
+                &self.component
+            }
+        }
+
+        impl std::ops::DerefMut for #wrapper_name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.component
+            }
+        }
+
+        impl From<#concrete> for #wrapper_name {
+            fn from(component: #concrete) -> Self {
+                Self { component }
+            }
+        }
+
+        impl<C> #from_config_path<C> for #wrapper_name
+        where
+            #concrete: #from_config_path<C>,
+        {
+            fn from_config(config: std::rc::Rc<C>) -> Self {
+                Self {
+                    component: <#concrete as #from_config_path<C>>::from_config(config),
+                }
+            }
+        }
+    }
+}
+
 fn expand_web_component_struct(
-    item_struct: ItemStruct,
+    mut item_struct: ItemStruct,
     config: AttributeConfig,
 ) -> TokenStream {
-    let struct_name = item_struct.ident.clone();
+    let docs = struct_docs(&item_struct.attrs);
+    if !config.parts.is_empty() {
+        let parts = config
+            .parts
+            .iter()
+            .map(|part| format!("`{}`", part))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let doc = format!(" Shadow parts: {}.", parts);
+        item_struct.attrs.push(parse_quote!(#[doc = ""]));
+        item_struct.attrs.push(parse_quote!(#[doc = #doc]));
+    }
+    if !item_struct.generics.params.is_empty() && config.concrete.is_none() {
+        return syn::Error::new_spanned(
+            &item_struct.generics,
+            "generic web components need a concrete instantiation, e.g. concrete = \"MyElement<RealBackend>\"",
+        )
+        .to_compile_error()
+        .into();
+    }
+    // The struct that gets exported to javascript. For generic structs this is a wrapper
+    // around the concrete instantiation.
+    let (struct_name, struct_type, wrapper) = match &config.concrete {
+        Some(concrete) => {
+            let wrapper_name = Ident::new(
+                &quote!(#concrete).to_string().to_pascal_case(),
+                Span::call_site(),
+            );
+            let wrapper = expand_concrete_wrapper(&wrapper_name, concrete);
+            (wrapper_name, concrete.clone(), wrapper)
+        }
+        None => {
+            let struct_name = item_struct.ident.clone();
+            (struct_name.clone(), parse_quote!(#struct_name), quote!())
+        }
+    };
     let struct_once_name = Ident::new(
         &(struct_name.to_string().to_snake_case().to_uppercase() + "_ONCE"),
         Span::call_site(),
     );
-    let component_def = expand_component_def(&struct_name, &config.class_name, &config.element_name);
-    let non_wasm_impl =
-        expand_wc_struct_trait_shim(&struct_name, &struct_once_name, config);
-    let wasm_shim = expand_wasm_shim(&struct_name);
-    let binding_trait = expand_binding(&struct_name);
+    let component_def = expand_component_def(&struct_name, &config, &docs);
+    let snapshot_shim = if config.snapshot {
+        let shim = expand_snapshot_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let data_source_shim = if config.data_src {
+        let shim = expand_data_source_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let live_shim = if config.live {
+        let shim = expand_live_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let dialog_shim = if config.dialog {
+        let shim = expand_dialog_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let drag_source_shim = if config.drag_source {
+        let shim = expand_drag_source_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let drop_target_shim = if config.drop_target {
+        let shim = expand_drop_target_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let gestures_shim = if config.gestures {
+        let shim = expand_gestures_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let shortcuts_shim = if config.shortcuts.is_empty() {
+        quote!()
+    } else {
+        let shim = expand_shortcuts_shim(&struct_name, &config.shortcuts);
+        quote!(#shim)
+    };
+    let files_shim = if config.files {
+        let shim = expand_files_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    let idb_shim = if config.persist_idb.is_some() {
+        let shim = expand_idb_shim(&struct_name);
+        quote!(#shim)
+    } else {
+        quote!()
+    };
+    // Structs with a custom constructor don't have to implement Default. Traits the
+    // struct already derives aren't derived again.
+    let derived = derived_traits(&item_struct.attrs);
+    let auto_derives: &[&str] = if MINIMAL {
+        &["Default"]
+    } else {
+        &["Default", "Debug"]
+    };
+    let derives = auto_derives
+        .iter()
+        .copied()
+        .filter(|name| *name != "Default" || config.constructor.is_none())
+        .filter(|name| !derived.iter().any(|derived| derived == name))
+        .map(|name| Ident::new(name, Span::call_site()))
+        .collect::<Vec<_>>();
+    if !derives.is_empty() {
+        item_struct
+            .attrs
+            .insert(0, parse_quote!(#[derive(#(#derives),*)]));
+    }
+    // Generated items are only there when the struct is.
+    let cfgs = item_struct
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .cloned()
+        .collect::<Vec<_>>();
+    let wasm_bindgen = if config.concrete.is_some() {
+        quote!()
+    } else {
+        quote!(#[::wasm_bindgen::prelude::wasm_bindgen])
+    };
+    let element_ref = expand_element_ref(&struct_name, &item_struct.vis, &config);
+    let wasm_shim = expand_wasm_shim(&struct_name, &config, expand_constructor(&config));
+    let non_wasm_impl = expand_wc_struct_trait_shim(&struct_name, &struct_once_name, config);
+    let binding_trait = expand_binding(&struct_type);
     let expanded = quote! {
         #[allow(non_snake_case)]
         static #struct_once_name: std::sync::Once = std::sync::Once::new();
-        #[::wasm_bindgen::prelude::wasm_bindgen]
-        #[derive(Default, Debug)]
+        #wasm_bindgen
         #item_struct
+        #wrapper
         #component_def
         #non_wasm_impl
         #binding_trait
         #wasm_shim
+        #snapshot_shim
+        #data_source_shim
+        #live_shim
+        #dialog_shim
+        #drag_source_shim
+        #drop_target_shim
+        #gestures_shim
+        #shortcuts_shim
+        #files_shim
+        #idb_shim
+        #element_ref
     };
 
-    TokenStream::from(expanded)
+    TokenStream::from(with_cfgs(expanded, &cfgs))
+}
+
+/// The names of the traits in the derive attributes.
+fn derived_traits(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The `///` docs of a component struct.
+#[derive(Default)]
+struct StructDocs {
+    /// The docs before the first heading.
+    description: String,
+    /// The items of the list under the `# Attributes` heading.
+    attributes: Vec<(String, String)>,
+    /// The items of the list under the `# Events` heading.
+    events: Vec<(String, String)>,
+}
+
+/// Which part of the docs a line belongs to.
+enum DocSection {
+    Description,
+    Attributes,
+    Events,
+    Other,
+}
+
+/// Splits the docs into the description and the items like ``* `open` - Whether...`` of
+/// the attributes and events lists.
+fn struct_docs(attrs: &[Attribute]) -> StructDocs {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => match nv.lit {
+                Lit::Str(doc) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        });
+    let mut docs = StructDocs::default();
+    let mut description = Vec::new();
+    let mut section = DocSection::Description;
+    let mut in_code = false;
+    for line in lines {
+        let line = line.strip_prefix(' ').unwrap_or(&line);
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code && trimmed.starts_with('#') {
+            section = match trimmed
+                .trim_start_matches('#')
+                .trim()
+                .to_lowercase()
+                .as_str()
+            {
+                "attributes" => DocSection::Attributes,
+                "events" => DocSection::Events,
+                _ => DocSection::Other,
+            };
+            continue;
+        }
+        let items = match section {
+            DocSection::Description => {
+                description.push(line.to_owned());
+                continue;
+            }
+            DocSection::Attributes => &mut docs.attributes,
+            DocSection::Events => &mut docs.events,
+            DocSection::Other => continue,
+        };
+        let item = trimmed
+            .strip_prefix("* ")
+            .or_else(|| trimmed.strip_prefix("- "))
+            .and_then(|item| item.strip_prefix('`'))
+            .and_then(|item| item.split_once('`'));
+        if let Some((name, doc)) = item {
+            let doc = doc.trim_start_matches(|c: char| c == '-' || c == ':' || c.is_whitespace());
+            items.push((name.to_owned(), doc.to_owned()));
+        } else if line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            // An indented line continues the item before it.
+            if let Some((_, doc)) = items.last_mut() {
+                doc.push(' ');
+                doc.push_str(trimmed);
+            }
+        }
+    }
+    docs.description = description.join("\n").trim().to_owned();
+    docs
+}
+
+/// Adds the cfg attributes to every item in the tokens.
+fn with_cfgs(tokens: proc_macro2::TokenStream, cfgs: &[Attribute]) -> proc_macro2::TokenStream {
+    if cfgs.is_empty() {
+        return tokens;
+    }
+    let mut file: syn::File = match syn::parse2(tokens.clone()) {
+        Ok(file) => file,
+        Err(_) => return tokens,
+    };
+    for item in file.items.iter_mut() {
+        let attrs = match item {
+            Item::Static(item) => &mut item.attrs,
+            Item::Struct(item) => &mut item.attrs,
+            Item::Impl(item) => &mut item.attrs,
+            _ => continue,
+        };
+        // Repeating a cfg on the annotated struct itself is harmless.
+        attrs.splice(0..0, cfgs.iter().cloned());
+    }
+    quote!(#file)
 }
 
 #[cfg(feature = "HtmlTemplateElement")]
@@ -376,7 +2807,7 @@ fn expand_template_struct(item_struct: ItemStruct) -> TokenStream {
                 });
                 return #struct_once_name.get();
             }
-            
+
             #[doc = "Returns the the template element id it exists. None if the element has not been defined yet. Some(&None) if the element has no id. Some(&Some(id)) if the element has an id."]
             pub fn get_id() -> Option<&'static Option<String>> {
                 return #struct_once_name.get();
@@ -393,12 +2824,21 @@ pub fn web_component(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as AttributeArgs);
     let item_struct = parse_macro_input!(item as ItemStruct);
 
-    let config =
-        get_class_and_element_names(args, &item_struct.ident);
+    let config = match get_class_and_element_names(args, &item_struct.ident) {
+        Ok(config) => config,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     expand_web_component_struct(item_struct, config)
 }
 
+/// Generates typed wrappers for the elements declared in a custom elements manifest.
+#[proc_macro]
+pub fn custom_elements(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    TokenStream::from(manifest::expand_custom_elements(path))
+}
+
 /// Creates the neccessary Rust and Javascript shims for rendering an HtmlTemplateElement
 #[cfg(feature = "HtmlTemplateElement")]
 #[proc_macro_attribute]
@@ -406,3 +2846,191 @@ pub fn template_element(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let item_struct = parse_macro_input!(item as ItemStruct);
     expand_template_struct(item_struct)
 }
+
+/// Imports the javascript runtime shared by every web component into the extern block.
+/// The block gets `#[runtime(feature)]` attributes for the optional features of the
+/// runtime that are on, usually through `cfg_attr`.
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn component_runtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut block = parse_macro_input!(item as syn::ItemForeignMod);
+    let mut enabled = Vec::new();
+    let mut errors = proc_macro2::TokenStream::new();
+    block.attrs.retain(|attr| {
+        if !attr.path.is_ident("runtime") {
+            return true;
+        }
+        match attr.parse_args::<Ident>() {
+            Ok(feature) if RUNTIME_FEATURES.contains(&feature.to_string().as_str()) => {
+                enabled.push(feature.to_string());
+            }
+            Ok(feature) => errors.extend(
+                syn::Error::new(feature.span(), "unknown runtime feature").to_compile_error(),
+            ),
+            Err(err) => errors.extend(err.to_compile_error()),
+        }
+        false
+    });
+    let enabled = enabled.iter().map(String::as_str).collect::<Vec<_>>();
+    let js = minify_js(&select_features(RUNTIME, &enabled));
+    TokenStream::from(quote! {
+        #errors
+        #[::wasm_bindgen::prelude::wasm_bindgen(inline_js = #js)]
+        #block
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_selector() {
+        assert_eq!(scope_selector(".a, .b > .c", "s1"), ".a-s1, .b-s1 > .c-s1");
+        assert_eq!(
+            scope_selector(":host(.active) .label", "s1"),
+            ":host(.active-s1) .label-s1"
+        );
+        assert_eq!(
+            scope_selector("a[href$='.pdf'].link", "s1"),
+            "a[href$='.pdf'].link-s1"
+        );
+        assert_eq!(
+            scope_selector("p:not(.x)::before", "s1"),
+            "p:not(.x-s1)::before"
+        );
+        assert_eq!(scope_selector(".5em", "s1"), ".5em");
+    }
+
+    #[test]
+    fn test_scope_css() {
+        assert_eq!(
+            scope_css(".a, .b { color: red; } .c{}", "s1"),
+            ".a-s1, .b-s1 { color: red; } .c-s1{}"
+        );
+        assert_eq!(
+            scope_css(
+                ":host { display: block; } :host(.open) .body { display: none; }",
+                "s1"
+            ),
+            ":host { display: block; } :host(.open-s1) .body-s1 { display: none; }"
+        );
+        assert_eq!(
+            scope_css(
+                "@media (min-width: 10.5em) { .a { width: 1.5em; } @supports (gap: 1px) { .b { gap: 1px; } } }",
+                "s1"
+            ),
+            "@media (min-width: 10.5em) { .a-s1 { width: 1.5em; } @supports (gap: 1px) { .b-s1 { gap: 1px; } } }"
+        );
+        assert_eq!(
+            scope_css(
+                "@keyframes spin { from { opacity: .5; } } .spin { content: '.x {'; }",
+                "s1"
+            ),
+            "@keyframes spin { from { opacity: .5; } } .spin-s1 { content: '.x {'; }"
+        );
+        assert_eq!(
+            scope_css("@import url('a.css'); /* .a { } */ .b {}", "s1"),
+            "@import url('a.css'); /* .a { } */ .b-s1 {}"
+        );
+    }
+
+    #[test]
+    fn test_select_features() {
+        let js = "a();\n// #if live\nb();\n    // #if files\n    c();\n    // #endif\nd();\n// #endif\ne();";
+        assert_eq!(select_features(js, &[]), "a();\ne();");
+        assert_eq!(select_features(js, &["live"]), "a();\nb();\nd();\ne();");
+        assert_eq!(select_features(js, &["files"]), "a();\ne();");
+        assert_eq!(
+            select_features(js, &["live", "files"]),
+            "a();\nb();\n    c();\nd();\ne();"
+        );
+    }
+
+    #[test]
+    fn test_select_features_else() {
+        let js = "if (x) {\n    // #if dialog\n    a();\n    // #endif\n} else {\n    // #if dialog\n    b();\n    // #endif\n    c();\n}";
+        assert_eq!(
+            minify_js(&select_features(js, &[])),
+            "if (x) {\n} else {\nc();\n}"
+        );
+        assert_eq!(
+            minify_js(&select_features(js, &["dialog"])),
+            "if (x) {\na();\n} else {\nb();\nc();\n}"
+        );
+    }
+
+    #[test]
+    fn test_runtime_features() {
+        let all = select_features(RUNTIME, RUNTIME_FEATURES);
+        for js in [select_features(RUNTIME, &[]), all] {
+            assert!(!js.contains("// #if"));
+            assert!(!js.contains("// #endif"));
+        }
+        for line in RUNTIME.lines() {
+            if let Some(feature) = line.trim().strip_prefix("// #if ") {
+                assert!(RUNTIME_FEATURES.contains(&feature), "{}", feature);
+            }
+        }
+        assert_eq!(
+            RUNTIME.matches("// #if ").count(),
+            RUNTIME.matches("// #endif").count()
+        );
+    }
+
+    #[test]
+    fn test_minify_js() {
+        assert_eq!(
+            minify_js("  a();\n\n  // note\n    b(\"// kept\");\n"),
+            "a();\nb(\"// kept\");"
+        );
+    }
+
+    #[test]
+    fn test_parse_shortcuts() {
+        let parse = |value: &str| {
+            parse_shortcuts(&LitStr::new(value, Span::call_site())).map(|shortcuts| {
+                shortcuts
+                    .into_iter()
+                    .map(|(combo, method)| (combo, method.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            parse("['Ctrl+K => open_search', \"Escape=>close\"]").unwrap(),
+            vec![
+                ("Ctrl+K".to_owned(), "open_search".to_owned()),
+                ("Escape".to_owned(), "close".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse("['Ctrl+, => settings', 'Shift++ => zoom']").unwrap(),
+            vec![
+                ("Ctrl+,".to_owned(), "settings".to_owned()),
+                ("Shift++".to_owned(), "zoom".to_owned())
+            ]
+        );
+        assert!(parse("[]").unwrap().is_empty());
+        assert!(parse("['Ctrl+K']").is_err());
+        assert!(parse("['Hyper+K => open']").is_err());
+        assert!(parse("['Ctrl+K => open-search']").is_err());
+    }
+
+    #[test]
+    fn test_parse_js_lists() {
+        assert_eq!(
+            parse_js_string_array("['a', \"b,c\" , ]"),
+            vec!["a".to_owned(), "b,c".to_owned()]
+        );
+        assert_eq!(
+            parse_js_object("{ariaLevel: 2, 'old-name': \"name, first\", hidden: true}").unwrap(),
+            vec![
+                ("ariaLevel".to_owned(), serde_json::json!(2)),
+                ("old-name".to_owned(), serde_json::json!("name, first")),
+                ("hidden".to_owned(), serde_json::json!(true))
+            ]
+        );
+        assert!(parse_js_object("{label: [1]}").is_none());
+        assert!(parse_js_object("label: 'a'").is_none());
+    }
+}
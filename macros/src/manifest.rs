@@ -0,0 +1,505 @@
+// Copyright 2022 Jeremy Wall (Jeremy@marzhilsltudios.com)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed element wrappers generated from a custom elements manifest.
+//!
+//! See <https://github.com/webcomponents/custom-elements-manifest> for the schema. The
+//! types of attributes, fields and event details are typescript type expressions which
+//! get mapped to Rust types where there is an obvious one and to `JsValue` otherwise.
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use inflector::Inflector;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use serde_json::Value;
+use syn::{parse_quote, Ident, LitStr};
+
+use crate::{expand_crate_ref, expand_element_ref_conversions, rust_ident};
+
+/// Splits the typescript type expression at the separators that aren't nested inside of
+/// brackets or strings.
+fn split_top_level(text: &str, separators: &[char]) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '<' | '(' | '[' | '{') => depth += 1,
+            (None, '>' | ')' | ']' | '}') => depth -= 1,
+            (None, c) if depth == 0 && separators.contains(&c) => {
+                parts.push(std::mem::take(&mut part));
+                continue;
+            }
+            _ => {}
+        }
+        part.push(c);
+    }
+    parts.push(part);
+    parts
+        .into_iter()
+        .map(|part| part.trim().to_owned())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn is_string_literal(text: &str) -> bool {
+    ["'", "\"", "`"]
+        .iter()
+        .any(|q| text.len() > 1 && text.starts_with(q) && text.ends_with(q))
+}
+
+/// The Rust type of a typescript union without `undefined` and `null`, and whether they
+/// were part of it.
+fn union_type(text: &str) -> (Option<syn::Type>, bool) {
+    let parts = split_top_level(text, &['|']);
+    let optional = parts
+        .iter()
+        .any(|part| part == "undefined" || part == "null");
+    let parts = parts
+        .iter()
+        .filter(|part| *part != "undefined" && *part != "null")
+        .collect::<Vec<_>>();
+    let all = |f: fn(&str) -> bool| !parts.is_empty() && parts.iter().all(|part| f(part));
+    let ty = if all(|part| matches!(part, "boolean" | "true" | "false")) {
+        Some(parse_quote!(bool))
+    } else if all(|part| part == "number" || part.parse::<f64>().is_ok()) {
+        Some(parse_quote!(f64))
+    } else if all(|part| part == "string" || is_string_literal(part)) {
+        Some(parse_quote!(String))
+    } else if let [part] = parts.as_slice() {
+        let item = part
+            .strip_suffix("[]")
+            .or_else(|| part.strip_prefix("Array<")?.strip_suffix('>'));
+        item.map(|item| {
+            let item = property_type(item, false);
+            parse_quote!(Vec<#item>)
+        })
+    } else {
+        None
+    };
+    (ty, optional)
+}
+
+/// The Rust type of a property, optional for properties that may be missing. `JsValue`
+/// if there is no obvious one.
+fn property_type(text: &str, optional: bool) -> syn::Type {
+    match union_type(text) {
+        (Some(ty), nullable) if nullable || optional => parse_quote!(Option<#ty>),
+        (Some(ty), _) => ty,
+        (None, _) => parse_quote!(::wasm_bindgen::JsValue),
+    }
+}
+
+/// The Rust type of an attribute. Attributes without a boolean or number type are
+/// Strings.
+fn attribute_type(text: &str) -> syn::Type {
+    match union_type(text).0 {
+        Some(ty) if matches!(quote!(#ty).to_string().as_str(), "bool" | "f64") => ty,
+        _ => parse_quote!(String),
+    }
+}
+
+/// A snake case identifier for a javascript name, e.g. `page_changed` for `page-changed`.
+fn snake_name(name: &str) -> String {
+    name.to_snake_case()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// The identifier for a name from the manifest, an error pointing at the manifest path if
+/// there is none.
+fn ident(name: &str, span: Span) -> syn::Result<Ident> {
+    rust_ident(name).ok_or_else(|| {
+        syn::Error::new(
+            span,
+            format!(
+                "{:?} in the manifest can't be turned into a Rust name",
+                name
+            ),
+        )
+    })
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Option<&'a str> {
+    value.get(field)?.as_str()
+}
+
+fn type_text(value: &Value) -> Option<&str> {
+    value.get("type")?.get("text")?.as_str()
+}
+
+fn docs(value: &Value, default: String) -> TokenStream {
+    let doc = str_field(value, "description")
+        .or_else(|| str_field(value, "summary"))
+        .map(str::to_owned)
+        .unwrap_or(default);
+    quote!(#[doc = #doc])
+}
+
+fn array<'a>(value: &'a Value, field: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(field)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// Generates the struct for an event detail declared as an object type like
+/// `CustomEvent<{page: number; size?: number}>`.
+fn expand_detail_struct(
+    detail_name: &Ident,
+    event: &str,
+    fields: &str,
+    span: Span,
+) -> syn::Result<TokenStream> {
+    let property_value = expand_crate_ref("wasm-web-component", parse_quote!(PropertyValue));
+    let get_prop = expand_crate_ref("wasm-web-component", parse_quote!(get_prop));
+    let set_prop = expand_crate_ref("wasm-web-component", parse_quote!(set_prop));
+    let fields = split_top_level(fields, &[';', ','])
+        .iter()
+        .filter_map(|field| {
+            let (name, ty) = field.split_once(':')?;
+            let (name, optional) = match name.trim().strip_suffix('?') {
+                Some(name) => (name, true),
+                None => (name.trim(), false),
+            };
+            let name = name.trim_matches(|c| c == '\'' || c == '"').to_owned();
+            Some((name, property_type(ty, optional)))
+        })
+        .collect::<Vec<_>>();
+    let idents = fields
+        .iter()
+        .map(|(name, _)| ident(&snake_name(name), span))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let names = fields.iter().map(|(name, _)| name).collect::<Vec<_>>();
+    let types = fields.iter().map(|(_, ty)| ty);
+    let doc = format!(" The detail of `{}` events.", event);
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct #detail_name {
+            #(pub #idents: #types,)*
+        }
+
+        impl #property_value for #detail_name {
+            fn from_property(value: &::wasm_bindgen::JsValue) -> Option<Self> {
+                Some(Self {
+                    #(#idents: #get_prop(value, #names)?,)*
+                })
+            }
+
+            fn property_value(&self) -> ::wasm_bindgen::JsValue {
+                let object: ::wasm_bindgen::JsValue = js_sys::Object::new().into();
+                #(let _ = #set_prop(&object, #names, &self.#idents);)*
+                object
+            }
+        }
+    })
+}
+
+fn expand_declaration(declaration: &Value, span: Span) -> syn::Result<Option<TokenStream>> {
+    let tag_name = match str_field(declaration, "tagName") {
+        Some(tag_name) => tag_name,
+        None => return Ok(None),
+    };
+    if declaration.get("customElement").and_then(Value::as_bool) != Some(true) {
+        return Ok(None);
+    }
+    let class_name = str_field(declaration, "name")
+        .map(str::to_owned)
+        .unwrap_or_else(|| tag_name.to_pascal_case());
+    let ref_name = ident(&format!("{}Ref", class_name), span)?;
+    let get_attr = expand_crate_ref("wasm-web-component", parse_quote!(get_attr));
+    let set_attr = expand_crate_ref("wasm-web-component", parse_quote!(set_attr));
+    let get_prop = expand_crate_ref("wasm-web-component", parse_quote!(get_prop));
+    let set_prop = expand_crate_ref("wasm-web-component", parse_quote!(set_prop));
+    let subscription = expand_crate_ref("wasm-web-component", parse_quote!(EventSubscription));
+    let check_tag_name = expand_crate_ref("wasm-web-component", parse_quote!(check_tag_name));
+
+    // Attribute accessors take precedence over the accessors of the fields they set.
+    let mut methods: HashSet<String> = ["element", "create"].map(str::to_owned).into();
+    let mut attribute_fields = HashSet::new();
+    let mut accessors = Vec::new();
+    for attribute in array(declaration, "attributes") {
+        let Some(name) = str_field(attribute, "name") else {
+            continue;
+        };
+        let snake = snake_name(name);
+        if !methods.insert(snake.clone()) {
+            continue;
+        }
+        attribute_fields.extend(str_field(attribute, "fieldName"));
+        let ty = attribute_type(type_text(attribute).unwrap_or("string"));
+        let getter = ident(&snake, span)?;
+        let setter = ident(&format!("set_{}", snake), span)?;
+        let get_doc = docs(
+            attribute,
+            format!(" The parsed value of the `{}` attribute.", name),
+        );
+        let set_doc = format!(" Sets the `{}` attribute to the value.", name);
+        accessors.push(quote! {
+            #get_doc
+            pub fn #getter(&self) -> Option<#ty> {
+                #get_attr::<#ty>(&self.0, #name)
+            }
+
+            #[doc = #set_doc]
+            pub fn #setter(&self, value: &#ty) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                #set_attr(&self.0, #name, value)
+            }
+        });
+    }
+    for member in array(declaration, "members") {
+        let Some(name) = str_field(member, "name") else {
+            continue;
+        };
+        let public = str_field(member, "privacy").is_none_or(|privacy| privacy == "public");
+        let is_static = member.get("static").and_then(Value::as_bool) == Some(true);
+        if str_field(member, "kind") != Some("field")
+            || !public
+            || is_static
+            || name.starts_with(['#', '_'])
+            || attribute_fields.contains(name)
+        {
+            continue;
+        }
+        let snake = snake_name(name);
+        if !methods.insert(snake.clone()) {
+            continue;
+        }
+        let ty = type_text(member)
+            .map(|ty| property_type(ty, false))
+            .unwrap_or_else(|| parse_quote!(::wasm_bindgen::JsValue));
+        let getter = ident(&snake, span)?;
+        let get_doc = docs(member, format!(" The value of the `{}` property.", name));
+        accessors.push(quote! {
+            #get_doc
+            pub fn #getter(&self) -> Option<#ty> {
+                #get_prop::<#ty>(&self.0, #name)
+            }
+        });
+        if member.get("readonly").and_then(Value::as_bool) != Some(true) {
+            let setter = ident(&format!("set_{}", snake), span)?;
+            let set_doc = format!(" Sets the `{}` property to the value.", name);
+            accessors.push(quote! {
+                #[doc = #set_doc]
+                pub fn #setter(&self, value: &#ty) -> std::result::Result<(), ::wasm_bindgen::JsValue> {
+                    #set_prop(&self.0, #name, value)
+                }
+            });
+        }
+    }
+
+    let mut details = Vec::new();
+    let mut events = Vec::new();
+    for event in array(declaration, "events") {
+        let Some(name) = str_field(event, "name") else {
+            continue;
+        };
+        let method = ident(&format!("on_{}", snake_name(name)), span)?;
+        let doc = docs(
+            event,
+            format!(
+                " Calls the callback with the `{}` events of the element until the subscription is dropped.",
+                name
+            ),
+        );
+        let detail = type_text(event)
+            .and_then(|ty| ty.trim().strip_prefix("CustomEvent<")?.strip_suffix('>'))
+            .map(str::trim);
+        let detail_type: Option<syn::Type> = match detail {
+            Some(fields) if fields.starts_with('{') && fields.ends_with('}') => {
+                let detail_name = ident(
+                    &format!("{}{}Detail", class_name, name.to_pascal_case()),
+                    span,
+                )?;
+                details.push(expand_detail_struct(
+                    &detail_name,
+                    name,
+                    &fields[1..fields.len() - 1],
+                    span,
+                )?);
+                Some(parse_quote!(#detail_name))
+            }
+            Some(ty) => Some(property_type(ty, false)),
+            None => None,
+        };
+        events.push(match detail_type {
+            Some(detail_type) => quote! {
+                #doc
+                pub fn #method<F: FnMut(#detail_type, web_sys::Event) + 'static>(&self, callback: F) -> std::result::Result<#subscription, ::wasm_bindgen::JsValue> {
+                    #subscription::listen_detail(&self.0, #name, callback)
+                }
+            },
+            None => quote! {
+                #doc
+                pub fn #method<F: FnMut(web_sys::Event) + 'static>(&self, callback: F) -> std::result::Result<#subscription, ::wasm_bindgen::JsValue> {
+                    #subscription::listen(&self.0, #name, callback)
+                }
+            },
+        });
+    }
+
+    let doc = docs(
+        declaration,
+        format!(" A typed handle to a `<{}>` element.", tag_name),
+    );
+    let create_doc = format!(" Creates a `<{}>` element.", tag_name);
+    let conversions = expand_element_ref_conversions(
+        &ref_name,
+        quote!(|element| #check_tag_name(element, #tag_name)),
+    );
+    Ok(Some(quote! {
+        #doc
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct #ref_name(web_sys::HtmlElement);
+
+        impl #ref_name {
+            pub const TAG_NAME: &'static str = #tag_name;
+
+            #[doc = #create_doc]
+            pub fn create() -> std::result::Result<Self, ::wasm_bindgen::JsValue> {
+                let element = web_sys::window()
+                    .and_then(|window| window.document())
+                    .ok_or_else(|| ::wasm_bindgen::JsValue::from_str("No document to create the element in"))?
+                    .create_element(#tag_name)?;
+                Ok(Self(::wasm_bindgen::JsCast::unchecked_into(element)))
+            }
+
+            #[doc = "The element."]
+            pub fn element(&self) -> &web_sys::HtmlElement {
+                &self.0
+            }
+
+            #(#accessors)*
+
+            #(#events)*
+        }
+
+        #conversions
+
+        #(#details)*
+    }))
+}
+
+/// Generates the wrappers for every custom element declared in the manifest at the path
+/// relative to the crate's `Cargo.toml`.
+pub fn expand_custom_elements(path: LitStr) -> TokenStream {
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = PathBuf::from(root).join(path.value());
+    let manifest = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+        .and_then(|json| {
+            serde_json::from_str::<Value>(&json)
+                .map_err(|e| format!("Failed to parse {}: {}", file.display(), e))
+        });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(message) => return syn::Error::new(path.span(), message).to_compile_error(),
+    };
+    let wrappers = array(&manifest, "modules")
+        .flat_map(|module| array(module, "declarations"))
+        .map(|declaration| expand_declaration(declaration, path.span()))
+        .collect::<syn::Result<Vec<_>>>();
+    let wrappers = match wrappers {
+        Ok(wrappers) => wrappers.into_iter().flatten(),
+        Err(err) => return err.to_compile_error(),
+    };
+    // Recompiles the crate when the manifest changes.
+    let file = file.display().to_string();
+    quote! {
+        const _: &[u8] = include_bytes!(#file);
+        #(#wrappers)*
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_string(ty: syn::Type) -> String {
+        quote!(#ty).to_string().replace(' ', "")
+    }
+
+    #[test]
+    fn test_split_top_level() {
+        assert_eq!(
+            split_top_level("string | Array<string | number> | 'a|b'", &['|']),
+            vec!["string", "Array<string | number>", "'a|b'"]
+        );
+        assert_eq!(
+            split_top_level("page: number; size?: {a: 1, b: 2},", &[';', ',']),
+            vec!["page: number", "size?: {a: 1, b: 2}"]
+        );
+        assert!(split_top_level("  ", &['|']).is_empty());
+    }
+
+    #[test]
+    fn test_union_type() {
+        let union = |text| {
+            let (ty, optional) = union_type(text);
+            (ty.map(type_string), optional)
+        };
+        assert_eq!(union("boolean"), (Some("bool".to_owned()), false));
+        assert_eq!(
+            union("true | false | null"),
+            (Some("bool".to_owned()), true)
+        );
+        assert_eq!(union("1 | 2 | number"), (Some("f64".to_owned()), false));
+        assert_eq!(
+            union("'small' | \"large\" | undefined"),
+            (Some("String".to_owned()), true)
+        );
+        assert_eq!(union("string[]"), (Some("Vec<String>".to_owned()), false));
+        assert_eq!(
+            union("Array<number | null>"),
+            (Some("Vec<Option<f64>>".to_owned()), false)
+        );
+        assert_eq!(union("string | number"), (None, false));
+        assert_eq!(union("null"), (None, true));
+    }
+
+    #[test]
+    fn test_property_type() {
+        assert_eq!(type_string(property_type("number", false)), "f64");
+        assert_eq!(type_string(property_type("number", true)), "Option<f64>");
+        assert_eq!(
+            type_string(property_type("string | null", false)),
+            "Option<String>"
+        );
+        assert_eq!(
+            type_string(property_type("HTMLElement", false)),
+            "::wasm_bindgen::JsValue"
+        );
+        assert_eq!(type_string(attribute_type("'a' | 'b'")), "String");
+        assert_eq!(type_string(attribute_type("Date")), "String");
+        assert_eq!(type_string(attribute_type("boolean")), "bool");
+    }
+
+    #[test]
+    fn test_ident() {
+        let span = Span::call_site();
+        assert_eq!(ident(&snake_name("2x"), span).unwrap(), "_2x");
+        assert_eq!(ident("self", span).unwrap(), "self_");
+        assert_eq!(ident("type", span).unwrap(), "r#type");
+        assert_eq!(ident("$FooRef", span).unwrap(), "_FooRef");
+        assert!(ident("$", span).is_err());
+    }
+}
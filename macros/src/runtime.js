@@ -0,0 +1,1197 @@
+// The javascript shared by every web component. Components are thin subclasses of
+// the class for their base class with their settings in a static config.
+function prefersReducedMotion() {
+    return !!window.matchMedia && window.matchMedia("(prefers-reduced-motion: reduce)").matches;
+}
+// Frees the Rust side of an element once the element has been garbage collected.
+const implRegistry = typeof FinalizationRegistry !== "undefined"
+    ? new FinalizationRegistry(function(i) { i.free(); })
+    : null;
+const componentBases = new Map();
+// The attribute changes waiting for the view transition that delivers them.
+let transitionBatch = null;
+
+// Runs the change inside of a view transition. Every change queued before the
+// transition's update runs shares the transition, so changes made in one task don't
+// start transitions that skip each other, and they run in the order they were made.
+function inViewTransition(change) {
+    if (transitionBatch) {
+        transitionBatch.push(change);
+        return;
+    }
+    const batch = transitionBatch = [change];
+    const update = () => {
+        try {
+            // Changes made by the changes join the batch too.
+            for (let i = 0; i < batch.length; i++) {
+                batch[i]();
+            }
+        } finally {
+            transitionBatch = null;
+        }
+    };
+    Promise.resolve().then(() => {
+        try {
+            document.startViewTransition(update);
+        } catch (e) {
+            update();
+        }
+    });
+}
+
+function componentBase(Base) {
+    return class extends Base {
+        get _config() {
+            return this.constructor.config;
+        }
+
+        constructor() {
+            super();
+            // Properties set on the element before it was upgraded shadow any
+            // setters on our prototype so we capture them and replay them below.
+            var upgradedProps = {};
+            for (const p of Object.keys(this)) {
+                upgradedProps[p] = this[p];
+                delete this[p];
+            }
+            const config = this._config;
+            if (config.stateless) {
+                // Stateless components share a single Rust impl across every element
+                // instance so it is never freed.
+                this._impl = config.sharedImpl || (config.sharedImpl = config.impl());
+            } else {
+                this._impl = config.impl();
+                if (implRegistry) {
+                    implRegistry.register(this, this._impl, this);
+                }
+            }
+            const aria = config.aria;
+            if (config.role || Object.keys(aria).length) {
+                const internals = this.internals();
+                if (internals) {
+                    if (config.role) {
+                        internals.role = config.role;
+                    }
+                    Object.assign(internals, aria);
+                }
+            }
+            if (config.formAssociated) {
+                this._onSubmitAttempt = (evt) => {
+                    const target = evt.composedPath()[0];
+                    const submits = evt.type === "keydown"
+                        ? evt.key === "Enter"
+                        : target instanceof Element && target.matches("button:not([type=button]):not([type=reset]), input[type=submit], input[type=image]");
+                    if (submits) {
+                        this.runValidation();
+                    }
+                };
+            }
+            // #if dialog
+            if (config.dialog) {
+                this.addEventListener("close", () => {
+                    this.callImpl((impl) => impl.dialog_closed_impl(this, this.returnValue));
+                });
+                this.addEventListener("cancel", (evt) => {
+                    this.callImpl((impl) => impl.dialog_cancel_impl(this, evt));
+                });
+                this.addEventListener("click", (evt) => {
+                    // Clicks on the backdrop target the dialog itself but land outside its box.
+                    if (evt.target !== this || !this.open || !this.hasAttribute("light-dismiss")) {
+                        return;
+                    }
+                    const rect = this.getBoundingClientRect();
+                    if (evt.clientX < rect.left || evt.clientX > rect.right
+                        || evt.clientY < rect.top || evt.clientY > rect.bottom) {
+                        if (this.dispatchEvent(new Event("cancel", { cancelable: true }))) {
+                            this.close();
+                        }
+                    }
+                });
+            }
+            // #endif
+            if (config.formControl) {
+                // Writes to the value property don't fire any events so they get reported
+                // from an accessor shadowing the built-in one.
+                const valueProperty = Object.getOwnPropertyDescriptor(Base.prototype, "value");
+                Object.defineProperty(this, "value", {
+                    configurable: true,
+                    enumerable: true,
+                    get() {
+                        return valueProperty.get.call(this);
+                    },
+                    set(value) {
+                        valueProperty.set.call(this, value);
+                        this.valueChangedCallback("property");
+                    },
+                });
+                this._lastValue = this.value;
+                this._committedValue = this._lastValue;
+                this._valueDirty = false;
+                this.addEventListener("input", () => this.valueChangedCallback("input"));
+                this.addEventListener("change", () => this.valueChangedCallback("commit"));
+            }
+            // #if drag_drop
+            if (config.dragSource) {
+                this.addEventListener("dragstart", (evt) => {
+                    this.callImpl((impl) => impl.drag_started_impl(this, evt));
+                });
+                this.addEventListener("dragend", (evt) => {
+                    this.callImpl((impl) => impl.drag_ended_impl(this, evt));
+                });
+            }
+            if (config.dropTarget) {
+                const dragOver = (evt) => {
+                    let accepted = false;
+                    this.callImpl((impl) => {
+                        accepted = impl.dragged_over_impl(this, evt);
+                    });
+                    // Dropping is only allowed when the browser's default is prevented.
+                    if (accepted) {
+                        evt.preventDefault();
+                    }
+                };
+                this.addEventListener("dragenter", dragOver);
+                this.addEventListener("dragover", dragOver);
+                this.addEventListener("dragleave", (evt) => {
+                    // Moving onto a child of the element isn't leaving it.
+                    if (!this.contains(evt.relatedTarget)) {
+                        this.callImpl((impl) => impl.drag_left_impl(this));
+                    }
+                });
+                this.addEventListener("drop", (evt) => {
+                    // Keeps the browser from navigating to dropped files.
+                    evt.preventDefault();
+                    this.callImpl((impl) => impl.dropped_impl(this, evt));
+                });
+            }
+            // #endif
+            if (config.contentVisibility) {
+                this.addEventListener("contentvisibilityautostatechange", (evt) => {
+                    this.callImpl((impl) => impl.content_visibility_changed_impl(this, evt.skipped));
+                });
+            }
+            // #if shortcuts
+            if (config.shortcuts) {
+                this._onShortcut = (evt) => this.callImpl((impl) => impl.shortcut_impl(this, evt));
+                if (!config.globalShortcuts) {
+                    this.addEventListener("keydown", this._onShortcut);
+                }
+            }
+            // #endif
+            if (config.trackFocus) {
+                this._hasFocus = false;
+                this.addEventListener("focusin", () => this.focusChangedCallback(true));
+                this.addEventListener("focusout", (evt) => {
+                    // Focus moving between elements of the component isn't a change.
+                    const next = evt.relatedTarget;
+                    this.focusChangedCallback(next instanceof Node && this.contains(next));
+                });
+            }
+            // #if gestures
+            if (config.gestures) {
+                const pointer = (evt) => {
+                    this.callImpl((impl) => impl.gesture_pointer_impl(this, evt));
+                };
+                for (const type of ["pointerdown", "pointermove", "pointerup", "pointercancel"]) {
+                    this.addEventListener(type, pointer);
+                }
+            }
+            // #endif
+            if (this.constructor.deferInit) {
+                // The custom elements polyfill constructs elements before their attributes
+                // and children are there so initialization waits for the first callback.
+                this._deferredInit = true;
+            } else {
+                this.callImpl((impl) => impl.init_impl(this));
+            }
+            if (config.listenHost && !(config.listenAuto && this.shadowRoot)) {
+                this.listenOn(this);
+            }
+            if (config.listenShadow && this.shadowRoot) {
+                this.listenOn(this.shadowRoot);
+            }
+            // #if files
+            if (config.files) {
+                // change events of file inputs in the shadow root don't leave it.
+                const changed = (evt) => {
+                    const input = evt.target;
+                    if (input instanceof HTMLInputElement && input.type === "file" && input.files) {
+                        this.readFiles(Array.from(input.files));
+                    }
+                };
+                this.addEventListener("change", changed);
+                if (this.shadowRoot) {
+                    this.shadowRoot.addEventListener("change", changed);
+                }
+                this.addEventListener("dragover", (evt) => {
+                    if (evt.dataTransfer && evt.dataTransfer.types.includes("Files")) {
+                        evt.preventDefault();
+                    }
+                });
+                this.addEventListener("drop", (evt) => {
+                    if (evt.dataTransfer && evt.dataTransfer.types.includes("Files")) {
+                        evt.preventDefault();
+                        this.droppedFiles(evt.dataTransfer).then((files) => this.readFiles(files));
+                    }
+                });
+            }
+            // #endif
+            for (const p in upgradedProps) {
+                this[p] = upgradedProps[p];
+            }
+        }
+
+        internals() {
+            if (this.constructor.disabledFeatures.includes("internals")) {
+                return null;
+            }
+            if (this._internals === undefined) {
+                // attachInternals throws for customized built-in elements and when the
+                // internals have already been attached by someone else.
+                try {
+                    this._internals = this.attachInternals();
+                } catch (e) {
+                    this._internals = null;
+                }
+            }
+            return this._internals;
+        }
+
+        // #if gestures
+        gestureTimeout(press) {
+            this.callImpl((impl) => impl.gesture_timeout_impl(this, press));
+        }
+        // #endif
+
+        // #if files
+        async readFiles(files) {
+            if (!this._config.files) {
+                throw new Error("[" + this._config.elementName + "] doesn't read files");
+            }
+            // Files are read one after another so only one is in memory at a time.
+            for (const entry of files) {
+                const [file, path] = Array.isArray(entry) ? entry : [entry, entry.webkitRelativePath || entry.name];
+                const id = this._fileReads = (this._fileReads || 0) + 1;
+                this.callImpl((impl) => impl.file_started_impl(this, id, file.name, path, file.type, file.size));
+                try {
+                    for (let loaded = 0; loaded < file.size;) {
+                        const chunk = new Uint8Array(await file.slice(loaded, loaded + 1048576).arrayBuffer());
+                        loaded += chunk.length;
+                        this.callImpl((impl) => impl.file_chunk_impl(this, id, chunk));
+                    }
+                    this.callImpl((impl) => impl.file_read_impl(this, id));
+                } catch (e) {
+                    this.callImpl((impl) => impl.file_error_impl(this, id, e));
+                }
+            }
+        }
+
+        async droppedFiles(transfer) {
+            // The handles and entries have to be requested before the drop event returns.
+            const items = Array.from(transfer.items || []).filter((item) => item.kind === "file");
+            const handles = items.map((item) => item.getAsFileSystemHandle ? item.getAsFileSystemHandle() : null);
+            const entries = items.map((item) => item.webkitGetAsEntry ? item.webkitGetAsEntry() : null);
+            const plain = items.map((item) => item.getAsFile());
+            const files = [];
+            for (let i = 0; i < items.length; i++) {
+                const handle = await handles[i];
+                if (handle) {
+                    await this.collectHandle(handle, "", files);
+                } else if (entries[i]) {
+                    await this.collectEntry(entries[i], "", files);
+                } else if (plain[i]) {
+                    files.push([plain[i], plain[i].name]);
+                }
+            }
+            return files;
+        }
+
+        async collectHandle(handle, path, files) {
+            if (handle.kind === "file") {
+                files.push([await handle.getFile(), path + handle.name]);
+            } else if (handle.kind === "directory") {
+                const children = handle.values();
+                for (let next = await children.next(); !next.done; next = await children.next()) {
+                    await this.collectHandle(next.value, path + handle.name + "/", files);
+                }
+            }
+        }
+
+        async collectEntry(entry, path, files) {
+            if (entry.isFile) {
+                files.push([await new Promise((resolve, reject) => entry.file(resolve, reject)), path + entry.name]);
+            } else if (entry.isDirectory) {
+                const reader = entry.createReader();
+                // readEntries returns the entries in batches until it returns none.
+                for (;;) {
+                    const batch = await new Promise((resolve, reject) => reader.readEntries(resolve, reject));
+                    if (!batch.length) {
+                        break;
+                    }
+                    for (const child of batch) {
+                        await this.collectEntry(child, path + entry.name + "/", files);
+                    }
+                }
+            }
+        }
+
+        pickFiles(accept, multiple, directory) {
+            const input = document.createElement("input");
+            input.type = "file";
+            input.accept = accept;
+            input.multiple = multiple;
+            input.webkitdirectory = directory;
+            input.addEventListener("change", () => this.readFiles(Array.from(input.files)));
+            input.click();
+        }
+
+        async pickDirectory() {
+            if (!window.showDirectoryPicker) {
+                this.pickFiles("", true, true);
+                return;
+            }
+            let handle;
+            try {
+                handle = await window.showDirectoryPicker();
+            } catch (e) {
+                // The picker was cancelled.
+                return;
+            }
+            const files = [];
+            await this.collectHandle(handle, "", files);
+            await this.readFiles(files);
+        }
+        // #endif
+
+        connectedCallback() {
+            const config = this._config;
+            this.runDeferredInit();
+            if (this._detachedObserver) {
+                this._detachedObserver.disconnect();
+            }
+            // #if drag_drop
+            if (config.dragSource && !this.hasAttribute("draggable")) {
+                this.draggable = true;
+            }
+            // #endif
+            if (!this._persistRestored) {
+                this._persistRestored = true;
+                this.restorePersistedAttributes();
+            }
+            // #if persist_idb
+            if (config.persistIdb !== null) {
+                if (!this._idbLoading) {
+                    this._idbLoading = this.loadIdbState();
+                }
+                if (!this._onPageHide) {
+                    this._onPageHide = () => this.saveIdbState();
+                }
+                // Elements aren't disconnected when the page is unloaded.
+                window.addEventListener("pagehide", this._onPageHide);
+            }
+            // #endif
+            if (this.constructor.urlAttributes.length) {
+                this.readUrlAttributes();
+                if (!this._onPopState) {
+                    this._onPopState = () => this.readUrlAttributes();
+                }
+                window.addEventListener("popstate", this._onPopState);
+            }
+            this.callImpl((impl) => impl.connected_impl(this));
+            if (config.childrenReady) {
+                this.waitForChildren();
+            }
+            // #if shortcuts
+            if (config.shortcuts && config.globalShortcuts) {
+                document.addEventListener("keydown", this._onShortcut);
+            }
+            // #endif
+            if (config.mediaQueries.length) {
+                this.watchMedia();
+            }
+            if (config.themed) {
+                if (!this._onThemeChanged) {
+                    this._onThemeChanged = (evt) => {
+                        // Only themes applied to this element or one of its ancestors apply.
+                        for (let node = this; node; node = node.parentNode || node.host) {
+                            if (node === evt.target) {
+                                this.callImpl((impl) => impl.theme_changed_impl(this));
+                                return;
+                            }
+                        }
+                    };
+                }
+                document.addEventListener("wasm-theme-changed", this._onThemeChanged);
+            }
+            if (config.formAssociated) {
+                this.runValidation();
+                if (!this._onLabelClick) {
+                    this._onLabelClick = (evt) => {
+                        // Clicks inside of the element already reach its control.
+                        const path = evt.composedPath();
+                        const label = path.find((node) => node instanceof HTMLLabelElement);
+                        const internals = this.internals();
+                        const labels = internals && internals.labels;
+                        if (label && labels && !path.includes(this) && Array.from(labels).includes(label)) {
+                            this.focusLabelTarget();
+                        }
+                    };
+                }
+                document.addEventListener("click", this._onLabelClick);
+            }
+            if (config.localized) {
+                if (!this._langObserver) {
+                    this._langObserver = new MutationObserver(() => this.checkLocale(false));
+                    this._onBundlesChanged = () => this.checkLocale(true);
+                }
+                // The lang attribute of the element or any of its ancestors can change its language.
+                for (let node = this; node; node = node.parentNode || node.host) {
+                    if (node.nodeType === Node.ELEMENT_NODE) {
+                        this._langObserver.observe(node, { attributes: true, attributeFilter: ["lang"] });
+                    }
+                }
+                document.addEventListener("wasm-bundles-changed", this._onBundlesChanged);
+                this.checkLocale(false);
+            }
+            if (config.viewTransition && !this.style.viewTransitionName) {
+                config.viewTransitionCount = (config.viewTransitionCount || 0) + 1;
+                this._viewTransitionName = config.elementName + "-" + config.viewTransitionCount;
+                this.style.viewTransitionName = this._viewTransitionName;
+            }
+            // Authors can still override the hints with the element's style.
+            if (config.contentVisibility && !this.style.contentVisibility) {
+                this.style.contentVisibility = "auto";
+                if (!this.style.containIntrinsicSize) {
+                    this.style.containIntrinsicSize = config.contentVisibility;
+                }
+            }
+            if (config.animateIn && !prefersReducedMotion()) {
+                this.callImpl((impl) => impl.animate_in_impl(this));
+            }
+            // #if data_src
+            if (config.dataSrc) {
+                this.loadDataSrc();
+            }
+            // #endif
+            // #if live
+            if (config.live) {
+                this.connectLive();
+            }
+            // #endif
+        }
+
+        disconnectedCallback() {
+            const config = this._config;
+            if (this._onPopState) {
+                window.removeEventListener("popstate", this._onPopState);
+            }
+            // #if persist_idb
+            if (this._onPageHide) {
+                window.removeEventListener("pagehide", this._onPageHide);
+                this.saveIdbState();
+            }
+            // #endif
+            // #if data_src
+            this.abortDataSrc();
+            // #endif
+            // #if live
+            this.disconnectLive();
+            // #endif
+            this.stopWaitingForChildren();
+            // #if shortcuts
+            if (config.shortcuts && config.globalShortcuts) {
+                document.removeEventListener("keydown", this._onShortcut);
+            }
+            // #endif
+            this.unwatchMedia();
+            if (this._onThemeChanged) {
+                document.removeEventListener("wasm-theme-changed", this._onThemeChanged);
+            }
+            if (this._onLabelClick) {
+                document.removeEventListener("click", this._onLabelClick);
+            }
+            if (this._langObserver) {
+                this._langObserver.disconnect();
+                document.removeEventListener("wasm-bundles-changed", this._onBundlesChanged);
+            }
+            if (this._viewTransitionName) {
+                if (this.style.viewTransitionName === this._viewTransitionName) {
+                    this.style.viewTransitionName = "";
+                }
+                this._viewTransitionName = null;
+            }
+            if (config.trackFocus) {
+                // Removing the focused element doesn't fire focusout.
+                this.focusChangedCallback(false);
+            }
+            this.callImpl((impl) => impl.disconnected_impl(this));
+            // #if diagnostics
+            if (config.diagnostics) {
+                if (!this._detachedObserver) {
+                    this._detachedObserver = new MutationObserver((records) => {
+                        if (!this.isConnected && records.some((r) => r.addedNodes.length)) {
+                            console.warn("[" + config.elementName + "] Nodes were added to the element while it is disconnected.", this);
+                        }
+                    });
+                }
+                this._detachedObserver.observe(this, { childList: true, subtree: true });
+            }
+            // #endif
+        }
+
+        waitForChildren() {
+            if (this._childrenReady || this._childrenObserver) {
+                return;
+            }
+            const ready = () => {
+                this.stopWaitingForChildren();
+                this._childrenReady = true;
+                this.callImpl((impl) => impl.children_ready_impl(this));
+            };
+            // The parser is done with the element's children once it has moved on to a node
+            // after the element, or once the whole document has been parsed.
+            const parsed = () => {
+                if (document.readyState !== "loading") {
+                    return true;
+                }
+                for (let node = this; node; node = node.parentNode) {
+                    if (node.nextSibling) {
+                        return true;
+                    }
+                }
+                return false;
+            };
+            if (parsed()) {
+                ready();
+                return;
+            }
+            this._childrenObserver = new MutationObserver(() => {
+                if (parsed()) {
+                    ready();
+                }
+            });
+            for (let node = this.parentNode; node; node = node.parentNode) {
+                this._childrenObserver.observe(node, { childList: true });
+            }
+            this._onChildrenParsed = ready;
+            document.addEventListener("DOMContentLoaded", ready);
+        }
+
+        stopWaitingForChildren() {
+            if (this._childrenObserver) {
+                this._childrenObserver.disconnect();
+                this._childrenObserver = null;
+                document.removeEventListener("DOMContentLoaded", this._onChildrenParsed);
+            }
+        }
+
+        watchMedia() {
+            if (this._mediaWatchers || !window.matchMedia) {
+                return;
+            }
+            this._mediaWatchers = [];
+            for (const query of this._config.mediaQueries) {
+                const list = window.matchMedia(query);
+                const listener = (evt) => this.callImpl((impl) => impl.media_changed_impl(this, query, evt.matches));
+                list.addEventListener("change", listener);
+                this._mediaWatchers.push([list, listener]);
+                this.callImpl((impl) => impl.media_changed_impl(this, query, list.matches));
+            }
+        }
+
+        unwatchMedia() {
+            if (this._mediaWatchers) {
+                for (const [list, listener] of this._mediaWatchers) {
+                    list.removeEventListener("change", listener);
+                }
+                this._mediaWatchers = null;
+            }
+        }
+
+        // #if live
+        connectLive() {
+            if (this._liveSocket || !this.isConnected) {
+                return;
+            }
+            const url = this._impl && this._impl.live_url_impl(this);
+            if (!url) {
+                return;
+            }
+            const socket = new WebSocket(url);
+            this._liveSocket = socket;
+            socket.onopen = () => {
+                this._liveRetries = 0;
+                this.callImpl((impl) => impl.live_opened_impl(this));
+            };
+            socket.onmessage = (evt) => {
+                if (typeof evt.data === "string") {
+                    this.callImpl((impl) => impl.live_message_impl(this, evt.data));
+                }
+            };
+            socket.onclose = () => {
+                if (this._liveSocket !== socket) {
+                    return;
+                }
+                this._liveSocket = null;
+                this.callImpl((impl) => impl.live_closed_impl(this));
+                const retries = this._liveRetries || 0;
+                this._liveRetries = retries + 1;
+                this._liveTimer = setTimeout(() => {
+                    this._liveTimer = null;
+                    this.connectLive();
+                }, Math.min(30000, 500 * Math.pow(2, retries)));
+            };
+        }
+
+        disconnectLive() {
+            if (this._liveTimer) {
+                clearTimeout(this._liveTimer);
+                this._liveTimer = null;
+            }
+            const socket = this._liveSocket;
+            if (socket) {
+                this._liveSocket = null;
+                socket.close();
+                this.callImpl((impl) => impl.live_closed_impl(this));
+            }
+            this._liveRetries = 0;
+        }
+
+        liveSend(data) {
+            if (this._liveSocket && this._liveSocket.readyState === WebSocket.OPEN) {
+                this._liveSocket.send(data);
+                return true;
+            }
+            return false;
+        }
+        // #endif
+
+        // #if data_src
+        loadDataSrc() {
+            this.abortDataSrc();
+            const src = this.getAttribute("data-src");
+            if (!src || !this.isConnected) {
+                return;
+            }
+            const controller = new AbortController();
+            this._dataAbort = controller;
+            fetch(src, { signal: controller.signal })
+                .then((response) => response.ok
+                    ? response.text()
+                    : Promise.reject(new Error(response.status + " " + response.statusText)))
+                .then((text) => {
+                    if (this._dataAbort === controller) {
+                        this._dataAbort = null;
+                        this.callImpl((impl) => impl.data_loaded_impl(this, text));
+                    }
+                }, (error) => {
+                    if (this._dataAbort === controller) {
+                        this._dataAbort = null;
+                        this.callImpl((impl) => impl.data_error_impl(this, error));
+                    }
+                });
+        }
+
+        abortDataSrc() {
+            if (this._dataAbort) {
+                this._dataAbort.abort();
+                this._dataAbort = null;
+            }
+        }
+        // #endif
+
+        beforeRemove() {
+            return this._impl ? this._impl.before_remove_impl(this) : undefined;
+        }
+
+        resetCallback() {
+            // Pooled elements get reused so changes to them while disconnected are expected.
+            if (this._detachedObserver) {
+                this._detachedObserver.disconnect();
+            }
+            this.callImpl((impl) => impl.reset_impl(this));
+        }
+
+        invalidateCallback() {
+            this.callImpl((impl) => impl.invalidated_impl(this));
+        }
+
+        focusChangedCallback(hasFocus) {
+            if (hasFocus === this._hasFocus) {
+                return;
+            }
+            this._hasFocus = hasFocus;
+            this.callImpl((impl) => impl.focus_changed_impl(this, hasFocus));
+        }
+
+        broadcastCallback(id) {
+            this.callImpl((impl) => impl.broadcast_impl(this, id));
+        }
+
+        // Commits compare against the value of the last commit, everything else against
+        // the last value seen so typing and then committing reports both steps.
+        valueChangedCallback(source) {
+            const value = this.value;
+            const oldValue = source === "commit" ? this._committedValue : this._lastValue;
+            this._lastValue = value;
+            if (source !== "input") {
+                this._committedValue = value;
+            }
+            this._valueDirty = true;
+            if (value !== oldValue) {
+                this.callImpl((impl) => impl.value_changed_impl(this, oldValue, value, source));
+            }
+        }
+
+        snapshotState() {
+            return this._impl && this._impl.snapshot_impl ? this._impl.snapshot_impl() : undefined;
+        }
+
+        restoreState(state) {
+            if (this._impl && this._impl.restore_impl) {
+                this.callImpl((impl) => impl.restore_impl(this, state));
+            }
+        }
+
+        dispose() {
+            if (this._impl) {
+                if (!this._config.stateless) {
+                    if (implRegistry) {
+                        implRegistry.unregister(this);
+                    }
+                    // An impl that is handling a call gets freed once the call returns.
+                    if (this._impl._dispatching) {
+                        this._impl._freeAfterDispatch = true;
+                    } else {
+                        this._impl.free();
+                    }
+                }
+                this._impl = null;
+            }
+        }
+
+        static get observedAttributes() {
+            const config = this.config;
+            return config.observedAttributes.concat(
+                Object.keys(config.aliases),
+                config.persistedAttributes,
+                config.urlAttributes,
+                config.observedDataset,
+                config.dataSrc ? ["data-src"] : []
+            );
+        }
+
+        static get formAssociated() {
+            return this.config.formAssociated;
+        }
+
+        static get labelTarget() {
+            return this.config.labelTarget;
+        }
+
+        focusLabelTarget() {
+            const target = (this.shadowRoot || this).querySelector(this.constructor.labelTarget);
+            (target || this).focus();
+        }
+
+        runValidation() {
+            this.callImpl((impl) => impl.validate_impl(this));
+        }
+
+        formAssociatedCallback(form) {
+            if (this._validationForm) {
+                this._validationForm.removeEventListener("click", this._onSubmitAttempt, true);
+                this._validationForm.removeEventListener("keydown", this._onSubmitAttempt, true);
+            }
+            this._validationForm = form;
+            if (form) {
+                // Capturing runs before the browser checks the form's validity.
+                form.addEventListener("click", this._onSubmitAttempt, true);
+                form.addEventListener("keydown", this._onSubmitAttempt, true);
+            }
+        }
+
+        formResetCallback() {
+            this.runValidation();
+        }
+
+        static get disabledFeatures() {
+            return this.config.disabledFeatures;
+        }
+
+        static get deferInit() {
+            let polyfilled = false;
+            // #if polyfill
+            polyfilled = !!(window.customElements && window.customElements.polyfillWrapFlushCallback);
+            // #endif
+            return polyfilled;
+        }
+
+        runDeferredInit() {
+            if (!this._deferredInit) {
+                return;
+            }
+            this._deferredInit = false;
+            this.callImpl((impl) => impl.init_impl(this));
+            if (this._config.listenShadow && this.shadowRoot) {
+                this.listenOn(this.shadowRoot);
+            }
+        }
+
+        static get persistedAttributes() {
+            return this.config.persistedAttributes;
+        }
+
+        persistStorage() {
+            // Accessing web storage throws when it has been disabled.
+            try {
+                return window[this._config.persistStorage];
+            } catch (e) {
+                return null;
+            }
+        }
+
+        persistKey(name) {
+            return this._config.elementName + ":" + (this.getAttribute("persist-key") || "") + ":" + name;
+        }
+
+        locale() {
+            for (let node = this; node; node = node.getRootNode().host) {
+                const owner = node.closest("[lang]");
+                const lang = owner && owner.getAttribute("lang");
+                if (lang) {
+                    return lang;
+                }
+            }
+            return navigator.language;
+        }
+
+        checkLocale(force) {
+            const locale = this.locale();
+            if (force || locale !== this._locale) {
+                this._locale = locale;
+                this.callImpl((impl) => impl.locale_changed_impl(this, locale));
+            }
+        }
+
+        // #if persist_idb
+        static idbDatabase() {
+            // The database is shared by every component on the page.
+            if (!window.__wasmWebComponentIdb) {
+                window.__wasmWebComponentIdb = new Promise((resolve, reject) => {
+                    const request = indexedDB.open("wasm-web-component", 1);
+                    request.onupgradeneeded = () => request.result.createObjectStore("state");
+                    request.onsuccess = () => resolve(request.result);
+                    request.onerror = () => reject(request.error);
+                });
+            }
+            return window.__wasmWebComponentIdb;
+        }
+
+        idbKey() {
+            return this._config.elementName + ":" + this._config.persistIdb + ":" + (this.getAttribute("persist-key") || "");
+        }
+
+        async idbRequest(mode, request) {
+            const db = await this.constructor.idbDatabase();
+            return new Promise((resolve, reject) => {
+                const pending = request(db.transaction("state", mode).objectStore("state"));
+                pending.onsuccess = () => resolve(pending.result);
+                pending.onerror = () => reject(pending.error);
+            });
+        }
+
+        async loadIdbState() {
+            try {
+                const stored = await this.idbRequest("readonly", (store) => store.get(this.idbKey()));
+                if (typeof stored === "string") {
+                    this.callImpl((impl) => impl.idb_load_impl(this, stored));
+                }
+                // Saving before the state has been loaded would overwrite it.
+                this._idbLoaded = true;
+            } catch (e) {
+                // Loads again the next time the element is connected.
+                this._idbLoading = null;
+                this.callImpl((impl) => impl.idb_error_impl(this, e));
+            }
+        }
+
+        async saveIdbState() {
+            if (this._config.persistIdb === null) {
+                throw new Error("[" + this._config.elementName + "] doesn't persist its state");
+            }
+            if (!this._idbLoaded || !this._impl) {
+                return;
+            }
+            // Saving from inside a callback has to wait for the impl to be done with it.
+            const stored = await new Promise((resolve, reject) => {
+                this.callImpl((impl) => {
+                    try {
+                        resolve(impl.idb_save_impl(this));
+                    } catch (e) {
+                        reject(e);
+                    }
+                });
+            });
+            try {
+                await this.idbRequest("readwrite", (store) => store.put(stored, this.idbKey()));
+            } catch (e) {
+                console.warn("[" + this._config.elementName + "] Saving the state to IndexedDB failed.", e);
+            }
+        }
+
+        async clearIdbState() {
+            if (this._config.persistIdb === null) {
+                throw new Error("[" + this._config.elementName + "] doesn't persist its state");
+            }
+            await this.idbRequest("readwrite", (store) => store.delete(this.idbKey()));
+        }
+        // #endif
+
+        static get urlAttributes() {
+            return this.config.urlAttributes;
+        }
+
+        readUrlAttributes() {
+            const params = new URLSearchParams(window.location.search);
+            this._readingUrl = true;
+            try {
+                for (const name of this.constructor.urlAttributes) {
+                    const value = params.get(name);
+                    if (value === null) {
+                        this.removeAttribute(name);
+                    } else if (this.getAttribute(name) !== value) {
+                        this.setAttribute(name, value);
+                    }
+                }
+            } finally {
+                this._readingUrl = false;
+            }
+        }
+
+        writeUrlAttribute(name, value) {
+            const url = new URL(window.location.href);
+            if (value === null) {
+                url.searchParams.delete(name);
+            } else {
+                url.searchParams.set(name, value);
+            }
+            if (url.href !== window.location.href) {
+                window.history.replaceState(window.history.state, "", url.href);
+            }
+        }
+
+        restorePersistedAttributes() {
+            const storage = this.persistStorage();
+            if (!storage) {
+                return;
+            }
+            for (const name of this.constructor.persistedAttributes) {
+                const value = storage.getItem(this.persistKey(name));
+                if (value !== null && this.getAttribute(name) !== value) {
+                    this.setAttribute(name, value);
+                }
+            }
+        }
+
+        observedEvents() {
+            return this._config.observedEvents;
+        }
+
+        listenFor(type, options) {
+            this.unlistenFor(type);
+            const listener = (evt) => {
+                if (this._handledEvents) {
+                    if (this._handledEvents.has(evt)) {
+                        return;
+                    }
+                    this._handledEvents.add(evt);
+                }
+                this.handleComponentEvent(evt);
+            };
+            this.addEventListener(type, listener, options);
+            if (this._runtimeListeners === undefined) {
+                this._runtimeListeners = new Map();
+            }
+            this._runtimeListeners.set(type, { listener, options });
+        }
+
+        unlistenFor(type) {
+            const entry = this._runtimeListeners && this._runtimeListeners.get(type);
+            if (entry) {
+                this.removeEventListener(type, entry.listener, entry.options);
+                this._runtimeListeners.delete(type);
+            }
+        }
+
+        listenOn(root) {
+            if (root === this ? !this._config.listenHost : !this._config.listenShadow) {
+                return;
+            }
+            if (this._config.listenAuto && this._listenedRoots !== undefined) {
+                // Composed events from a shadow root attached later still reach the host.
+                return;
+            }
+            if (this._listenedRoots === undefined) {
+                this._listenedRoots = new WeakSet();
+                this._handledEvents = new WeakSet();
+            }
+            if (this._listenedRoots.has(root)) {
+                return;
+            }
+            this._listenedRoots.add(root);
+            var self = this;
+            for (const t of this.observedEvents()) {
+                root.addEventListener(t, function(evt) {
+                    // Events from the shadow root reach both the shadow root and the host.
+                    if (self._handledEvents.has(evt)) {
+                        return;
+                    }
+                    self._handledEvents.add(evt);
+                    self.handleComponentEvent(evt);
+                });
+            }
+        }
+
+        adoptedCallback() {
+            this.callImpl((impl) => impl.adopted_impl(this));
+        }
+
+        attributeChangedCallback(name, oldValue, newValue) {
+            this.runDeferredInit();
+            // Only the map's own keys, observed names like `constructor` aren't aliases.
+            const aliases = this._config.aliases;
+            const aliased = Object.prototype.hasOwnProperty.call(aliases, name) ? aliases[name] : undefined;
+            if (aliased !== undefined) {
+                // A deprecated name of the attribute. Writing through to the current name
+                // delivers the change under that name.
+                // #if diagnostics
+                if (this._config.diagnostics) {
+                    console.warn("[" + this._config.elementName + "] The " + name + " attribute is deprecated. Use " + aliased + " instead.", this);
+                }
+                // #endif
+                if (newValue === null) {
+                    this.removeAttribute(aliased);
+                } else if (this.getAttribute(aliased) !== newValue) {
+                    this.setAttribute(aliased, newValue);
+                }
+                return;
+            }
+            if (this._persistRestored && this.constructor.persistedAttributes.includes(name)) {
+                const storage = this.persistStorage();
+                if (storage && newValue === null) {
+                    storage.removeItem(this.persistKey(name));
+                } else if (storage) {
+                    storage.setItem(this.persistKey(name), newValue);
+                }
+            }
+            if (!this._readingUrl && this.isConnected && this.constructor.urlAttributes.includes(name)) {
+                this.writeUrlAttribute(name, newValue);
+            }
+            if (this._reflectingAttribute === name) {
+                // The component reflected the attribute itself with reflect_attribute.
+            } else if (this._attributeTransaction) {
+                // element_attrs_txn delivers the net changes in attributesChangedCallback.
+                this._attributeTransaction.push([name, oldValue, newValue]);
+            } else if (this._config.viewTransition && this.isConnected && document.startViewTransition && !prefersReducedMotion()) {
+                // The component sees the change before anything reacts to it, same as
+                // without the transition.
+                inViewTransition(() => {
+                    this.callAttributeChanged(name, oldValue, newValue);
+                    this.afterAttributeChanged(name, oldValue, newValue);
+                });
+                return;
+            } else {
+                this.callAttributeChanged(name, oldValue, newValue);
+            }
+            this.afterAttributeChanged(name, oldValue, newValue);
+        }
+
+        afterAttributeChanged(name, oldValue, newValue) {
+            // #if data_src
+            if (this._config.dataSrc && name === "data-src" && this.isConnected && oldValue !== newValue) {
+                this.loadDataSrc();
+            }
+            // #endif
+        }
+
+        attributesChangedCallback(changes) {
+            this.callImpl((impl) => impl.attributes_changed_impl(this, changes));
+        }
+
+        callAttributeChanged(name, oldValue, newValue) {
+            // #if diagnostics
+            if (this._config.diagnostics && this._inAttributeChanged) {
+                console.warn("[" + this._config.elementName + "] The " + name + " attribute was changed from inside of attribute_changed which can loop.", this);
+            }
+            // #endif
+            const outer = this._inAttributeChanged;
+            this._inAttributeChanged = true;
+            try {
+                this.callImpl((impl) => impl.attribute_changed_impl(this, name, oldValue, newValue));
+            } finally {
+                this._inAttributeChanged = outer;
+            }
+        }
+
+        handleComponentEvent(evt) {
+            // Both are gone once the dispatch is over so capture them for deferred calls.
+            const currentTarget = evt.currentTarget;
+            const path = evt.composedPath();
+            this.callImpl((impl) => impl.handle_component_event_impl(this, evt, currentTarget, path));
+        }
+
+        // Calls into the Rust impl while it is already handling a call, e.g. because it
+        // set an observed attribute, would find it borrowed so they are deferred until
+        // the outer call returns.
+        callImpl(call) {
+            const impl = this._impl;
+            if (!impl) {
+                return;
+            }
+            if (impl._dispatching) {
+                impl._pending.push(() => {
+                    if (this._impl === impl) {
+                        call(impl);
+                    }
+                });
+                return;
+            }
+            impl._dispatching = true;
+            impl._pending = [];
+            try {
+                call(impl);
+                while (impl._pending.length) {
+                    impl._pending.shift()();
+                }
+            } finally {
+                impl._dispatching = false;
+                impl._pending = [];
+                if (impl._freeAfterDispatch) {
+                    impl.free();
+                }
+            }
+        }
+    };
+}
+
+export function defineComponent(config) {
+    const Base = config.baseClass.split(".").reduce((scope, name) => scope[name], window);
+    if (!componentBases.has(Base)) {
+        componentBases.set(Base, componentBase(Base));
+    }
+    const events = config.observedEvents.length > 0;
+    config.listenHost = events && config.listenOn !== "shadow";
+    config.listenShadow = events && config.listenOn !== "host";
+    config.listenAuto = config.listenOn === "auto";
+    const Component = class extends componentBases.get(Base) {};
+    Object.defineProperty(Component, "name", { value: config.className });
+    Component.config = config;
+    Component.wasmWebComponent = { version: config.crateVersion, className: config.className };
+    const options = config.extends ? { extends: config.extends } : {};
+    customElements.define(config.elementName, Component, options);
+    if (config.alias && !customElements.get(config.alias)) {
+        customElements.define(config.alias, class extends Component {}, options);
+    }
+    return customElements.get(config.elementName);
+}
@@ -0,0 +1,30 @@
+//! A small component to measure the size of the generated wasm with. See the `size`
+//! target of the Makefile.
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_web_component::{web_component, WebComponentBinding};
+use web_sys::HtmlElement;
+
+#[web_component(
+    class_name = "SizeCounter",
+    observed_events = "['click']",
+    mutability = "mut"
+)]
+pub struct SizeCounter {
+    count: u32,
+}
+
+impl WebComponentBinding for SizeCounter {
+    fn connected_mut(&mut self, element: &HtmlElement) {
+        element.set_text_content(Some("0"));
+    }
+
+    fn handle_event_mut(&mut self, element: &HtmlElement, _event: &web_sys::Event) {
+        self.count += 1;
+        element.set_text_content(Some(&self.count.to_string()));
+    }
+}
+
+#[wasm_bindgen(start)]
+pub fn start() {
+    SizeCounter::define_once();
+}
@@ -0,0 +1,89 @@
+//! Enter and exit animations for web components.
+//!
+//! The platform removes elements synchronously so exit animations need the element
+//! to be removed through [WebComponentDef::remove](crate::WebComponentDef::remove)
+//! instead of `Element::remove`. The element gets the `data-leaving` attribute, the
+//! [before_remove](crate::WebComponentBinding::before_remove) hook runs and the
+//! element is only removed once the promise the hook returned and any animations or
+//! CSS transitions running on the element and its descendants have finished.
+//!
+//! ```css
+//! my-element {
+//!     transition: opacity 200ms;
+//! }
+//! my-element[data-leaving] {
+//!     opacity: 0;
+//! }
+//! ```
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+const LEAVING: &str = "data-leaving";
+
+#[wasm_bindgen(inline_js = "export function removeAfterAnimations(el) {
+    const hook = el.beforeRemove ? el.beforeRemove() : undefined;
+    return Promise.resolve(hook).then(function() {
+        const animations = el.getAnimations ? el.getAnimations({ subtree: true }) : [];
+        return Promise.all(animations
+            .filter(function(a) {
+                const timing = a.effect && a.effect.getComputedTiming();
+                return !timing || timing.endTime !== Infinity;
+            })
+            .map(function(a) { return a.finished.catch(function() {}); }));
+    }).then(function() {
+        el.remove();
+        el.removeAttribute(\"data-leaving\");
+    });
+}")]
+extern "C" {
+    #[wasm_bindgen(js_name = removeAfterAnimations, catch)]
+    fn remove_after_animations(element: &Element) -> Result<Promise, JsValue>;
+}
+
+/// Removes the element after running its exit animations. The returned promise
+/// resolves once the element has been removed.
+pub fn remove_animated(element: &Element) -> Promise {
+    if let Err(e) = element.set_attribute(LEAVING, "") {
+        return Promise::reject(&e);
+    }
+    match remove_after_animations(element) {
+        Ok(promise) => promise,
+        Err(e) => Promise::reject(&e),
+    }
+}
+
+/// Starts a Web Animations API animation on the element and returns a promise
+/// that resolves when it has finished. The keyframes and options are the same as
+/// for javascript's `Element.animate`.
+pub fn animate(
+    element: &Element,
+    keyframes: &JsValue,
+    options: &JsValue,
+) -> Result<Promise, JsValue> {
+    let animate: Function = Reflect::get(element, &JsValue::from_str("animate"))?.dyn_into()?;
+    let animation = animate.call2(element, keyframes, options)?;
+    Ok(Reflect::get(&animation, &JsValue::from_str("finished"))?.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_remove_animated_marks_element_leaving() {
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let element = document.create_element("div").unwrap();
+        body.append_child(&element).unwrap();
+        let _removed = remove_animated(&element);
+        // Removal is deferred until after the exit animations.
+        assert!(element.has_attribute(LEAVING));
+        assert!(element.is_connected());
+    }
+}
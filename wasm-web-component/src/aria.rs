@@ -0,0 +1,63 @@
+//! Default ARIA semantics through `ElementInternals`.
+//!
+//! Semantics set on the element internals act as defaults. Unlike host attributes
+//! they can't be clobbered by authors, and any `role` or `aria-*` attribute an author
+//! sets still takes precedence. Default semantics can be declared with the `role` and
+//! `aria` parameters of the [`#[web_component]`](crate::web_component) attribute or
+//! set at runtime through [Aria].
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlElement;
+
+/// Returns the `ElementInternals` of one of our web component elements if the browser
+/// supports them.
+pub(crate) fn internals(element: &HtmlElement) -> Option<JsValue> {
+    let internals = Reflect::get(element, &JsValue::from_str("internals"))
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?
+        .call0(element)
+        .ok()?;
+    if internals.is_null() || internals.is_undefined() {
+        return None;
+    }
+    Some(internals)
+}
+
+/// The ARIA mixin of a web component element's internals.
+pub struct Aria {
+    internals: JsValue,
+}
+
+impl Aria {
+    /// Returns the ARIA mixin of the web component element. Returns None if the
+    /// element isn't one of our web components or the browser doesn't support
+    /// `ElementInternals`.
+    pub fn of(element: &HtmlElement) -> Option<Self> {
+        internals(element).map(|internals| Self { internals })
+    }
+
+    /// Returns the default role.
+    pub fn role(&self) -> Option<String> {
+        self.get("role")
+    }
+
+    /// Sets or clears the default role.
+    pub fn set_role(&self, role: Option<&str>) -> Result<(), JsValue> {
+        self.set("role", role)
+    }
+
+    /// Returns an ARIA property like `ariaLabel` or `ariaExpanded`.
+    pub fn get(&self, property: &str) -> Option<String> {
+        Reflect::get(&self.internals, &JsValue::from_str(property))
+            .ok()
+            .and_then(|v| v.as_string())
+    }
+
+    /// Sets or clears an ARIA property like `ariaLabel` or `ariaExpanded`.
+    pub fn set(&self, property: &str, value: Option<&str>) -> Result<(), JsValue> {
+        let value = value.map(JsValue::from_str).unwrap_or(JsValue::NULL);
+        Reflect::set(&self.internals, &JsValue::from_str(property), &value)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+//! Helpers for the attributes of web component elements.
+//!
+//! [AttributeValue] converts between attribute values and Rust types following the
+//! HTML conventions, most importantly for boolean attributes like `disabled` and `open`
+//! which are true when present and false when absent. Setting them to `"false"` still
+//! makes them true.
+//!
+//! [element_attrs_txn] changes several attributes at once. The element's
+//! `attribute_changed` callbacks aren't called for the intermediate changes, instead the
+//! component gets the net changes in one `attributes_changed` callback at the end.
+//!
+//! ```ignore
+//! element_attrs_txn(&element, |txn| {
+//!     txn.set("min", "10");
+//!     txn.set("max", "20");
+//!     txn.remove("value");
+//! })?;
+//! ```
+use js_sys::{Array, Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+/// Reflects a property of the component to an attribute of its element. `None` removes
+/// the attribute. Nothing is written if the attribute already has the value.
+///
+/// The element's `attribute_changed` callbacks aren't called for the change, so
+/// reflecting a property from inside of `attribute_changed` doesn't loop. Persisted and
+/// URL attributes are still written through. Returns whether the attribute changed.
+pub fn reflect_attribute(
+    element: &Element,
+    name: &str,
+    value: Option<&str>,
+) -> Result<bool, JsValue> {
+    if element.get_attribute(name).as_deref() == value {
+        return Ok(false);
+    }
+    write_silently(element, name, value).map(|_| true)
+}
+
+/// Sets the attribute without calling the element's `attribute_changed` callbacks for
+/// the change, e.g. when a component writes its own state to an attribute. Unlike
+/// [reflect_attribute] the attribute is written even if it already has the value so
+/// mutation observers still see the write.
+pub fn set_attribute_silent(element: &Element, name: &str, value: &str) -> Result<(), JsValue> {
+    write_silently(element, name, Some(value))
+}
+
+fn write_silently(element: &Element, name: &str, value: Option<&str>) -> Result<(), JsValue> {
+    // attributeChangedCallback runs synchronously inside of setAttribute and checks
+    // this to skip calling into the component.
+    let key = JsValue::from_str("_reflectingAttribute");
+    let outer = Reflect::get(element, &key)?;
+    Reflect::set(element, &key, &JsValue::from_str(name))?;
+    let result = match value {
+        Some(value) => element.set_attribute(name, value),
+        None => element.remove_attribute(name),
+    };
+    Reflect::set(element, &key, &outer)?;
+    result
+}
+
+/// The net change of an observed attribute in an attribute transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeChange {
+    pub name: String,
+    /// The value before the transaction, None if the attribute was absent.
+    pub old_value: Option<String>,
+    /// The value after the transaction, None if the attribute is absent.
+    pub new_value: Option<String>,
+}
+
+fn optional_value(value: &Option<String>) -> JsValue {
+    value
+        .as_deref()
+        .map(JsValue::from_str)
+        .unwrap_or(JsValue::NULL)
+}
+
+impl AttributeChange {
+    /// The name and values as the `attribute_changed` callbacks get them.
+    pub fn to_js(&self) -> (JsValue, JsValue, JsValue) {
+        (
+            JsValue::from_str(&self.name),
+            optional_value(&self.old_value),
+            optional_value(&self.new_value),
+        )
+    }
+}
+
+/// Converts the changes passed from the element class.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn attribute_changes(changes: &JsValue) -> Vec<AttributeChange> {
+    Array::from(changes)
+        .iter()
+        .map(|change| {
+            let change = Array::from(&change);
+            AttributeChange {
+                name: change.get(0).as_string().unwrap_or_default(),
+                old_value: change.get(1).as_string(),
+                new_value: change.get(2).as_string(),
+            }
+        })
+        .collect()
+}
+
+/// The attribute changes to apply in [element_attrs_txn].
+#[derive(Debug, Default)]
+pub struct AttributeTransaction {
+    operations: Vec<(String, Option<String>)>,
+}
+
+impl AttributeTransaction {
+    /// Sets the attribute to the value.
+    pub fn set(&mut self, name: &str, value: &str) -> &mut Self {
+        self.operations
+            .push((name.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// Removes the attribute.
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.operations.push((name.to_owned(), None));
+        self
+    }
+}
+
+/// Merges the changes of every attribute into its first old value and its last new
+/// value and drops attributes that ended up with their old value.
+fn coalesce(changes: &Array) -> Vec<AttributeChange> {
+    let mut coalesced: Vec<AttributeChange> = Vec::new();
+    for change in attribute_changes(changes) {
+        match coalesced.iter_mut().find(|c| c.name == change.name) {
+            Some(existing) => existing.new_value = change.new_value,
+            None => coalesced.push(change),
+        }
+    }
+    coalesced.retain(|change| change.old_value != change.new_value);
+    coalesced
+}
+
+/// Applies the attribute changes of the transaction to the element and then calls its
+/// `attributes_changed` callback once with the net changes of its observed attributes.
+/// Transactions nested in a transaction on the same element are delivered with the
+/// outer one. If an operation fails the changes applied before it are still delivered
+/// and then the error is returned.
+pub fn element_attrs_txn<F>(element: &Element, build: F) -> Result<(), JsValue>
+where
+    F: FnOnce(&mut AttributeTransaction),
+{
+    let mut txn = AttributeTransaction::default();
+    build(&mut txn);
+    // attributeChangedCallback records the changes in this instead of calling into the
+    // component while it is set.
+    let key = JsValue::from_str("_attributeTransaction");
+    let outer = Reflect::get(element, &key)?;
+    let changes = Array::new();
+    if outer.is_undefined() || outer.is_null() {
+        Reflect::set(element, &key, &changes)?;
+    }
+    let result = txn
+        .operations
+        .iter()
+        .try_for_each(|(name, value)| match value {
+            Some(value) => element.set_attribute(name, value),
+            None => element.remove_attribute(name),
+        });
+    if !outer.is_undefined() && !outer.is_null() {
+        return result;
+    }
+    Reflect::set(element, &key, &JsValue::UNDEFINED)?;
+    // The component has to learn about the writes that happened before a failure.
+    let delivered = deliver_changes(element, &changes);
+    result.and(delivered)
+}
+
+fn deliver_changes(element: &Element, changes: &Array) -> Result<(), JsValue> {
+    let changes = coalesce(changes);
+    let callback = Reflect::get(element, &JsValue::from_str("attributesChangedCallback"))?;
+    if changes.is_empty() || !callback.is_function() {
+        return Ok(());
+    }
+    let changes: Array = changes
+        .iter()
+        .map(|change| {
+            let (name, old_value, new_value) = change.to_js();
+            Array::of3(&name, &old_value, &new_value)
+        })
+        .collect();
+    callback
+        .unchecked_into::<Function>()
+        .call1(element, &changes)?;
+    Ok(())
+}
+
+/// A value of an attribute following the HTML attribute conventions.
+///
+/// `bool` follows the boolean attribute semantics: the attribute being present means
+/// true, whatever its value, so `disabled="false"` is disabled, and false removes it.
+/// `Option<T>` is None for an absent attribute.
+pub trait AttributeValue: Sized {
+    /// Parses the value of the attribute. The value is None for an absent attribute.
+    /// Returns None if it can't be parsed.
+    fn parse_attribute(value: Option<&str>) -> Option<Self>;
+
+    /// The value to set the attribute to. None removes the attribute.
+    fn attribute_value(&self) -> Option<String>;
+}
+
+impl AttributeValue for bool {
+    fn parse_attribute(value: Option<&str>) -> Option<Self> {
+        Some(value.is_some())
+    }
+
+    fn attribute_value(&self) -> Option<String> {
+        self.then(String::new)
+    }
+}
+
+impl AttributeValue for String {
+    fn parse_attribute(value: Option<&str>) -> Option<Self> {
+        value.map(str::to_owned)
+    }
+
+    fn attribute_value(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+macro_rules! number_attribute_value {
+    ($($ty:ty),*) => {
+        $(
+            impl AttributeValue for $ty {
+                fn parse_attribute(value: Option<&str>) -> Option<Self> {
+                    value?.trim().parse().ok()
+                }
+
+                fn attribute_value(&self) -> Option<String> {
+                    Some(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+number_attribute_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl<T: AttributeValue> AttributeValue for Option<T> {
+    fn parse_attribute(value: Option<&str>) -> Option<Self> {
+        Some(value.and_then(|value| T::parse_attribute(Some(value))))
+    }
+
+    fn attribute_value(&self) -> Option<String> {
+        self.as_ref().and_then(T::attribute_value)
+    }
+}
+
+/// Returns the parsed value of the element's attribute.
+pub fn get_attr<T: AttributeValue>(element: &Element, name: &str) -> Option<T> {
+    T::parse_attribute(element.get_attribute(name).as_deref())
+}
+
+/// Sets the element's attribute to the value, or removes it if the value has none.
+pub fn set_attr<T: AttributeValue>(
+    element: &Element,
+    name: &str,
+    value: &T,
+) -> Result<(), JsValue> {
+    match value.attribute_value() {
+        Some(value) => element.set_attribute(name, &value),
+        None => element.remove_attribute(name),
+    }
+}
+
+/// Parses an attribute value passed to `attribute_changed`, which is null for an
+/// absent attribute.
+pub fn parse_attr<T: AttributeValue>(value: &JsValue) -> Option<T> {
+    T::parse_attribute(value.as_string().as_deref())
+}
+
+/// Whether the element has the boolean attribute. Boolean attributes are true when
+/// they are present, whatever their value.
+pub fn has_bool_attr(element: &Element, name: &str) -> bool {
+    element.has_attribute(name)
+}
+
+/// Adds the boolean attribute with an empty value if true and removes it if false.
+pub fn set_bool_attr(element: &Element, name: &str, value: bool) -> Result<(), JsValue> {
+    set_attr(element, name, &value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_attribute_value() {
+        assert_eq!(bool::parse_attribute(Some("false")), Some(true));
+        assert_eq!(bool::parse_attribute(Some("")), Some(true));
+        assert_eq!(bool::parse_attribute(None), Some(false));
+        assert_eq!(true.attribute_value(), Some(String::new()));
+        assert_eq!(false.attribute_value(), None);
+        assert_eq!(u32::parse_attribute(Some(" 42 ")), Some(42));
+        assert_eq!(u32::parse_attribute(Some("forty")), None);
+        assert_eq!(u32::parse_attribute(None), None);
+        assert_eq!(Option::<f64>::parse_attribute(None), Some(None));
+        assert_eq!(Option::<f64>::parse_attribute(Some("1.5")), Some(Some(1.5)));
+        assert_eq!(Some(3).attribute_value(), Some("3".to_owned()));
+        assert_eq!(None::<i32>.attribute_value(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bool_attr() {
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        set_bool_attr(&element, "open", true).unwrap();
+        assert_eq!(element.get_attribute("open").as_deref(), Some(""));
+        assert!(has_bool_attr(&element, "open"));
+        element.set_attribute("open", "false").unwrap();
+        assert_eq!(get_attr::<bool>(&element, "open"), Some(true));
+        set_bool_attr(&element, "open", false).unwrap();
+        assert!(!element.has_attribute("open"));
+        assert!(!parse_attr::<bool>(&JsValue::NULL).unwrap());
+
+        set_attr(&element, "count", &Some(3)).unwrap();
+        assert_eq!(get_attr::<u32>(&element, "count"), Some(3));
+        set_attr(&element, "count", &None::<u32>).unwrap();
+        assert!(!element.has_attribute("count"));
+    }
+}
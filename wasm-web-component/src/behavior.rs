@@ -0,0 +1,149 @@
+//! Reusable behaviors shared by web components.
+//!
+//! A [Behavior] bundles attributes, events and lifecycle hooks that would otherwise be
+//! copied into every component needing them. Components list their behaviors with
+//! `behaviors = "[Tooltip, Draggable]"` in the [`#[web_component]`](crate::web_component)
+//! attribute. The behaviors' attributes and events are observed along with the
+//! component's own and their hooks run in the listed order before the component's
+//! callbacks, except for [disconnected](Behavior::disconnected) which runs after them in
+//! reverse order.
+//!
+//! Behaviors don't have an instance per element. Per element state goes on the element
+//! itself, e.g. in an attribute, or is kept alive with [on_disconnect](crate::on_disconnect).
+//!
+//! ```ignore
+//! struct Tooltip;
+//!
+//! impl Behavior for Tooltip {
+//!     const OBSERVED_ATTRIBUTES: &'static [&'static str] = &["tooltip"];
+//!
+//!     fn attribute_changed(element: &HtmlElement, _name: &str, _old: Option<&str>, new: Option<&str>) {
+//!         element.set_title(new.unwrap_or_default());
+//!     }
+//! }
+//! ```
+use wasm_bindgen::JsValue;
+use web_sys::{Event, HtmlElement};
+
+use crate::WebComponentDef;
+
+/// Attributes, events and lifecycle hooks that can be added to any web component.
+pub trait Behavior {
+    /// The attributes the behavior observes. Changes to them are delivered to
+    /// [attribute_changed](Self::attribute_changed).
+    const OBSERVED_ATTRIBUTES: &'static [&'static str] = &[];
+
+    /// The event types the behavior observes. They are delivered to
+    /// [handle_event](Self::handle_event).
+    const OBSERVED_EVENTS: &'static [&'static str] = &[];
+
+    /// Called when the element gets connected.
+    fn connected(_element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when the element gets disconnected.
+    fn disconnected(_element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when one of the behavior's observed attributes changes.
+    fn attribute_changed(
+        _element: &HtmlElement,
+        _name: &str,
+        _old_value: Option<&str>,
+        _new_value: Option<&str>,
+    ) {
+        // noop
+    }
+
+    /// Called with events of the behavior's observed event types.
+    fn handle_event(_element: &HtmlElement, _event: &Event) {
+        // noop
+    }
+}
+
+type AttributeChanged = fn(&HtmlElement, &str, Option<&str>, Option<&str>);
+
+/// The hooks of a [Behavior] for dispatching to it without knowing its type. This is
+/// generated by the [`#[web_component]`](crate::web_component) attribute for you.
+#[doc(hidden)]
+pub struct BehaviorHooks {
+    pub observed_attributes: &'static [&'static str],
+    pub observed_events: &'static [&'static str],
+    connected: fn(&HtmlElement),
+    disconnected: fn(&HtmlElement),
+    attribute_changed: AttributeChanged,
+    handle_event: fn(&HtmlElement, &Event),
+}
+
+impl BehaviorHooks {
+    pub const fn of<B: Behavior>() -> Self {
+        Self {
+            observed_attributes: B::OBSERVED_ATTRIBUTES,
+            observed_events: B::OBSERVED_EVENTS,
+            connected: B::connected,
+            disconnected: B::disconnected,
+            attribute_changed: B::attribute_changed,
+            handle_event: B::handle_event,
+        }
+    }
+}
+
+/// The event types observed by the component's behaviors.
+#[doc(hidden)]
+pub fn behavior_events<T: WebComponentDef>() -> Vec<&'static str> {
+    T::behaviors()
+        .iter()
+        .flat_map(|behavior| behavior.observed_events.iter().copied())
+        .collect()
+}
+
+#[doc(hidden)]
+pub fn behaviors_connected<T: WebComponentDef>(element: &HtmlElement) {
+    for behavior in T::behaviors() {
+        (behavior.connected)(element);
+    }
+}
+
+#[doc(hidden)]
+pub fn behaviors_disconnected<T: WebComponentDef>(element: &HtmlElement) {
+    for behavior in T::behaviors().iter().rev() {
+        (behavior.disconnected)(element);
+    }
+}
+
+#[doc(hidden)]
+pub fn behaviors_attribute_changed<T: WebComponentDef>(
+    element: &HtmlElement,
+    name: &JsValue,
+    old_value: &JsValue,
+    new_value: &JsValue,
+) {
+    let name = match name.as_string() {
+        Some(name) => name,
+        None => return,
+    };
+    let old_value = old_value.as_string();
+    let new_value = new_value.as_string();
+    for behavior in T::behaviors() {
+        if behavior.observed_attributes.contains(&name.as_str()) {
+            (behavior.attribute_changed)(
+                element,
+                &name,
+                old_value.as_deref(),
+                new_value.as_deref(),
+            );
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn behaviors_handle_event<T: WebComponentDef>(element: &HtmlElement, event: &Event) {
+    let event_type = event.type_();
+    for behavior in T::behaviors() {
+        if behavior.observed_events.contains(&event_type.as_str()) {
+            (behavior.handle_event)(element, event);
+        }
+    }
+}
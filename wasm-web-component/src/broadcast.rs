@@ -0,0 +1,132 @@
+//! Calling Rust code on every connected instance of a component.
+//!
+//! [WebComponentDef::broadcast](crate::WebComponentDef::broadcast) calls a callback with
+//! the Rust side and the element of every connected instance of a component, e.g. to
+//! have them render again after the locale changed. The callback goes through the
+//! element's shim so an instance that is in the middle of one of its own callbacks gets
+//! it right after that callback instead of while its state is borrowed. [broadcast_all]
+//! calls a callback with every connected element of every component.
+//!
+//! Instances that get disconnected by an earlier callback of the same broadcast are
+//! skipped.
+//!
+//! ```ignore
+//! let locale = new_locale.clone();
+//! MyElement::broadcast(move |component, element| {
+//!     component.locale = locale.clone();
+//!     component.render_into(element);
+//! })?;
+//! ```
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlElement;
+
+use crate::instances::{connected_element_names, Instances};
+use crate::WebComponentDef;
+
+type Callback<T> = RefCell<Box<dyn FnMut(&mut T, &HtmlElement)>>;
+
+/// A broadcast callback and how many of its calls haven't run yet, plus one while the
+/// broadcast is still calling instances.
+struct Broadcast {
+    callback: Rc<dyn Any>,
+    pending: usize,
+}
+
+thread_local! {
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+    static BROADCASTS: RefCell<HashMap<u32, Broadcast>> = RefCell::new(HashMap::new());
+}
+
+/// Counts one call of the broadcast as done and returns its callback. The callback is
+/// dropped after the last call.
+fn release(id: u32) -> Option<Rc<dyn Any>> {
+    BROADCASTS.with(|broadcasts| {
+        let mut broadcasts = broadcasts.borrow_mut();
+        let broadcast = broadcasts.get_mut(&id)?;
+        broadcast.pending -= 1;
+        let callback = broadcast.callback.clone();
+        if broadcast.pending == 0 {
+            broadcasts.remove(&id);
+        }
+        Some(callback)
+    })
+}
+
+fn call_broadcast(element: &HtmlElement, id: u32) -> Result<(), JsValue> {
+    let method: Function =
+        Reflect::get(element, &JsValue::from_str("broadcastCallback"))?.dyn_into()?;
+    method.call1(element, &JsValue::from(id))?;
+    Ok(())
+}
+
+/// Calls the callback with every connected instance of the component and returns how
+/// many instances there were.
+pub(crate) fn broadcast<T, F>(callback: F) -> Result<usize, JsValue>
+where
+    T: WebComponentDef + 'static,
+    F: FnMut(&mut T, &HtmlElement) + 'static,
+{
+    let id = NEXT_ID.with(|next| next.replace(next.get().wrapping_add(1)));
+    let callback: Rc<Callback<T>> = Rc::new(RefCell::new(Box::new(callback)));
+    BROADCASTS.with(|broadcasts| {
+        broadcasts.borrow_mut().insert(
+            id,
+            Broadcast {
+                callback,
+                pending: 1,
+            },
+        )
+    });
+    let mut count = 0;
+    let mut result = Ok(());
+    for element in T::instances() {
+        BROADCASTS.with(|broadcasts| {
+            if let Some(broadcast) = broadcasts.borrow_mut().get_mut(&id) {
+                broadcast.pending += 1;
+            }
+        });
+        count += 1;
+        result = call_broadcast(&element, id);
+        if result.is_err() {
+            break;
+        }
+    }
+    // Calls that got deferred because the instance was busy finish the broadcast later.
+    release(id);
+    result.map(|_| count)
+}
+
+/// Runs the broadcast callback with the component.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn run_broadcast<T: 'static>(component: &mut T, element: &HtmlElement, id: u32) {
+    let broadcast = match release(id).and_then(|callback| callback.downcast::<Callback<T>>().ok()) {
+        Some(broadcast) => broadcast,
+        None => return,
+    };
+    let mut callback = match broadcast.try_borrow_mut() {
+        Ok(callback) => callback,
+        Err(_) => return,
+    };
+    callback(component, element);
+}
+
+/// Calls the callback with the element name and the element of every connected
+/// instance of every component and returns how many there were.
+pub fn broadcast_all<F: FnMut(&'static str, &HtmlElement)>(mut callback: F) -> usize {
+    let mut count = 0;
+    for element_name in connected_element_names() {
+        for element in Instances::new(element_name) {
+            callback(element_name, &element);
+            count += 1;
+        }
+    }
+    count
+}
@@ -0,0 +1,84 @@
+use wasm_bindgen::JsValue;
+use web_sys::{Element, Node};
+
+use crate::attributes::{set_attr, set_bool_attr, AttributeValue};
+
+/// A fluent builder for configuring a new instance of a custom element.
+///
+/// Any error encountered along the way is held onto and returned from
+/// [ElementBuilder::build] or [ElementBuilder::mount]. Subsequent steps after
+/// an error are skipped.
+pub struct ElementBuilder {
+    element: Result<Element, JsValue>,
+}
+
+impl ElementBuilder {
+    /// Starts a builder for the already created element.
+    pub fn new(element: Element) -> Self {
+        Self {
+            element: Ok(element),
+        }
+    }
+
+    fn with<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&Element) -> Result<(), JsValue>,
+    {
+        if let Ok(element) = &self.element {
+            if let Err(e) = f(element) {
+                self.element = Err(e);
+            }
+        }
+        self
+    }
+
+    /// Sets an attribute on the element.
+    pub fn attr(self, name: &str, value: &str) -> Self {
+        self.with(|el| el.set_attribute(name, value))
+    }
+
+    /// Adds the boolean attribute if true. See [set_bool_attr].
+    pub fn bool_attr(self, name: &str, value: bool) -> Self {
+        self.with(|el| set_bool_attr(el, name, value))
+    }
+
+    /// Sets an attribute to a typed value, or leaves it out if the value has none. See
+    /// [AttributeValue].
+    pub fn typed_attr<T: AttributeValue>(self, name: &str, value: &T) -> Self {
+        self.with(|el| set_attr(el, name, value))
+    }
+
+    /// Sets a javascript property on the element.
+    pub fn prop<V: Into<JsValue>>(self, name: &str, value: V) -> Self {
+        let value = value.into();
+        self.with(|el| js_sys::Reflect::set(el, &JsValue::from_str(name), &value).map(|_| ()))
+    }
+
+    /// Appends a child node to the element.
+    pub fn child<N: AsRef<Node>>(self, node: N) -> Self {
+        self.with(|el| el.append_child(node.as_ref()).map(|_| ()))
+    }
+
+    /// Appends a text node to the element.
+    pub fn text(self, text: &str) -> Self {
+        self.with(|el| {
+            let node = el
+                .owner_document()
+                .ok_or_else(|| JsValue::from_str("Element has no owner document"))?
+                .create_text_node(text);
+            el.append_child(&node).map(|_| ())
+        })
+    }
+
+    /// Returns the configured element.
+    pub fn build(self) -> Result<Element, JsValue> {
+        self.element
+    }
+
+    /// Appends the configured element to the parent and returns it.
+    pub fn mount<N: AsRef<Node>>(self, parent: N) -> Result<Element, JsValue> {
+        let element = self.element?;
+        parent.as_ref().append_child(&element)?;
+        Ok(element)
+    }
+}
@@ -0,0 +1,155 @@
+//! A global typed event bus for communication between components.
+//!
+//! Events are dispatched as `CustomEvent`s on the document with the payload
+//! serialized as JSON in the event detail so non-Rust code can participate too.
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomEvent, CustomEventInit, Element, Event, EventTarget};
+
+use crate::cleanup::on_disconnect;
+
+/// An event that can be published on the [EventBus].
+pub trait BusEvent: Serialize + DeserializeOwned + 'static {
+    /// The event type used for the dispatched `CustomEvent`.
+    const NAME: &'static str;
+}
+
+/// Publishes and subscribes to [BusEvent]s.
+pub struct EventBus {
+    target: EventTarget,
+}
+
+/// Returns the event bus for the current document.
+pub fn bus() -> EventBus {
+    let document = web_sys::window()
+        .expect("Failed to get window")
+        .document()
+        .expect("Failed to get document");
+    EventBus {
+        target: document.into(),
+    }
+}
+
+impl EventBus {
+    /// Publishes an event to every subscriber.
+    pub fn publish<E: BusEvent>(&self, payload: &E) -> Result<(), JsValue> {
+        let detail =
+            serde_json::to_string(payload).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let init = CustomEventInit::new();
+        init.set_detail(&JsValue::from_str(&detail));
+        let event = CustomEvent::new_with_event_init_dict(E::NAME, &init)?;
+        self.target.dispatch_event(&event)?;
+        Ok(())
+    }
+
+    /// Subscribes to an event. The subscription lasts until the returned
+    /// [Subscription] is dropped.
+    pub fn subscribe<E, F>(&self, mut callback: F) -> Result<Subscription<E>, JsValue>
+    where
+        E: BusEvent,
+        F: FnMut(E) + 'static,
+    {
+        let listener = Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+            let payload = event
+                .dyn_ref::<CustomEvent>()
+                .and_then(|e| e.detail().as_string())
+                .and_then(|detail| serde_json::from_str(&detail).ok());
+            if let Some(payload) = payload {
+                callback(payload);
+            }
+        });
+        self.target
+            .add_event_listener_with_callback(E::NAME, listener.as_ref().unchecked_ref())?;
+        Ok(Subscription {
+            target: self.target.clone(),
+            listener,
+            _event: PhantomData,
+        })
+    }
+
+    /// Subscribes to an event until the web component element gets disconnected.
+    pub fn subscribe_while_connected<E, F>(
+        &self,
+        element: &Element,
+        callback: F,
+    ) -> Result<(), JsValue>
+    where
+        E: BusEvent,
+        F: FnMut(E) + 'static,
+    {
+        let subscription = self.subscribe(callback)?;
+        on_disconnect(element, move || drop(subscription));
+        Ok(())
+    }
+}
+
+/// A subscription to a [BusEvent]. Dropping it unsubscribes.
+pub struct Subscription<E: BusEvent> {
+    target: EventTarget,
+    listener: Closure<dyn FnMut(Event)>,
+    _event: PhantomData<E>,
+}
+
+impl<E: BusEvent> Drop for Subscription<E> {
+    fn drop(&mut self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(E::NAME, self.listener.as_ref().unchecked_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use serde::Deserialize;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct LoginChanged {
+        user: String,
+    }
+
+    impl BusEvent for LoginChanged {
+        const NAME: &'static str = "login-changed";
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bus_publish_and_subscribe() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let subscription = {
+            let seen = seen.clone();
+            bus()
+                .subscribe(move |event: LoginChanged| seen.borrow_mut().push(event))
+                .unwrap()
+        };
+        bus()
+            .publish(&LoginChanged {
+                user: "jwall".to_owned(),
+            })
+            .unwrap();
+        assert_eq!(
+            *seen.borrow(),
+            vec![LoginChanged {
+                user: "jwall".to_owned()
+            }]
+        );
+
+        drop(subscription);
+        bus()
+            .publish(&LoginChanged {
+                user: "nobody".to_owned(),
+            })
+            .unwrap();
+        assert_eq!(seen.borrow().len(), 1);
+    }
+}
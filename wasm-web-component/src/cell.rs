@@ -0,0 +1,214 @@
+//! Interior mutability for component state.
+//!
+//! A [ComponentCell] lets the non-mut lifecycle callbacks update state so a component
+//! doesn't have to split its logic across the `_mut` variants. Borrows name the
+//! callback they are made from and a borrow that conflicts with one still held, e.g.
+//! because setting an observed attribute inside `attribute_changed` re-entered the
+//! component, panics with a message naming the component and both callbacks.
+//!
+//! ```ignore
+//! #[web_component(class_name = "MyCounter", mutability = "ref")]
+//! pub struct MyCounterImpl {
+//!     count: ComponentCell<MyCounterImpl, u32>,
+//! }
+//!
+//! impl WebComponentBinding for MyCounterImpl {
+//!     fn handle_event(&self, element: &HtmlElement, _event: &Event) {
+//!         let mut count = self.count.borrow_mut("handle_event");
+//!         *count += 1;
+//!         element.set_text_content(Some(&count.to_string()));
+//!     }
+//! }
+//! ```
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::WebComponentDef;
+
+/// State of the component `C` that can be borrowed mutably from any callback.
+pub struct ComponentCell<C, T> {
+    value: RefCell<T>,
+    holder: Cell<Option<&'static str>>,
+    component: PhantomData<C>,
+}
+
+/// A borrow that conflicts with a borrow still held by another callback.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateBorrowError {
+    pub element_name: &'static str,
+    /// The callback that tried to borrow the state.
+    pub callback: &'static str,
+    /// The callback holding the conflicting borrow if it is known.
+    pub holder: Option<&'static str>,
+}
+
+impl fmt::Display for StateBorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} can't borrow the component state",
+            self.element_name, self.callback
+        )?;
+        if let Some(holder) = self.holder {
+            write!(f, " while {} holds a borrow of it", holder)?;
+        }
+        write!(
+            f,
+            ". This usually means the component was re-entered, e.g. by setting an observed attribute inside a callback."
+        )
+    }
+}
+
+impl std::error::Error for StateBorrowError {}
+
+/// A shared borrow of a [ComponentCell].
+pub struct ComponentRef<'a, T> {
+    value: Ref<'a, T>,
+    holder: Option<&'a Cell<Option<&'static str>>>,
+}
+
+impl<T> Deref for ComponentRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Drop for ComponentRef<'_, T> {
+    fn drop(&mut self) {
+        if let Some(holder) = self.holder {
+            holder.set(None);
+        }
+    }
+}
+
+/// A mutable borrow of a [ComponentCell].
+pub struct ComponentRefMut<'a, T> {
+    value: RefMut<'a, T>,
+    holder: &'a Cell<Option<&'static str>>,
+}
+
+impl<T> Deref for ComponentRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for ComponentRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for ComponentRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.holder.set(None);
+    }
+}
+
+impl<C, T> ComponentCell<C, T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: RefCell::new(value),
+            holder: Cell::new(None),
+            component: PhantomData,
+        }
+    }
+
+    /// Returns the state leaving the default in its place.
+    pub fn take(&mut self) -> T
+    where
+        T: Default,
+    {
+        self.value.take()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<C: WebComponentDef, T> ComponentCell<C, T> {
+    fn borrow_error(&self, callback: &'static str) -> StateBorrowError {
+        StateBorrowError {
+            element_name: C::element_name(),
+            callback,
+            holder: self.holder.get(),
+        }
+    }
+
+    /// Borrows the state from the callback.
+    pub fn try_borrow(
+        &self,
+        callback: &'static str,
+    ) -> Result<ComponentRef<'_, T>, StateBorrowError> {
+        let value = self
+            .value
+            .try_borrow()
+            .map_err(|_| self.borrow_error(callback))?;
+        // The first of several shared borrows is the one reported as the holder.
+        let holder = if self.holder.get().is_none() {
+            self.holder.set(Some(callback));
+            Some(&self.holder)
+        } else {
+            None
+        };
+        Ok(ComponentRef { value, holder })
+    }
+
+    /// Mutably borrows the state from the callback.
+    pub fn try_borrow_mut(
+        &self,
+        callback: &'static str,
+    ) -> Result<ComponentRefMut<'_, T>, StateBorrowError> {
+        let value = self
+            .value
+            .try_borrow_mut()
+            .map_err(|_| self.borrow_error(callback))?;
+        self.holder.set(Some(callback));
+        Ok(ComponentRefMut {
+            value,
+            holder: &self.holder,
+        })
+    }
+
+    /// Borrows the state from the callback.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a [StateBorrowError] if another callback mutably borrows the state.
+    pub fn borrow(&self, callback: &'static str) -> ComponentRef<'_, T> {
+        self.try_borrow(callback)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Mutably borrows the state from the callback.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a [StateBorrowError] if another callback borrows the state.
+    pub fn borrow_mut(&self, callback: &'static str) -> ComponentRefMut<'_, T> {
+        self.try_borrow_mut(callback)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<C, T: Default> Default for ComponentCell<C, T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<C, T: fmt::Debug> fmt::Debug for ComponentCell<C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentCell")
+            .field("value", &self.value)
+            .field("holder", &self.holder.get())
+            .finish()
+    }
+}
@@ -0,0 +1,60 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+type Hook = Box<dyn FnOnce()>;
+
+/// The element property holding the key of the element's hooks.
+const HOOKS_KEY: &str = "__wasmWebComponentHooks";
+
+thread_local! {
+    static DISCONNECT_HOOKS: RefCell<HashMap<u32, Vec<Hook>>> = RefCell::new(HashMap::new());
+    static NEXT_KEY: Cell<u32> = const { Cell::new(0) };
+}
+
+fn hooks_key(element: &Element) -> Option<u32> {
+    Reflect::get(element, &JsValue::from_str(HOOKS_KEY))
+        .ok()
+        .and_then(|key| key.as_f64())
+        .map(|key| key as u32)
+}
+
+/// Registers a hook that runs the next time the web component element gets
+/// disconnected from the DOM. Useful for tearing down subscriptions that
+/// should only live while the element is connected.
+pub fn on_disconnect<F: FnOnce() + 'static>(element: &Element, hook: F) {
+    let key = hooks_key(element).unwrap_or_else(|| {
+        let key = NEXT_KEY.with(|next| {
+            let key = next.get();
+            next.set(key.wrapping_add(1));
+            key
+        });
+        let _ = Reflect::set(element, &JsValue::from_str(HOOKS_KEY), &JsValue::from(key));
+        key
+    });
+    DISCONNECT_HOOKS.with(|hooks| {
+        hooks
+            .borrow_mut()
+            .entry(key)
+            .or_default()
+            .push(Box::new(hook));
+    });
+}
+
+/// Runs and removes every hook registered for the element.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn run_disconnect_hooks(element: &Element) {
+    let ready = match hooks_key(element) {
+        Some(key) => DISCONNECT_HOOKS.with(|hooks| hooks.borrow_mut().remove(&key)),
+        None => return,
+    };
+    // Run outside of the borrow so hooks can register new hooks.
+    for hook in ready.into_iter().flatten() {
+        hook();
+    }
+}
@@ -0,0 +1,26 @@
+//! Shared configuration for every instance of a component.
+//!
+//! Components that implement [FromConfig] can be defined with the generated
+//! `define_with(config)` method. The config is put in an [Rc] that every instance of
+//! the component gets a clone of when it is constructed, e.g. for API base URLs or
+//! feature flags, instead of reading them from global statics.
+//!
+//! ```ignore
+//! struct AppConfig {
+//!     api_url: String,
+//! }
+//!
+//! impl FromConfig<AppConfig> for MyElementImpl {
+//!     fn from_config(config: Rc<AppConfig>) -> Self {
+//!         Self { config }
+//!     }
+//! }
+//!
+//! MyElementImpl::define_with(AppConfig { api_url: "/api".to_owned() })?;
+//! ```
+use std::rc::Rc;
+
+/// Constructs the Rust side of a component from a config shared by every instance.
+pub trait FromConfig<C> {
+    fn from_config(config: Rc<C>) -> Self;
+}
@@ -0,0 +1,244 @@
+//! Support for the Web Components Community Group
+//! [context protocol](https://github.com/webcomponents-cg/community-protocols/blob/main/proposals/context.md).
+//!
+//! A provider answers the `context-request` events dispatched by its descendants. Since the
+//! protocol is event based it works across Rust and non-Rust components alike.
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Event, EventInit, HtmlElement};
+
+const CONTEXT_REQUEST: &str = "context-request";
+
+/// Defines a context that can be provided and requested.
+pub trait Context: 'static {
+    /// The key identifying this context. Non-Rust components use this key
+    /// to request the same context.
+    const KEY: &'static str;
+
+    /// The type of the context value.
+    type Value;
+
+    /// Converts a value into the javascript value handed to consumers.
+    fn to_js(value: &Self::Value) -> JsValue;
+
+    /// Converts a javascript value handed out by a provider back into a value.
+    fn from_js(value: JsValue) -> Option<Self::Value>;
+}
+
+type Subscribers = Rc<RefCell<Vec<Function>>>;
+
+/// Provides a context value to the descendants of an element.
+///
+/// The provider stops answering requests when it is dropped.
+pub struct ContextProvider<C: Context> {
+    element: HtmlElement,
+    value: Rc<RefCell<JsValue>>,
+    subscribers: Subscribers,
+    listener: Closure<dyn FnMut(Event)>,
+    _context: PhantomData<C>,
+}
+
+impl<C: Context> ContextProvider<C> {
+    /// Updates the provided value and notifies every subscribed consumer.
+    pub fn set(&self, value: &C::Value) {
+        let value = C::to_js(value);
+        *self.value.borrow_mut() = value.clone();
+        // Clone the subscribers so a callback can unsubscribe while we iterate.
+        let subscribers = self.subscribers.borrow().clone();
+        for callback in subscribers {
+            let _ = callback.call1(&JsValue::NULL, &value);
+        }
+    }
+}
+
+impl<C: Context> Drop for ContextProvider<C> {
+    fn drop(&mut self) {
+        let _ = self.element.remove_event_listener_with_callback(
+            CONTEXT_REQUEST,
+            self.listener.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// Starts providing the context value to the descendants of the element.
+pub fn provide_context<C: Context>(
+    element: &HtmlElement,
+    value: &C::Value,
+) -> Result<ContextProvider<C>, JsValue> {
+    let value = Rc::new(RefCell::new(C::to_js(value)));
+    let subscribers: Subscribers = Rc::new(RefCell::new(Vec::new()));
+    let listener = {
+        let value = value.clone();
+        let subscribers = subscribers.clone();
+        Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+            let key = Reflect::get(&event, &JsValue::from_str("context")).unwrap_or_default();
+            if key.as_string().as_deref() != Some(C::KEY) {
+                return;
+            }
+            let callback = match Reflect::get(&event, &JsValue::from_str("callback"))
+                .ok()
+                .and_then(|c| c.dyn_into::<Function>().ok())
+            {
+                Some(callback) => callback,
+                None => return,
+            };
+            event.stop_propagation();
+            let subscribe = Reflect::get(&event, &JsValue::from_str("subscribe"))
+                .unwrap_or_default()
+                .is_truthy();
+            if subscribe {
+                subscribers.borrow_mut().push(callback.clone());
+                let subscribers = subscribers.clone();
+                let subscribed = callback.clone();
+                let unsubscribe = Closure::once_into_js(move || {
+                    subscribers
+                        .borrow_mut()
+                        .retain(|c| !Object::is(c, &subscribed));
+                });
+                let _ = callback.call2(&JsValue::NULL, &value.borrow(), &unsubscribe);
+            } else {
+                let _ = callback.call1(&JsValue::NULL, &value.borrow());
+            }
+        })
+    };
+    element.add_event_listener_with_callback(CONTEXT_REQUEST, listener.as_ref().unchecked_ref())?;
+    Ok(ContextProvider {
+        element: element.clone(),
+        value,
+        subscribers,
+        listener,
+        _context: PhantomData,
+    })
+}
+
+/// A request for a context value.
+///
+/// Subscriptions get unsubscribed from the provider when this is dropped.
+pub struct ContextRequest {
+    _callback: Closure<dyn FnMut(JsValue, JsValue)>,
+    answered: Rc<Cell<bool>>,
+    unsubscribe: Rc<RefCell<Option<Function>>>,
+}
+
+impl ContextRequest {
+    /// Returns true if a provider answered the request.
+    pub fn is_answered(&self) -> bool {
+        self.answered.get()
+    }
+}
+
+impl Drop for ContextRequest {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.borrow_mut().take() {
+            let _ = unsubscribe.call0(&JsValue::NULL);
+        }
+    }
+}
+
+/// Requests the context value from the closest ancestor providing it.
+///
+/// The callback is called synchronously if a provider answers. If `subscribe` is true
+/// the callback is called again whenever the provider updates the value.
+pub fn request_context<C, F>(
+    element: &HtmlElement,
+    subscribe: bool,
+    mut callback: F,
+) -> Result<ContextRequest, JsValue>
+where
+    C: Context,
+    F: FnMut(C::Value) + 'static,
+{
+    let answered = Rc::new(Cell::new(false));
+    let unsubscribe: Rc<RefCell<Option<Function>>> = Rc::new(RefCell::new(None));
+    let callback = {
+        let answered = answered.clone();
+        let unsubscribe = unsubscribe.clone();
+        Closure::<dyn FnMut(JsValue, JsValue)>::new(move |value: JsValue, unsub: JsValue| {
+            answered.set(true);
+            if let Some(unsub) = unsub.dyn_ref::<Function>() {
+                *unsubscribe.borrow_mut() = Some(unsub.clone());
+            }
+            if let Some(value) = C::from_js(value) {
+                callback(value);
+            }
+        })
+    };
+    let init = EventInit::new();
+    init.set_bubbles(true);
+    init.set_composed(true);
+    let event = Event::new_with_event_init_dict(CONTEXT_REQUEST, &init)?;
+    Reflect::set(
+        &event,
+        &JsValue::from_str("context"),
+        &JsValue::from_str(C::KEY),
+    )?;
+    Reflect::set(&event, &JsValue::from_str("contextTarget"), element)?;
+    Reflect::set(&event, &JsValue::from_str("callback"), callback.as_ref())?;
+    Reflect::set(
+        &event,
+        &JsValue::from_str("subscribe"),
+        &JsValue::from_bool(subscribe),
+    )?;
+    element.dispatch_event(&event)?;
+    Ok(ContextRequest {
+        _callback: callback,
+        answered,
+        unsubscribe,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    struct Theme;
+
+    impl Context for Theme {
+        const KEY: &'static str = "theme";
+        type Value = String;
+
+        fn to_js(value: &String) -> JsValue {
+            JsValue::from_str(value)
+        }
+
+        fn from_js(value: JsValue) -> Option<String> {
+            value.as_string()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_context_provide_and_request() {
+        let document = window().unwrap().document().unwrap();
+        let parent: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        let child: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        parent.append_child(&child).unwrap();
+
+        let provider = provide_context::<Theme>(&parent, &"dark".to_owned()).unwrap();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let request = {
+            let seen = seen.clone();
+            request_context::<Theme, _>(&child, true, move |theme| {
+                seen.borrow_mut().push(theme);
+            })
+            .unwrap()
+        };
+        assert!(request.is_answered());
+        assert_eq!(*seen.borrow(), vec!["dark".to_owned()]);
+
+        provider.set(&"light".to_owned());
+        assert_eq!(*seen.borrow(), vec!["dark".to_owned(), "light".to_owned()]);
+
+        drop(request);
+        provider.set(&"dark".to_owned());
+        assert_eq!(seen.borrow().len(), 2);
+    }
+}
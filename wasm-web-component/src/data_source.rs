@@ -0,0 +1,31 @@
+//! Declarative data fetching for web components.
+//!
+//! Components opt in by setting `data_src = true` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing [DataSource].
+//! Whenever the element is connected with a `data-src` attribute, or the attribute
+//! changes while it is connected, the url gets fetched and the JSON response is handed
+//! to [DataSource::data_loaded]. Fetches still in flight are aborted when the element
+//! gets disconnected or the `data-src` attribute changes.
+use serde::de::DeserializeOwned;
+use wasm_bindgen::JsValue;
+use web_sys::HtmlElement;
+
+/// Callbacks for the data fetched from a web component's `data-src` attribute.
+pub trait DataSource {
+    /// The type the fetched JSON gets deserialized into.
+    type Data: DeserializeOwned;
+
+    /// Called with the fetched data.
+    fn data_loaded(&mut self, element: &HtmlElement, data: Self::Data);
+
+    /// Called when fetching or deserializing the data failed.
+    fn data_error(&mut self, _element: &HtmlElement, _error: JsValue) {
+        // noop
+    }
+}
+
+/// Decodes fetched data. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn decode<D: DeserializeOwned>(data: &str) -> Result<D, JsValue> {
+    serde_json::from_str(data).map_err(|e| JsValue::from_str(&e.to_string()))
+}
@@ -0,0 +1,164 @@
+//! Typed access to the `data-*` attributes of an element.
+//!
+//! Keys are the camelCase names of the element's `dataset`, e.g. `userId` for the
+//! `data-user-id` attribute. Components observe `data-*` attributes by listing their
+//! keys in `observed_dataset = "['userId']"` in the
+//! [`#[web_component]`](crate::web_component) attribute and map the changed attribute
+//! back to its key with [dataset_key].
+//!
+//! ```ignore
+//! fn attribute_changed(&self, element: &HtmlElement, name: JsValue, _old: JsValue, _new: JsValue) {
+//!     if dataset_key(&name.as_string().unwrap_or_default()).as_deref() == Some("filter") {
+//!         let filter: Option<Filter> = Dataset::of(element).get_json("filter").unwrap_or(None);
+//!     }
+//! }
+//! ```
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::attributes::{set_attr, AttributeValue};
+
+/// Converts a dataset key like `userId` to its attribute name `data-user-id`.
+pub fn dataset_attribute(key: &str) -> String {
+    let mut name = String::from("data-");
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('-');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Converts an attribute name like `data-user-id` to its dataset key `userId`. Returns
+/// None for attributes that aren't `data-*` attributes.
+pub fn dataset_key(attribute: &str) -> Option<String> {
+    let name = attribute.strip_prefix("data-")?;
+    let mut key = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '-' && next.is_ascii_lowercase() => {
+                key.push(next.to_ascii_uppercase());
+                chars.next();
+            }
+            _ => key.push(c),
+        }
+    }
+    Some(key)
+}
+
+fn to_js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// The `data-*` attributes of an element.
+#[derive(Clone, Debug)]
+pub struct Dataset {
+    element: Element,
+}
+
+impl Dataset {
+    pub fn of(element: &Element) -> Self {
+        Self {
+            element: element.clone(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.element.get_attribute(&dataset_attribute(key))
+    }
+
+    /// Returns the parsed value of the key. See [AttributeValue].
+    pub fn get_as<T: AttributeValue>(&self, key: &str) -> Option<T> {
+        T::parse_attribute(self.get(key).as_deref())
+    }
+
+    /// Returns the value of the key deserialized from JSON. None if the key isn't set.
+    pub fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, JsValue> {
+        self.get(key)
+            .map(|value| serde_json::from_str(&value).map_err(to_js_error))
+            .transpose()
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.element.has_attribute(&dataset_attribute(key))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), JsValue> {
+        self.element.set_attribute(&dataset_attribute(key), value)
+    }
+
+    /// Sets the key to a typed value, or removes it if the value has none. See
+    /// [AttributeValue].
+    pub fn set_as<T: AttributeValue>(&self, key: &str, value: &T) -> Result<(), JsValue> {
+        set_attr(&self.element, &dataset_attribute(key), value)
+    }
+
+    /// Sets the key to the value serialized as JSON.
+    pub fn set_json<T: Serialize>(&self, key: &str, value: &T) -> Result<(), JsValue> {
+        self.set(key, &serde_json::to_string(value).map_err(to_js_error)?)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), JsValue> {
+        self.element.remove_attribute(&dataset_attribute(key))
+    }
+
+    /// The keys of every `data-*` attribute of the element.
+    pub fn keys(&self) -> Vec<String> {
+        self.element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| dataset_key(&name.as_string()?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_dataset_names() {
+        assert_eq!(dataset_attribute("userId"), "data-user-id");
+        assert_eq!(dataset_attribute("x"), "data-x");
+        assert_eq!(dataset_key("data-user-id").as_deref(), Some("userId"));
+        assert_eq!(dataset_key("data-a-1").as_deref(), Some("a-1"));
+        assert_eq!(dataset_key("aria-label"), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_dataset() {
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        let dataset = Dataset::of(&element);
+        dataset.set_as("pageSize", &25u32).unwrap();
+        assert_eq!(
+            element.get_attribute("data-page-size").as_deref(),
+            Some("25")
+        );
+        assert_eq!(dataset.get_as::<u32>("pageSize"), Some(25));
+        dataset.set_json("sort", &vec!["name", "date"]).unwrap();
+        assert_eq!(
+            dataset.get_json::<Vec<String>>("sort").unwrap(),
+            Some(vec!["name".to_owned(), "date".to_owned()])
+        );
+        assert_eq!(dataset.get_json::<u32>("missing").unwrap(), None);
+        assert!(dataset.get_json::<u32>("sort").is_err());
+        assert_eq!(dataset.keys(), vec!["pageSize", "sort"]);
+        dataset.remove("pageSize").unwrap();
+        assert!(!dataset.has("pageSize"));
+    }
+}
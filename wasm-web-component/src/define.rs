@@ -0,0 +1,362 @@
+//! Custom element definition conflicts.
+//!
+//! Custom element names are global to the page so two independently built wasm
+//! bundles defining the same element name collide. Every class defined by this crate
+//! carries a static `wasmWebComponent` marker with the crate version and class name so
+//! a conflicting definition can be reported with where the existing one came from.
+//!
+//! With [ConflictPolicy::Namespace] a conflicting element gets defined under a
+//! prefixed name instead, e.g. `mfe-my-button`, and
+//! [WebComponentDef::tag_name](crate::WebComponentDef::tag_name) returns the name it
+//! actually got defined under.
+//!
+//! ```ignore
+//! set_conflict_policy(ConflictPolicy::Namespace("checkout".to_owned()));
+//! MyButton::define()?;
+//! let button = MyButton::create(); // A <checkout-my-button> if my-button was taken.
+//! ```
+//!
+//! A whole component library can also be mounted under a prefix chosen at runtime
+//! with [set_element_prefix]. The prefix is part of every
+//! [element_name](crate::WebComponentDef::element_name) so it has to be set before any
+//! component gets defined.
+//!
+//! ```ignore
+//! set_element_prefix("acme");
+//! MyButton::define()?; // Defines <acme-my-button>.
+//! ```
+//!
+//! Components with a `version` in the [`#[web_component]`](crate::web_component)
+//! attribute get defined under their versioned name, e.g. `my-button-v2`, so breaking
+//! versions of a design system can be used side by side on a page. With
+//! `version_alias = true` the unversioned name gets defined as an alias of the
+//! versioned element too if nothing else has defined it.
+//!
+//! Defining fails with a [DefineError] instead of panicking in javascript contexts
+//! without a window or a custom elements registry, like workers, some browser
+//! extension contexts and embedded webviews.
+//!
+//! [define_components](crate::define_components) defines a list of components once and
+//! reports which of them it defined.
+//!
+//! ```ignore
+//! let report = define_components!(ButtonEl, CardEl, DialogEl);
+//! assert!(report.failed.is_empty(), "{}", report);
+//! ```
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use web_sys::Window;
+
+use crate::environment::custom_elements_registry;
+use crate::WebComponentDef;
+
+/// The version of this crate recorded on the classes it defines.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// What to do when an element name has already been defined.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail with a [DefineConflict] error.
+    #[default]
+    Error,
+    /// Define the element under the name with the prefix instead.
+    Namespace(String),
+}
+
+/// An element name that has already been defined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefineConflict {
+    pub element_name: String,
+    /// The crate version of the existing definition if it was defined by this crate.
+    pub existing_version: Option<String>,
+    /// The class name of the existing definition if it was defined by this crate.
+    pub existing_class: Option<String>,
+}
+
+impl fmt::Display for DefineConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.existing_version, &self.existing_class) {
+            (Some(version), Some(class)) => write!(
+                f,
+                "Custom element {} has already been defined by wasm-web-component {} (class {}). This module uses wasm-web-component {}.",
+                self.element_name, version, class, CRATE_VERSION
+            ),
+            _ => write!(
+                f,
+                "Custom element {} has already been defined by code outside of wasm-web-component.",
+                self.element_name
+            ),
+        }
+    }
+}
+
+impl From<DefineConflict> for JsValue {
+    fn from(conflict: DefineConflict) -> Self {
+        js_sys::Error::new(&conflict.to_string()).into()
+    }
+}
+
+/// What the javascript context is missing to define components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DefineErrorKind {
+    /// There is no window, e.g. in a worker.
+    NoWindow,
+    /// The window doesn't have a `customElements` registry.
+    NoRegistry,
+}
+
+/// A component that can't be defined in the current javascript context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefineError {
+    pub element_name: &'static str,
+    pub kind: DefineErrorKind,
+}
+
+impl fmt::Display for DefineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DefineErrorKind::NoWindow => write!(
+                f,
+                "[{}] Custom elements can't be defined without a window",
+                self.element_name
+            ),
+            DefineErrorKind::NoRegistry => write!(
+                f,
+                "[{}] Custom elements can't be defined without a customElements registry",
+                self.element_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DefineError {}
+
+impl From<DefineError> for JsValue {
+    fn from(error: DefineError) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+/// Returns the window to define the component in if it has a registry.
+///
+/// This is called by the generated `define` for you.
+#[doc(hidden)]
+pub fn define_window<T: WebComponentDef>() -> Result<Window, DefineError> {
+    let error = |kind| DefineError {
+        element_name: T::element_name(),
+        kind,
+    };
+    let window = web_sys::window().ok_or_else(|| error(DefineErrorKind::NoWindow))?;
+    custom_elements_registry().ok_or_else(|| error(DefineErrorKind::NoRegistry))?;
+    Ok(window)
+}
+
+thread_local! {
+    static POLICY: RefCell<ConflictPolicy> = RefCell::new(ConflictPolicy::default());
+    static DEFINED_NAMES: RefCell<HashMap<&'static str, String>> = RefCell::new(HashMap::new());
+    static PREFIX: RefCell<Option<String>> = const { RefCell::new(None) };
+    static NAMES: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+/// Returns a static copy of the element name. There is a bounded number of element
+/// names per page so leaking them is fine.
+fn intern(name: String) -> &'static str {
+    NAMES.with(|names| {
+        let mut names = names.borrow_mut();
+        match names.get(name.as_str()) {
+            Some(name) => *name,
+            None => {
+                let name: &'static str = Box::leak(name.into_boxed_str());
+                names.insert(name);
+                name
+            }
+        }
+    })
+}
+
+/// Sets the prefix for the element names of every component, e.g. `acme` turns
+/// `my-button` into `acme-my-button`. An empty prefix removes the prefix. Set it
+/// before defining any components.
+pub fn set_element_prefix(prefix: &str) {
+    let prefix = prefix.trim_end_matches('-');
+    PREFIX.with(|current| {
+        *current.borrow_mut() = if prefix.is_empty() {
+            None
+        } else {
+            Some(prefix.to_owned())
+        }
+    });
+}
+
+/// Returns the element name prefix if there is one.
+pub fn element_prefix() -> Option<String> {
+    PREFIX.with(|prefix| prefix.borrow().clone())
+}
+
+/// Returns the element name with the element prefix.
+///
+/// This is called by the generated `element_name` for you.
+#[doc(hidden)]
+pub fn prefixed_element_name(element_name: &'static str) -> &'static str {
+    match element_prefix() {
+        Some(prefix) => intern(format!("{}-{}", prefix, element_name)),
+        None => element_name,
+    }
+}
+
+/// Returns the element name with the version appended.
+#[doc(hidden)]
+pub fn versioned_element_name(element_name: &'static str, version: &str) -> &'static str {
+    let version = version.trim_start_matches('v');
+    intern(format!("{}-v{}", element_name, version))
+}
+
+/// Sets what happens when defining an element whose name has already been defined.
+pub fn set_conflict_policy(policy: ConflictPolicy) {
+    POLICY.with(|current| *current.borrow_mut() = policy);
+}
+
+/// Returns the conflict for the element name if it has already been defined.
+pub fn define_conflict(element_name: &str) -> Option<DefineConflict> {
+    let registry = custom_elements_registry()?;
+    let existing = registry.get(element_name);
+    if !existing.is_truthy() {
+        return None;
+    }
+    let marker = Reflect::get(&existing, &JsValue::from_str("wasmWebComponent"))
+        .ok()
+        .filter(|marker| marker.is_object());
+    let field = |name: &str| {
+        marker
+            .as_ref()
+            .and_then(|marker| Reflect::get(marker, &JsValue::from_str(name)).ok())
+            .and_then(|value| value.as_string())
+    };
+    Some(DefineConflict {
+        element_name: element_name.to_owned(),
+        existing_version: field("version"),
+        existing_class: field("className"),
+    })
+}
+
+/// Returns the name to define the component under according to the conflict policy.
+///
+/// This is called by the generated `define` for you.
+#[doc(hidden)]
+pub fn resolve_define_name<T: WebComponentDef>() -> Result<String, JsValue> {
+    let element_name = T::element_name_versioned();
+    let conflict = match define_conflict(element_name) {
+        Some(conflict) => conflict,
+        None => {
+            DEFINED_NAMES.with(|names| names.borrow_mut().remove(element_name));
+            return Ok(element_name.to_owned());
+        }
+    };
+    if crate::diagnostics_enabled()
+        && conflict.existing_class.as_deref() == Some(T::class_name())
+        && conflict.existing_version.as_deref() == Some(CRATE_VERSION)
+    {
+        crate::warn_diagnostic::<T>(
+            &JsValue::UNDEFINED,
+            "The element has already been defined. Use define_once to define it exactly once.",
+        );
+    }
+    match POLICY.with(|policy| policy.borrow().clone()) {
+        ConflictPolicy::Error => Err(conflict.into()),
+        ConflictPolicy::Namespace(prefix) => {
+            let namespaced = format!("{}-{}", prefix, element_name);
+            if let Some(conflict) = define_conflict(&namespaced) {
+                return Err(conflict.into());
+            }
+            DEFINED_NAMES.with(|names| names.borrow_mut().insert(element_name, namespaced.clone()));
+            Ok(namespaced)
+        }
+    }
+}
+
+/// Returns the name the element actually got defined under.
+pub fn defined_name(element_name: &'static str) -> String {
+    DEFINED_NAMES.with(|names| {
+        names
+            .borrow()
+            .get(element_name)
+            .cloned()
+            .unwrap_or_else(|| element_name.to_owned())
+    })
+}
+
+/// Whether the component's element name is defined in the registry.
+pub fn is_defined<T: WebComponentDef>() -> bool {
+    custom_elements_registry()
+        .map(|registry| {
+            registry
+                .get(&defined_name(T::element_name_versioned()))
+                .is_truthy()
+        })
+        .unwrap_or(false)
+}
+
+/// The element names of the components passed to
+/// [define_components](crate::define_components) by what happened to them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DefineReport {
+    /// Components that got defined.
+    pub defined: Vec<&'static str>,
+    /// Components that had already been defined.
+    pub already_defined: Vec<&'static str>,
+    /// Components that couldn't be defined, e.g. because of a conflict.
+    pub failed: Vec<&'static str>,
+}
+
+impl DefineReport {
+    /// Records whether the component was defined before and after defining it.
+    ///
+    /// This is called by [define_components](crate::define_components) for you.
+    #[doc(hidden)]
+    pub fn record(&mut self, element_name: &'static str, before: bool, after: bool) {
+        match (before, after) {
+            (true, _) => self.already_defined.push(element_name),
+            (false, true) => self.defined.push(element_name),
+            (false, false) => self.failed.push(element_name),
+        }
+    }
+}
+
+impl fmt::Display for DefineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Defined: [{}] Already defined: [{}] Failed: [{}]",
+            self.defined.join(", "),
+            self.already_defined.join(", "),
+            self.failed.join(", ")
+        )
+    }
+}
+
+/// Calls `define_once` on every component and returns a [DefineReport] of which
+/// components got defined and which had already been defined.
+///
+/// ```ignore
+/// let report = define_components!(ButtonEl, CardEl, DialogEl);
+/// ```
+#[macro_export]
+macro_rules! define_components {
+    ($($component:ty),* $(,)?) => {{
+        let mut report = $crate::DefineReport::default();
+        $(
+            let before = $crate::is_defined::<$component>();
+            <$component>::define_once();
+            report.record(
+                <$component as $crate::WebComponentDef>::element_name_versioned(),
+                before,
+                $crate::is_defined::<$component>(),
+            );
+        )*
+        report
+    }};
+}
@@ -0,0 +1,217 @@
+//! Browser devtools inspector hook.
+//!
+//! With the `Devtools` feature enabled the connected web components can be inspected
+//! from the browser console through the `window.__WASM_WEB_COMPONENTS__` registry.
+//! The registry gets installed when the first component is connected.
+//!
+//! ```text
+//! > __WASM_WEB_COMPONENTS__.components()
+//! ["my-counter", "my-list"]
+//! > __WASM_WEB_COMPONENTS__.instances("my-counter")
+//! [{ component: "my-counter", element: <my-counter>, index: 0 }]
+//! > __WASM_WEB_COMPONENTS__.inspect($0)
+//! { component: "my-counter", element: <my-counter>, index: 0, state: { count: 3 }, attributes: {…} }
+//! > __WASM_WEB_COMPONENTS__.installFormatters()
+//! ```
+//!
+//! The `state` is the component's [snapshot](crate::WebComponentSnapshot) for
+//! components with `snapshot = true`. `installFormatters` adds a custom formatter that
+//! shows the component and its state when logging an element. Custom formatters have
+//! to be enabled in the devtools settings.
+use std::cell::Cell;
+
+use js_sys::{Array, Function, Object, Reflect, JSON};
+use wasm_bindgen::prelude::{wasm_bindgen, Closure};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+use crate::instances::{connected_element_names, connected_instances};
+
+/// The name of the registry on the window.
+pub const DEVTOOLS_GLOBAL: &str = "__WASM_WEB_COMPONENTS__";
+
+#[wasm_bindgen(inline_js = "export function installFormatter(registry) {
+    const formatters = window.devtoolsFormatters || (window.devtoolsFormatters = []);
+    if (formatters.some(function(f) { return f.wasmWebComponents; })) {
+        return;
+    }
+    formatters.push({
+        wasmWebComponents: true,
+        header: function(obj) {
+            if (!(obj instanceof Element)) {
+                return null;
+            }
+            const info = registry.inspect(obj);
+            if (!info) {
+                return null;
+            }
+            const state = info.state === undefined ? \"\" : \" \" + JSON.stringify(info.state);
+            return [\"span\", { style: \"color: #8a2be2\" }, \"<\" + info.component + \">\" + state];
+        },
+        hasBody: function() {
+            return false;
+        },
+    });
+}")]
+extern "C" {
+    #[wasm_bindgen(js_name = installFormatter, catch)]
+    fn install_formatter(registry: &JsValue) -> Result<JsValue, JsValue>;
+}
+
+thread_local! {
+    static INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn set(target: &Object, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    Reflect::set(target, &JsValue::from_str(key), value)?;
+    Ok(())
+}
+
+fn instance_info(element_name: &str, element: &Element, index: usize) -> Result<Object, JsValue> {
+    let info = Object::new();
+    set(&info, "component", &JsValue::from_str(element_name))?;
+    set(&info, "element", element)?;
+    set(&info, "index", &JsValue::from(index as u32))?;
+    Ok(info)
+}
+
+/// Returns the element name and connection order of a connected component element.
+fn find_instance(element: &Element) -> Option<(&'static str, usize)> {
+    connected_element_names().into_iter().find_map(|name| {
+        connected_instances(name)
+            .iter()
+            .position(|instance| Object::is(instance, element))
+            .map(|index| (name, index))
+    })
+}
+
+/// Returns the connected instances of the component, or of every component if there
+/// is no element name.
+pub fn instances(element_name: Option<&str>) -> Result<Array, JsValue> {
+    let result = Array::new();
+    for name in connected_element_names() {
+        if element_name.map(|n| n != name).unwrap_or(false) {
+            continue;
+        }
+        for (index, element) in connected_instances(name).iter().enumerate() {
+            let info: JsValue = instance_info(name, element, index)?.into();
+            result.push(&info);
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the component name, element, attributes and state of a connected
+/// component element or undefined if the element isn't one.
+pub fn inspect(element: &Element) -> Result<JsValue, JsValue> {
+    let (name, index) = match find_instance(element) {
+        Some(found) => found,
+        None => return Ok(JsValue::UNDEFINED),
+    };
+    let info = instance_info(name, element, index)?;
+    let attributes = Object::new();
+    for attribute in element.get_attribute_names().iter() {
+        if let Some(attribute) = attribute.as_string() {
+            let value = element.get_attribute(&attribute).unwrap_or_default();
+            set(&attributes, &attribute, &JsValue::from_str(&value))?;
+        }
+    }
+    set(&info, "attributes", &attributes)?;
+    let snapshot = Reflect::get(element, &JsValue::from_str("snapshotState"))?;
+    let state = match snapshot.dyn_ref::<Function>() {
+        Some(snapshot) => match snapshot.call0(element)?.as_string() {
+            Some(state) => JSON::parse(&state)?,
+            None => JsValue::UNDEFINED,
+        },
+        None => JsValue::UNDEFINED,
+    };
+    set(&info, "state", &state)?;
+    Ok(info.into())
+}
+
+fn registry() -> Result<JsValue, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+    Reflect::get(&window, &JsValue::from_str(DEVTOOLS_GLOBAL))
+}
+
+/// Adds a devtools custom formatter that shows connected component elements with
+/// their state.
+pub fn install_console_formatters() -> Result<(), JsValue> {
+    install_devtools_hook()?;
+    let registry = registry()?;
+    install_formatter(&registry)?;
+    Ok(())
+}
+
+/// Installs the `window.__WASM_WEB_COMPONENTS__` registry. This is called for you
+/// when a component gets connected.
+pub fn install_devtools_hook() -> Result<(), JsValue> {
+    if INSTALLED.with(|installed| installed.get()) {
+        return Ok(());
+    }
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+    let registry = Object::new();
+    // The closures live as long as the page so they get leaked into javascript.
+    let components = Closure::<dyn Fn() -> Array>::new(|| {
+        connected_element_names()
+            .into_iter()
+            .map(JsValue::from_str)
+            .collect()
+    });
+    set(&registry, "components", &components.into_js_value())?;
+    let list = Closure::<dyn Fn(JsValue) -> Result<Array, JsValue>>::new(|name: JsValue| {
+        instances(name.as_string().as_deref())
+    });
+    set(&registry, "instances", &list.into_js_value())?;
+    let inspect = Closure::<dyn Fn(JsValue) -> Result<JsValue, JsValue>>::new(
+        |element: JsValue| match element.dyn_ref::<Element>() {
+            Some(element) => inspect(element),
+            None => Ok(JsValue::UNDEFINED),
+        },
+    );
+    set(&registry, "inspect", &inspect.into_js_value())?;
+    let formatters = Closure::<dyn Fn() -> Result<(), JsValue>>::new(install_console_formatters);
+    set(&registry, "installFormatters", &formatters.into_js_value())?;
+    set(&window, DEVTOOLS_GLOBAL, &registry)?;
+    INSTALLED.with(|installed| installed.set(true));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{track_connected, track_disconnected};
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_devtools_registry() {
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        element.set_attribute("title", "inspected").unwrap();
+        track_connected("devtools-test", &element);
+        install_devtools_hook().unwrap();
+        assert!(registry().unwrap().is_object());
+
+        let found = instances(Some("devtools-test")).unwrap();
+        assert_eq!(found.length(), 1);
+        let info = inspect(&element).unwrap();
+        assert_eq!(
+            Reflect::get(&info, &"component".into()).unwrap(),
+            JsValue::from_str("devtools-test")
+        );
+        let attributes = Reflect::get(&info, &"attributes".into()).unwrap();
+        assert_eq!(
+            Reflect::get(&attributes, &"title".into()).unwrap(),
+            JsValue::from_str("inspected")
+        );
+        track_disconnected("devtools-test", &element);
+        assert!(inspect(&element).unwrap().is_undefined());
+    }
+}
@@ -0,0 +1,180 @@
+//! Development diagnostics for common mistakes.
+//!
+//! With the `Diagnostics` feature enabled components warn in the console, with the
+//! element name and the element, when they:
+//!
+//! * Get defined more than once.
+//! * Attach a shadow root to an element that already has one.
+//! * Change an attribute from inside of `attribute_changed`, which can loop forever.
+//! * Get nodes added to them while they are disconnected, usually from a timer or
+//!   listener that wasn't cleaned up on disconnect.
+//! * Handle an event type that isn't listed in `observed_events`.
+//!
+//! The checks compile to nothing without the feature.
+use wasm_bindgen::JsValue;
+use web_sys::{Event, HtmlElement};
+
+use crate::behavior::behavior_events;
+use crate::listeners::is_listening;
+use crate::WebComponentDef;
+
+/// Returns true if the diagnostics are enabled.
+#[doc(hidden)]
+pub fn diagnostics_enabled() -> bool {
+    cfg!(feature = "Diagnostics")
+}
+
+/// Warns in the console about a mistake made with the component. The element is
+/// logged with the warning unless it is undefined.
+pub fn warn_diagnostic<T: WebComponentDef>(element: &JsValue, message: &str) {
+    let message = JsValue::from_str(&format!("[{}] {}", T::element_name(), message));
+    if element.is_undefined() {
+        web_sys::console::warn_1(&message);
+    } else {
+        web_sys::console::warn_2(&message, element);
+    }
+}
+
+/// Warns if the element already has a shadow root or shadow roots are disabled for it.
+#[doc(hidden)]
+pub fn check_attach_shadow<T: WebComponentDef>(element: &HtmlElement) {
+    if !diagnostics_enabled() {
+        return;
+    }
+    if T::disabled_features().contains(&"shadow") {
+        warn_diagnostic::<T>(
+            element,
+            "A shadow root was attached to an element that lists shadow in disabled_features.",
+        );
+    } else if element.shadow_root().is_some() {
+        warn_diagnostic::<T>(
+            element,
+            "A shadow root was attached to an element that already has one.",
+        );
+    }
+}
+
+/// Warns if the event isn't one of the component's observed events.
+#[doc(hidden)]
+pub fn check_observed_event<T: WebComponentDef>(element: &HtmlElement, event: &Event) {
+    let event_type = event.type_();
+    if diagnostics_enabled()
+        && !T::observed_events().contains(&event_type.as_str())
+        && !behavior_events::<T>().contains(&event_type.as_str())
+        && !is_listening(element, &event_type)
+    {
+        warn_diagnostic::<T>(
+            element,
+            &format!(
+                "Handling a {} event which isn't listed in observed_events.",
+                event.type_()
+            ),
+        );
+    }
+}
+
+#[cfg(all(test, feature = "Diagnostics"))]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use js_sys::Reflect;
+    use wasm_bindgen::prelude::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{web_component, WebComponentBinding};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Runs the function with `console.warn` replaced and returns the warnings.
+    fn capture_warnings<F: FnOnce()>(run: F) -> Vec<String> {
+        let console = Reflect::get(&js_sys::global(), &JsValue::from_str("console")).unwrap();
+        let warn = JsValue::from_str("warn");
+        let original = Reflect::get(&console, &warn).unwrap();
+        let warnings = Rc::new(RefCell::new(Vec::new()));
+        let capture = {
+            let warnings = warnings.clone();
+            Closure::<dyn FnMut(JsValue)>::new(move |message: JsValue| {
+                warnings
+                    .borrow_mut()
+                    .push(message.as_string().unwrap_or_default());
+            })
+        };
+        Reflect::set(&console, &warn, capture.as_ref().unchecked_ref()).unwrap();
+        run();
+        Reflect::set(&console, &warn, &original).unwrap();
+        let warnings = warnings.borrow().clone();
+        warnings
+    }
+
+    #[web_component(
+        class_name = "DiagnosedElement",
+        observed_attrs = "['value', 'mirror']",
+        aliases = "{'old-value': 'value'}"
+    )]
+    pub struct DiagnosedElement {}
+    impl WebComponentBinding for DiagnosedElement {
+        fn attribute_changed(
+            &self,
+            element: &HtmlElement,
+            name: JsValue,
+            _old_value: JsValue,
+            new_value: JsValue,
+        ) {
+            if name == "value" {
+                element
+                    .set_attribute("mirror", &new_value.as_string().unwrap_or_default())
+                    .unwrap();
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_redefinition_warning() {
+        DiagnosedElement::define_once();
+        let warnings = capture_warnings(|| {
+            assert!(DiagnosedElement::define().is_err());
+        });
+        assert_eq!(
+            warnings,
+            ["[diagnosed-element] The element has already been defined. Use define_once to define it exactly once."]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_attribute_warnings() {
+        DiagnosedElement::define_once();
+        let element = DiagnosedElement::create();
+        let warnings = capture_warnings(|| {
+            element.set_attribute("old-value", "1").unwrap();
+        });
+        assert_eq!(
+            warnings,
+            [
+                "[diagnosed-element] The old-value attribute is deprecated. Use value instead.",
+                "[diagnosed-element] The mirror attribute was changed from inside of attribute_changed which can loop.",
+            ]
+        );
+        assert_eq!(element.get_attribute("mirror").as_deref(), Some("1"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_shadow_and_event_warnings() {
+        DiagnosedElement::define_once();
+        let element: HtmlElement = DiagnosedElement::create().unchecked_into();
+        DiagnosedElement::render(&element, "").unwrap();
+        let warnings = capture_warnings(|| {
+            check_attach_shadow::<DiagnosedElement>(&element);
+            check_observed_event::<DiagnosedElement>(&element, &Event::new("click").unwrap());
+        });
+        assert_eq!(
+            warnings,
+            [
+                "[diagnosed-element] A shadow root was attached to an element that already has one.",
+                "[diagnosed-element] Handling a click event which isn't listed in observed_events.",
+            ]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Modal support for web components built on `<dialog>`.
+//!
+//! Components with `base_class = "HTMLDialogElement"` are defined as customized
+//! built-in elements extending `<dialog>`. The browser then handles the backdrop,
+//! making the rest of the page inert while the dialog is modal and returning focus
+//! when it closes. The dialog's `close` and `cancel` events are delivered to the
+//! [WebComponentBinding](crate::WebComponentBinding) `dialog_closed` and
+//! `dialog_cancel` callbacks. Dialogs with a `light-dismiss` attribute also get
+//! cancelled by clicks on their backdrop.
+//!
+//! Safari does not support customized built-in elements.
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlDialogElement};
+
+fn as_dialog(element: &Element) -> Result<&HtmlDialogElement, JsValue> {
+    element
+        .dyn_ref::<HtmlDialogElement>()
+        .ok_or_else(|| JsValue::from_str("Element is not a dialog"))
+}
+
+/// Shows the dialog as a modal. Does nothing if the dialog is already open.
+pub fn show_modal(element: &Element) -> Result<(), JsValue> {
+    let dialog = as_dialog(element)?;
+    if dialog.open() {
+        return Ok(());
+    }
+    dialog.show_modal()
+}
+
+/// Closes the dialog, optionally setting its return value.
+pub fn close_dialog(element: &Element, return_value: Option<&str>) -> Result<(), JsValue> {
+    let dialog = as_dialog(element)?;
+    match return_value {
+        Some(return_value) => dialog.close_with_return_value(return_value),
+        None => dialog.close(),
+    }
+    Ok(())
+}
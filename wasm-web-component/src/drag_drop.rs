@@ -0,0 +1,157 @@
+//! Drag and drop for web components.
+//!
+//! Components opt in by setting `drag_source = true` and implementing [DragSource] or by
+//! setting `drop_target = true` and implementing [DropTarget] in the
+//! [`#[web_component]`](crate::web_component) attribute. Drag sources are made
+//! draggable when they get connected unless they have a `draggable` attribute already.
+//! Drop targets call `preventDefault` on the `dragenter` and `dragover` events for the
+//! drags they accept so the browser allows dropping there.
+use js_sys::Reflect;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DataTransfer, Event, File, HtmlElement};
+
+/// The data being dragged.
+pub struct DragData {
+    transfer: Option<DataTransfer>,
+}
+
+impl DragData {
+    /// Returns the data of a drag event. This is called by the generated shims for you.
+    #[doc(hidden)]
+    pub fn from_event(event: &Event) -> Self {
+        let transfer = Reflect::get(event, &JsValue::from_str("dataTransfer"))
+            .ok()
+            .and_then(|transfer| transfer.dyn_into::<DataTransfer>().ok());
+        Self { transfer }
+    }
+
+    /// The underlying `DataTransfer` of the drag if the event had one.
+    pub fn data_transfer(&self) -> Option<&DataTransfer> {
+        self.transfer.as_ref()
+    }
+
+    /// The MIME types of the data being dragged. Dragged files have the type `Files`.
+    pub fn types(&self) -> Vec<String> {
+        self.transfer
+            .as_ref()
+            .map(|transfer| {
+                transfer
+                    .types()
+                    .iter()
+                    .filter_map(|t| t.as_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether data of the MIME type is being dragged.
+    pub fn has_type(&self, mime: &str) -> bool {
+        self.types().iter().any(|t| t == mime)
+    }
+
+    /// Returns the data for the MIME type. Browsers only allow reading the data once it
+    /// has been dropped.
+    pub fn get(&self, mime: &str) -> Option<String> {
+        self.transfer
+            .as_ref()
+            .and_then(|transfer| transfer.get_data(mime).ok())
+            .filter(|data| !data.is_empty())
+    }
+
+    /// Returns the `text/plain` data.
+    pub fn text(&self) -> Option<String> {
+        self.get("text/plain")
+    }
+
+    /// Deserializes the JSON data for a custom MIME type like `application/x-card+json`.
+    pub fn get_json<T: DeserializeOwned>(&self, mime: &str) -> Option<Result<T, JsValue>> {
+        self.get(mime)
+            .map(|data| serde_json::from_str(&data).map_err(|e| JsValue::from_str(&e.to_string())))
+    }
+
+    /// Sets the data for the MIME type. Only allowed while the drag is starting.
+    pub fn set(&self, mime: &str, data: &str) -> Result<(), JsValue> {
+        match &self.transfer {
+            Some(transfer) => transfer.set_data(mime, data),
+            None => Err(JsValue::from_str("The event has no data transfer")),
+        }
+    }
+
+    /// Sets the `text/plain` data.
+    pub fn set_text(&self, data: &str) -> Result<(), JsValue> {
+        self.set("text/plain", data)
+    }
+
+    /// Serializes the data as JSON for a custom MIME type.
+    pub fn set_json<T: Serialize>(&self, mime: &str, data: &T) -> Result<(), JsValue> {
+        let data = serde_json::to_string(data).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.set(mime, &data)
+    }
+
+    /// The files being dropped.
+    pub fn files(&self) -> Vec<File> {
+        let files = match self.transfer.as_ref().and_then(|t| t.files()) {
+            Some(files) => files,
+            None => return Vec::new(),
+        };
+        (0..files.length()).filter_map(|i| files.get(i)).collect()
+    }
+
+    /// Sets which of `copy`, `move` and `link` are allowed for the drag.
+    pub fn set_effect_allowed(&self, effect: &str) {
+        if let Some(transfer) = &self.transfer {
+            transfer.set_effect_allowed(effect);
+        }
+    }
+
+    /// Sets the effect shown to the user while dragging over the target.
+    pub fn set_drop_effect(&self, effect: &str) {
+        if let Some(transfer) = &self.transfer {
+            transfer.set_drop_effect(effect);
+        }
+    }
+
+    /// The effect the drag ended with, `none` if it was cancelled.
+    pub fn drop_effect(&self) -> String {
+        self.transfer
+            .as_ref()
+            .map(|transfer| transfer.drop_effect())
+            .unwrap_or_else(|| "none".to_owned())
+    }
+}
+
+/// Callbacks for a web component that can be dragged.
+pub trait DragSource {
+    /// Called when the element starts getting dragged. This is the only place the
+    /// dragged data can be set.
+    fn drag_started(&mut self, element: &HtmlElement, data: &DragData);
+
+    /// Called when the drag ends, whether it was dropped or cancelled.
+    fn drag_ended(&mut self, _element: &HtmlElement, _data: &DragData) {
+        // noop
+    }
+}
+
+/// Callbacks for a web component that things can be dropped on.
+pub trait DropTarget {
+    /// Whether the drag can be dropped on the element. Only the types of the data are
+    /// available until it is dropped. Accepts everything by default.
+    fn accepts(&self, _element: &HtmlElement, _data: &DragData) -> bool {
+        true
+    }
+
+    /// Called when an accepted drag enters or moves over the element.
+    fn dragged_over(&mut self, _element: &HtmlElement, _data: &DragData) {
+        // noop
+    }
+
+    /// Called when a drag leaves the element.
+    fn drag_left(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when an accepted drag is dropped on the element.
+    fn dropped(&mut self, element: &HtmlElement, data: DragData);
+}
@@ -0,0 +1,241 @@
+//! Typed handles to the elements of a web component.
+//!
+//! The [`#[web_component]`](crate::web_component) attribute generates a newtype around
+//! `HtmlElement` named after the struct with a `Ref` suffix, e.g. `MyElementRef` for
+//! `MyElement`, for code using the component's elements, including other components.
+//! It has a getter and setter for every observed attribute, typed with `attr_types`,
+//! an `on_*` method for every event declared in `emits`, and is created from an
+//! `Element` with `try_from`, which checks the element's tag name.
+//!
+//! ```ignore
+//! #[web_component(
+//!     observed_attrs = "['open', 'page-size']",
+//!     attr_types = "{open: bool, 'page-size': u32}",
+//!     emits = "['page-changed']"
+//! )]
+//! pub struct Pager {}
+//!
+//! let pager = PagerRef::try_from(element)?;
+//! pager.set_page_size(&50)?;
+//! let _subscription = pager.on_page_changed(|event| { /* ... */ })?;
+//! ```
+use std::fmt;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    CustomEvent, Document, DocumentFragment, Element, Event, EventTarget, HtmlElement, Node,
+};
+
+use crate::environment::registry;
+use crate::properties::PropertyValue;
+use crate::WebComponentDef;
+
+/// An event listener. Dropping it removes the listener.
+pub struct EventSubscription {
+    target: EventTarget,
+    event_type: String,
+    listener: Closure<dyn FnMut(Event)>,
+}
+
+impl EventSubscription {
+    /// Calls the callback with the events of the type dispatched on the target.
+    pub fn listen<F>(target: &EventTarget, event_type: &str, callback: F) -> Result<Self, JsValue>
+    where
+        F: FnMut(Event) + 'static,
+    {
+        let listener = Closure::<dyn FnMut(Event)>::new(callback);
+        target.add_event_listener_with_callback(event_type, listener.as_ref().unchecked_ref())?;
+        Ok(Self {
+            target: target.clone(),
+            event_type: event_type.to_owned(),
+            listener,
+        })
+    }
+
+    /// Calls the callback with the converted `detail` of the `CustomEvent`s of the type
+    /// dispatched on the target. Events without a detail of the type are skipped.
+    pub fn listen_detail<D, F>(
+        target: &EventTarget,
+        event_type: &str,
+        mut callback: F,
+    ) -> Result<Self, JsValue>
+    where
+        D: PropertyValue,
+        F: FnMut(D, Event) + 'static,
+    {
+        Self::listen(target, event_type, move |event: Event| {
+            let detail = event
+                .dyn_ref::<CustomEvent>()
+                .and_then(|event| D::from_property(&event.detail()));
+            if let Some(detail) = detail {
+                callback(detail, event);
+            }
+        })
+    }
+}
+
+impl fmt::Debug for EventSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSubscription")
+            .field("target", &self.target)
+            .field("event_type", &self.event_type)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        let _ = self.target.remove_event_listener_with_callback(
+            &self.event_type,
+            self.listener.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// The element isn't an element of the web component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrongElementError {
+    /// The tag name of the web component's elements.
+    pub expected: String,
+    /// The tag name of the element.
+    pub found: String,
+}
+
+impl fmt::Display for WrongElementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected a <{}> but got a <{}>",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for WrongElementError {}
+
+impl From<WrongElementError> for JsValue {
+    fn from(error: WrongElementError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Checks that the element is an element of the web component. This is called by the
+/// generated `try_from` for you.
+#[doc(hidden)]
+pub fn check_element_ref<T: WebComponentDef>(
+    element: Element,
+) -> Result<HtmlElement, WrongElementError> {
+    let expected = T::tag_name();
+    let found = element.tag_name().to_lowercase();
+    // Customized built-in elements have the tag they extend and the name in `is`.
+    let (tag, name) = match T::extends() {
+        Some(tag) => (tag, element.get_attribute("is").unwrap_or_default()),
+        None => ("", found.clone()),
+    };
+    let matches = (tag.is_empty() || found == tag)
+        && (name == expected || (T::version_alias() && name == T::element_name()));
+    if !matches {
+        return Err(WrongElementError { expected, found });
+    }
+    into_html_element(element, expected)
+}
+
+/// Checks that the element has the tag name. This is called by the `try_from` of the
+/// wrappers generated by [`custom_elements!`](crate::custom_elements).
+#[doc(hidden)]
+pub fn check_tag_name(element: Element, tag_name: &str) -> Result<HtmlElement, WrongElementError> {
+    let found = element.tag_name().to_lowercase();
+    if found != tag_name {
+        return Err(WrongElementError {
+            expected: tag_name.to_owned(),
+            found,
+        });
+    }
+    into_html_element(element, found)
+}
+
+fn into_html_element(element: Element, expected: String) -> Result<HtmlElement, WrongElementError> {
+    element
+        .dyn_into()
+        .map_err(|element: Element| WrongElementError {
+            expected,
+            found: element.tag_name().to_lowercase(),
+        })
+}
+
+/// Whether the element's class is the constructor or a subclass of it.
+fn is_instance(element: &Element, constructor: &JsValue) -> bool {
+    let prototype = match Reflect::get(constructor, &JsValue::from_str("prototype")) {
+        Ok(prototype) if prototype.is_object() => prototype,
+        _ => return false,
+    };
+    let mut current = Object::get_prototype_of(element);
+    while !current.is_null() {
+        if JsValue::from(&current) == prototype {
+            return true;
+        }
+        current = Object::get_prototype_of(&current);
+    }
+    false
+}
+
+/// Upgrades the elements under the root with `customElements.upgrade` and returns the
+/// upgraded elements of the web component under it, including the root itself. Elements
+/// inside of shadow roots under the root get upgraded but aren't returned.
+///
+/// This is called by the generated `upgrade_all` for you.
+#[doc(hidden)]
+pub fn upgrade_all<T: WebComponentDef>(root: &Node) -> Result<Vec<HtmlElement>, JsValue> {
+    let registry = registry()?;
+    let tag_name = T::tag_name();
+    if !registry.get(&tag_name).is_truthy() {
+        return Err(JsValue::from_str(&format!(
+            "[{}] The element has to be defined before upgrading it",
+            T::element_name()
+        )));
+    }
+    registry.upgrade(root);
+    let mut names = vec![tag_name];
+    if T::version_alias() {
+        names.push(T::element_name().to_owned());
+    }
+    let selector = names
+        .iter()
+        .map(|name| match T::extends() {
+            Some(tag) => format!("{}[is=\"{}\"]", tag, name),
+            None => name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let descendants = if let Some(element) = root.dyn_ref::<Element>() {
+        Some(element.query_selector_all(&selector)?)
+    } else if let Some(document) = root.dyn_ref::<Document>() {
+        Some(document.query_selector_all(&selector)?)
+    } else if let Some(fragment) = root.dyn_ref::<DocumentFragment>() {
+        Some(fragment.query_selector_all(&selector)?)
+    } else {
+        None
+    };
+    let mut candidates: Vec<Element> = root.dyn_ref::<Element>().cloned().into_iter().collect();
+    if let Some(descendants) = descendants {
+        candidates.extend(
+            (0..descendants.length())
+                .filter_map(|i| descendants.item(i))
+                .map(|node| node.unchecked_into::<Element>()),
+        );
+    }
+    Ok(candidates
+        .into_iter()
+        .filter(|element| {
+            // Upgraded elements are instances of the class their name is defined with.
+            let name = match T::extends() {
+                Some(_) => element.get_attribute("is").unwrap_or_default(),
+                None => element.tag_name().to_lowercase(),
+            };
+            is_instance(element, &registry.get(&name))
+        })
+        .filter_map(|element| check_element_ref::<T>(element).ok())
+        .collect())
+}
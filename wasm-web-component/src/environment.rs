@@ -0,0 +1,100 @@
+//! Capability checks for the javascript context the crate runs in.
+//!
+//! The document and the custom elements registry are looked up on `globalThis` instead
+//! of assuming a `window`, so in workers, ShadowRealms, extension contexts and stripped
+//! down webviews they are simply missing and the crate fails with clear errors instead
+//! of aborting.
+//!
+//! ```ignore
+//! if custom_elements_supported() {
+//!     MyElement::define()?;
+//! } else {
+//!     render_static_fallback();
+//! }
+//! ```
+use js_sys::{Array, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomElementRegistry, Document, Element};
+
+use crate::WebComponentDef;
+
+/// Returns the global's property if it is an object.
+fn global_object(name: &str) -> Option<JsValue> {
+    Reflect::get(&js_sys::global(), &JsValue::from_str(name))
+        .ok()
+        .filter(|value| value.is_object())
+}
+
+/// Returns the custom elements registry of the global if it has one.
+pub fn custom_elements_registry() -> Option<CustomElementRegistry> {
+    global_object("customElements").map(JsCast::unchecked_into)
+}
+
+/// Returns the document of the global if it has one.
+pub fn global_document() -> Option<Document> {
+    global_object("document").map(JsCast::unchecked_into)
+}
+
+/// Whether components can be defined, i.e. there is a custom elements registry.
+pub fn custom_elements_supported() -> bool {
+    custom_elements_registry().is_some()
+}
+
+/// Whether there is a document to create elements in.
+pub fn dom_supported() -> bool {
+    global_document().is_some()
+}
+
+/// Whether elements can attach shadow roots.
+pub fn shadow_dom_supported() -> bool {
+    global_object("Element")
+        .and_then(|element| Reflect::get(&element, &JsValue::from_str("prototype")).ok())
+        .and_then(|prototype| Reflect::get(&prototype, &JsValue::from_str("attachShadow")).ok())
+        .map(|attach| attach.is_function())
+        .unwrap_or(false)
+}
+
+/// Returns the document or an error for contexts without one.
+pub(crate) fn document() -> Result<Document, JsValue> {
+    global_document().ok_or_else(|| JsValue::from_str("No document in this javascript context"))
+}
+
+/// Returns the custom elements registry or an error for contexts without one.
+pub(crate) fn registry() -> Result<CustomElementRegistry, JsValue> {
+    custom_elements_registry()
+        .ok_or_else(|| JsValue::from_str("No custom elements registry in this javascript context"))
+}
+
+/// Calls the fallback with every element in the document that would have been an
+/// instance of the component and returns how many there were.
+///
+/// This is called by the generated `define_or_fallback` for you.
+#[doc(hidden)]
+pub fn apply_fallback<T: WebComponentDef, F: Fn(&Element)>(fallback: F) -> Result<usize, JsValue> {
+    let name = T::element_name_versioned();
+    let selector = match T::extends() {
+        Some(tag) => format!("{}[is=\"{}\"]", tag, name),
+        None => name.to_owned(),
+    };
+    let elements = Array::from(document()?.query_selector_all(&selector)?.as_ref());
+    for element in elements.iter() {
+        fallback(element.unchecked_ref());
+    }
+    Ok(elements.length() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_capabilities() {
+        assert!(custom_elements_supported());
+        assert!(dom_supported());
+        assert!(shadow_dom_supported());
+        assert!(document().unwrap().body().is_some());
+    }
+}
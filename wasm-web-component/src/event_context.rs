@@ -0,0 +1,149 @@
+//! Where an event handled by a component came from.
+//!
+//! Components listen for their `observed_events` on both their element and their shadow
+//! root by default. By the time an event from inside of the shadow root reaches the
+//! element its target has been retargeted to the element, so the
+//! `handle_event_with` callbacks get an [EventContext] with the original target
+//! and whether the event came from the shadow root, from light DOM content or from the
+//! element itself.
+//!
+//! The shim captures the event's current target and composed path while the event is
+//! being dispatched, so the context is complete even for events a component handles
+//! after the dispatch because it was busy with another callback.
+//!
+//! ```ignore
+//! fn handle_event_with(&self, element: &HtmlElement, event: &Event, context: &EventContext) {
+//!     match context.origin {
+//!         EventOrigin::Shadow => { /* One of the component's own controls. */ }
+//!         EventOrigin::Light if context.slot.is_some() => { /* Slotted content. */ }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+use js_sys::Array;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Event, EventTarget, HtmlElement, HtmlSlotElement, Node};
+
+/// Which part of a component an event was dispatched in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventOrigin {
+    /// The element itself.
+    Host,
+    /// A node inside of the element's shadow root.
+    Shadow,
+    /// A light DOM descendant of the element, slotted or not.
+    Light,
+}
+
+/// Where an event came from relative to the component handling it.
+#[derive(Clone, Debug)]
+pub struct EventContext {
+    pub origin: EventOrigin,
+    /// The target the event was dispatched on, even if that is inside of the shadow root.
+    pub original_target: Option<EventTarget>,
+    /// The target as seen from outside of the component, the element for events from
+    /// the shadow root.
+    pub retargeted_target: Option<EventTarget>,
+    /// The target whose listener handled the event, the element or its shadow root.
+    pub current_target: Option<EventTarget>,
+    /// The slot of the element's shadow root the event passed through for events from
+    /// slotted content.
+    pub slot: Option<HtmlSlotElement>,
+    /// The event's `composedPath()` from the original target outwards.
+    pub composed_path: Vec<EventTarget>,
+}
+
+impl EventContext {
+    /// Works out where the event handled by the element came from. This has to be
+    /// called while the event is dispatched.
+    pub fn new(element: &HtmlElement, event: &Event) -> Self {
+        let composed_path = event
+            .composed_path()
+            .iter()
+            .map(|target| target.unchecked_into())
+            .collect();
+        Self::with_path(element, event, event.current_target(), composed_path)
+    }
+
+    /// Works out where the event came from with the current target and composed path
+    /// the shim captured during the dispatch.
+    ///
+    /// This is called by the generated shims for you.
+    #[doc(hidden)]
+    pub fn from_dispatch(
+        element: &HtmlElement,
+        event: &Event,
+        current_target: JsValue,
+        composed_path: JsValue,
+    ) -> Self {
+        let composed_path = Array::from(&composed_path)
+            .iter()
+            .map(|target| target.unchecked_into())
+            .collect();
+        Self::with_path(
+            element,
+            event,
+            current_target.dyn_into().ok(),
+            composed_path,
+        )
+    }
+
+    fn with_path(
+        element: &HtmlElement,
+        event: &Event,
+        current_target: Option<EventTarget>,
+        composed_path: Vec<EventTarget>,
+    ) -> Self {
+        let original_target = composed_path.first().cloned().or_else(|| event.target());
+        let shadow_root: Option<Node> = element.shadow_root().map(Into::into);
+        let node = original_target
+            .as_ref()
+            .and_then(|target| target.dyn_ref::<Node>());
+        let element_node: &Node = element.as_ref();
+        // The shadow root's root node is the shadow root itself.
+        let in_shadow = |node: &Node| Some(node.get_root_node()) == shadow_root;
+        let origin = match node {
+            Some(node) if node == element_node => EventOrigin::Host,
+            Some(node) if in_shadow(node) => EventOrigin::Shadow,
+            Some(_) => EventOrigin::Light,
+            None => EventOrigin::Host,
+        };
+        let retargeted_target = match origin {
+            EventOrigin::Shadow => Some(element.clone().into()),
+            _ => original_target.clone(),
+        };
+        let slot = match origin {
+            EventOrigin::Light => composed_path
+                .iter()
+                .filter_map(|target| target.dyn_ref::<HtmlSlotElement>())
+                .find(|slot| in_shadow(slot))
+                .cloned(),
+            _ => None,
+        };
+        Self {
+            origin,
+            original_target,
+            retargeted_target,
+            current_target,
+            slot,
+            composed_path,
+        }
+    }
+
+    /// Whether the event came from light DOM content slotted into the shadow root.
+    pub fn is_slotted(&self) -> bool {
+        self.slot.is_some()
+    }
+
+    /// The part of the composed path inside of the component, from the original target
+    /// up to but not including the element. Empty for events on the element itself.
+    pub fn internal_path(&self, element: &HtmlElement) -> &[EventTarget] {
+        let element: &EventTarget = element.as_ref();
+        let end = self
+            .composed_path
+            .iter()
+            .position(|target| target == element)
+            .unwrap_or(0);
+        &self.composed_path[..end]
+    }
+}
@@ -0,0 +1,135 @@
+//! Typed access to the events handled by components.
+//!
+//! [EventExt] is implemented for `Event` so handlers can downcast the event with an
+//! error naming the component and the event instead of chaining `dyn_ref` calls.
+//!
+//! ```ignore
+//! fn handle_event(&self, element: &HtmlElement, event: &Event) {
+//!     if event.matches_key("Ctrl+Enter") {
+//!         self.submit(element);
+//!     }
+//!     let key: KeyboardEvent = event.downcast().unwrap();
+//! }
+//! ```
+use std::fmt;
+
+use js_sys::Object;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, ShadowRoot};
+
+#[cfg(feature = "Keys")]
+use crate::keys::KeyCombo;
+use crate::query::short_type_name;
+
+/// An event isn't of the requested type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventCastError {
+    /// The tag name of the element handling the event. None outside of event handlers.
+    pub element_name: Option<String>,
+    pub event_type: String,
+    /// The name of the requested event type.
+    pub expected: &'static str,
+    /// The name of the event's class.
+    pub found: String,
+}
+
+impl fmt::Display for EventCastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(element_name) = &self.element_name {
+            write!(f, "[{}] ", element_name)?;
+        }
+        write!(
+            f,
+            "The `{}` event is of type {}, not {}",
+            self.event_type, self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for EventCastError {}
+
+impl From<EventCastError> for JsValue {
+    fn from(error: EventCastError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// The tag name of the element whose listener is handling the event.
+fn handling_element(event: &Event) -> Option<String> {
+    let target = event.current_target()?;
+    let element = match target.dyn_ref::<ShadowRoot>() {
+        Some(root) => root.host(),
+        None => target.dyn_into::<Element>().ok()?,
+    };
+    Some(element.tag_name().to_lowercase())
+}
+
+/// Helpers for the events passed to `handle_event`.
+pub trait EventExt {
+    /// Casts the event to a more specific event type like `KeyboardEvent`.
+    fn downcast<T: JsCast>(&self) -> Result<T, EventCastError>;
+
+    /// Whether the event is a keydown or keyup of the key combination, like `"Enter"`,
+    /// `"Ctrl+Shift+K"` or `"Mod+S"`. False for invalid combinations and other events.
+    /// See [KeyCombo].
+    #[cfg(feature = "Keys")]
+    fn matches_key(&self, combo: &str) -> bool;
+}
+
+impl EventExt for Event {
+    fn downcast<T: JsCast>(&self) -> Result<T, EventCastError> {
+        self.clone()
+            .dyn_into()
+            .map_err(|event: Event| EventCastError {
+                element_name: handling_element(&event),
+                event_type: event.type_(),
+                expected: short_type_name::<T>(),
+                found: String::from(Object::constructor(&event).name()),
+            })
+    }
+
+    #[cfg(feature = "Keys")]
+    fn matches_key(&self, combo: &str) -> bool {
+        match (self.dyn_ref(), combo.parse::<KeyCombo>()) {
+            (Some(event), Ok(combo)) => combo.matches(event),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::{window, CustomEvent};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_downcast() {
+        let event = Event::new("click").unwrap();
+        let error = event.downcast::<CustomEvent>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `click` event is of type Event, not CustomEvent"
+        );
+
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("my-list")
+            .unwrap();
+        let listener = wasm_bindgen::prelude::Closure::<dyn FnMut(Event)>::new(|event: Event| {
+            let error = event.downcast::<CustomEvent>().unwrap_err();
+            assert_eq!(error.element_name.as_deref(), Some("my-list"));
+            assert!(event.downcast::<Event>().is_ok());
+        });
+        element
+            .add_event_listener_with_callback("select", listener.as_ref().unchecked_ref())
+            .unwrap();
+        element
+            .dispatch_event(&Event::new("select").unwrap())
+            .unwrap();
+    }
+}
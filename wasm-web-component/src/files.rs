@@ -0,0 +1,185 @@
+//! Reading files picked with `<input type=file>` or dropped on a web component.
+//!
+//! Components opt in by setting `files = true` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing
+//! [FileReceiver]. Files chosen in a file input inside the element, dropped on the
+//! element or picked with [pick_files] and [pick_directory] are read one after another
+//! in chunks. Every chunk is handed to [FileReceiver::file_chunk] and
+//! [FileReceiver::file_progress] and the whole file to [FileReceiver::file_read] once
+//! it has been read.
+//!
+//! Dropped directories are traversed with the File System Access API where the browser
+//! supports it and with the older file and directory entries API otherwise. The
+//! [path](FileInfo::path) of a file in a directory starts with the directory's name.
+use std::cell::RefCell;
+
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, File, HtmlElement};
+
+/// A file being read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileInfo {
+    pub name: String,
+    /// The path of the file relative to the picked or dropped directory. The same as
+    /// the name for files that weren't in a directory.
+    pub path: String,
+    /// The MIME type of the file. Empty if the browser doesn't know it.
+    pub mime: String,
+    /// The size of the file in bytes.
+    pub size: f64,
+}
+
+/// Callbacks for the files read by a web component.
+pub trait FileReceiver {
+    /// Whether the chunks of a file are collected for [file_read](Self::file_read).
+    /// Components that only consume the chunks, e.g. to hash or upload large files, can
+    /// turn this off to keep the files out of memory.
+    const BUFFER: bool = true;
+
+    /// Called with every chunk of a file that has been read.
+    fn file_chunk(&mut self, _element: &HtmlElement, _file: &FileInfo, _chunk: &[u8]) {
+        // noop
+    }
+
+    /// Called after every chunk with how many bytes of the file have been read.
+    fn file_progress(&mut self, _element: &HtmlElement, _file: &FileInfo, _loaded: f64) {
+        // noop
+    }
+
+    /// Called when the file has been read. The bytes are empty if
+    /// [BUFFER](Self::BUFFER) is off.
+    fn file_read(&mut self, element: &HtmlElement, file: FileInfo, bytes: Vec<u8>);
+
+    /// Called when reading the file failed.
+    fn file_error(&mut self, _element: &HtmlElement, _file: FileInfo, _error: JsValue) {
+        // noop
+    }
+}
+
+struct Read {
+    element: Element,
+    id: u32,
+    file: FileInfo,
+    loaded: f64,
+    bytes: Vec<u8>,
+}
+
+thread_local! {
+    static READS: RefCell<Vec<Read>> = const { RefCell::new(Vec::new()) };
+}
+
+fn with_read<R>(element: &HtmlElement, id: u32, f: impl FnOnce(&mut Read) -> R) -> Option<R> {
+    READS.with(|reads| {
+        reads
+            .borrow_mut()
+            .iter_mut()
+            .find(|read| read.id == id && Object::is(&read.element, element))
+            .map(f)
+    })
+}
+
+fn take_read(element: &HtmlElement, id: u32) -> Option<Read> {
+    READS.with(|reads| {
+        let mut reads = reads.borrow_mut();
+        let index = reads
+            .iter()
+            .position(|read| read.id == id && Object::is(&read.element, element))?;
+        Some(reads.remove(index))
+    })
+}
+
+/// Starts reading a file. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn file_started(element: &HtmlElement, id: u32, file: FileInfo) {
+    READS.with(|reads| {
+        reads.borrow_mut().push(Read {
+            element: element.clone().into(),
+            id,
+            file,
+            loaded: 0.0,
+            bytes: Vec::new(),
+        })
+    });
+}
+
+/// Delivers a chunk of a file. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn file_chunk<T: FileReceiver>(
+    component: &mut T,
+    element: &HtmlElement,
+    id: u32,
+    chunk: &[u8],
+) {
+    let file = with_read(element, id, |read| {
+        read.loaded += chunk.len() as f64;
+        if T::BUFFER {
+            read.bytes.extend_from_slice(chunk);
+        }
+        (read.file.clone(), read.loaded)
+    });
+    if let Some((file, loaded)) = file {
+        component.file_chunk(element, &file, chunk);
+        component.file_progress(element, &file, loaded);
+    }
+}
+
+/// Delivers a file that has been read. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn file_read<T: FileReceiver>(component: &mut T, element: &HtmlElement, id: u32) {
+    if let Some(read) = take_read(element, id) {
+        component.file_read(element, read.file, read.bytes);
+    }
+}
+
+/// Delivers an error reading a file. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn file_error<T: FileReceiver>(
+    component: &mut T,
+    element: &HtmlElement,
+    id: u32,
+    error: JsValue,
+) {
+    if let Some(read) = take_read(element, id) {
+        component.file_error(element, read.file, error);
+    }
+}
+
+fn call_method(element: &HtmlElement, method: &str, args: &Array) -> Result<JsValue, JsValue> {
+    let method: Function = Reflect::get(element, &JsValue::from_str(method))?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("The element doesn't read files"))?;
+    method.apply(element, args)
+}
+
+/// Reads the files with the element's [FileReceiver], e.g. the
+/// [files](crate::DragData::files) of a drop. The returned promise resolves once every
+/// file has been read.
+pub fn read_files(element: &HtmlElement, files: &[File]) -> Result<Promise, JsValue> {
+    let files = files.iter().collect::<Array>();
+    Ok(call_method(element, "readFiles", &Array::of1(&files))?.unchecked_into())
+}
+
+/// Opens the browser's file picker and reads the picked files with the element's
+/// [FileReceiver]. `accept` is a comma separated list of file extensions and MIME
+/// types like `".png,image/jpeg"`, empty to accept everything. Browsers only open the
+/// picker while handling a user interaction like a click.
+pub fn pick_files(element: &HtmlElement, accept: &str, multiple: bool) -> Result<(), JsValue> {
+    call_method(
+        element,
+        "pickFiles",
+        &Array::of3(
+            &JsValue::from_str(accept),
+            &JsValue::from_bool(multiple),
+            &JsValue::FALSE,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Opens the browser's directory picker and reads every file in the picked directory
+/// with the element's [FileReceiver]. The returned promise resolves once every file has
+/// been read or the picker was cancelled.
+pub fn pick_directory(element: &HtmlElement) -> Result<Promise, JsValue> {
+    Ok(call_method(element, "pickDirectory", &Array::new())?.unchecked_into())
+}
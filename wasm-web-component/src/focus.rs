@@ -0,0 +1,116 @@
+//! Focus helpers that see through shadow roots.
+//!
+//! `document.activeElement` only points at the outermost shadow host containing the
+//! focused element, and `element.contains` doesn't look into shadow roots, so checking
+//! whether focus is inside of a component takes the component's root node into
+//! account. Components with `track_focus = true` get the
+//! [WebComponentBinding](crate::WebComponentBinding) `focus_changed` callback whenever
+//! focus enters or leaves them, but not when it moves between elements inside of them.
+//!
+//! ```ignore
+//! fn focus_changed(&self, element: &HtmlElement, has_focus: bool) {
+//!     element.toggle_attribute_with_force("active", has_focus).unwrap();
+//! }
+//!
+//! fn connected(&self, element: &HtmlElement) {
+//!     focus_first(element).unwrap();
+//! }
+//! ```
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Document, Element, HtmlElement, ShadowRoot};
+
+/// The elements that take focus with the Tab key.
+pub(crate) const TABBABLE: &str = "a[href], area[href], button:not([disabled]), \
+    input:not([disabled]):not([type=hidden]), select:not([disabled]), \
+    textarea:not([disabled]), iframe, [contenteditable], \
+    [tabindex]:not([tabindex='-1'])";
+
+/// Returns the focused element of the element's document or shadow root, retargeted to
+/// the shadow host if focus is inside of a shadow root nested in it.
+fn root_active_element(element: &Element) -> Option<Element> {
+    let root = element.get_root_node();
+    if let Some(shadow_root) = root.dyn_ref::<ShadowRoot>() {
+        shadow_root.active_element()
+    } else {
+        root.dyn_ref::<Document>()?.active_element()
+    }
+}
+
+/// Whether the element, or an element in its shadow root or light DOM, has focus.
+pub fn contains_focus(element: &Element) -> bool {
+    root_active_element(element)
+        .map(|active| element.contains(Some(&active)))
+        .unwrap_or(false)
+}
+
+/// Focuses the first element that takes focus with the Tab key, looking in the
+/// element's shadow root before its light DOM. Returns whether an element got focus,
+/// elements that are hidden or not rendered are skipped.
+pub fn focus_first(element: &HtmlElement) -> Result<bool, JsValue> {
+    let shadow = match element.shadow_root() {
+        Some(root) => Some(root.query_selector_all(TABBABLE)?),
+        None => None,
+    };
+    let light = element.query_selector_all(TABBABLE)?;
+    for nodes in shadow.iter().chain(Some(&light)) {
+        for index in 0..nodes.length() {
+            let candidate = match nodes
+                .item(index)
+                .and_then(|node| node.dyn_into::<HtmlElement>().ok())
+            {
+                Some(candidate) => candidate,
+                None => continue,
+            };
+            if candidate.closest("[hidden]")?.is_some() {
+                continue;
+            }
+            candidate.focus()?;
+            if contains_focus(&candidate) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::environment::document;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_focus_first() {
+        let document = document().unwrap();
+        let body = document.body().unwrap();
+        let host: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        host.set_inner_html("<button>Light</button>");
+        let shadow_root = host
+            .attach_shadow(&web_sys::ShadowRootInit::new(web_sys::ShadowRootMode::Open))
+            .unwrap();
+        shadow_root.set_inner_html(
+            "<button hidden>Hidden</button><button id='first'>First</button><slot></slot>",
+        );
+        body.append_child(&host).unwrap();
+        assert!(!contains_focus(&host));
+
+        assert!(focus_first(&host).unwrap());
+        let first = shadow_root.get_element_by_id("first").unwrap();
+        assert!(contains_focus(&host));
+        assert!(contains_focus(&first));
+        assert_eq!(document.active_element(), Some(host.clone().into()));
+        assert_eq!(shadow_root.active_element(), Some(first));
+
+        host.first_element_child()
+            .unwrap()
+            .unchecked_into::<HtmlElement>()
+            .focus()
+            .unwrap();
+        assert!(contains_focus(&host));
+        body.remove_child(&host).unwrap();
+        assert!(!contains_focus(&host));
+    }
+}
@@ -0,0 +1,257 @@
+//! Keeping keyboard focus inside of modal-like components.
+//!
+//! A [FocusTrap] moves focus into its container when activated, wraps Tab and
+//! Shift+Tab at the edges of the container and returns focus to the previously
+//! focused element when deactivated. Traps are deactivated automatically when their
+//! component element gets disconnected.
+//!
+//! ```ignore
+//! impl WebComponentBinding for MyDialog {
+//!     fn connected(&self, element: &HtmlElement) {
+//!         let container = element.shadow_root().unwrap().get_element_by_id("dialog").unwrap();
+//!         FocusTrap::activate(element, container.unchecked_ref()).unwrap();
+//!     }
+//! }
+//! ```
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, FocusEvent, HtmlElement, KeyboardEvent};
+
+use crate::cleanup::on_disconnect;
+use crate::focus::TABBABLE;
+
+const SENTINEL: &str = "data-focus-sentinel";
+
+/// Returns the focused element looking through open shadow roots.
+fn deep_active_element() -> Option<Element> {
+    let document = web_sys::window()?.document()?;
+    let mut active = document.active_element()?;
+    while let Some(inner) = active.shadow_root().and_then(|root| root.active_element()) {
+        active = inner;
+    }
+    Some(active)
+}
+
+fn tabbable_elements(container: &HtmlElement) -> Vec<HtmlElement> {
+    let mut tabbable = Vec::new();
+    if let Ok(nodes) = container.query_selector_all(TABBABLE) {
+        for i in 0..nodes.length() {
+            let element = match nodes.item(i).and_then(|n| n.dyn_into::<HtmlElement>().ok()) {
+                Some(element) => element,
+                None => continue,
+            };
+            if element.has_attribute(SENTINEL)
+                || element.closest("[hidden]").ok().flatten().is_some()
+            {
+                continue;
+            }
+            tabbable.push(element);
+        }
+    }
+    tabbable
+}
+
+struct Inner {
+    container: HtmlElement,
+    previous: Option<HtmlElement>,
+    sentinels: [HtmlElement; 2],
+    on_keydown: Closure<dyn FnMut(KeyboardEvent)>,
+    on_sentinel_focus: Closure<dyn FnMut(FocusEvent)>,
+    active: Cell<bool>,
+}
+
+impl Inner {
+    fn deactivate(&self) {
+        if !self.active.replace(false) {
+            return;
+        }
+        let _ = self.container.remove_event_listener_with_callback(
+            "keydown",
+            self.on_keydown.as_ref().unchecked_ref(),
+        );
+        for sentinel in &self.sentinels {
+            let _ = sentinel.remove_event_listener_with_callback(
+                "focus",
+                self.on_sentinel_focus.as_ref().unchecked_ref(),
+            );
+            sentinel.remove();
+        }
+        if let Some(previous) = &self.previous {
+            if previous.is_connected() {
+                let _ = previous.focus();
+            }
+        }
+    }
+}
+
+/// An active focus trap. Cloning returns a handle to the same trap.
+#[derive(Clone)]
+pub struct FocusTrap {
+    inner: Rc<Inner>,
+}
+
+impl FocusTrap {
+    /// Traps focus inside of the container until the trap is deactivated or the
+    /// component element gets disconnected. The container can be the element itself
+    /// or an element in its shadow root.
+    pub fn activate(element: &HtmlElement, container: &HtmlElement) -> Result<Self, JsValue> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or_else(|| JsValue::from_str("No document"))?;
+        let previous = deep_active_element().and_then(|e| e.dyn_into::<HtmlElement>().ok());
+        let sentinel = || -> Result<HtmlElement, JsValue> {
+            let sentinel: HtmlElement = document.create_element("span")?.unchecked_into();
+            sentinel.set_tab_index(0);
+            sentinel.set_attribute(SENTINEL, "")?;
+            sentinel.set_attribute("aria-hidden", "true")?;
+            Ok(sentinel)
+        };
+        let sentinels = [sentinel()?, sentinel()?];
+        let on_keydown = {
+            let container = container.clone();
+            Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if event.key() != "Tab" {
+                    return;
+                }
+                let tabbable = tabbable_elements(&container);
+                let (first, last) = match (tabbable.first(), tabbable.last()) {
+                    (Some(first), Some(last)) => (first, last),
+                    _ => {
+                        event.prevent_default();
+                        return;
+                    }
+                };
+                let active = deep_active_element();
+                let at_edge = if event.shift_key() { first } else { last };
+                if active
+                    .as_ref()
+                    .map(|a| a.is_same_node(Some(at_edge)))
+                    .unwrap_or(false)
+                {
+                    event.prevent_default();
+                    let _ = if event.shift_key() {
+                        last.focus()
+                    } else {
+                        first.focus()
+                    };
+                }
+            })
+        };
+        let on_sentinel_focus = {
+            let container = container.clone();
+            let start = sentinels[0].clone();
+            Closure::<dyn FnMut(FocusEvent)>::new(move |event: FocusEvent| {
+                // Focus reached a sentinel without going through our keydown handler
+                // so send it around to the other end of the container.
+                let tabbable = tabbable_elements(&container);
+                let from_start = event
+                    .target()
+                    .map(|t| start.is_same_node(t.dyn_ref()))
+                    .unwrap_or(false);
+                let target = if from_start {
+                    tabbable.last()
+                } else {
+                    tabbable.first()
+                };
+                match target {
+                    Some(target) => {
+                        let _ = target.focus();
+                    }
+                    None => {
+                        let _ = container.focus();
+                    }
+                }
+            })
+        };
+        container
+            .add_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref())?;
+        for sentinel in &sentinels {
+            sentinel.add_event_listener_with_callback(
+                "focus",
+                on_sentinel_focus.as_ref().unchecked_ref(),
+            )?;
+        }
+        container.prepend_with_node_1(&sentinels[0])?;
+        container.append_with_node_1(&sentinels[1])?;
+
+        match tabbable_elements(container).first() {
+            Some(first) => first.focus()?,
+            None => {
+                if !container.has_attribute("tabindex") {
+                    container.set_tab_index(-1);
+                }
+                container.focus()?;
+            }
+        }
+
+        let inner = Rc::new(Inner {
+            container: container.clone(),
+            previous,
+            sentinels,
+            on_keydown,
+            on_sentinel_focus,
+            active: Cell::new(true),
+        });
+        {
+            let inner = inner.clone();
+            on_disconnect(element, move || inner.deactivate());
+        }
+        Ok(Self { inner })
+    }
+
+    /// Returns true until the trap has been deactivated.
+    pub fn is_active(&self) -> bool {
+        self.inner.active.get()
+    }
+
+    /// Releases the trap and returns focus to the element that was focused
+    /// when the trap was activated.
+    pub fn deactivate(&self) {
+        self.inner.deactivate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_focus_trap_activate_and_restore() {
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let outside: HtmlElement = document.create_element("button").unwrap().unchecked_into();
+        let dialog: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        dialog.set_inner_html("<button id='first'>a</button><button id='last'>b</button>");
+        body.append_child(&outside).unwrap();
+        body.append_child(&dialog).unwrap();
+        outside.focus().unwrap();
+
+        let trap = FocusTrap::activate(&dialog, &dialog).unwrap();
+        assert!(trap.is_active());
+        assert_eq!(document.active_element().unwrap().id(), "first");
+        assert!(dialog
+            .first_element_child()
+            .unwrap()
+            .has_attribute(SENTINEL));
+
+        trap.deactivate();
+        assert!(!trap.is_active());
+        assert!(dialog
+            .query_selector("[data-focus-sentinel]")
+            .unwrap()
+            .is_none());
+        assert!(document
+            .active_element()
+            .unwrap()
+            .is_same_node(Some(&outside)));
+        body.remove_child(&dialog).unwrap();
+        body.remove_child(&outside).unwrap();
+    }
+}
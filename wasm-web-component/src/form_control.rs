@@ -0,0 +1,66 @@
+//! Value plumbing for components built on form controls.
+//!
+//! Components with `base_class = "HTMLInputElement"`, `"HTMLSelectElement"` or
+//! `"HTMLTextAreaElement"` are defined as customized built-in elements extending
+//! `<input>`, `<select>` or `<textarea>`. Their `value` gets reported to the
+//! [WebComponentBinding](crate::WebComponentBinding) `value_changed` callback however
+//! it changes, so components don't have to juggle the `value` attribute, which is
+//! only the default value, the `value` property and the `input` and `change` events.
+//!
+//! * Writes to the `value` property are reported as [ValueSource::Property].
+//! * `input` events are reported as [ValueSource::Input] with the value before the
+//!   edit.
+//! * `change` events are reported as [ValueSource::Commit] with the value of the last
+//!   commit, so typing and then leaving the control reports the edits one at a time
+//!   and the whole change at once.
+//!
+//! Callbacks only happen when the value actually differs. Changes of a `<select>`'s
+//! `selectedIndex` are only seen through its `input` and `change` events.
+//!
+//! ```ignore
+//! fn value_changed(&self, element: &HtmlElement, old: &str, new: &str, source: ValueSource) {
+//!     if source == ValueSource::Commit {
+//!         save(new);
+//!     }
+//! }
+//! ```
+//!
+//! Safari does not support customized built-in elements.
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+/// What changed the value of a form control component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueSource {
+    /// The `value` property was set.
+    Property,
+    /// The user edited the value.
+    Input,
+    /// The user committed the value, e.g. by leaving the control or pressing enter.
+    Commit,
+}
+
+impl ValueSource {
+    /// Returns the source for the name the generated class reports it with.
+    ///
+    /// This is called by the generated shims for you.
+    #[doc(hidden)]
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "input" => Self::Input,
+            "commit" => Self::Commit,
+            _ => Self::Property,
+        }
+    }
+}
+
+/// Whether the value of the form control component was changed by the user or through
+/// the `value` property since it was created, i.e. it no longer follows its `value`
+/// attribute.
+pub fn is_value_dirty(element: &Element) -> bool {
+    Reflect::get(element, &JsValue::from_str("_valueDirty"))
+        .ok()
+        .and_then(|dirty| dirty.as_bool())
+        .unwrap_or(false)
+}
@@ -0,0 +1,93 @@
+//! Batching DOM mutations in detached document fragments.
+//!
+//! Appending nodes to a connected element one at a time can make the browser
+//! recalculate styles and layout after every append when callbacks read layout in
+//! between. [with_fragment] builds the nodes in a detached `DocumentFragment` instead
+//! so they can be inserted in one operation, and [replace_children_efficiently] swaps
+//! all of an element's children at once.
+//!
+//! ```ignore
+//! let rows = with_fragment(|fragment| {
+//!     for item in &self.items {
+//!         let row = document.create_element("li")?;
+//!         row.set_text_content(Some(item));
+//!         fragment.append_child(&row)?;
+//!     }
+//!     Ok(())
+//! })?;
+//! list.append_child(&rows)?;
+//! ```
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DocumentFragment, Element, Node};
+
+use crate::environment::document;
+
+/// Returns a fragment with the nodes the build function appended to it.
+pub fn with_fragment<F>(build: F) -> Result<DocumentFragment, JsValue>
+where
+    F: FnOnce(&DocumentFragment) -> Result<(), JsValue>,
+{
+    let fragment = document()?.create_document_fragment();
+    build(&fragment)?;
+    Ok(fragment)
+}
+
+/// Replaces the children of the parent, an element or a shadow root, with the nodes in
+/// one operation.
+pub fn replace_children_efficiently<I, N>(parent: &Node, nodes: I) -> Result<(), JsValue>
+where
+    I: IntoIterator<Item = N>,
+    N: AsRef<Node>,
+{
+    let fragment = with_fragment(|fragment| {
+        for node in nodes {
+            fragment.append_child(node.as_ref())?;
+        }
+        Ok(())
+    })?;
+    if let Some(element) = parent.dyn_ref::<Element>() {
+        element.replace_children_with_node_1(&fragment);
+    } else if let Some(root) = parent.dyn_ref::<DocumentFragment>() {
+        root.replace_children_with_node_1(&fragment);
+    } else {
+        return Err(JsValue::from_str(
+            "Only elements and fragments have children",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_replace_children_efficiently() {
+        let document = window().unwrap().document().unwrap();
+        let list = document.create_element("ul").unwrap();
+        list.set_inner_html("<li>old</li>");
+        let items: Vec<Element> = ["one", "two"]
+            .iter()
+            .map(|text| {
+                let item = document.create_element("li").unwrap();
+                item.set_text_content(Some(text));
+                item
+            })
+            .collect();
+        replace_children_efficiently(&list, &items).unwrap();
+        assert_eq!(list.inner_html(), "<li>one</li><li>two</li>");
+
+        let fragment = with_fragment(|fragment| {
+            fragment.append_child(&document.create_text_node("three"))?;
+            Ok(())
+        })
+        .unwrap();
+        list.append_child(&fragment).unwrap();
+        assert_eq!(list.child_nodes().length(), 3);
+        assert!(!fragment.has_child_nodes());
+    }
+}
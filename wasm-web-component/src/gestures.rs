@@ -0,0 +1,404 @@
+//! Pointer gesture recognition.
+//!
+//! Components opt in by setting `gestures = true` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing
+//! [GestureHandler]. The element's pointer events are fed to a [GestureRecognizer]
+//! and every recognized [Gesture] is delivered to [GestureHandler::gesture]. The
+//! recognizer state of an element is dropped when the element gets disconnected.
+//!
+//! Browsers cancel the pointers of touches they use for scrolling or zooming so
+//! elements that recognize swipes or pinches usually want `touch-action: none`.
+use std::cell::RefCell;
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, HtmlElement, PointerEvent};
+
+use crate::cleanup::on_disconnect;
+
+/// The direction of a swipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A recognized gesture. Positions are in client coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// A pointer went down and up again without moving.
+    Tap { x: f64, y: f64 },
+    /// A second tap close to the previous one. The taps are delivered too.
+    DoubleTap { x: f64, y: f64 },
+    /// A pointer stayed down without moving. It doesn't end in a tap.
+    LongPress { x: f64, y: f64 },
+    /// A pointer moved far and fast before going up. The velocity is in pixels per
+    /// millisecond.
+    Swipe {
+        direction: SwipeDirection,
+        velocity: f64,
+    },
+    /// Two pointers moved. The scale is their distance relative to when the second
+    /// pointer went down.
+    Pinch { scale: f64 },
+}
+
+/// Thresholds for recognizing gestures. Distances are in pixels and durations in
+/// milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    /// How far a pointer may move and still tap or long press.
+    pub slop: f64,
+    /// How long a pointer has to stay down for a long press.
+    pub long_press: f64,
+    /// How soon after a tap another tap makes a double tap.
+    pub double_tap: f64,
+    /// How far a pointer has to move for a swipe.
+    pub swipe_distance: f64,
+    /// How long a swipe may take at most.
+    pub swipe_duration: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            slop: 10.0,
+            long_press: 500.0,
+            double_tap: 300.0,
+            swipe_distance: 50.0,
+            swipe_duration: 500.0,
+        }
+    }
+}
+
+/// Callbacks for a web component that recognizes gestures.
+pub trait GestureHandler {
+    /// The thresholds to recognize gestures with.
+    fn gesture_config(&self) -> GestureConfig {
+        GestureConfig::default()
+    }
+
+    /// Called with every recognized gesture.
+    fn gesture(&mut self, element: &HtmlElement, gesture: Gesture);
+}
+
+struct Pointer {
+    id: i32,
+    start: (f64, f64),
+    position: (f64, f64),
+    started: f64,
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Recognizes gestures from a sequence of pointer events. Times are in milliseconds.
+#[derive(Default)]
+pub struct GestureRecognizer {
+    pointers: Vec<Pointer>,
+    pinch_distance: Option<f64>,
+    /// Set once the current pointers can't end in a tap, swipe or long press.
+    cancelled: bool,
+    press: u32,
+    last_tap: Option<(f64, f64, f64)>,
+}
+
+impl GestureRecognizer {
+    /// Handles a pointer going down. Returns the press number that
+    /// [long_press_elapsed](Self::long_press_elapsed) has to be called with once the
+    /// long press duration has passed.
+    pub fn pointer_down(&mut self, id: i32, x: f64, y: f64, time: f64) -> u32 {
+        self.pointers.retain(|p| p.id != id);
+        self.pointers.push(Pointer {
+            id,
+            start: (x, y),
+            position: (x, y),
+            started: time,
+        });
+        if self.pointers.len() == 1 {
+            self.cancelled = false;
+            self.pinch_distance = None;
+        } else {
+            self.cancelled = true;
+            self.pinch_distance = Some(distance(
+                self.pointers[0].position,
+                self.pointers[1].position,
+            ));
+        }
+        self.press += 1;
+        self.press
+    }
+
+    /// Handles a pointer moving.
+    pub fn pointer_move(
+        &mut self,
+        config: &GestureConfig,
+        id: i32,
+        x: f64,
+        y: f64,
+    ) -> Option<Gesture> {
+        let pointer = self.pointers.iter_mut().find(|p| p.id == id)?;
+        pointer.position = (x, y);
+        if distance(pointer.start, pointer.position) > config.slop {
+            // Moved too far for a tap or long press but it might still be a swipe.
+            self.press += 1;
+        }
+        match (self.pinch_distance, self.pointers.as_slice()) {
+            (Some(initial), [first, second, ..]) if initial > 0.0 => Some(Gesture::Pinch {
+                scale: distance(first.position, second.position) / initial,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Handles a pointer going up.
+    pub fn pointer_up(
+        &mut self,
+        config: &GestureConfig,
+        id: i32,
+        x: f64,
+        y: f64,
+        time: f64,
+    ) -> Vec<Gesture> {
+        let index = match self.pointers.iter().position(|p| p.id == id) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        let pointer = self.pointers.remove(index);
+        let mut gestures = Vec::new();
+        if self.cancelled || !self.pointers.is_empty() {
+            self.pinch_distance = None;
+            return gestures;
+        }
+        self.press += 1;
+        let (dx, dy) = (x - pointer.start.0, y - pointer.start.1);
+        let moved = distance(pointer.start, (x, y));
+        let duration = time - pointer.started;
+        if moved <= config.slop {
+            gestures.push(Gesture::Tap { x, y });
+            match self.last_tap {
+                Some((tx, ty, tapped))
+                    if time - tapped <= config.double_tap
+                        && distance((tx, ty), (x, y)) <= config.slop * 2.0 =>
+                {
+                    gestures.push(Gesture::DoubleTap { x, y });
+                    self.last_tap = None;
+                }
+                _ => self.last_tap = Some((x, y, time)),
+            }
+        } else if moved >= config.swipe_distance && duration <= config.swipe_duration {
+            let direction = if dx.abs() > dy.abs() {
+                if dx > 0.0 {
+                    SwipeDirection::Right
+                } else {
+                    SwipeDirection::Left
+                }
+            } else if dy > 0.0 {
+                SwipeDirection::Down
+            } else {
+                SwipeDirection::Up
+            };
+            gestures.push(Gesture::Swipe {
+                direction,
+                velocity: moved / duration.max(1.0),
+            });
+        }
+        gestures
+    }
+
+    /// Handles a pointer getting cancelled by the browser.
+    pub fn pointer_cancel(&mut self, id: i32) {
+        self.pointers.retain(|p| p.id != id);
+        self.cancelled = true;
+        self.pinch_distance = None;
+        self.press += 1;
+    }
+
+    /// Returns a long press if the pointer of the press is still down and hasn't moved.
+    pub fn long_press_elapsed(&mut self, press: u32) -> Option<Gesture> {
+        if press != self.press || self.cancelled || self.pointers.len() != 1 {
+            return None;
+        }
+        // A long press doesn't end in a tap or swipe.
+        self.cancelled = true;
+        let (x, y) = self.pointers[0].position;
+        Some(Gesture::LongPress { x, y })
+    }
+}
+
+thread_local! {
+    static RECOGNIZERS: RefCell<Vec<(Element, GestureRecognizer)>> = const { RefCell::new(Vec::new()) };
+}
+
+fn with_recognizer<R>(element: &HtmlElement, f: impl FnOnce(&mut GestureRecognizer) -> R) -> R {
+    let (result, added) = RECOGNIZERS.with(|recognizers| {
+        let mut recognizers = recognizers.borrow_mut();
+        let index = recognizers
+            .iter()
+            .position(|(el, _)| Object::is(el, element));
+        let added = index.is_none();
+        let index = index.unwrap_or_else(|| {
+            recognizers.push((element.clone().into(), GestureRecognizer::default()));
+            recognizers.len() - 1
+        });
+        (f(&mut recognizers[index].1), added)
+    });
+    if added {
+        let element: Element = element.clone().into();
+        on_disconnect(&element.clone(), move || {
+            RECOGNIZERS.with(|recognizers| {
+                recognizers
+                    .borrow_mut()
+                    .retain(|(el, _)| !Object::is(el, &element));
+            });
+        });
+    }
+    result
+}
+
+/// Calls the element's `gestureTimeout` method with the press after the delay.
+fn schedule_long_press(element: &HtmlElement, press: u32, delay: f64) -> Result<(), JsValue> {
+    let timeout: Function =
+        Reflect::get(element, &JsValue::from_str("gestureTimeout"))?.dyn_into()?;
+    let callback: Function = timeout
+        .bind1(element, &JsValue::from(press))
+        .unchecked_into();
+    web_sys::window()
+        .ok_or_else(|| JsValue::from_str("No window"))?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(&callback, delay as i32)?;
+    Ok(())
+}
+
+/// Feeds a pointer event of the element to its recognizer. This is called by the
+/// generated shims for you.
+#[doc(hidden)]
+pub fn recognize_gesture<T: GestureHandler>(
+    component: &mut T,
+    element: &HtmlElement,
+    event: &Event,
+) {
+    let event = match event.dyn_ref::<PointerEvent>() {
+        Some(event) => event,
+        None => return,
+    };
+    let config = component.gesture_config();
+    let (id, x, y, time) = (
+        event.pointer_id(),
+        event.client_x() as f64,
+        event.client_y() as f64,
+        event.time_stamp(),
+    );
+    let gestures = match event.type_().as_str() {
+        "pointerdown" => {
+            let press = with_recognizer(element, |r| r.pointer_down(id, x, y, time));
+            // Keeps getting the pointer's events when it leaves the element.
+            let _ = element.set_pointer_capture(id);
+            let _ = schedule_long_press(element, press, config.long_press);
+            Vec::new()
+        }
+        "pointermove" => with_recognizer(element, |r| r.pointer_move(&config, id, x, y))
+            .into_iter()
+            .collect(),
+        "pointerup" => with_recognizer(element, |r| r.pointer_up(&config, id, x, y, time)),
+        "pointercancel" => {
+            with_recognizer(element, |r| r.pointer_cancel(id));
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    for gesture in gestures {
+        component.gesture(element, gesture);
+    }
+}
+
+/// Delivers a long press if the press is still going on. This is called by the
+/// generated shims for you.
+#[doc(hidden)]
+pub fn recognize_long_press<T: GestureHandler>(
+    component: &mut T,
+    element: &HtmlElement,
+    press: u32,
+) {
+    if let Some(gesture) = with_recognizer(element, |r| r.long_press_elapsed(press)) {
+        component.gesture(element, gesture);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_tap_and_double_tap() {
+        let config = GestureConfig::default();
+        let mut recognizer = GestureRecognizer::default();
+        recognizer.pointer_down(1, 10.0, 10.0, 0.0);
+        assert_eq!(
+            recognizer.pointer_up(&config, 1, 12.0, 10.0, 50.0),
+            vec![Gesture::Tap { x: 12.0, y: 10.0 }]
+        );
+        recognizer.pointer_down(1, 10.0, 10.0, 150.0);
+        assert_eq!(
+            recognizer.pointer_up(&config, 1, 10.0, 10.0, 200.0),
+            vec![
+                Gesture::Tap { x: 10.0, y: 10.0 },
+                Gesture::DoubleTap { x: 10.0, y: 10.0 }
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_long_press() {
+        let config = GestureConfig::default();
+        let mut recognizer = GestureRecognizer::default();
+        let press = recognizer.pointer_down(1, 10.0, 10.0, 0.0);
+        assert_eq!(
+            recognizer.long_press_elapsed(press),
+            Some(Gesture::LongPress { x: 10.0, y: 10.0 })
+        );
+        assert!(recognizer
+            .pointer_up(&config, 1, 10.0, 10.0, 600.0)
+            .is_empty());
+        // Moving or lifting the pointer in time cancels the long press.
+        let press = recognizer.pointer_down(1, 10.0, 10.0, 1000.0);
+        recognizer.pointer_move(&config, 1, 40.0, 10.0);
+        assert_eq!(recognizer.long_press_elapsed(press), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_swipe() {
+        let config = GestureConfig::default();
+        let mut recognizer = GestureRecognizer::default();
+        recognizer.pointer_down(1, 100.0, 10.0, 0.0);
+        recognizer.pointer_move(&config, 1, 60.0, 12.0);
+        assert_eq!(
+            recognizer.pointer_up(&config, 1, 20.0, 14.0, 100.0),
+            vec![Gesture::Swipe {
+                direction: SwipeDirection::Left,
+                velocity: distance((100.0, 10.0), (20.0, 14.0)) / 100.0,
+            }]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_pinch() {
+        let config = GestureConfig::default();
+        let mut recognizer = GestureRecognizer::default();
+        recognizer.pointer_down(1, 0.0, 0.0, 0.0);
+        recognizer.pointer_down(2, 100.0, 0.0, 10.0);
+        assert_eq!(
+            recognizer.pointer_move(&config, 2, 200.0, 0.0),
+            Some(Gesture::Pinch { scale: 2.0 })
+        );
+        assert!(recognizer
+            .pointer_up(&config, 2, 200.0, 0.0, 100.0)
+            .is_empty());
+        assert!(recognizer
+            .pointer_up(&config, 1, 0.0, 0.0, 110.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,884 @@
+//! Localization with messages in a subset of the Fluent syntax.
+//!
+//! Translations are written in the [Fluent](https://projectfluent.org) syntax, parsed
+//! into a [Bundle] per locale and registered with [register_bundle]. Render code then
+//! looks messages up with [t] for the document's language or [t_for] for the language
+//! of an element, i.e. the closest `lang` attribute of the element or its ancestors,
+//! crossing shadow roots.
+//!
+//! Components with `localized = true` in the
+//! [`#[web_component]`](crate::web_component) attribute get the
+//! [WebComponentBinding](crate::WebComponentBinding) `locale_changed` callback when
+//! they get connected, whenever a `lang` attribute changes the element's language and
+//! whenever a bundle gets registered so they can render again.
+//!
+//! ```ignore
+//! register_bundle(Bundle::new("en", r#"
+//! -brand = Acme
+//! welcome = Welcome to { -brand }, { $name }!
+//! emails = { $count ->
+//!     [one] You have one email.
+//!    *[other] You have { $count } emails.
+//! }
+//! "#)?);
+//!
+//! element.set_text_content(Some(&t_for(element, "emails", &[("count", 3.into())])));
+//! ```
+//!
+//! Only a subset of Fluent is supported, without the `fluent-bundle` crate and the
+//! size it adds to the binary. Messages, terms, attributes, variables, message and term
+//! references, string and number literals, multiline patterns and select expressions on
+//! strings, numbers and plural categories are supported. Sources using functions like
+//! `NUMBER`, parameterized terms or `\u` escapes are rejected with an [FtlError] by
+//! [Bundle::new]. Unlike `fluent-bundle` a whole source is rejected on the first error
+//! and placeables aren't wrapped in Unicode isolation marks.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Intl, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomEvent, Element, ShadowRoot};
+
+/// The event dispatched on the document when a bundle has been registered.
+pub const BUNDLES_CHANGED_EVENT: &str = "wasm-bundles-changed";
+
+/// A value passed to a message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    String(String),
+    Number(f64),
+}
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::String(value.to_owned())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::String(value)
+    }
+}
+
+macro_rules! number_arg {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Arg {
+            fn from(value: $t) -> Self {
+                Arg::Number(value as f64)
+            }
+        })*
+    };
+}
+
+number_arg!(i32, i64, u32, u64, usize, f32, f64);
+
+/// An error in a Fluent source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FtlError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FtlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for FtlError {}
+
+#[derive(Debug)]
+enum Inline {
+    String(String),
+    Number(f64),
+    Variable(String),
+    Message(String, Option<String>),
+    Term(String, Option<String>),
+    Placeable(Box<Expression>),
+}
+
+#[derive(Debug)]
+struct Variant {
+    key: String,
+    pattern: Pattern,
+}
+
+#[derive(Debug)]
+enum Expression {
+    Inline(Inline),
+    Select {
+        selector: Inline,
+        variants: Vec<Variant>,
+        default: usize,
+    },
+}
+
+#[derive(Debug)]
+enum Piece {
+    Text(String),
+    Placeable(Expression),
+}
+
+type Pattern = Vec<Piece>;
+
+/// Marks the start of a continuation line of a pattern until [dedent] has removed
+/// its indentation.
+const LINE_START: char = '\u{1}';
+
+/// Removes the indentation common to the continuation lines of a pattern. Lines with
+/// nothing but spaces don't count and lose their spaces.
+fn dedent(pieces: &mut Pattern) {
+    let count = pieces.len();
+    // The indentation of every line of the text pieces, None for blank lines. A line
+    // at the end of a piece followed by a placeable isn't blank.
+    let indents = |index: usize, text: &str| -> Vec<Option<usize>> {
+        let lines: Vec<&str> = text.split(LINE_START).collect();
+        let last = lines.len() - 1;
+        lines
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(line, text)| {
+                let rest = text.trim_start_matches(' ');
+                let blank = rest.is_empty() || rest.starts_with('\n');
+                let followed = line == last && index + 1 < count;
+                (!blank || (rest.is_empty() && followed)).then_some(text.len() - rest.len())
+            })
+            .collect()
+    };
+    let common = pieces
+        .iter()
+        .enumerate()
+        .filter_map(|(index, piece)| match piece {
+            Piece::Text(text) => Some(indents(index, text)),
+            Piece::Placeable(_) => None,
+        })
+        .flatten()
+        .flatten()
+        .min()
+        .unwrap_or(0);
+    for (index, piece) in pieces.iter_mut().enumerate() {
+        if let Piece::Text(text) = piece {
+            let lines = indents(index, text);
+            let mut parts = text.split(LINE_START);
+            let mut dedented = parts.next().unwrap_or_default().to_owned();
+            for (part, indent) in parts.zip(lines) {
+                dedented.push_str(match indent {
+                    Some(_) => &part[common..],
+                    None => part.trim_start_matches(' '),
+                });
+            }
+            *text = dedented;
+        }
+    }
+}
+
+type Entries = HashMap<String, Message>;
+
+#[derive(Debug, Default)]
+struct Message {
+    value: Option<Pattern>,
+    attributes: HashMap<String, Pattern>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, FtlError> {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1;
+        Err(FtlError {
+            line,
+            message: message.to_owned(),
+        })
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), FtlError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(&format!("Expected `{}`", c))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(
+            self.peek(),
+            Some(' ') | Some('\t') | Some('\n') | Some('\r')
+        ) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, FtlError> {
+        let start = self.pos;
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            return self.error("Expected an identifier");
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            self.pos += 1;
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn attribute_accessor(&mut self) -> Result<Option<String>, FtlError> {
+        if self.eat('.') {
+            Ok(Some(self.identifier()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The first character of the next line that isn't blank and its indentation.
+    fn next_line_start(&self) -> Option<(usize, char)> {
+        let mut pos = self.pos;
+        loop {
+            // Skips the newline the parser is at.
+            pos += 1;
+            let mut indent = 0;
+            while matches!(self.chars.get(pos), Some(' ') | Some('\t')) {
+                pos += 1;
+                indent += 1;
+            }
+            match self.chars.get(pos) {
+                Some('\n') | Some('\r') => continue,
+                Some(c) => return Some((indent, *c)),
+                None => return None,
+            }
+        }
+    }
+
+    /// Whether the pattern continues on the next line.
+    fn continues(&self, in_variant: bool) -> bool {
+        match self.next_line_start() {
+            Some((0, _)) | None => false,
+            Some((_, '[')) | Some((_, '*')) | Some((_, '}')) => !in_variant,
+            Some((_, '.')) => in_variant,
+            Some(_) => true,
+        }
+    }
+
+    fn pattern(&mut self, in_variant: bool) -> Result<Pattern, FtlError> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    self.pos += 1;
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Placeable(self.placeable()?));
+                }
+                '}' if in_variant => break,
+                '}' => return self.error("Unbalanced closing brace"),
+                '\r' => self.pos += 1,
+                '\n' if self.continues(in_variant) => {
+                    self.pos += 1;
+                    text.push('\n');
+                    text.push(LINE_START);
+                }
+                '\n' => break,
+                c => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        dedent(&mut pieces);
+        if let Some(Piece::Text(text)) = pieces.first_mut() {
+            *text = text.trim_start().to_owned();
+        }
+        if let Some(Piece::Text(text)) = pieces.last_mut() {
+            *text = text.trim_end().to_owned();
+        }
+        pieces.retain(|piece| !matches!(piece, Piece::Text(text) if text.is_empty()));
+        Ok(pieces)
+    }
+
+    fn inline(&mut self) -> Result<Inline, FtlError> {
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut value = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            self.pos += 1;
+                            match self.peek() {
+                                Some(c @ '"') | Some(c @ '\\') => value.push(c),
+                                _ => return self.error("Unknown escape sequence"),
+                            }
+                        }
+                        Some('\n') | None => return self.error("Unterminated string literal"),
+                        Some(c) => value.push(c),
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+                Ok(Inline::String(value))
+            }
+            Some(c) if c.is_ascii_digit() || (c == '-' && self.is_digit_at(1)) => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                match number.parse() {
+                    Ok(number) => Ok(Inline::Number(number)),
+                    Err(_) => self.error("Invalid number literal"),
+                }
+            }
+            Some('$') => {
+                self.pos += 1;
+                Ok(Inline::Variable(self.identifier()?))
+            }
+            Some('-') => {
+                self.pos += 1;
+                let id = self.identifier()?;
+                let attribute = self.attribute_accessor()?;
+                if self.peek() == Some('(') {
+                    return self.error("Parameterized terms aren't supported");
+                }
+                Ok(Inline::Term(id, attribute))
+            }
+            Some('{') => {
+                self.pos += 1;
+                Ok(Inline::Placeable(Box::new(self.placeable()?)))
+            }
+            _ => {
+                let id = self.identifier()?;
+                let attribute = self.attribute_accessor()?;
+                if self.peek() == Some('(') {
+                    return self.error("Functions aren't supported");
+                }
+                Ok(Inline::Message(id, attribute))
+            }
+        }
+    }
+
+    fn is_digit_at(&self, offset: usize) -> bool {
+        self.peek_at(offset).is_some_and(|c| c.is_ascii_digit())
+    }
+
+    /// Parses a placeable after its opening brace.
+    fn placeable(&mut self) -> Result<Expression, FtlError> {
+        self.skip_blank();
+        let selector = self.inline()?;
+        self.skip_blank();
+        if !(self.peek() == Some('-') && self.peek_at(1) == Some('>')) {
+            self.expect('}')?;
+            return Ok(Expression::Inline(selector));
+        }
+        self.pos += 2;
+        let mut variants = Vec::new();
+        let mut default = None;
+        loop {
+            self.skip_blank();
+            let is_default = self.eat('*');
+            if !self.eat('[') {
+                if is_default {
+                    return self.error("Expected `[`");
+                }
+                break;
+            }
+            self.skip_spaces();
+            let start = self.pos;
+            while !matches!(self.peek(), Some(']') | Some('\n') | None) {
+                self.pos += 1;
+            }
+            let key: String = self.chars[start..self.pos].iter().collect();
+            self.expect(']')?;
+            self.skip_spaces();
+            if is_default {
+                if default.is_some() {
+                    return self.error("Select expressions can only have one default variant");
+                }
+                default = Some(variants.len());
+            }
+            variants.push(Variant {
+                key: key.trim().to_owned(),
+                pattern: self.pattern(true)?,
+            });
+        }
+        self.skip_blank();
+        self.expect('}')?;
+        match default {
+            Some(default) => Ok(Expression::Select {
+                selector,
+                variants,
+                default,
+            }),
+            None => self.error("Select expressions need a default variant"),
+        }
+    }
+
+    fn resource(&mut self) -> Result<(Entries, Entries), FtlError> {
+        let mut messages = HashMap::new();
+        let mut terms = HashMap::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '#' | '\n' | '\r' => self.skip_line(),
+                ' ' | '\t' => {
+                    self.skip_spaces();
+                    if !matches!(self.peek(), Some('\n') | Some('\r') | None) {
+                        return self.error("Entries can't be indented");
+                    }
+                }
+                _ => {
+                    let is_term = self.eat('-');
+                    let id = self.identifier()?;
+                    self.skip_spaces();
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let mut message = Message::default();
+                    let value = self.pattern(false)?;
+                    if !value.is_empty() {
+                        message.value = Some(value);
+                    }
+                    while self.peek() == Some('\n')
+                        && matches!(self.next_line_start(), Some((indent, '.')) if indent > 0)
+                    {
+                        self.skip_blank();
+                        self.expect('.')?;
+                        let attribute = self.identifier()?;
+                        self.skip_spaces();
+                        self.expect('=')?;
+                        self.skip_spaces();
+                        let pattern = self.pattern(false)?;
+                        message.attributes.insert(attribute, pattern);
+                    }
+                    if is_term && message.value.is_none() {
+                        return self.error("Terms need a value");
+                    }
+                    if message.value.is_none() && message.attributes.is_empty() {
+                        return self.error("Messages need a value or an attribute");
+                    }
+                    if is_term {
+                        terms.insert(id, message);
+                    } else {
+                        messages.insert(id, message);
+                    }
+                }
+            }
+        }
+        Ok((messages, terms))
+    }
+}
+
+enum Value {
+    String(String),
+    Number(f64),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(value) => f.write_str(value),
+            Value::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Value::Number(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Message references nested deeper than this are assumed to be cyclic.
+const MAX_DEPTH: usize = 16;
+
+/// The Fluent messages of a locale.
+#[derive(Debug)]
+pub struct Bundle {
+    locale: String,
+    messages: HashMap<String, Message>,
+    terms: HashMap<String, Message>,
+}
+
+impl Bundle {
+    /// Parses the Fluent source with the messages of the locale. Fails on the first
+    /// syntax error or syntax that isn't supported.
+    pub fn new(locale: &str, source: &str) -> Result<Self, FtlError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let (messages, terms) = parser.resource()?;
+        Ok(Self {
+            locale: locale.to_owned(),
+            messages,
+            terms,
+        })
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Whether the bundle has the message. Attributes are looked up with
+    /// `message.attribute`.
+    pub fn has_message(&self, id: &str) -> bool {
+        self.pattern(&self.messages, id).is_some()
+    }
+
+    /// Formats the message, or one of its attributes with `message.attribute`.
+    pub fn format(&self, id: &str, args: &[(&str, Arg)]) -> Option<String> {
+        let pattern = self.pattern(&self.messages, id)?;
+        let mut out = String::new();
+        self.format_pattern(pattern, args, &mut out, 0);
+        Some(out)
+    }
+
+    fn pattern<'a>(&self, entries: &'a HashMap<String, Message>, id: &str) -> Option<&'a Pattern> {
+        let (id, attribute) = match id.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (id, None),
+        };
+        let message = entries.get(id)?;
+        match attribute {
+            Some(attribute) => message.attributes.get(attribute),
+            None => message.value.as_ref(),
+        }
+    }
+
+    fn format_pattern(
+        &self,
+        pattern: &Pattern,
+        args: &[(&str, Arg)],
+        out: &mut String,
+        depth: usize,
+    ) {
+        for piece in pattern {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Placeable(expression) => {
+                    out.push_str(&self.evaluate(expression, args, depth).to_string())
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, expression: &Expression, args: &[(&str, Arg)], depth: usize) -> Value {
+        match expression {
+            Expression::Inline(inline) => self.evaluate_inline(inline, args, depth),
+            Expression::Select {
+                selector,
+                variants,
+                default,
+            } => {
+                let selector = self.evaluate_inline(selector, args, depth);
+                let variant = variants
+                    .iter()
+                    .find(|variant| match &selector {
+                        Value::String(value) => variant.key == *value,
+                        Value::Number(value) => variant.key.parse::<f64>().ok() == Some(*value),
+                    })
+                    .or_else(|| match &selector {
+                        Value::Number(value) => {
+                            let category = plural_category(&self.locale, *value)?;
+                            variants.iter().find(|variant| variant.key == category)
+                        }
+                        Value::String(_) => None,
+                    })
+                    .unwrap_or(&variants[*default]);
+                let mut out = String::new();
+                self.format_pattern(&variant.pattern, args, &mut out, depth);
+                Value::String(out)
+            }
+        }
+    }
+
+    fn evaluate_inline(&self, inline: &Inline, args: &[(&str, Arg)], depth: usize) -> Value {
+        let reference = |entries: &HashMap<String, Message>,
+                         id: &str,
+                         attribute: &Option<String>,
+                         shown: String| {
+            let id = match attribute {
+                Some(attribute) => format!("{}.{}", id, attribute),
+                None => id.to_owned(),
+            };
+            match self.pattern(entries, &id) {
+                Some(pattern) if depth < MAX_DEPTH => {
+                    let mut out = String::new();
+                    // Terms only see the arguments passed to them, which aren't supported.
+                    let args = if std::ptr::eq(entries, &self.terms) {
+                        &[]
+                    } else {
+                        args
+                    };
+                    self.format_pattern(pattern, args, &mut out, depth + 1);
+                    Value::String(out)
+                }
+                _ => Value::String(shown),
+            }
+        };
+        match inline {
+            Inline::String(value) => Value::String(value.clone()),
+            Inline::Number(value) => Value::Number(*value),
+            Inline::Variable(name) => match args.iter().find(|(arg, _)| arg == name) {
+                Some((_, Arg::String(value))) => Value::String(value.clone()),
+                Some((_, Arg::Number(value))) => Value::Number(*value),
+                None => Value::String(format!("{{${}}}", name)),
+            },
+            Inline::Message(id, attribute) => {
+                reference(&self.messages, id, attribute, format!("{{{}}}", id))
+            }
+            Inline::Term(id, attribute) => {
+                reference(&self.terms, id, attribute, format!("{{-{}}}", id))
+            }
+            Inline::Placeable(expression) => self.evaluate(expression, args, depth),
+        }
+    }
+}
+
+/// The plural category of the number in the locale, e.g. `one` or `other`.
+fn plural_category(locale: &str, number: f64) -> Option<String> {
+    let intl = Reflect::get(&js_sys::global(), &JsValue::from_str("Intl")).ok()?;
+    let constructor: Function = Reflect::get(&intl, &JsValue::from_str("PluralRules"))
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    // The constructor throws for invalid locales.
+    let rules: Intl::PluralRules =
+        Reflect::construct(&constructor, &Array::of1(&JsValue::from_str(locale)))
+            .ok()?
+            .unchecked_into();
+    rules.select(number).as_string()
+}
+
+struct Registry {
+    bundles: Vec<Rc<Bundle>>,
+    default_locale: String,
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry {
+        bundles: Vec::new(),
+        default_locale: "en".to_owned(),
+    });
+}
+
+/// Registers the bundle and notifies localized components so they can render again.
+/// Messages are looked up in the bundles of a locale in the order they got registered.
+pub fn register_bundle(bundle: Bundle) {
+    REGISTRY.with(|registry| registry.borrow_mut().bundles.push(Rc::new(bundle)));
+    if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+        if let Ok(event) = CustomEvent::new(BUNDLES_CHANGED_EVENT) {
+            let _ = document.dispatch_event(&event);
+        }
+    }
+}
+
+/// Sets the locale used when neither a message's locale nor its language has a
+/// bundle with the message. Defaults to `en`.
+pub fn set_default_locale(locale: &str) {
+    REGISTRY.with(|registry| registry.borrow_mut().default_locale = locale.to_owned());
+}
+
+/// The language of the document, i.e. the `lang` attribute of the `<html>` element,
+/// or else the browser's language.
+pub fn document_locale() -> String {
+    let window = web_sys::window();
+    let lang = window
+        .as_ref()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element())
+        .and_then(|html| html.get_attribute("lang"))
+        .filter(|lang| !lang.is_empty());
+    let navigator = || {
+        let navigator = Reflect::get(window.as_ref()?, &JsValue::from_str("navigator")).ok()?;
+        Reflect::get(&navigator, &JsValue::from_str("language"))
+            .ok()?
+            .as_string()
+    };
+    lang.or_else(navigator)
+        .unwrap_or_else(|| REGISTRY.with(|registry| registry.borrow().default_locale.clone()))
+}
+
+/// The language of the element, i.e. the closest `lang` attribute of the element or
+/// its ancestors, crossing shadow roots, or else the [document_locale].
+pub fn element_locale(element: &Element) -> String {
+    let mut node = Some(element.clone());
+    while let Some(element) = node {
+        let lang = element
+            .closest("[lang]")
+            .ok()
+            .flatten()
+            .and_then(|element| element.get_attribute("lang"))
+            .filter(|lang| !lang.is_empty());
+        if let Some(lang) = lang {
+            return lang;
+        }
+        node = element
+            .get_root_node()
+            .dyn_into::<ShadowRoot>()
+            .ok()
+            .map(|root| root.host());
+    }
+    document_locale()
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// Formats the message in the locale. Falls back to the bundles of the locale's
+/// language and then of the default locale. Messages that aren't in any of them are
+/// returned as their id.
+pub fn t_in(locale: &str, id: &str, args: &[(&str, Arg)]) -> String {
+    let (bundles, default_locale) = REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        (registry.bundles.clone(), registry.default_locale.clone())
+    });
+    let candidates = [
+        locale,
+        language(locale),
+        &default_locale,
+        language(&default_locale),
+    ];
+    for candidate in candidates {
+        let message = bundles
+            .iter()
+            .filter(|bundle| bundle.locale.eq_ignore_ascii_case(candidate))
+            .find_map(|bundle| bundle.format(id, args));
+        if let Some(message) = message {
+            return message;
+        }
+    }
+    id.to_owned()
+}
+
+/// Formats the message in the [document_locale].
+pub fn t(id: &str, args: &[(&str, Arg)]) -> String {
+    t_in(&document_locale(), id, args)
+}
+
+/// Formats the message in the [element_locale] of the element.
+pub fn t_for(element: &Element, id: &str, args: &[(&str, Arg)]) -> String {
+    t_in(&element_locale(element), id, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_bundle_format() {
+        let bundle = Bundle::new(
+            "en",
+            r#"
+# A comment
+-brand = Acme
+welcome = Welcome to { -brand }, { $name }!
+    .title = { welcome }
+multiline =
+    First line
+      indented line
+
+    last line
+quoted = { "{" } and { 42 }
+emails = { $count ->
+    [0] No emails.
+    [one] One email.
+   *[other] { $count } emails.
+}
+mode = { $mode ->
+    [dark] Dark
+   *[light] Light
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            bundle.format("welcome", &[("name", "Ann".into())]).unwrap(),
+            "Welcome to Acme, Ann!"
+        );
+        assert_eq!(
+            bundle.format("welcome.title", &[]).unwrap(),
+            "Welcome to Acme, {$name}!"
+        );
+        assert_eq!(
+            bundle.format("multiline", &[]).unwrap(),
+            "First line\n  indented line\n\nlast line"
+        );
+        assert_eq!(bundle.format("quoted", &[]).unwrap(), "{ and 42");
+        assert_eq!(
+            bundle.format("emails", &[("count", 0.into())]).unwrap(),
+            "No emails."
+        );
+        assert_eq!(
+            bundle.format("emails", &[("count", 5.into())]).unwrap(),
+            "5 emails."
+        );
+        assert_eq!(
+            bundle.format("mode", &[("mode", "dark".into())]).unwrap(),
+            "Dark"
+        );
+        assert_eq!(bundle.format("mode", &[]).unwrap(), "Light");
+        assert!(!bundle.has_message("brand"));
+        assert_eq!(bundle.format("missing", &[]), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bundle_errors() {
+        let error =
+            Bundle::new("en", "ok = fine\nbroken = { $count ->\n    [one] One\n}\n").unwrap_err();
+        assert_eq!(error.line, 4);
+        assert!(Bundle::new("en", "broken = {").is_err());
+        assert!(Bundle::new("en", "-term =\n    .attr = only").is_err());
+        // Unsupported syntax is rejected instead of being formatted as text.
+        assert!(Bundle::new("en", "price = { NUMBER($amount) }").is_err());
+        assert!(Bundle::new("en", "-brand = Acme\nhi = { -brand(case: \"x\") }").is_err());
+        assert!(Bundle::new("en", "smile = { \"\\u263A\" }").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Persisting component state in IndexedDB.
+//!
+//! Components opt in by setting `persist_idb = "key"` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing
+//! [IdbState]. The state is loaded the first time the element gets connected and
+//! saved when it gets disconnected or the page gets hidden. Like persisted attributes
+//! it is keyed by the element name, the key from the attribute and the element's
+//! optional `persist-key` attribute.
+//!
+//! Every saved state records the [IdbState::VERSION] it was saved with. States saved
+//! by an older version are passed through [IdbState::migrate] one version at a time
+//! before they are loaded.
+use js_sys::{Function, Promise, Reflect};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement};
+
+/// State of a web component that is persisted in IndexedDB.
+pub trait IdbState {
+    /// The serializable state of the component.
+    type State: Serialize + DeserializeOwned;
+
+    /// The version of the state. Bump it and handle the old version in
+    /// [migrate](Self::migrate) when the state changes incompatibly.
+    const VERSION: u32 = 1;
+
+    /// Returns the state to save.
+    fn save_state(&self) -> Self::State;
+
+    /// Loads a saved state.
+    fn load_state(&mut self, element: &HtmlElement, state: Self::State);
+
+    /// Migrates a state saved with version `from` to version `from + 1`. Returns None to
+    /// discard the state, which is the default.
+    fn migrate(&self, _from: u32, _state: Value) -> Option<Value> {
+        None
+    }
+
+    /// Called when a saved state couldn't be read from IndexedDB or loaded. A state
+    /// that couldn't be read is read again the next time the element gets connected and
+    /// the state isn't saved until then.
+    fn state_error(&mut self, _element: &HtmlElement, _error: JsValue) {
+        // noop
+    }
+}
+
+fn to_js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Encodes the state of a component for saving. This is called by the generated shims
+/// for you.
+#[doc(hidden)]
+pub fn idb_encode<T: IdbState>(component: &T) -> Result<String, JsValue> {
+    let state = serde_json::to_value(component.save_state()).map_err(to_js_error)?;
+    serde_json::to_string(&json!({ "version": T::VERSION, "state": state })).map_err(to_js_error)
+}
+
+fn decode<T: IdbState>(component: &T, stored: &str) -> Result<Option<T::State>, JsValue> {
+    let mut stored: Value = serde_json::from_str(stored).map_err(to_js_error)?;
+    let mut version = stored
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| JsValue::from_str("The saved state has no version"))?
+        as u32;
+    if version > T::VERSION {
+        return Err(JsValue::from_str(&format!(
+            "The saved state has version {} which is newer than {}",
+            version,
+            T::VERSION
+        )));
+    }
+    let mut state = stored["state"].take();
+    while version < T::VERSION {
+        state = match component.migrate(version, state) {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        version += 1;
+    }
+    serde_json::from_value(state).map(Some).map_err(to_js_error)
+}
+
+/// Loads a saved state into a component. This is called by the generated shims for
+/// you.
+#[doc(hidden)]
+pub fn idb_load<T: IdbState>(component: &mut T, element: &HtmlElement, stored: &str) {
+    match decode(component, stored) {
+        Ok(Some(state)) => component.load_state(element, state),
+        Ok(None) => {}
+        Err(error) => component.state_error(element, error),
+    }
+}
+
+fn call_method(element: &Element, name: &str) -> Result<Promise, JsValue> {
+    let method: Function = Reflect::get(element, &JsValue::from_str(name))?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("The element doesn't persist its state"))?;
+    Ok(method.call0(element)?.unchecked_into())
+}
+
+/// Saves the element's state right away instead of waiting for it to get
+/// disconnected. The returned promise resolves once the state has been written.
+pub fn save_idb_state(element: &Element) -> Result<Promise, JsValue> {
+    call_method(element, "saveIdbState")
+}
+
+/// Deletes the element's saved state. The state of the element itself is left alone.
+pub fn clear_idb_state(element: &Element) -> Result<Promise, JsValue> {
+    call_method(element, "clearIdbState")
+}
@@ -0,0 +1,81 @@
+//! What a web component element looks like when it gets constructed.
+//!
+//! The constructor is the only place where some setup is allowed by the custom
+//! elements spec, e.g. attaching element internals before anything else does or
+//! deciding whether to hydrate a declarative shadow root rather than attaching a new
+//! one. [WebComponentBinding::init_with](crate::WebComponentBinding::init_with) gets an
+//! [InitContext] with the information needed to make those decisions.
+use js_sys::Reflect;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{HtmlElement, ShadowRoot};
+
+/// Construction time information about a web component element.
+#[derive(Clone, Debug)]
+pub struct InitContext {
+    shadow_root: Option<ShadowRoot>,
+    attributes: Vec<(String, String)>,
+    upgrade: bool,
+}
+
+impl InitContext {
+    /// Captures the context of the element being constructed. This is called by the
+    /// generated shims for you.
+    #[doc(hidden)]
+    pub fn new(element: &HtmlElement) -> Self {
+        // A closed declarative shadow root is only reachable through the internals.
+        let shadow_root = element.shadow_root().or_else(|| {
+            Reflect::get(element, &JsValue::from_str("_internals"))
+                .ok()
+                .filter(|internals| internals.is_object())
+                .and_then(|internals| {
+                    Reflect::get(&internals, &JsValue::from_str("shadowRoot")).ok()
+                })
+                .and_then(|root| root.dyn_into::<ShadowRoot>().ok())
+        });
+        let attributes = element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .filter_map(|name| {
+                let value = element.get_attribute(&name)?;
+                Some((name, value))
+            })
+            .collect();
+        Self {
+            shadow_root,
+            attributes,
+            upgrade: element.is_connected(),
+        }
+    }
+
+    /// The shadow root the element already has, i.e. one created from a declarative
+    /// `<template shadowrootmode>` in the HTML.
+    pub fn shadow_root(&self) -> Option<&ShadowRoot> {
+        self.shadow_root.as_ref()
+    }
+
+    /// Whether the element has a declarative shadow root to hydrate.
+    pub fn has_declarative_shadow_root(&self) -> bool {
+        self.shadow_root.is_some()
+    }
+
+    /// The attributes the element had when it got constructed. Elements created with
+    /// `document.createElement` have none yet.
+    pub fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+
+    /// The value of an attribute the element had when it got constructed.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether an element that was already in the document got upgraded, e.g. because
+    /// it was parsed before the component was defined.
+    pub fn is_upgrade(&self) -> bool {
+        self.upgrade
+    }
+}
@@ -0,0 +1,577 @@
+//! Masked and numeric text inputs.
+//!
+//! An [InputFormatter] keeps the value of an `<input>` formatted while the user types,
+//! e.g. inside a form-associated component's shadow root. Characters the
+//! [InputFormat] doesn't accept are rejected in `beforeinput` and the value is
+//! reformatted on every `input` without moving the caret away from the character the
+//! user just typed or deleted.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     let input: HtmlInputElement = element
+//!         .shadow_root().unwrap()
+//!         .query_selector("input").unwrap().unwrap()
+//!         .unchecked_into();
+//!     InputFormatter::attach(element, &input, Mask::new("(999) 999-9999")).unwrap();
+//! }
+//! ```
+//!
+//! The formatter's listeners are added when it is attached so `input` listeners added
+//! afterwards, e.g. one calling [set_form_value](crate::set_form_value), see the
+//! formatted value.
+use std::cell::Cell;
+use std::rc::{Rc, Weak};
+
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Event, HtmlElement, HtmlInputElement, InputEvent};
+
+use crate::cleanup::on_disconnect;
+
+/// A format applied to the value of an input while the user types.
+pub trait InputFormat {
+    /// Formats the value.
+    fn format(&self, value: &str) -> String;
+
+    /// Whether the character carries meaning in a value, as opposed to separators the
+    /// format inserts itself. The caret is kept after the same number of significant
+    /// characters when the value gets reformatted.
+    fn is_significant(&self, c: char) -> bool;
+
+    /// Whether the user may type or paste the character.
+    fn accepts(&self, c: char) -> bool {
+        self.is_significant(c)
+    }
+}
+
+/// Formats the value and moves the caret, a character offset into the value, to the
+/// same significant character in the formatted value.
+pub fn format_with_caret<F: InputFormat + ?Sized>(
+    format: &F,
+    value: &str,
+    caret: usize,
+) -> (String, usize) {
+    let significant = value
+        .chars()
+        .take(caret)
+        .filter(|c| format.is_significant(*c))
+        .count();
+    let formatted = format.format(value);
+    let mut seen = 0;
+    let mut caret = 0;
+    for (i, c) in formatted.chars().enumerate() {
+        if seen == significant {
+            break;
+        }
+        if format.is_significant(c) {
+            seen += 1;
+        }
+        caret = i + 1;
+    }
+    (formatted, caret)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Digit,
+    Letter,
+    Alphanumeric,
+    Literal(char),
+}
+
+impl Slot {
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Slot::Digit => c.is_ascii_digit(),
+            Slot::Letter => c.is_alphabetic(),
+            Slot::Alphanumeric => c.is_alphanumeric(),
+            Slot::Literal(_) => false,
+        }
+    }
+}
+
+/// A fixed pattern like a phone number or a date. In the pattern `9` stands for a
+/// digit, `a` for a letter and `*` for a letter or digit. Every other character is
+/// inserted by the mask. A backslash inserts the next character literally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    slots: Vec<Slot>,
+}
+
+impl Mask {
+    pub fn new(pattern: &str) -> Self {
+        let mut slots = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            slots.push(match c {
+                '9' => Slot::Digit,
+                'a' => Slot::Letter,
+                '*' => Slot::Alphanumeric,
+                '\\' => match chars.next() {
+                    Some(c) => Slot::Literal(c),
+                    None => break,
+                },
+                c => Slot::Literal(c),
+            });
+        }
+        Self { slots }
+    }
+
+    /// Fills the mask with the value's characters. Characters that don't fit the next
+    /// slot are dropped and the value is cut off at the end of the mask. Literals are
+    /// only inserted in front of a filled slot.
+    pub fn apply(&self, value: &str) -> String {
+        let mut chars = value.chars().filter(|c| self.is_significant(*c));
+        let mut formatted = String::new();
+        let mut literals = String::new();
+        for slot in &self.slots {
+            if let Slot::Literal(literal) = slot {
+                literals.push(*literal);
+                continue;
+            }
+            let c = loop {
+                match chars.next() {
+                    Some(c) if slot.accepts(c) => break Some(c),
+                    Some(_) => continue,
+                    None => break None,
+                }
+            };
+            match c {
+                Some(c) => {
+                    formatted.push_str(&literals);
+                    literals.clear();
+                    formatted.push(c);
+                }
+                None => break,
+            }
+        }
+        formatted
+    }
+
+    /// Returns the characters filling the mask's slots, e.g. the digits of a phone
+    /// number without its punctuation.
+    pub fn strip(&self, value: &str) -> String {
+        self.apply(value)
+            .chars()
+            .zip(&self.slots)
+            .filter(|(_, slot)| !matches!(slot, Slot::Literal(_)))
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// Whether every slot of the mask is filled.
+    pub fn is_complete(&self, value: &str) -> bool {
+        self.apply(value).chars().count() == self.slots.len()
+    }
+}
+
+impl InputFormat for Mask {
+    fn format(&self, value: &str) -> String {
+        self.apply(value)
+    }
+
+    fn is_significant(&self, c: char) -> bool {
+        self.slots.iter().any(|slot| slot.accepts(c))
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        self.is_significant(c) || self.slots.contains(&Slot::Literal(c))
+    }
+}
+
+/// Decimal numbers written with a locale's group and decimal separators, e.g.
+/// `1,234.5` in English and `1.234,5` in German.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecimalFormat {
+    group: char,
+    decimal: char,
+    max_fraction_digits: Option<usize>,
+    negative: bool,
+}
+
+impl DecimalFormat {
+    pub fn new(group: char, decimal: char) -> Self {
+        Self {
+            group,
+            decimal,
+            max_fraction_digits: None,
+            negative: true,
+        }
+    }
+
+    /// Uses the separators `Intl.NumberFormat` uses for the locale.
+    pub fn for_locale(locale: &str) -> Result<Self, JsValue> {
+        let intl = Reflect::get(&js_sys::global(), &JsValue::from_str("Intl"))?;
+        let constructor: Function =
+            Reflect::get(&intl, &JsValue::from_str("NumberFormat"))?.dyn_into()?;
+        // The constructor throws for invalid locales.
+        let formatter = Reflect::construct(
+            &constructor,
+            &Array::of2(&JsValue::from_str(locale), &Object::new()),
+        )?;
+        let format_to_parts: Function =
+            Reflect::get(&formatter, &JsValue::from_str("formatToParts"))?.dyn_into()?;
+        let parts: Array = format_to_parts
+            .call1(&formatter, &JsValue::from_f64(1234567.5))?
+            .dyn_into()?;
+        let mut format = Self::new(',', '.');
+        for part in parts.iter() {
+            let kind = Reflect::get(&part, &JsValue::from_str("type"))?.as_string();
+            let separator = Reflect::get(&part, &JsValue::from_str("value"))?
+                .as_string()
+                .and_then(|value| value.chars().next());
+            match (kind.as_deref(), separator) {
+                (Some("group"), Some(c)) => format.group = c,
+                (Some("decimal"), Some(c)) => format.decimal = c,
+                _ => {}
+            }
+        }
+        Ok(format)
+    }
+
+    /// Limits the number of digits after the decimal separator. Zero only allows whole
+    /// numbers.
+    pub fn max_fraction_digits(mut self, digits: usize) -> Self {
+        self.max_fraction_digits = Some(digits);
+        self
+    }
+
+    /// Whether negative numbers may be entered. Defaults to true.
+    pub fn allow_negative(mut self, negative: bool) -> Self {
+        self.negative = negative;
+        self
+    }
+
+    pub fn group_separator(&self) -> char {
+        self.group
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        self.decimal
+    }
+
+    /// Splits the value into its sign, integer digits and fraction digits if it has a
+    /// decimal separator.
+    fn split(&self, value: &str) -> (bool, String, Option<String>) {
+        let value = value.trim();
+        let negative = self.negative && value.starts_with('-');
+        let mut integer = String::new();
+        let mut fraction: Option<String> = None;
+        for c in value.chars() {
+            if c == self.decimal && self.max_fraction_digits != Some(0) {
+                fraction.get_or_insert_with(String::new);
+            } else if c.is_ascii_digit() {
+                match &mut fraction {
+                    Some(fraction) => {
+                        if self
+                            .max_fraction_digits
+                            .is_none_or(|max| fraction.len() < max)
+                        {
+                            fraction.push(c);
+                        }
+                    }
+                    None => integer.push(c),
+                }
+            }
+        }
+        (negative, integer, fraction)
+    }
+
+    /// Parses a value written in the format. Group separators and whitespace are
+    /// ignored. Returns None if the value has no digits.
+    pub fn parse(&self, value: &str) -> Option<f64> {
+        let (negative, integer, fraction) = self.split(value);
+        if integer.is_empty() && fraction.as_ref().is_none_or(String::is_empty) {
+            return None;
+        }
+        let mut number = String::new();
+        if negative {
+            number.push('-');
+        }
+        number.push_str(if integer.is_empty() { "0" } else { &integer });
+        if let Some(fraction) = fraction {
+            number.push('.');
+            number.push_str(&fraction);
+        }
+        number.parse().ok()
+    }
+}
+
+impl InputFormat for DecimalFormat {
+    /// Groups the integer digits and keeps the fraction as typed so far, including a
+    /// trailing decimal separator.
+    fn format(&self, value: &str) -> String {
+        let (negative, integer, fraction) = self.split(value);
+        let mut formatted = String::new();
+        if negative {
+            formatted.push('-');
+        }
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push(self.group);
+            }
+            formatted.push(c);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal);
+            formatted.push_str(&fraction);
+        }
+        formatted
+    }
+
+    fn is_significant(&self, c: char) -> bool {
+        c.is_ascii_digit() || c == self.decimal || (self.negative && c == '-')
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        self.is_significant(c) || c == self.group || c.is_whitespace()
+    }
+}
+
+struct Inner {
+    input: HtmlInputElement,
+    format: Box<dyn InputFormat>,
+    on_beforeinput: Closure<dyn FnMut(InputEvent)>,
+    on_input: Closure<dyn FnMut(Event)>,
+    attached: Cell<bool>,
+}
+
+impl Inner {
+    fn handle_beforeinput(&self, event: &InputEvent) {
+        if !event.input_type().starts_with("insert") {
+            return;
+        }
+        if let Some(data) = event.data() {
+            if !data.chars().all(|c| self.format.accepts(c)) {
+                event.prevent_default();
+            }
+        }
+    }
+
+    fn reformat(&self) {
+        let value = self.input.value();
+        let caret = self
+            .input
+            .selection_start()
+            .ok()
+            .flatten()
+            .map(|caret| utf16_to_char_offset(&value, caret as usize))
+            .unwrap_or_else(|| value.chars().count());
+        let (formatted, caret) = format_with_caret(self.format.as_ref(), &value, caret);
+        if formatted == value {
+            return;
+        }
+        self.input.set_value(&formatted);
+        let caret = char_to_utf16_offset(&formatted, caret) as u32;
+        // Inputs like type=number don't support selections.
+        let _ = self.input.set_selection_range(caret, caret);
+    }
+
+    fn detach(&self) {
+        if !self.attached.replace(false) {
+            return;
+        }
+        let _ = self.input.remove_event_listener_with_callback(
+            "beforeinput",
+            self.on_beforeinput.as_ref().unchecked_ref(),
+        );
+        let _ = self
+            .input
+            .remove_event_listener_with_callback("input", self.on_input.as_ref().unchecked_ref());
+    }
+}
+
+fn utf16_to_char_offset(value: &str, offset: usize) -> usize {
+    let mut units = 0;
+    value
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= offset
+        })
+        .count()
+}
+
+fn char_to_utf16_offset(value: &str, offset: usize) -> usize {
+    value.chars().take(offset).map(char::len_utf16).sum()
+}
+
+/// Keeps the value of an input formatted while the user types. Cloning returns a
+/// handle to the same formatter.
+#[derive(Clone)]
+pub struct InputFormatter {
+    inner: Rc<Inner>,
+}
+
+impl InputFormatter {
+    /// Formats the input's value with the format until the formatter is detached or the
+    /// component element gets disconnected. The current value is formatted right away.
+    pub fn attach<F: InputFormat + 'static>(
+        element: &HtmlElement,
+        input: &HtmlInputElement,
+        format: F,
+    ) -> Result<Self, JsValue> {
+        let inner = Rc::new_cyclic(|weak: &Weak<Inner>| {
+            let on_beforeinput = {
+                let weak = weak.clone();
+                Closure::<dyn FnMut(InputEvent)>::new(move |event: InputEvent| {
+                    if let Some(inner) = weak.upgrade() {
+                        inner.handle_beforeinput(&event);
+                    }
+                })
+            };
+            let on_input = {
+                let weak = weak.clone();
+                Closure::<dyn FnMut(Event)>::new(move |_event: Event| {
+                    if let Some(inner) = weak.upgrade() {
+                        inner.reformat();
+                    }
+                })
+            };
+            Inner {
+                input: input.clone(),
+                format: Box::new(format),
+                on_beforeinput,
+                on_input,
+                attached: Cell::new(true),
+            }
+        });
+        input.add_event_listener_with_callback(
+            "beforeinput",
+            inner.on_beforeinput.as_ref().unchecked_ref(),
+        )?;
+        input.add_event_listener_with_callback("input", inner.on_input.as_ref().unchecked_ref())?;
+
+        let formatter = Self { inner };
+        formatter.set_value(&formatter.inner.input.value());
+        {
+            let inner = formatter.inner.clone();
+            on_disconnect(element, move || inner.detach());
+        }
+        Ok(formatter)
+    }
+
+    /// The input's formatted value.
+    pub fn value(&self) -> String {
+        self.inner.input.value()
+    }
+
+    /// Formats the value and sets it on the input, e.g. when the component's value
+    /// gets set from outside.
+    pub fn set_value(&self, value: &str) {
+        self.inner.input.set_value(&self.inner.format.format(value));
+    }
+
+    /// Stops formatting the input. The input keeps its current value.
+    pub fn detach(&self) {
+        self.inner.detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_mask() {
+        let phone = Mask::new("(999) 999-9999");
+        assert_eq!(phone.apply(""), "");
+        assert_eq!(phone.apply("5"), "(5");
+        assert_eq!(phone.apply("555"), "(555");
+        assert_eq!(phone.apply("5551"), "(555) 1");
+        assert_eq!(phone.apply("(555) 123-4567 89"), "(555) 123-4567");
+        assert_eq!(phone.apply("55a5"), "(555");
+        assert_eq!(phone.strip("(555) 123-45"), "55512345");
+        assert!(!phone.is_complete("(555) 123-456"));
+        assert!(phone.is_complete("5551234567"));
+        assert!(phone.accepts('('));
+        assert!(!phone.accepts('x'));
+
+        let plate = Mask::new("aa\\9-999");
+        assert_eq!(plate.apply("ab12"), "ab9-12");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_decimal_format() {
+        let en = DecimalFormat::new(',', '.');
+        assert_eq!(en.format("1234567.5"), "1,234,567.5");
+        assert_eq!(en.format("1,2345"), "12,345");
+        assert_eq!(en.format("-12."), "-12.");
+        assert_eq!(en.format(".5"), ".5");
+        assert_eq!(en.parse("1,234.5"), Some(1234.5));
+        assert_eq!(en.parse("-.5"), Some(-0.5));
+        assert_eq!(en.parse(""), None);
+        assert_eq!(en.parse("-"), None);
+
+        let de = DecimalFormat::new('.', ',')
+            .max_fraction_digits(2)
+            .allow_negative(false);
+        assert_eq!(de.format("-1234,567"), "1.234,56");
+        assert_eq!(de.parse("1.234,5"), Some(1234.5));
+        assert_eq!(
+            DecimalFormat::new(',', '.')
+                .max_fraction_digits(0)
+                .format("12.5"),
+            "125"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_format_with_caret() {
+        let en = DecimalFormat::new(',', '.');
+        // Typing the 4th digit at the end moves the caret past the inserted group.
+        assert_eq!(format_with_caret(&en, "1234", 4), ("1,234".to_owned(), 5));
+        // Typing in the middle keeps the caret after the typed digit.
+        assert_eq!(
+            format_with_caret(&en, "1,2934", 4),
+            ("12,934".to_owned(), 4)
+        );
+        // Deleting a digit keeps the caret in place.
+        assert_eq!(format_with_caret(&en, "1,34", 2), ("134".to_owned(), 1));
+
+        let phone = Mask::new("(999) 999-9999");
+        assert_eq!(
+            format_with_caret(&phone, "(5551", 5),
+            ("(555) 1".to_owned(), 7)
+        );
+        assert_eq!(
+            format_with_caret(&phone, "(55) 1", 3),
+            ("(551".to_owned(), 3)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_decimal_format_for_locale() {
+        let de = DecimalFormat::for_locale("de-DE").unwrap();
+        assert_eq!(de.group_separator(), '.');
+        assert_eq!(de.decimal_separator(), ',');
+        assert!(DecimalFormat::for_locale("not a locale!").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_input_formatter_attach() {
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let host: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        let input: HtmlInputElement = document.create_element("input").unwrap().unchecked_into();
+        input.set_value("5551234");
+        host.append_child(&input).unwrap();
+        body.append_child(&host).unwrap();
+
+        let formatter = InputFormatter::attach(&host, &input, Mask::new("(999) 999-9999")).unwrap();
+        assert_eq!(formatter.value(), "(555) 123-4");
+        input.set_value("(555) 123-45678");
+        input.dispatch_event(&Event::new("input").unwrap()).unwrap();
+        assert_eq!(formatter.value(), "(555) 123-4567");
+        formatter.detach();
+        input.set_value("5");
+        input.dispatch_event(&Event::new("input").unwrap()).unwrap();
+        assert_eq!(formatter.value(), "5");
+        body.remove_child(&host).unwrap();
+    }
+}
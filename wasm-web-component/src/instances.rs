@@ -0,0 +1,112 @@
+//! Tracking the connected instances of every web component.
+//!
+//! Elements are tracked from their `connected` to their `disconnected` callback so
+//! disconnected elements aren't kept alive. [WebComponentDef::instances] iterates over
+//! the connected instances of a component, e.g. to broadcast an update to all of them
+//! without matching tag names with `querySelectorAll`.
+//!
+//! ```ignore
+//! for element in MyElement::instances() {
+//!     MyElement::render(&element, &render_for_locale(&locale))?;
+//! }
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement};
+
+#[cfg(doc)]
+use crate::WebComponentDef;
+
+thread_local! {
+    static CONNECTED: RefCell<HashMap<&'static str, Vec<Element>>> = RefCell::new(HashMap::new());
+}
+
+/// Tracks a web component element as connected.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn track_connected(element_name: &'static str, element: &Element) {
+    CONNECTED.with(|connected| {
+        connected
+            .borrow_mut()
+            .entry(element_name)
+            .or_default()
+            .push(element.clone());
+    });
+    #[cfg(feature = "Devtools")]
+    let _ = crate::devtools::install_devtools_hook();
+}
+
+/// Stops tracking a web component element as connected.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn track_disconnected(element_name: &'static str, element: &Element) {
+    CONNECTED.with(|connected| {
+        if let Some(elements) = connected.borrow_mut().get_mut(element_name) {
+            elements.retain(|el| !Object::is(el, element));
+        }
+    });
+}
+
+/// Returns the connected instances of the element name in the order they got connected.
+pub(crate) fn connected_instances(element_name: &str) -> Vec<Element> {
+    CONNECTED.with(|connected| {
+        connected
+            .borrow()
+            .get(element_name)
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+/// An iterator over the connected instances of a web component in the order they got
+/// connected. Instances that get disconnected during the iteration are skipped.
+#[derive(Debug)]
+pub struct Instances {
+    elements: std::vec::IntoIter<Element>,
+}
+
+impl Instances {
+    pub(crate) fn new(element_name: &str) -> Self {
+        Self {
+            elements: connected_instances(element_name).into_iter(),
+        }
+    }
+}
+
+impl Iterator for Instances {
+    type Item = HtmlElement;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elements
+            .by_ref()
+            .find(|element| element.is_connected())
+            .map(JsCast::unchecked_into)
+    }
+}
+
+/// Returns the element names of every web component with connected instances.
+pub(crate) fn connected_element_names() -> Vec<&'static str> {
+    CONNECTED.with(|connected| {
+        connected
+            .borrow()
+            .iter()
+            .filter(|(_, elements)| !elements.is_empty())
+            .map(|(name, _)| *name)
+            .collect()
+    })
+}
+
+/// Calls the `invalidated` callback of every connected instance of the element name.
+pub(crate) fn invalidate_all(element_name: &str) -> Result<(), JsValue> {
+    for element in connected_instances(element_name) {
+        let invalidate: Function =
+            Reflect::get(&element, &JsValue::from_str("invalidateCallback"))?.dyn_into()?;
+        invalidate.call0(&element)?;
+    }
+    Ok(())
+}
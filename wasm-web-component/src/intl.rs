@@ -0,0 +1,433 @@
+//! Number, date and relative time formatting with the browser's `Intl` objects.
+//!
+//! Creating an `Intl` formatter is expensive compared to formatting with one so the
+//! formatters are cached by locale and options. Components usually keep a [Formatter]
+//! for their [element_locale](crate::element_locale) and update it from the
+//! `locale_changed` callback, which drops the formatters of the old locale.
+//!
+//! ```ignore
+//! impl WebComponentBinding for PriceTagImpl {
+//!     fn locale_changed_mut(&mut self, element: &HtmlElement, locale: &str) {
+//!         self.formatter.set_locale(locale);
+//!         let options = NumberOptions::currency("EUR");
+//!         element.set_text_content(Some(&self.formatter.number(self.price, &options)));
+//!     }
+//! }
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use js_sys::{Array, Date, Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+use crate::i18n::element_locale;
+
+/// How a number is formatted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NumberStyle {
+    #[default]
+    Decimal,
+    Percent,
+    /// An ISO 4217 currency code like `EUR`.
+    Currency(String),
+    /// A unit like `kilometer-per-hour`.
+    Unit(String),
+}
+
+/// Options for `Intl.NumberFormat`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NumberOptions {
+    pub style: NumberStyle,
+    pub minimum_fraction_digits: Option<u8>,
+    pub maximum_fraction_digits: Option<u8>,
+    /// Formats large numbers like `1.2K`.
+    pub compact: bool,
+    /// Leaves out the grouping separators like in `1234`.
+    pub no_grouping: bool,
+}
+
+impl NumberOptions {
+    pub fn percent() -> Self {
+        Self {
+            style: NumberStyle::Percent,
+            ..Self::default()
+        }
+    }
+
+    pub fn currency(code: &str) -> Self {
+        Self {
+            style: NumberStyle::Currency(code.to_owned()),
+            ..Self::default()
+        }
+    }
+
+    pub fn unit(unit: &str) -> Self {
+        Self {
+            style: NumberStyle::Unit(unit.to_owned()),
+            ..Self::default()
+        }
+    }
+}
+
+/// The length of a formatted date or time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DateTimeStyle {
+    Full,
+    Long,
+    Medium,
+    Short,
+}
+
+impl DateTimeStyle {
+    fn as_str(self) -> &'static str {
+        match self {
+            DateTimeStyle::Full => "full",
+            DateTimeStyle::Long => "long",
+            DateTimeStyle::Medium => "medium",
+            DateTimeStyle::Short => "short",
+        }
+    }
+}
+
+/// Options for `Intl.DateTimeFormat`. Only the date is formatted if neither style is
+/// set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DateTimeOptions {
+    pub date_style: Option<DateTimeStyle>,
+    pub time_style: Option<DateTimeStyle>,
+    /// An IANA time zone like `Europe/Berlin`. Defaults to the user's time zone.
+    pub time_zone: Option<String>,
+}
+
+/// The unit of a relative time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RelativeTimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl RelativeTimeUnit {
+    fn as_str(self) -> &'static str {
+        match self {
+            RelativeTimeUnit::Second => "second",
+            RelativeTimeUnit::Minute => "minute",
+            RelativeTimeUnit::Hour => "hour",
+            RelativeTimeUnit::Day => "day",
+            RelativeTimeUnit::Week => "week",
+            RelativeTimeUnit::Month => "month",
+            RelativeTimeUnit::Year => "year",
+        }
+    }
+
+    fn seconds(self) -> f64 {
+        match self {
+            RelativeTimeUnit::Second => 1.0,
+            RelativeTimeUnit::Minute => 60.0,
+            RelativeTimeUnit::Hour => 3600.0,
+            RelativeTimeUnit::Day => 86400.0,
+            RelativeTimeUnit::Week => 604800.0,
+            RelativeTimeUnit::Month => 2629800.0,
+            RelativeTimeUnit::Year => 31557600.0,
+        }
+    }
+}
+
+/// Options for `Intl.RelativeTimeFormat`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RelativeTimeOptions {
+    /// Formats `1 day ago` as `yesterday` where the language has a word for it.
+    pub auto: bool,
+    pub style: Option<DateTimeStyle>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Number(NumberOptions),
+    DateTime(DateTimeOptions),
+    RelativeTime(RelativeTimeOptions),
+}
+
+impl Key {
+    fn constructor(&self) -> &'static str {
+        match self {
+            Key::Number(_) => "NumberFormat",
+            Key::DateTime(_) => "DateTimeFormat",
+            Key::RelativeTime(_) => "RelativeTimeFormat",
+        }
+    }
+
+    fn options(&self) -> Object {
+        let options = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = Reflect::set(&options, &JsValue::from_str(name), &value);
+        };
+        match self {
+            Key::Number(number) => {
+                match &number.style {
+                    NumberStyle::Decimal => {}
+                    NumberStyle::Percent => set("style", "percent".into()),
+                    NumberStyle::Currency(code) => {
+                        set("style", "currency".into());
+                        set("currency", code.as_str().into());
+                    }
+                    NumberStyle::Unit(unit) => {
+                        set("style", "unit".into());
+                        set("unit", unit.as_str().into());
+                    }
+                }
+                if let Some(digits) = number.minimum_fraction_digits {
+                    set("minimumFractionDigits", digits.into());
+                }
+                if let Some(digits) = number.maximum_fraction_digits {
+                    set("maximumFractionDigits", digits.into());
+                }
+                if number.compact {
+                    set("notation", "compact".into());
+                }
+                if number.no_grouping {
+                    set("useGrouping", false.into());
+                }
+            }
+            Key::DateTime(date_time) => {
+                if let Some(style) = date_time.date_style {
+                    set("dateStyle", style.as_str().into());
+                }
+                if let Some(style) = date_time.time_style {
+                    set("timeStyle", style.as_str().into());
+                }
+                if let Some(time_zone) = &date_time.time_zone {
+                    set("timeZone", time_zone.as_str().into());
+                }
+            }
+            Key::RelativeTime(relative) => {
+                if relative.auto {
+                    set("numeric", "auto".into());
+                }
+                if let Some(style) = relative.style {
+                    // Relative times only have long, short and narrow styles.
+                    let style = match style {
+                        DateTimeStyle::Full | DateTimeStyle::Long => "long",
+                        DateTimeStyle::Medium => "short",
+                        DateTimeStyle::Short => "narrow",
+                    };
+                    set("style", style.into());
+                }
+            }
+        }
+        options
+    }
+}
+
+thread_local! {
+    static FORMATTERS: RefCell<HashMap<(String, Key), Function>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the cached `format` function of the formatter for the locale and options.
+fn format_fn(locale: &str, key: Key) -> Result<Function, JsValue> {
+    let cache_key = (locale.to_owned(), key);
+    if let Some(format) = FORMATTERS.with(|formatters| formatters.borrow().get(&cache_key).cloned())
+    {
+        return Ok(format);
+    }
+    let (locale, key) = cache_key;
+    let intl = Reflect::get(&js_sys::global(), &JsValue::from_str("Intl"))?;
+    let constructor: Function =
+        Reflect::get(&intl, &JsValue::from_str(key.constructor()))?.dyn_into()?;
+    // The constructors throw for invalid locales and options.
+    let formatter = Reflect::construct(
+        &constructor,
+        &Array::of2(&JsValue::from_str(&locale), &key.options()),
+    )?;
+    let format: Function = Reflect::get(&formatter, &JsValue::from_str("format"))?.dyn_into()?;
+    let format = format.bind0(&formatter).unchecked_into::<Function>();
+    FORMATTERS.with(|formatters| {
+        formatters
+            .borrow_mut()
+            .insert((locale, key), format.clone())
+    });
+    Ok(format)
+}
+
+fn drop_locale(locale: &str) {
+    FORMATTERS.with(|formatters| {
+        formatters
+            .borrow_mut()
+            .retain(|(cached, _), _| cached != locale)
+    });
+}
+
+/// Drops every cached formatter.
+pub fn clear_format_cache() {
+    FORMATTERS.with(|formatters| formatters.borrow_mut().clear());
+}
+
+/// Formats the number in the locale.
+pub fn format_number(locale: &str, value: f64, options: &NumberOptions) -> Result<String, JsValue> {
+    let format = format_fn(locale, Key::Number(options.clone()))?;
+    Ok(format
+        .call1(&JsValue::NULL, &value.into())?
+        .as_string()
+        .unwrap_or_default())
+}
+
+/// Formats the date in the locale.
+pub fn format_date(
+    locale: &str,
+    date: &Date,
+    options: &DateTimeOptions,
+) -> Result<String, JsValue> {
+    let format = format_fn(locale, Key::DateTime(options.clone()))?;
+    Ok(format
+        .call1(&JsValue::NULL, date)?
+        .as_string()
+        .unwrap_or_default())
+}
+
+/// Formats a relative time like `in 3 days` or `2 hours ago` in the locale.
+pub fn format_relative_time(
+    locale: &str,
+    value: f64,
+    unit: RelativeTimeUnit,
+    options: &RelativeTimeOptions,
+) -> Result<String, JsValue> {
+    let format = format_fn(locale, Key::RelativeTime(options.clone()))?;
+    Ok(format
+        .call2(&JsValue::NULL, &value.into(), &unit.as_str().into())?
+        .as_string()
+        .unwrap_or_default())
+}
+
+/// Formats the time from now until the date in the largest unit it has at least one
+/// of, e.g. `in 3 days` or `5 minutes ago`.
+pub fn format_relative_to_now(
+    locale: &str,
+    date: &Date,
+    options: &RelativeTimeOptions,
+) -> Result<String, JsValue> {
+    let seconds = (date.get_time() - Date::now()) / 1000.0;
+    let unit = [
+        RelativeTimeUnit::Year,
+        RelativeTimeUnit::Month,
+        RelativeTimeUnit::Week,
+        RelativeTimeUnit::Day,
+        RelativeTimeUnit::Hour,
+        RelativeTimeUnit::Minute,
+    ]
+    .into_iter()
+    .find(|unit| seconds.abs() >= unit.seconds())
+    .unwrap_or(RelativeTimeUnit::Second);
+    format_relative_time(locale, (seconds / unit.seconds()).trunc(), unit, options)
+}
+
+/// Formats in one locale, usually the locale of a component's element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Formatter {
+    locale: String,
+}
+
+impl Formatter {
+    pub fn new(locale: &str) -> Self {
+        Self {
+            locale: locale.to_owned(),
+        }
+    }
+
+    /// A formatter for the [element_locale] of the element.
+    pub fn for_element(element: &Element) -> Self {
+        Self::new(&element_locale(element))
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Switches to the locale, dropping the cached formatters of the old one. Returns
+    /// whether the locale changed.
+    pub fn set_locale(&mut self, locale: &str) -> bool {
+        if self.locale == locale {
+            return false;
+        }
+        drop_locale(&self.locale);
+        self.locale = locale.to_owned();
+        true
+    }
+
+    /// Formats the number. Falls back to the number's plain formatting if the locale or
+    /// options are invalid.
+    pub fn number(&self, value: f64, options: &NumberOptions) -> String {
+        format_number(&self.locale, value, options).unwrap_or_else(|_| value.to_string())
+    }
+
+    /// Formats the date. Falls back to the date's ISO format if the locale or options
+    /// are invalid.
+    pub fn date(&self, date: &Date, options: &DateTimeOptions) -> String {
+        format_date(&self.locale, date, options).unwrap_or_else(|_| date.to_iso_string().into())
+    }
+
+    /// Formats a relative time. Falls back to the value and unit if the locale is invalid.
+    pub fn relative_time(
+        &self,
+        value: f64,
+        unit: RelativeTimeUnit,
+        options: &RelativeTimeOptions,
+    ) -> String {
+        format_relative_time(&self.locale, value, unit, options)
+            .unwrap_or_else(|_| format!("{} {}", value, unit.as_str()))
+    }
+
+    /// Formats the time from now until the date.
+    pub fn relative_to_now(&self, date: &Date, options: &RelativeTimeOptions) -> String {
+        format_relative_to_now(&self.locale, date, options)
+            .unwrap_or_else(|_| date.to_iso_string().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_formatters_are_cached() {
+        let options = NumberOptions {
+            maximum_fraction_digits: Some(1),
+            ..NumberOptions::default()
+        };
+        let first = format_fn("en", Key::Number(options.clone())).unwrap();
+        let second = format_fn("en", Key::Number(options.clone())).unwrap();
+        assert!(Object::is(&first, &second));
+        assert_eq!(format_number("en", 1234.56, &options).unwrap(), "1,234.6");
+
+        let mut formatter = Formatter::new("en");
+        assert!(formatter.set_locale("de"));
+        assert!(!formatter.set_locale("de"));
+        assert_eq!(formatter.number(1234.56, &options), "1.234,6");
+        // Invalid locales fall back to the plain formatting.
+        assert_eq!(Formatter::new("not a locale!").number(1.5, &options), "1.5");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_format_relative_time() {
+        let options = RelativeTimeOptions {
+            auto: true,
+            style: None,
+        };
+        assert_eq!(
+            format_relative_time("en", -1.0, RelativeTimeUnit::Day, &options).unwrap(),
+            "yesterday"
+        );
+        let date = Date::new(&JsValue::from_f64(
+            Date::now() + 3.0 * 3600.0 * 1000.0 + 1000.0,
+        ));
+        assert_eq!(
+            format_relative_to_now("en", &date, &RelativeTimeOptions::default()).unwrap(),
+            "in 3 hours"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Runtime introspection of the components defined through this crate.
+//!
+//! Every successful `define` records the component in a registry that [registry]
+//! returns, e.g. for a diagnostics page or an integration test that checks the whole
+//! component set got defined.
+//!
+//! ```ignore
+//! for component in registry() {
+//!     log(&format!("<{}> {} ({})", component.element_name, component.class_name, component.crate_version));
+//! }
+//! assert!(registry_entry("my-button").is_some());
+//! ```
+//!
+//! With the `Docs` feature the `///` docs of the component struct are kept as
+//! [ComponentDocs]. Items in bullet lists under `# Attributes` and `# Events` headings
+//! document the attribute or event in backticks at their start.
+//!
+//! ```ignore
+//! /// A button that counts its clicks.
+//! ///
+//! /// # Attributes
+//! /// * `step` - How much every click adds.
+//! ///
+//! /// # Events
+//! /// * `counted` - Dispatched after every click.
+//! #[web_component(observed_attrs = "['step']", emits = "['counted']")]
+//! pub struct MyCounter {}
+//!
+//! assert_eq!(MyCounter::docs().attribute("step"), Some("How much every click adds."));
+//! ```
+use std::cell::RefCell;
+
+use crate::define::CRATE_VERSION;
+use crate::WebComponentDef;
+
+/// The `///` docs of a component. They are empty without the `Docs` feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentDocs {
+    /// The docs before the first heading.
+    pub description: &'static str,
+    /// The attribute names with their docs.
+    pub attributes: &'static [(&'static str, &'static str)],
+    /// The event types with their docs.
+    pub events: &'static [(&'static str, &'static str)],
+}
+
+impl ComponentDocs {
+    /// Returns the docs of the attribute.
+    pub fn attribute(&self, name: &str) -> Option<&'static str> {
+        find_doc(self.attributes, name)
+    }
+
+    /// Returns the docs of the event type.
+    pub fn event(&self, event_type: &str) -> Option<&'static str> {
+        find_doc(self.events, event_type)
+    }
+}
+
+fn find_doc(docs: &'static [(&'static str, &'static str)], name: &str) -> Option<&'static str> {
+    docs.iter()
+        .find(|(documented, _)| *documented == name)
+        .map(|(_, doc)| *doc)
+}
+
+/// A component defined through this crate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentInfo {
+    /// The name the element got defined under.
+    pub element_name: String,
+    pub class_name: &'static str,
+    /// The built-in element a customized built-in element extends.
+    pub extends: Option<&'static str>,
+    /// The event types the component emits.
+    pub events: &'static [&'static str],
+    /// The version of this crate that defined the component.
+    pub crate_version: &'static str,
+    /// When the component got defined in milliseconds since the unix epoch.
+    pub defined_at: f64,
+    pub docs: ComponentDocs,
+}
+
+thread_local! {
+    static DEFINED: RefCell<Vec<ComponentInfo>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records the component as defined under the element name.
+///
+/// This is called by the generated `define` for you.
+#[doc(hidden)]
+pub fn record_definition<T: WebComponentDef>(element_name: &str) {
+    let info = ComponentInfo {
+        element_name: element_name.to_owned(),
+        class_name: T::class_name(),
+        extends: T::extends(),
+        events: T::emitted_events(),
+        crate_version: CRATE_VERSION,
+        defined_at: js_sys::Date::now(),
+        docs: T::docs(),
+    };
+    DEFINED.with(|defined| defined.borrow_mut().push(info));
+}
+
+/// Returns every component defined through this crate in the order they got defined.
+pub fn registry() -> Vec<ComponentInfo> {
+    DEFINED.with(|defined| defined.borrow().clone())
+}
+
+/// Returns the component defined under the element name if it was defined through this
+/// crate.
+pub fn registry_entry(element_name: &str) -> Option<ComponentInfo> {
+    DEFINED.with(|defined| {
+        defined
+            .borrow()
+            .iter()
+            .find(|info| info.element_name == element_name)
+            .cloned()
+    })
+}
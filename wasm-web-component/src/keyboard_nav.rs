@@ -0,0 +1,382 @@
+//! Roving tabindex keyboard navigation.
+//!
+//! Menus, listboxes, toolbars and tabs are a single tab stop. The arrow keys move
+//! focus between their items, Home and End jump to the first and last item and typing
+//! jumps to the next item whose text starts with the typed characters. Only the
+//! focused item has `tabindex="0"`, every other item gets `tabindex="-1"`.
+//!
+//! ```ignore
+//! impl WebComponentBinding for MyMenu {
+//!     fn connected(&self, element: &HtmlElement) {
+//!         let list = element.shadow_root().unwrap().get_element_by_id("items").unwrap();
+//!         RovingTabindex::attach(element, &list, "[role=menuitem]", Orientation::Vertical).unwrap();
+//!     }
+//! }
+//! ```
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, HtmlElement, KeyboardEvent};
+
+use crate::cleanup::on_disconnect;
+
+/// Typed characters are combined into a single search until typing pauses this long.
+const TYPEAHEAD_TIMEOUT_MS: f64 = 500.0;
+
+/// The arrow keys that move between items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Left and right move between items. They are swapped in right to left layouts.
+    Horizontal,
+    /// Up and down move between items.
+    Vertical,
+    /// Every arrow key moves between items.
+    Both,
+}
+
+/// Returns the index of the item the key moves to if the key moves focus.
+fn index_for_key(
+    key: &str,
+    current: usize,
+    len: usize,
+    orientation: Orientation,
+    rtl: bool,
+    wrap: bool,
+) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let horizontal = orientation != Orientation::Vertical;
+    let vertical = orientation != Orientation::Horizontal;
+    let forward = match key {
+        "ArrowDown" if vertical => true,
+        "ArrowUp" if vertical => false,
+        "ArrowRight" if horizontal => !rtl,
+        "ArrowLeft" if horizontal => rtl,
+        "Home" => return Some(0),
+        "End" => return Some(len - 1),
+        _ => return None,
+    };
+    let last = len - 1;
+    Some(match (forward, current) {
+        (true, c) if c >= last => {
+            if wrap {
+                0
+            } else {
+                last
+            }
+        }
+        (true, c) => c + 1,
+        (false, 0) => {
+            if wrap {
+                last
+            } else {
+                0
+            }
+        }
+        (false, c) => c - 1,
+    })
+}
+
+struct Inner {
+    container: Element,
+    item_selector: String,
+    orientation: Orientation,
+    wrap: Cell<bool>,
+    typeahead: RefCell<(String, f64)>,
+    on_keydown: Closure<dyn FnMut(KeyboardEvent)>,
+    on_focusin: Closure<dyn FnMut(Event)>,
+    attached: Cell<bool>,
+}
+
+impl Inner {
+    fn items(&self) -> Vec<HtmlElement> {
+        let mut items = Vec::new();
+        if let Ok(nodes) = self.container.query_selector_all(&self.item_selector) {
+            for i in 0..nodes.length() {
+                if let Some(item) = nodes.item(i).and_then(|n| n.dyn_into::<HtmlElement>().ok()) {
+                    if !item.hidden() {
+                        items.push(item);
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    fn is_rtl(&self) -> bool {
+        web_sys::window()
+            .and_then(|w| w.get_computed_style(&self.container).ok().flatten())
+            .and_then(|style| style.get_property_value("direction").ok())
+            .map(|direction| direction == "rtl")
+            .unwrap_or(false)
+    }
+
+    fn index_of(items: &[HtmlElement], target: Option<&Element>) -> Option<usize> {
+        let target = target?;
+        items.iter().position(|item| item.contains(Some(target)))
+    }
+
+    fn set_active(&self, items: &[HtmlElement], index: usize, focus: bool) {
+        for (i, item) in items.iter().enumerate() {
+            item.set_tab_index(if i == index { 0 } else { -1 });
+        }
+        if focus {
+            if let Some(item) = items.get(index) {
+                let _ = item.focus();
+            }
+        }
+    }
+
+    fn typeahead_index(&self, items: &[HtmlElement], current: usize, key: &str) -> Option<usize> {
+        let now = js_sys::Date::now();
+        let mut typeahead = self.typeahead.borrow_mut();
+        if now - typeahead.1 > TYPEAHEAD_TIMEOUT_MS {
+            typeahead.0.clear();
+        }
+        typeahead.0.push_str(&key.to_lowercase());
+        typeahead.1 = now;
+        // A fresh search starts after the current item, a continued one includes it.
+        let start = if typeahead.0.chars().count() == 1 {
+            current + 1
+        } else {
+            current
+        };
+        (0..items.len())
+            .map(|i| (start + i) % items.len())
+            .find(|i| {
+                items[*i]
+                    .text_content()
+                    .map(|t| t.trim().to_lowercase().starts_with(typeahead.0.as_str()))
+                    .unwrap_or(false)
+            })
+    }
+
+    fn handle_keydown(&self, event: &KeyboardEvent) {
+        if event.ctrl_key() || event.meta_key() || event.alt_key() {
+            return;
+        }
+        let items = self.items();
+        let target = event.target().and_then(|t| t.dyn_into::<Element>().ok());
+        let current = match Self::index_of(&items, target.as_ref()) {
+            Some(current) => current,
+            None => return,
+        };
+        let key = event.key();
+        let next = if key.chars().count() == 1 && key != " " {
+            self.typeahead_index(&items, current, &key)
+        } else {
+            index_for_key(
+                &key,
+                current,
+                items.len(),
+                self.orientation,
+                self.is_rtl(),
+                self.wrap.get(),
+            )
+        };
+        if let Some(next) = next {
+            event.prevent_default();
+            self.set_active(&items, next, true);
+        }
+    }
+
+    fn detach(&self) {
+        if !self.attached.replace(false) {
+            return;
+        }
+        let _ = self.container.remove_event_listener_with_callback(
+            "keydown",
+            self.on_keydown.as_ref().unchecked_ref(),
+        );
+        let _ = self.container.remove_event_listener_with_callback(
+            "focusin",
+            self.on_focusin.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// Roving tabindex navigation over the items of a container. Cloning returns a
+/// handle to the same navigation.
+#[derive(Clone)]
+pub struct RovingTabindex {
+    inner: Rc<Inner>,
+}
+
+impl RovingTabindex {
+    /// Starts keyboard navigation over the container's items matching the selector
+    /// until it is detached or the component element gets disconnected. The container
+    /// can be the element itself or an element in its shadow root.
+    pub fn attach(
+        element: &HtmlElement,
+        container: &Element,
+        item_selector: &str,
+        orientation: Orientation,
+    ) -> Result<Self, JsValue> {
+        let inner = Rc::new_cyclic(|weak: &Weak<Inner>| {
+            let on_keydown = {
+                let weak = weak.clone();
+                Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                    if let Some(inner) = weak.upgrade() {
+                        inner.handle_keydown(&event);
+                    }
+                })
+            };
+            let on_focusin = {
+                let weak = weak.clone();
+                Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+                    if let Some(inner) = weak.upgrade() {
+                        // Keep the tab stop on items focused by clicking them.
+                        let items = inner.items();
+                        let target = event.target().and_then(|t| t.dyn_into::<Element>().ok());
+                        if let Some(index) = Inner::index_of(&items, target.as_ref()) {
+                            inner.set_active(&items, index, false);
+                        }
+                    }
+                })
+            };
+            Inner {
+                container: container.clone(),
+                item_selector: item_selector.to_owned(),
+                orientation,
+                wrap: Cell::new(true),
+                typeahead: RefCell::new((String::new(), 0.0)),
+                on_keydown,
+                on_focusin,
+                attached: Cell::new(true),
+            }
+        });
+        container.add_event_listener_with_callback(
+            "keydown",
+            inner.on_keydown.as_ref().unchecked_ref(),
+        )?;
+        container.add_event_listener_with_callback(
+            "focusin",
+            inner.on_focusin.as_ref().unchecked_ref(),
+        )?;
+
+        let navigation = Self { inner };
+        navigation.refresh();
+        {
+            let inner = navigation.inner.clone();
+            on_disconnect(element, move || inner.detach());
+        }
+        Ok(navigation)
+    }
+
+    /// Sets whether moving past the last or first item wraps around. Defaults to true.
+    pub fn set_wrap(&self, wrap: bool) {
+        self.inner.wrap.set(wrap);
+    }
+
+    /// Resets the tabindex of the items. Call this after adding or removing items.
+    /// The tab stop stays on the current item if it is still there and otherwise
+    /// moves to the first item.
+    pub fn refresh(&self) {
+        let items = self.inner.items();
+        let index = items
+            .iter()
+            .position(|item| item.tab_index() == 0)
+            .unwrap_or(0);
+        self.inner.set_active(&items, index, false);
+    }
+
+    /// Returns the index of the item holding the tab stop.
+    pub fn active_index(&self) -> Option<usize> {
+        self.inner
+            .items()
+            .iter()
+            .position(|item| item.tab_index() == 0)
+    }
+
+    /// Moves the tab stop to the item and focuses it.
+    pub fn focus_item(&self, index: usize) {
+        let items = self.inner.items();
+        if index < items.len() {
+            self.inner.set_active(&items, index, true);
+        }
+    }
+
+    /// Stops the keyboard navigation. The items keep their current tabindex.
+    pub fn detach(&self) {
+        self.inner.detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_index_for_key() {
+        use Orientation::*;
+        assert_eq!(
+            index_for_key("ArrowDown", 0, 3, Vertical, false, true),
+            Some(1)
+        );
+        assert_eq!(
+            index_for_key("ArrowDown", 2, 3, Vertical, false, true),
+            Some(0)
+        );
+        assert_eq!(
+            index_for_key("ArrowDown", 2, 3, Vertical, false, false),
+            Some(2)
+        );
+        assert_eq!(
+            index_for_key("ArrowUp", 0, 3, Vertical, false, true),
+            Some(2)
+        );
+        assert_eq!(
+            index_for_key("ArrowRight", 0, 3, Vertical, false, true),
+            None
+        );
+        assert_eq!(
+            index_for_key("ArrowRight", 0, 3, Horizontal, false, true),
+            Some(1)
+        );
+        assert_eq!(
+            index_for_key("ArrowRight", 1, 3, Horizontal, true, true),
+            Some(0)
+        );
+        assert_eq!(
+            index_for_key("ArrowLeft", 1, 3, Horizontal, true, true),
+            Some(2)
+        );
+        assert_eq!(index_for_key("End", 0, 3, Both, false, true), Some(2));
+        assert_eq!(index_for_key("Home", 2, 3, Both, false, true), Some(0));
+        assert_eq!(index_for_key("Home", 0, 0, Both, false, true), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_roving_tabindex_attach() {
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let menu: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        menu.set_inner_html("<button>Apple</button><button>Banana</button><button>Cherry</button>");
+        body.append_child(&menu).unwrap();
+
+        let navigation =
+            RovingTabindex::attach(&menu, &menu, "button", Orientation::Vertical).unwrap();
+        assert_eq!(navigation.active_index(), Some(0));
+        navigation.focus_item(2);
+        assert_eq!(navigation.active_index(), Some(2));
+        assert_eq!(
+            document.active_element().unwrap().text_content().unwrap(),
+            "Cherry"
+        );
+        assert_eq!(
+            menu.first_element_child()
+                .unwrap()
+                .get_attribute("tabindex")
+                .unwrap(),
+            "-1"
+        );
+        body.remove_child(&menu).unwrap();
+    }
+}
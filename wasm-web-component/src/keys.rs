@@ -0,0 +1,211 @@
+//! Matching keyboard events against key combinations.
+//!
+//! A combination is a key with its modifiers joined by `+`, like `"Enter"`,
+//! `"Ctrl+Shift+K"` or `"Alt+ArrowUp"`. Keys are the `key` values of keyboard events,
+//! compared case insensitively, with a few shorter aliases like `Esc`, `Space`, `Up`
+//! and `Plus`. `Mod` is `Meta` on Apple platforms and `Ctrl` everywhere else.
+//!
+//! The modifiers have to match exactly so `"Ctrl+K"` doesn't match Ctrl+Shift+K. The
+//! exception is Shift for symbols which need it on some keyboard layouts, like `"?"`.
+//!
+//! ```ignore
+//! let save: KeyCombo = "Mod+S".parse().unwrap();
+//! if save.matches(&keyboard_event) {
+//!     // ...
+//! }
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use js_sys::Reflect;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, KeyboardEvent};
+
+/// A key combination that isn't valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidKeyCombo(pub String);
+
+impl fmt::Display for InvalidKeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a valid key combination", self.0)
+    }
+}
+
+impl std::error::Error for InvalidKeyCombo {}
+
+/// A key with the modifiers that have to be held.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyCombo {
+    /// The lowercase `key` value.
+    pub key: String,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+fn is_apple() -> bool {
+    web_sys::window()
+        .and_then(|window| window.navigator().platform().ok())
+        .map(|platform| platform.starts_with("Mac") || platform.starts_with("i"))
+        .unwrap_or(false)
+}
+
+fn key_alias(key: &str) -> &str {
+    match key {
+        "esc" => "escape",
+        "space" => " ",
+        "up" => "arrowup",
+        "down" => "arrowdown",
+        "left" => "arrowleft",
+        "right" => "arrowright",
+        "del" => "delete",
+        "plus" => "+",
+        _ => key,
+    }
+}
+
+impl KeyCombo {
+    /// Whether the keyboard event is for this combination.
+    pub fn matches(&self, event: &KeyboardEvent) -> bool {
+        let symbol = self.key.chars().count() == 1
+            && !self.key.chars().all(|c| c.is_alphanumeric() || c == ' ');
+        self.ctrl == event.ctrl_key()
+            && self.alt == event.alt_key()
+            && self.meta == event.meta_key()
+            && (self.shift == event.shift_key() || (symbol && !self.shift))
+            && self.matches_key(event)
+    }
+
+    fn matches_key(&self, event: &KeyboardEvent) -> bool {
+        if event.key().to_lowercase() == self.key {
+            return true;
+        }
+        // Alt changes the key of letters and digits on some layouts, e.g. Alt+K is ˚ on
+        // macOS, so they also match by their physical key.
+        let code = event.code();
+        match self.key.as_bytes() {
+            [c] if c.is_ascii_lowercase() => {
+                code == format!("Key{}", c.to_ascii_uppercase() as char)
+            }
+            [c] if c.is_ascii_digit() => code == format!("Digit{}", *c as char),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = InvalidKeyCombo;
+
+    fn from_str(combo: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidKeyCombo(combo.to_owned());
+        let combo = combo.trim();
+        // The + key itself can be written as the last part, e.g. Ctrl++.
+        let (modifiers, key) = match combo.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if combo == "+" => ("", "+"),
+            None => combo.rsplit_once('+').unwrap_or(("", combo)),
+        };
+        let key = key.trim().to_lowercase();
+        if key.is_empty() {
+            return Err(invalid());
+        }
+        let mut parsed = KeyCombo {
+            key: key_alias(&key).to_owned(),
+            ..Default::default()
+        };
+        for modifier in modifiers.split('+').filter(|m| !m.trim().is_empty()) {
+            match modifier.trim().to_lowercase().as_str() {
+                "ctrl" | "control" => parsed.ctrl = true,
+                "alt" | "option" => parsed.alt = true,
+                "shift" => parsed.shift = true,
+                "meta" | "cmd" | "command" => parsed.meta = true,
+                "mod" if is_apple() => parsed.meta = true,
+                "mod" => parsed.ctrl = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Whether the keys typed into the target are text, like in inputs.
+fn is_editable(target: &Element) -> bool {
+    matches!(
+        target.tag_name().to_lowercase().as_str(),
+        "input" | "textarea" | "select"
+    ) || Reflect::get(target, &JsValue::from_str("isContentEditable"))
+        .ok()
+        .and_then(|editable| editable.as_bool())
+        .unwrap_or(false)
+}
+
+/// Returns the keyboard event if it can trigger a shortcut. Keys without Ctrl, Alt or
+/// Meta typed into inputs and other editable elements are text, except for Escape.
+///
+/// This is called by the generated shortcut dispatcher for you.
+#[doc(hidden)]
+pub fn shortcut_event(event: &Event) -> Option<KeyboardEvent> {
+    let event: KeyboardEvent = event.clone().dyn_into().ok()?;
+    if event.default_prevented() || event.is_composing() {
+        return None;
+    }
+    let modified = event.ctrl_key() || event.alt_key() || event.meta_key();
+    let target = event.composed_path().get(0).dyn_into::<Element>().ok();
+    if !modified && event.key() != "Escape" && target.is_some_and(|target| is_editable(&target)) {
+        return None;
+    }
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::KeyboardEventInit;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn key_event(key: &str, code: &str, ctrl: bool, shift: bool, alt: bool) -> KeyboardEvent {
+        let init = KeyboardEventInit::new();
+        init.set_key(key);
+        init.set_code(code);
+        init.set_ctrl_key(ctrl);
+        init.set_shift_key(shift);
+        init.set_alt_key(alt);
+        KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_parse() {
+        let combo: KeyCombo = "Ctrl+Shift+K".parse().unwrap();
+        assert_eq!(
+            combo,
+            KeyCombo {
+                key: "k".to_owned(),
+                ctrl: true,
+                shift: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!("Ctrl++".parse::<KeyCombo>().unwrap().key, "+");
+        assert_eq!("Esc".parse::<KeyCombo>().unwrap().key, "escape");
+        assert!("Hyper+K".parse::<KeyCombo>().is_err());
+        assert!("Ctrl+".parse::<KeyCombo>().is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_matches() {
+        let ctrl_enter: KeyCombo = "Ctrl+Enter".parse().unwrap();
+        assert!(ctrl_enter.matches(&key_event("Enter", "Enter", true, false, false)));
+        assert!(!ctrl_enter.matches(&key_event("Enter", "Enter", false, false, false)));
+        assert!(!ctrl_enter.matches(&key_event("Enter", "Enter", true, true, false)));
+        let question: KeyCombo = "?".parse().unwrap();
+        assert!(question.matches(&key_event("?", "Slash", false, true, false)));
+        let alt_k: KeyCombo = "Alt+K".parse().unwrap();
+        assert!(alt_k.matches(&key_event("˚", "KeyK", false, false, true)));
+        let shift_k: KeyCombo = "Shift+K".parse().unwrap();
+        assert!(shift_k.matches(&key_event("K", "KeyK", false, true, false)));
+        assert!(!shift_k.matches(&key_event("k", "KeyK", false, false, false)));
+    }
+}
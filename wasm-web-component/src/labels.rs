@@ -0,0 +1,52 @@
+//! Labels of form-associated web components.
+//!
+//! Components with `form_associated = true` in the
+//! [`#[web_component]`](crate::web_component) attribute take part in forms and can be
+//! labeled with `<label for>` or by being inside of a `<label>` like the built-in
+//! form controls. Clicking one of their labels focuses the control inside of the
+//! element, the first element in the shadow root matching the `label_target` selector.
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{HtmlElement, NodeList};
+
+use crate::aria::internals;
+
+/// The labels associated with the form-associated element.
+pub fn labels(element: &HtmlElement) -> Vec<HtmlElement> {
+    let labels = internals(element)
+        .and_then(|internals| Reflect::get(&internals, &JsValue::from_str("labels")).ok())
+        .and_then(|labels| labels.dyn_into::<NodeList>().ok());
+    let labels = match labels {
+        Some(labels) => labels,
+        None => return Vec::new(),
+    };
+    (0..labels.length())
+        .filter_map(|i| labels.get(i))
+        .filter_map(|label| label.dyn_into::<HtmlElement>().ok())
+        .collect()
+}
+
+/// The text of the element's labels, e.g. to label the control inside of the element.
+/// Returns None if the element has no labels.
+pub fn label_text(element: &HtmlElement) -> Option<String> {
+    let text = labels(element)
+        .iter()
+        .map(|label| label.inner_text().trim().to_owned())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Focuses the control inside of the element that its labels are for.
+pub fn focus_label_target(element: &HtmlElement) -> Result<(), JsValue> {
+    let focus: Function = Reflect::get(element, &JsValue::from_str("focusLabelTarget"))?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("The element isn't a web component"))?;
+    focus.call0(element)?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Lazy loading of web components.
+//!
+//! Until a custom element is defined any instances of it in the document are plain
+//! placeholder elements. A lazy definition waits for the first placeholder to get
+//! connected to the document, runs the loader that defines the real component and
+//! the browser then upgrades every placeholder to the real component.
+//!
+//! Only elements in the document itself are noticed. Placeholders that only ever
+//! get connected inside of a shadow root need to be loaded with [load_now].
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Promise;
+use wasm_bindgen::prelude::{wasm_bindgen, Closure};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{MutationObserver, MutationObserverInit};
+
+#[wasm_bindgen(inline_js = "export function importModule(url) {
+    return import(url).then(function(m) { return m.default ? m.default() : m; });
+}")]
+extern "C" {
+    #[wasm_bindgen(js_name = importModule, catch)]
+    fn import_module(url: &str) -> Result<Promise, JsValue>;
+}
+
+type Loader = Box<dyn FnOnce() -> Promise>;
+
+thread_local! {
+    static PENDING: RefCell<Vec<(&'static str, Loader)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Lazily defines the element with a loader that gets called when the first
+/// instance of the element is connected to the document. The loader is expected
+/// to define the element and return a promise that resolves once it has.
+///
+/// Returns a promise that resolves when the element has been defined. Lazily
+/// defining an element that is already pending keeps the first loader.
+pub fn lazy_define<F>(element_name: &'static str, loader: F) -> Result<Promise, JsValue>
+where
+    F: FnOnce() -> Promise + 'static,
+{
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+    let registry = window.custom_elements();
+    let defined = registry.when_defined(element_name)?;
+    if registry.get(element_name).is_truthy() {
+        return Ok(defined);
+    }
+    let queued = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.iter().any(|(name, _)| *name == element_name) {
+            return false;
+        }
+        pending.push((element_name, Box::new(loader)));
+        true
+    });
+    if !queued {
+        return Ok(defined);
+    }
+    let document = window
+        .document()
+        .ok_or_else(|| JsValue::from_str("No document"))?;
+    if document.query_selector(element_name)?.is_some() {
+        load_now(element_name);
+        return Ok(defined);
+    }
+    let observer_handle: Rc<RefCell<Option<MutationObserver>>> = Rc::new(RefCell::new(None));
+    let callback = {
+        let observer_handle = observer_handle.clone();
+        let document = document.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Ok(Some(_)) = document.query_selector(element_name) {
+                if let Some(observer) = observer_handle.borrow_mut().take() {
+                    observer.disconnect();
+                }
+                load_now(element_name);
+            }
+        })
+    };
+    let observer = MutationObserver::new(callback.as_ref().unchecked_ref())?;
+    let init = MutationObserverInit::new();
+    init.set_child_list(true);
+    init.set_subtree(true);
+    observer.observe_with_options(&document, &init)?;
+    *observer_handle.borrow_mut() = Some(observer);
+    // The element can also get defined without a placeholder showing up, e.g. with
+    // load_now or by another bundle, so watching stops once it is defined.
+    let stop = Closure::once(move |_: JsValue| {
+        if let Some(observer) = observer_handle.borrow_mut().take() {
+            observer.disconnect();
+        }
+        PENDING.with(|pending| {
+            pending
+                .borrow_mut()
+                .retain(|(name, _)| *name != element_name)
+        });
+        drop(callback);
+    });
+    let _ = defined.then(&stop);
+    stop.forget();
+    Ok(defined)
+}
+
+/// Lazily defines the element by dynamically importing a javascript module when the
+/// first instance of the element is connected. The module's default export is called
+/// to initialize it which is what `wasm-pack build --target web` generates. The module
+/// is expected to define the element during initialization.
+pub fn lazy_define_module(
+    element_name: &'static str,
+    module_url: &str,
+) -> Result<Promise, JsValue> {
+    let module_url = module_url.to_owned();
+    lazy_define(element_name, move || match import_module(&module_url) {
+        Ok(promise) => promise,
+        Err(e) => Promise::reject(&e),
+    })
+}
+
+/// Runs the pending loader for the element immediately if there is one.
+pub fn load_now(element_name: &str) {
+    let loader = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        let index = pending.iter().position(|(name, _)| *name == element_name)?;
+        Some(pending.remove(index).1)
+    });
+    if let Some(loader) = loader {
+        // Callers wait on the element getting defined rather than on the loader.
+        let _ = loader();
+    }
+}
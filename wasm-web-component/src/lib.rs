@@ -1,9 +1,305 @@
-use js_sys::Function;
+use js_sys::{Array, Function, Promise, Reflect};
 use wasm_bindgen::JsCast;
 use wasm_bindgen::{convert::IntoWasmAbi, JsValue};
 #[cfg(feature = "HtmlTemplateElement")]
 use web_sys::HtmlTemplateElement;
-use web_sys::{window, Element, Event, HtmlElement, Window};
+use web_sys::{window, Element, Event, HtmlElement, ShadowRootMode, Window};
+
+mod animation;
+mod aria;
+mod attributes;
+mod behavior;
+mod broadcast;
+mod builder;
+#[cfg(feature = "EventBus")]
+mod bus;
+mod cell;
+mod cleanup;
+mod config;
+mod context;
+#[cfg(feature = "DataSource")]
+mod data_source;
+#[cfg(feature = "Dataset")]
+mod dataset;
+mod define;
+#[cfg(feature = "Devtools")]
+mod devtools;
+mod diagnostics;
+#[cfg(feature = "Dialog")]
+mod dialog;
+#[cfg(feature = "DragDrop")]
+mod drag_drop;
+mod element_ref;
+mod environment;
+mod event_context;
+mod events;
+#[cfg(feature = "Files")]
+mod files;
+mod focus;
+#[cfg(feature = "FocusTrap")]
+mod focus_trap;
+mod form_control;
+mod fragment;
+#[cfg(feature = "Gestures")]
+mod gestures;
+#[cfg(feature = "I18n")]
+mod i18n;
+#[cfg(feature = "IndexedDb")]
+mod idb;
+mod init;
+#[cfg(feature = "Inputs")]
+mod input;
+mod instances;
+#[cfg(feature = "I18n")]
+mod intl;
+mod introspection;
+#[cfg(feature = "KeyboardNav")]
+mod keyboard_nav;
+#[cfg(feature = "Keys")]
+mod keys;
+mod labels;
+#[cfg(feature = "LazyLoad")]
+mod lazy;
+mod listeners;
+#[cfg(feature = "LiveComponent")]
+mod live;
+#[cfg(feature = "Media")]
+mod media;
+#[cfg(feature = "Metrics")]
+mod metrics;
+mod modality;
+mod parts;
+mod perf;
+#[cfg(feature = "Polyfill")]
+mod polyfill;
+mod pool;
+mod progressive;
+mod properties;
+mod query;
+#[cfg(feature = "Recorder")]
+mod recorder;
+mod render;
+#[cfg(feature = "Router")]
+mod router;
+mod runtime;
+#[cfg(feature = "Sanitize")]
+mod sanitize;
+mod scheduler;
+mod scoped_css;
+#[cfg(feature = "Scroll")]
+mod scroll;
+#[cfg(feature = "Showcase")]
+mod showcase;
+mod slots;
+#[cfg(feature = "Snapshot")]
+mod snapshot;
+mod store;
+mod template_cache;
+#[cfg(feature = "Theme")]
+mod theme;
+mod trace;
+mod trusted_types;
+mod validity;
+#[cfg(feature = "ViewTransition")]
+mod view_transition;
+#[cfg(feature = "VirtualList")]
+mod virtual_list;
+#[cfg(feature = "Worker")]
+mod worker;
+
+pub use animation::{animate, remove_animated};
+pub use aria::Aria;
+#[doc(hidden)]
+pub use attributes::attribute_changes;
+pub use attributes::{
+    element_attrs_txn, get_attr, has_bool_attr, parse_attr, reflect_attribute, set_attr,
+    set_attribute_silent, set_bool_attr, AttributeChange, AttributeTransaction, AttributeValue,
+};
+pub use behavior::Behavior;
+#[doc(hidden)]
+pub use behavior::{
+    behavior_events, behaviors_attribute_changed, behaviors_connected, behaviors_disconnected,
+    behaviors_handle_event, BehaviorHooks,
+};
+pub use broadcast::broadcast_all;
+#[doc(hidden)]
+pub use broadcast::run_broadcast;
+pub use builder::ElementBuilder;
+#[cfg(feature = "EventBus")]
+pub use bus::{bus, BusEvent, EventBus, Subscription};
+pub use cell::{ComponentCell, ComponentRef, ComponentRefMut, StateBorrowError};
+pub use cleanup::{on_disconnect, run_disconnect_hooks};
+pub use config::FromConfig;
+pub use context::{provide_context, request_context, Context, ContextProvider, ContextRequest};
+#[cfg(feature = "DataSource")]
+#[doc(hidden)]
+pub use data_source::decode as data_source_decode;
+#[cfg(feature = "DataSource")]
+pub use data_source::DataSource;
+#[doc(hidden)]
+#[cfg(feature = "Dataset")]
+pub use dataset::{dataset_attribute, dataset_key, Dataset};
+#[doc(hidden)]
+pub use define::define_window;
+pub use define::{
+    define_conflict, defined_name, element_prefix, is_defined, set_conflict_policy,
+    set_element_prefix, ConflictPolicy, DefineConflict, DefineError, DefineErrorKind, DefineReport,
+    CRATE_VERSION,
+};
+pub use define::{prefixed_element_name, resolve_define_name, versioned_element_name};
+#[cfg(feature = "Devtools")]
+pub use devtools::{
+    inspect, install_console_formatters, install_devtools_hook, instances, DEVTOOLS_GLOBAL,
+};
+pub use diagnostics::warn_diagnostic;
+#[doc(hidden)]
+pub use diagnostics::{check_attach_shadow, check_observed_event, diagnostics_enabled};
+#[cfg(feature = "Dialog")]
+pub use dialog::{close_dialog, show_modal};
+#[cfg(feature = "DragDrop")]
+pub use drag_drop::{DragData, DragSource, DropTarget};
+#[doc(hidden)]
+pub use element_ref::{check_element_ref, check_tag_name, upgrade_all};
+pub use element_ref::{EventSubscription, WrongElementError};
+#[doc(hidden)]
+pub use environment::apply_fallback;
+pub use environment::{
+    custom_elements_registry, custom_elements_supported, dom_supported, global_document,
+    shadow_dom_supported,
+};
+pub use event_context::{EventContext, EventOrigin};
+pub use events::{EventCastError, EventExt};
+#[cfg(feature = "Files")]
+#[doc(hidden)]
+pub use files::{file_chunk, file_error, file_read, file_started};
+#[cfg(feature = "Files")]
+pub use files::{pick_directory, pick_files, read_files, FileInfo, FileReceiver};
+pub use focus::{contains_focus, focus_first};
+#[cfg(feature = "FocusTrap")]
+pub use focus_trap::FocusTrap;
+pub use form_control::{is_value_dirty, ValueSource};
+pub use fragment::{replace_children_efficiently, with_fragment};
+#[cfg(feature = "Gestures")]
+#[doc(hidden)]
+pub use gestures::{recognize_gesture, recognize_long_press};
+#[cfg(feature = "Gestures")]
+pub use gestures::{Gesture, GestureConfig, GestureHandler, GestureRecognizer, SwipeDirection};
+#[cfg(feature = "I18n")]
+pub use i18n::{
+    document_locale, element_locale, register_bundle, set_default_locale, t, t_for, t_in, Arg,
+    Bundle, FtlError, BUNDLES_CHANGED_EVENT,
+};
+#[cfg(feature = "IndexedDb")]
+pub use idb::{clear_idb_state, save_idb_state, IdbState};
+#[cfg(feature = "IndexedDb")]
+#[doc(hidden)]
+pub use idb::{idb_encode, idb_load};
+pub use init::InitContext;
+#[cfg(feature = "Inputs")]
+pub use input::{format_with_caret, DecimalFormat, InputFormat, InputFormatter, Mask};
+pub use instances::Instances;
+pub use instances::{track_connected, track_disconnected};
+#[cfg(feature = "I18n")]
+pub use intl::{
+    clear_format_cache, format_date, format_number, format_relative_time, format_relative_to_now,
+    DateTimeOptions, DateTimeStyle, Formatter, NumberOptions, NumberStyle, RelativeTimeOptions,
+    RelativeTimeUnit,
+};
+#[doc(hidden)]
+pub use introspection::record_definition;
+pub use introspection::{registry, registry_entry, ComponentDocs, ComponentInfo};
+#[cfg(feature = "KeyboardNav")]
+pub use keyboard_nav::{Orientation, RovingTabindex};
+#[cfg(feature = "Keys")]
+#[doc(hidden)]
+pub use keys::shortcut_event;
+#[cfg(feature = "Keys")]
+pub use keys::{InvalidKeyCombo, KeyCombo};
+pub use labels::{focus_label_target, label_text, labels};
+#[cfg(feature = "LazyLoad")]
+pub use lazy::{lazy_define, lazy_define_module, load_now};
+pub use listeners::{listen, unlisten, ListenOptions};
+#[cfg(feature = "LiveComponent")]
+#[doc(hidden)]
+pub use live::decode as live_decode;
+#[cfg(feature = "LiveComponent")]
+pub use live::{live_send, LiveComponent};
+#[cfg(feature = "Media")]
+pub use media::{
+    matches_media, prefers_dark_color_scheme, prefers_reduced_motion, watch_media_while_connected,
+};
+#[cfg(feature = "Metrics")]
+pub use metrics::{
+    export_metrics, metrics, metrics_json, reset_metrics, ComponentMetrics, ConsoleTableExporter,
+    JsonExporter, MetricsExporter, Phase, PhaseStats,
+};
+#[cfg(feature = "Metrics")]
+#[doc(hidden)]
+pub use metrics::{metrics_timer, MetricsTimer};
+pub use modality::{modal_depth, ModalScope};
+pub use parts::{add_part, export_parts, exportparts_value, stamp_parts};
+#[cfg(feature = "Performance")]
+pub use perf::PerformanceMeasure;
+#[doc(hidden)]
+pub use perf::{measure_phase, PhaseMeasure};
+#[cfg(feature = "Polyfill")]
+pub use polyfill::{batch_definitions, custom_elements_polyfilled};
+pub use progressive::{render_progressive, ProgressiveRender};
+pub use properties::{get_prop, set_prop, PropertyValue};
+pub use query::{query, query_all, QueryError, QueryErrorKind};
+#[cfg(feature = "Recorder")]
+pub use recorder::{
+    is_recording, recording, recording_json, recordings_json, replay_attributes, start_recording,
+    stop_recording, InstanceRecording, Record, RecordedEvent,
+};
+#[cfg(feature = "Recorder")]
+#[doc(hidden)]
+pub use recorder::{record_attribute_change, record_lifecycle};
+pub use render::{render_light, render_shadow, slotted_nodes};
+#[cfg(feature = "Router")]
+pub use router::{match_path, GuardId, RouteElement, RouteMatch, Router, RouterElement};
+#[doc(hidden)]
+pub use runtime::define_component;
+#[cfg(feature = "Sanitize")]
+pub use sanitize::{sanitize_html, set_sanitized_html};
+pub use scheduler::{schedule, schedule_while_connected, Priority, Task};
+pub use scoped_css::{inject_style, scope_classes, scoped_class};
+#[cfg(feature = "Scroll")]
+pub use scroll::{
+    lock_scroll_while_connected, preserve_scroll, scroll_locked, ScrollLock, ScrollPositions,
+    SCROLL_KEY_ATTRIBUTE,
+};
+#[cfg(feature = "Showcase")]
+pub use showcase::Showcase;
+pub use slots::{assigned_elements, assigned_text, Slotted};
+#[cfg(feature = "Snapshot")]
+#[doc(hidden)]
+pub use snapshot::{decode as snapshot_decode, encode as snapshot_encode};
+#[cfg(feature = "Snapshot")]
+pub use snapshot::{restore_all, snapshot_all, WebComponentSnapshot};
+pub use store::{Store, StoreSubscription};
+pub use template_cache::{TemplateCache, TemplateInstance, REF_ATTRIBUTE};
+#[cfg(feature = "Theme")]
+pub use theme::{
+    apply_root_theme, apply_theme, custom_properties, theme_property, Theme, THEME_CHANGED_EVENT,
+};
+#[doc(hidden)]
+pub use trace::{attribute_span, event_span, lifecycle_span, LifecycleSpan};
+#[cfg(feature = "tracing")]
+pub use trace::{init_console_tracing, ConsoleWriter, MakeConsoleWriter};
+pub use trusted_types::{
+    set_inner_html, set_trusted_types_policy, trusted_html, trusted_script_url,
+};
+pub use validity::{revalidate, set_form_value, Validity};
+#[cfg(feature = "ViewTransition")]
+pub use view_transition::{
+    set_view_transition_name, view_transition_name, view_transitions_supported,
+    with_view_transition,
+};
+#[cfg(feature = "VirtualList")]
+pub use virtual_list::{ItemRenderer, VirtualList};
+#[cfg(feature = "Worker")]
+pub use worker::{register_handler, serve, ComputeHandler, ComputeWorker};
 
 /// This attribute proc-macro will generate the following trait implementations
 /// * [WebComponentDef](trait@WebComponentDef)
@@ -16,14 +312,175 @@ use web_sys::{window, Element, Event, HtmlElement, Window};
 ///
 /// It supports three optional attributes `name = value` parameters.
 /// * `class_name = "ClassName"` - The class name to use for the javascript shim. If not provided uses the structs name instead.
-/// * `element_name = "class-name"` - A valid custom element name to use for the element. if not proviced derives it from the class name.
+/// * `element_name = "class-name"` - A valid custom element name to use for the element. if not proviced derives it from the class name. The prefix set with [set_element_prefix] gets
+///   added at runtime.
 /// * `observed_attrs = "['attr1', 'attr2']"` - A javascript array with a list of observed attributes for this compoment. Defaults to "[]".
+/// * `aliases = "{'colour': 'color'}"` - A javascript object mapping deprecated attribute
+///   names to the observed attributes they were renamed to. Changes to a deprecated
+///   attribute are written through to its new name, with a warning when diagnostics are
+///   enabled. Defaults to "{}".
 /// * `observed_events = "['click', 'change']"` - A javascript array with a list of observed event types for this compoment. Defaults to "[]".
+/// * `attr_types = "{open: bool, 'page-size': u32}"` - The Rust types of observed attributes for
+///   the accessors of the generated `<Struct>Ref` element wrapper. See [AttributeValue].
+///   Untyped attributes are Strings.
+/// * `emits = "['page-changed']"` - A javascript array with the event types the element
+///   dispatches. The generated `<Struct>Ref` gets an `on_*` method for each of them returning
+///   an [EventSubscription]. Defaults to "[]".
+/// * `behaviors = "[Tooltip, Draggable]"` - A list of types implementing [Behavior] whose
+///   observed attributes, observed events and lifecycle hooks get added to the component's.
+///   Defaults to none.
 /// * `base_class = "HTMLInputElement"` - The HTMLElement base class this custom-element should
-/// inherit from. Defaults to "HTMLElement".
+///   inherit from. Defaults to "HTMLElement". With "HTMLDialogElement" the element is defined as
+///   a customized `<dialog>` and gets `show_modal` and `close` methods. Requires the `Dialog`
+///   feature. With "HTMLInputElement", "HTMLSelectElement" or "HTMLTextAreaElement" the element
+///   is defined as a customized `<input>`, `<select>` or `<textarea>` whose value changes are
+///   reported to `value_changed`.
+/// * `stateless = true` - Share a single instance of your struct across every element instead
+///   of constructing one per element. Useful for components with no per-instance state.
+///   Defaults to false.
+/// * `observed_dataset = "['userId']"` - A javascript array with a list of `dataset` keys whose
+///   `data-*` attributes are observed, e.g. `data-user-id` for `userId`. See [Dataset] which
+///   requires the `Dataset` feature. Defaults to "[]".
+/// * `persist_attrs = "['collapsed']"` - A javascript array with a list of attributes to persist
+///   in web storage. Persisted attributes are observed and restored when the element is first
+///   connected. They are keyed by the element name plus the element's optional `persist-key`
+///   attribute. Defaults to "[]".
+/// * `persist = "session"` - The web storage to persist attributes in. Either "local" or
+///   "session". Defaults to "local".
+/// * `url_attrs = "['q', 'sort']"` - A javascript array with a list of attributes to sync with
+///   the URL query parameters of the same name. They are read from the URL when the element
+///   gets connected and on `popstate`, and written back with `history.replaceState` when they
+///   change. Defaults to "[]".
+/// * `snapshot = true` - Include the component in [snapshot_all] and [restore_all]. Requires
+///   implementing [WebComponentSnapshot] and the `Snapshot` feature.
+/// * `data_src = true` - Fetch the JSON at the element's `data-src` attribute whenever the
+///   element is connected or the attribute changes. Requires implementing [DataSource] and the
+///   `DataSource` feature.
+/// * `live = true` - Keep a WebSocket open while the element is connected. Requires implementing
+///   [LiveComponent] and the `LiveComponent` feature.
+/// * `drag_source = true` - Make the element draggable. Requires implementing [DragSource] and
+///   the `DragDrop` feature.
+/// * `drop_target = true` - Accept drags dropped on the element. Requires implementing
+///   [DropTarget] and the `DragDrop` feature.
+/// * `localized = true` - Get the `locale_changed` callback when the element is connected, when a
+///   `lang` attribute changes the element's language and when a [Bundle] is registered. The
+///   [Bundle] helpers require the `I18n` feature. Defaults to false.
+/// * `children_ready = true` - Get the `children_ready` callback once the element's light DOM
+///   children have been parsed, for components that read them. Defaults to false.
+/// * `shortcuts = "['Ctrl+K => open_search', 'Escape => close']"` - Call the named methods
+///   with the element and the `KeyboardEvent` when one of the [KeyCombo]s is pressed. The
+///   first matching shortcut wins and prevents the event's default. Keys without Ctrl, Alt or
+///   Meta typed into inputs are ignored, except for Escape. Requires the `Keys` feature.
+/// * `shortcuts_scope = "global"` - Listen for the `shortcuts` on the document while the
+///   element is connected instead of only on the element and its contents. Defaults to
+///   "element".
+/// * `persist_idb = "editor"` - Persist the component's state in IndexedDB under the key. It is
+///   loaded when the element is first connected and saved when it is disconnected. Requires
+///   implementing [IdbState] and the `IndexedDb` feature.
+/// * `files = true` - Read the files chosen in file inputs inside the element or dropped on it.
+///   Requires implementing [FileReceiver] and the `Files` feature.
+/// * `gestures = true` - Recognize taps, double taps, long presses, swipes and pinches from
+///   the element's pointer events. Requires implementing [GestureHandler] and the `Gestures`
+///   feature.
+/// * `track_focus = true` - Call `focus_changed` when focus enters or leaves the element or its
+///   shadow root. See [contains_focus] and [focus_first]. Defaults to false.
+/// * `form_associated = true` - Make the element a form-associated custom element that can be
+///   labeled like the built-in form controls. Clicking one of its labels focuses the control
+///   inside of it. See [labels]. Defaults to false.
+/// * `label_target = "input"` - A selector for the control in the shadow root that clicking a
+///   label focuses. Defaults to the first input, select, textarea, button or element with a
+///   `tabindex`.
+/// * `role = "listbox"` - The default ARIA role of the element. It is set through
+///   `ElementInternals` so authors can still override it with a `role` attribute.
+/// * `aria = "{ariaOrientation: 'vertical'}"` - A javascript object with default ARIA
+///   properties to set through `ElementInternals`. See [Aria]. Defaults to "{}".
+/// * `view_transition = true` - Run `attribute_changed` inside of a view transition where the
+///   browser supports them and give connected elements a unique `view-transition-name`. The
+///   changes made in one task share a single transition and get delivered in order once it
+///   has captured the page, so `attribute_changed` runs asynchronously. See
+///   [with_view_transition]. Defaults to false.
+/// * `animate_in = true` - Call the `animate_in` callback after the element is connected unless
+///   the user prefers reduced motion. Defaults to false.
+/// * `media_queries = "['(prefers-color-scheme: dark)']"` - A javascript array with a list of
+///   media queries to watch while the element is connected. Changes are delivered to the
+///   `media_changed` callback. Defaults to "[]".
+/// * `content_visibility = "auto 320px"` - Style connected elements with
+///   `content-visibility: auto` so the browser skips rendering them while they are offscreen,
+///   using the value as their `contain-intrinsic-size` until they have been rendered. `true`
+///   uses "auto 500px". Changes are delivered to the `content_visibility_changed` callback.
+///   Styles set on the element take precedence.
+/// * `themed = true` - Get the `theme_changed` callback when a [Theme] is applied to the element
+///   or one of its ancestors. Requires the `Theme` feature. Defaults to false.
+/// * `parts = "['label', 'icon']"` - A javascript array with the names of the CSS shadow parts
+///   of the component. Elements in the shadow root attached with `attach_shadow` whose id or
+///   class matches a part get the `part` attribute. Defaults to "[]".
+/// * `scoped_style = ".title { font-weight: bold; }"` - A stylesheet for a component that renders
+///   into the light DOM. Its class selectors get a suffix unique to the component at compile
+///   time and it is added to the document head when the component is defined. Use
+///   `Self::scope_classes` or `Self::scoped_class` to render matching classes.
+/// * `light_dom = true` - Render into the element's light DOM instead of a shadow root with
+///   `Self::render`. `<slot>` elements get emulated by projecting the element's children
+///   into them. Defaults to false.
+/// * `version = "2"` - Define the element under its name with the version appended, e.g.
+///   `my-element-v2`, so several versions can be used side by side. Defaults to no version.
+/// * `version_alias = true` - Also define the unversioned element name as an alias of a
+///   versioned component if it isn't defined already. Defaults to false.
+/// * `disabled_features = "['shadow', 'internals']"` - A javascript array with the features
+///   in the element's `static disabledFeatures`. Consumers of the element can't attach a
+///   shadow root or element internals to it when they are disabled. Defaults to "[]".
+/// * `constructor = "with_config"` - The associated function without arguments that constructs
+///   the Rust side of every element instead of `Default::default`. The struct doesn't need to
+///   implement `Default` then. Defaults to none.
+/// * `concrete = "MyElement<RealBackend>"` - The instantiation of a generic struct that gets
+///   defined as the web component, so the struct can be written against traits and tested
+///   with mock implementations. The element is defined by a generated wrapper struct named
+///   after the instantiation, e.g. `MyElementRealBackend`, which derefs to it. Required for
+///   generic structs.
+/// * `delegates = "[selection, keyboard]"` - A list of fields of the struct implementing
+///   [WebComponentBinding] that get every callback after the component itself, in the listed
+///   order. `disconnected` goes to them first in reverse order instead. `before_remove` and
+///   `validate` return the first value returned by the component or a delegate. Defaults to
+///   none.
+/// * `mutability = "mut"` - Which variants of the [WebComponentBinding] callbacks get called.
+///   `"mut"` only calls the `_mut` variants and `"ref"` only the others. Defaults to "both"
+///   which calls the non-mut variant and then the `_mut` variant of every callback, or "ref"
+///   with the `Minimal` feature.
+/// * `listen_on = "host"` - Where the element listens for its `observed_events`. `"host"` only
+///   listens on the element itself, `"shadow"` only on its shadow root and `"both"` on both.
+///   Events are handled once either way. The `handle_event_with` callbacks get an
+///   [EventContext] with where an event came from. Nothing listens when there are no observed
+///   events. Defaults to `"auto"`, the shadow root if the element has one when it's
+///   constructed and the element itself otherwise.
+/// * `pool_size = 64` - Keep up to this many disconnected elements around to get reused by
+///   `create()`. Elements only go back to the pool through `release_to_pool()` once they are
+///   removed. Reused elements get the `reset` callback. Defaults to 0 which disables pooling.
 ///
 /// It will also create a `Self::define_once` method that will define the WebComponent exactly
-/// once.
+/// once and a `Self::builder` method that returns an [ElementBuilder] for a new instance.
+/// `Self::define_with_factory` defines the WebComponent with a closure that constructs the Rust
+/// side of every element, e.g. to capture handles that can't implement `Default`, and
+/// `Self::define_with` defines it with a config shared by every element. See [FromConfig].
+/// `Self::define_or_fallback` defines the WebComponent where custom elements are supported and
+/// otherwise calls a fallback with every matching element in the document so they can get a
+/// degraded enhancement instead.
+///
+/// ## Binary size
+///
+/// Every component only embeds the javascript and the exported callback shims for the
+/// `#[web_component]` options it uses.
+/// The `Minimal` feature generates the least code for embedded widgets where every byte of
+/// wasm counts. Components and their `Ref` wrappers don't get the `Debug` derive the macro
+/// otherwise adds, only the non-mut callbacks get called unless `mutability` says otherwise and
+/// the diagnostics checks aren't generated even with the `Diagnostics` feature. Derives written
+/// on the component are kept. The generated code never formats strings, with or without the
+/// feature. Combine it with `default-features = false`.
+///
+/// `make size` builds the `size` example both ways and prints the sizes. As built by cargo,
+/// before `wasm-bindgen` and `wasm-opt`, it is 967058 bytes with the default features and
+/// 966254 bytes with `HtmlTemplateElement,Minimal`. Most of that is the library itself. Unused
+/// `Debug` impls get dropped by the linker anyway so leaving out the derives mostly saves
+/// compile time, and `core::fmt` stays in every binary through std's panic handler unless std
+/// is rebuilt with `panic_immediate_abort`.
 ///
 /// ## Example
 ///
@@ -31,7 +488,7 @@ use web_sys::{window, Element, Event, HtmlElement, Window};
 /// use web_sys::*;
 /// use wasm_bindgen::*;
 /// use wasm_web_component::{web_component, WebComponent, WebComponentHandle, WebComponentDef, WebComponentBinding};
-/// 
+///
 /// #[web_component(
 ///     class_name = "MyElement",
 ///     element_name = "my-element",
@@ -40,25 +497,25 @@ use web_sys::{window, Element, Event, HtmlElement, Window};
 ///     base_class = "HTMLElement"
 /// )]
 /// pub struct MyElementImpl {}
-/// 
+///
 /// impl WebComponentBinding for MyElementImpl {
 ///     fn connected(&self, element: &HtmlElement) {
 ///         let node = Text::new().unwrap();
-///         node.set_text_content(Some("Added a text node on connect".into()));
+///         node.set_text_content(Some("Added a text node on connect"));
 ///         element.append_child(&node).unwrap();
 ///     }
-/// 
+///
 ///     fn disconnected(&self, element: &HtmlElement) {
 ///         let node = element.first_child().unwrap();
 ///         element.remove_child(&node).unwrap();
 ///     }
-/// 
+///
 ///     fn adopted(&self, element: &HtmlElement) {
 ///         let node = Text::new().unwrap();
-///         node.set_text_content(Some("Added a text node on adopt".into()));
+///         node.set_text_content(Some("Added a text node on adopt"));
 ///         element.append_child(&node).unwrap();
 ///     }
-/// 
+///
 ///     fn attribute_changed(
 ///         &self,
 ///         element: &HtmlElement,
@@ -88,6 +545,36 @@ use web_sys::{window, Element, Event, HtmlElement, Window};
 /// Reference [MDN Web Components Guide](https://developer.mozilla.org/en-US/docs/Web/Web_Components)
 pub use wasm_web_component_macros::web_component;
 
+/// Generates typed wrappers for the custom elements declared in a
+/// [custom elements manifest](https://github.com/webcomponents/custom-elements-manifest),
+/// e.g. for third party components written in javascript. The path is relative to the
+/// crate's `Cargo.toml`.
+///
+/// Every declaration with a `tagName` gets a newtype around `HtmlElement` named after its
+/// class with a `Ref` suffix, like the wrappers of the [`#[web_component]`](web_component)
+/// attribute, with:
+/// * A `create` function and a `TAG_NAME` constant.
+/// * A getter and setter for every attribute. `boolean` and `number` attributes are `bool`
+///   and `f64`, every other attribute is a `String`. See [AttributeValue].
+/// * A getter and setter for every public field that isn't set through an attribute, only
+///   a getter if it is readonly. See [PropertyValue] for the types.
+/// * An `on_*` method for every event. Events typed `CustomEvent<{...}>` get a struct for
+///   their detail which is passed to the callback.
+///
+/// Typescript types without an obvious Rust type are `JsValue`.
+///
+/// ## Example usage
+/// ```ignore
+/// custom_elements!("node_modules/@acme/pager/custom-elements.json");
+///
+/// let pager = AcmePagerRef::create()?;
+/// pager.set_page_size(&50.0)?;
+/// let _subscription = pager.on_page_changed(|detail: AcmePagerPageChangedDetail, _event| {
+///     // ...
+/// })?;
+/// ```
+pub use wasm_web_component_macros::custom_elements;
+
 /// This attribute proc-macro will generate the following trait implementation
 /// [TemplateElement](trait@TemplateElement)
 ///
@@ -134,45 +621,288 @@ pub use wasm_web_component_macros::template_element;
 
 /// Helper trait for Rust Web Components. This is autogenerated
 /// by the [`#[web_component]`](web_component) attribute.
-pub trait WebComponentDef: IntoWasmAbi + Default {
-    fn new() -> Self {
+pub trait WebComponentDef: IntoWasmAbi + Sized {
+    /// Constructs the Rust side of a new element. Components with a `constructor` are
+    /// constructed with it instead and don't have to implement Default.
+    fn new() -> Self
+    where
+        Self: Default,
+    {
         Self::default()
     }
 
+    /// Creates a new instance of this element. If the element is pooled then a
+    /// previously released instance will get reused when one is available.
     fn create() -> Element {
+        if Self::pool_size() > 0 {
+            if let Some(element) = pool::acquire(Self::element_name()) {
+                return element;
+            }
+        }
         Self::create_in_window(window().expect("Failed to get window"))
     }
 
     fn create_in_window(window: Window) -> Element {
-        window
-            .document()
-            .expect("Failed to get document")
-            .create_element(Self::element_name())
-            .expect("Failed to create element")
+        let document = window.document().expect("Failed to get document");
+        match Self::extends() {
+            Some(tag) => document.create_element_with_str(tag, &Self::tag_name()),
+            None => document.create_element(&Self::tag_name()),
+        }
+        .expect("Failed to create element")
+    }
+
+    /// Creates a new instance of this element with the given attributes set.
+    fn create_with_attrs(attrs: &[(&str, &str)]) -> Element {
+        let element = Self::create();
+        for (name, value) in attrs {
+            element
+                .set_attribute(name, value)
+                .expect("Failed to set attribute");
+        }
+        element
+    }
+
+    /// Starts an [ElementBuilder] for a new instance of this element.
+    fn builder() -> ElementBuilder {
+        ElementBuilder::new(Self::create())
     }
 
     /// Creates a custom event
     fn custom_event(event_type: &str) -> web_sys::Event {
-        web_sys::CustomEvent::new(event_type).unwrap().dyn_into().unwrap()
+        web_sys::CustomEvent::new(event_type)
+            .unwrap()
+            .dyn_into()
+            .unwrap()
+    }
+
+    /// The maximum number of disconnected instances to keep around for reuse.
+    /// A size of 0 disables pooling.
+    fn pool_size() -> usize {
+        0
+    }
+
+    /// Returns a disconnected element to this element's pool if pooling is enabled.
+    /// Disconnecting an element doesn't release it, elements that are moved around
+    /// the DOM get disconnected too. Releasing an already pooled element is a noop.
+    fn release_to_pool(element: &Element) {
+        if Self::pool_size() > 0 {
+            pool::release(Self::element_name(), element, Self::pool_size());
+        }
+    }
+
+    /// Frees the Rust side of the element immediately instead of waiting for the
+    /// element to get garbage collected. The element's callbacks become noops afterwards.
+    /// Disposing an element from inside one of its callbacks frees it once the callback
+    /// returns. Disposed elements are dropped from the pool and their pending
+    /// [on_disconnect] hooks run.
+    fn dispose(element: &Element) -> Result<(), JsValue> {
+        pool::remove(Self::element_name(), element);
+        run_disconnect_hooks(element);
+        let dispose: Function = Reflect::get(element, &JsValue::from_str("dispose"))?.dyn_into()?;
+        dispose.call0(element)?;
+        Ok(())
+    }
+
+    /// Removes the element after its [WebComponentBinding::before_remove] hook and any
+    /// running animations have finished. The returned promise resolves once the element
+    /// has been removed.
+    fn remove(element: &Element) -> Promise {
+        remove_animated(element)
+    }
+
+    /// Drops every pooled instance of this element.
+    fn clear_pool() {
+        pool::clear(Self::element_name());
+    }
+
+    /// Returns an iterator over the connected instances of this element.
+    fn instances() -> Instances {
+        Instances::new(Self::element_name())
+    }
+
+    /// Calls the callback with the Rust side and the element of every connected instance
+    /// of this element and returns how many instances there were. See [broadcast_all]
+    /// for every component's elements.
+    fn broadcast<F>(callback: F) -> Result<usize, JsValue>
+    where
+        Self: Sized + 'static,
+        F: FnMut(&mut Self, &HtmlElement) + 'static,
+    {
+        broadcast::broadcast::<Self, F>(callback)
+    }
+
+    /// Calls [WebComponentBinding::invalidated] on every connected instance of this
+    /// element so they render again, e.g. when a dev server reloads the component's
+    /// template or stylesheet without reloading the page.
+    fn invalidate_all() -> Result<(), JsValue> {
+        instances::invalidate_all(Self::element_name())
+    }
+
+    /// The CSS shadow parts declared in the `parts` argument to the `#[web_component]`
+    /// attribute.
+    fn parts() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Forwards this component's shadow parts out of the shadow root the element is in
+    /// by setting the element's `exportparts` attribute. The parts are renamed with the
+    /// prefix if there is one.
+    fn export_parts(element: &Element, prefix: Option<&str>) -> Result<(), JsValue> {
+        export_parts(element, Self::parts(), prefix)
+    }
+
+    /// The stylesheet from the `scoped_style` argument to the `#[web_component]`
+    /// attribute with its class selectors already scoped.
+    fn scoped_style() -> Option<&'static str> {
+        None
+    }
+
+    /// The suffix added to the classes of the `scoped_style` stylesheet.
+    fn style_scope() -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the class name as it appears in the `scoped_style` stylesheet.
+    fn scoped_class(name: &str) -> String {
+        match Self::style_scope() {
+            Some(scope) => scoped_class(name, scope),
+            None => name.to_owned(),
+        }
+    }
+
+    /// Scopes the classes of the element and its descendants so the `scoped_style`
+    /// stylesheet applies to them.
+    fn scope_classes(root: &Element) -> Result<(), JsValue> {
+        match Self::style_scope() {
+            Some(scope) => scope_classes(root, scope),
+            None => Ok(()),
+        }
+    }
+
+    /// The event types from the `observed_events` argument to the `#[web_component]`
+    /// attribute.
+    fn observed_events() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The event types from the `emits` argument to the `#[web_component]` attribute.
+    fn emitted_events() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The `///` docs of the component struct. They are only kept with the `Docs`
+    /// feature.
+    fn docs() -> ComponentDocs {
+        ComponentDocs::default()
+    }
+
+    /// The behaviors from the `behaviors` argument to the `#[web_component]` attribute.
+    #[doc(hidden)]
+    fn behaviors() -> &'static [BehaviorHooks] {
+        &[]
+    }
+
+    /// The element features disabled with the `disabled_features` argument to the
+    /// `#[web_component]` attribute.
+    fn disabled_features() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether the component renders into its light DOM instead of a shadow root.
+    fn light_dom() -> bool {
+        false
+    }
+
+    /// Renders the html into the element's light DOM if the component has `light_dom`
+    /// set and into an open shadow root otherwise.
+    fn render(element: &HtmlElement, html: &str) -> Result<(), JsValue> {
+        if Self::light_dom() {
+            render_light::<Self>(element, html)
+        } else {
+            render_shadow::<Self>(element, html, ShadowRootMode::Open).map(|_| ())
+        }
+    }
+
+    /// Renders the content of the template element like [WebComponentDef::render].
+    #[cfg(feature = "HtmlTemplateElement")]
+    fn render_template(
+        element: &HtmlElement,
+        template: &HtmlTemplateElement,
+    ) -> Result<(), JsValue> {
+        Self::render(element, &template.inner_html())
+    }
+
+    /// The built-in element a customized built-in element extends.
+    fn extends() -> Option<&'static str> {
+        None
     }
 
     fn element_name() -> &'static str;
+
+    /// The name the element actually got defined under. This differs from the
+    /// [element_name](WebComponentDef::element_name) when the element got namespaced
+    /// because of a conflicting definition. See [ConflictPolicy].
+    fn tag_name() -> String {
+        defined_name(Self::element_name_versioned())
+    }
+
+    /// The version from the `version` argument to the `#[web_component]` attribute.
+    fn version() -> Option<&'static str> {
+        None
+    }
+
+    /// The element name with the version appended if the component has a version.
+    /// Versioned components get defined under this name.
+    fn element_name_versioned() -> &'static str {
+        match Self::version() {
+            Some(version) => versioned_element_name(Self::element_name(), version),
+            None => Self::element_name(),
+        }
+    }
+
+    /// Whether the unversioned element name gets defined as an alias of a versioned
+    /// component.
+    fn version_alias() -> bool {
+        false
+    }
     fn class_name() -> &'static str;
 }
 
 /// Trait defining the lifecycle callbacks for a Custom Element.
 /// Each method is optional. You only need to implement the ones
 /// you want to specify behavior for.
-pub trait WebComponentBinding: WebComponentDef {
+///
+/// State that the non-mut callbacks need to update can be kept in a [ComponentCell]
+/// instead of implementing the `_mut` variants.
+///
+/// Callbacks that get triggered from inside another callback of the same element, e.g.
+/// by setting one of its observed attributes, are deferred until that callback returns.
+///
+/// The trait isn't bound on [WebComponentDef] so `delegates` can implement it too. Generic
+/// code that needs both has to ask for both. This changed in 0.4.0, see the changelog.
+pub trait WebComponentBinding {
     /// Called during element construction.
     fn init(&self, _element: &HtmlElement) {
         // noop
     }
-    
+
     fn init_mut(&mut self, _element: &HtmlElement) {
         // noop
     }
-    
+
+    /// Called during element construction after `init` with what the element looked
+    /// like when it got constructed. This is where setup that the spec only allows in
+    /// the constructor belongs, like hydrating a declarative shadow root.
+    fn init_with(&self, _element: &HtmlElement, _context: &InitContext) {
+        // noop
+    }
+
+    /// Called during element construction after `init_mut` with what the element looked
+    /// like when it got constructed.
+    fn init_with_mut(&mut self, _element: &HtmlElement, _context: &InitContext) {
+        // noop
+    }
+
     /// Called when the web component is connected to the DOM.
     /// This is when you should do any setup like attaching a ShadowDom
     /// or appending elements.
@@ -233,15 +963,234 @@ pub trait WebComponentBinding: WebComponentDef {
         // noop
     }
 
+    /// Called once with the net changes of the observed attributes at the end of an
+    /// [element_attrs_txn]. Calls `attribute_changed` for every change by default.
+    fn attributes_changed(&self, element: &HtmlElement, changes: &[AttributeChange]) {
+        for change in changes {
+            let (name, old_value, new_value) = change.to_js();
+            self.attribute_changed(element, name, old_value, new_value);
+        }
+    }
+
+    /// Called once with the net changes of the observed attributes at the end of an
+    /// [element_attrs_txn]. Calls `attribute_changed_mut` for every change by default.
+    fn attributes_changed_mut(&mut self, element: &HtmlElement, changes: &[AttributeChange]) {
+        for change in changes {
+            let (name, old_value, new_value) = change.to_js();
+            self.attribute_changed_mut(element, name, old_value, new_value);
+        }
+    }
+
+    /// Called with whether one of the media queries listed in the `media_queries`
+    /// argument to the `#[web_component]` attribute matches. It is called for every
+    /// query when the element is connected and then whenever a query's result changes.
+    fn media_changed(&self, _element: &HtmlElement, _query: &str, _matches: bool) {
+        // noop
+    }
+
+    /// Called with whether one of the media queries listed in the `media_queries`
+    /// argument to the `#[web_component]` attribute matches. It is called for every
+    /// query when the element is connected and then whenever a query's result changes.
+    fn media_changed_mut(&mut self, _element: &HtmlElement, _query: &str, _matches: bool) {
+        // noop
+    }
+
+    /// Called when the browser starts or stops skipping the rendering of the element's
+    /// contents because it is offscreen. Only called for components with
+    /// `content_visibility` in the `#[web_component]` attribute.
+    fn content_visibility_changed(&self, _element: &HtmlElement, _skipped: bool) {
+        // noop
+    }
+
+    /// Called when the browser starts or stops skipping the rendering of the element's
+    /// contents because it is offscreen. Only called for components with
+    /// `content_visibility` in the `#[web_component]` attribute.
+    fn content_visibility_changed_mut(&mut self, _element: &HtmlElement, _skipped: bool) {
+        // noop
+    }
+
+    /// Called when a [Theme] has been applied to the element or one of its ancestors.
+    /// Only called for components with `themed = true` in the `#[web_component]` attribute.
+    fn theme_changed(&self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when a [Theme] has been applied to the element or one of its ancestors.
+    /// Only called for components with `themed = true` in the `#[web_component]` attribute.
+    fn theme_changed_mut(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called with the element's language when it is connected, when a `lang` attribute
+    /// changes it and when a bundle is registered. Only called for components with
+    /// `localized = true` in the `#[web_component]` attribute.
+    fn locale_changed(&self, _element: &HtmlElement, _locale: &str) {
+        // noop
+    }
+
+    /// Called with the element's language when it is connected, when a `lang` attribute
+    /// changes it and when a bundle is registered. Only called for components with
+    /// `localized = true` in the `#[web_component]` attribute.
+    fn locale_changed_mut(&mut self, _element: &HtmlElement, _locale: &str) {
+        // noop
+    }
+
+    /// Called once the parser has added the element's children, which it may not have
+    /// when the element gets connected while the document is still loading. Called right
+    /// after `connected` for elements created after that. Only called for components with
+    /// `children_ready = true` in the `#[web_component]` attribute.
+    fn children_ready(&self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called once the parser has added the element's children, which it may not have
+    /// when the element gets connected while the document is still loading. Called right
+    /// after `connected` for elements created after that. Only called for components with
+    /// `children_ready = true` in the `#[web_component]` attribute.
+    fn children_ready_mut(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called after the web component is connected to the DOM to start any enter
+    /// animations. Only called for components with `animate_in = true` in the
+    /// `#[web_component]` attribute and not when the user prefers reduced motion.
+    fn animate_in(&self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called after the web component is connected to the DOM to start any enter
+    /// animations. Only called for components with `animate_in = true` in the
+    /// `#[web_component]` attribute and not when the user prefers reduced motion.
+    fn animate_in_mut(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called by [WebComponentDef::remove] before the element is removed. Removal
+    /// waits for the returned promise to settle.
+    fn before_remove(&self, _element: &HtmlElement) -> Option<Promise> {
+        None
+    }
+
+    /// Returns the [Validity] of a form-associated element's value. Called when the
+    /// element is connected, when its value is set with [set_form_value], when its form
+    /// is reset and when the user tries to submit its form. Returning None leaves the
+    /// element's validity alone.
+    fn validate(&self, _element: &HtmlElement) -> Option<Validity> {
+        None
+    }
+
+    /// Called when a pooled element is reused by [WebComponentDef::create].
+    /// This is where you should return the element to a pristine state.
+    fn reset(&self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when a pooled element is reused by [WebComponentDef::create].
+    /// This is where you should return the element to a pristine state.
+    fn reset_mut(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called on every connected element by [WebComponentDef::invalidate_all] after the
+    /// component's template or stylesheet changed. This is where you should render the
+    /// element again.
+    fn invalidated(&self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called on every connected element by [WebComponentDef::invalidate_all] after the
+    /// component's template or stylesheet changed. This is where you should render the
+    /// element again.
+    fn invalidated_mut(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when a dialog element has closed with the dialog's return value.
+    fn dialog_closed(&self, _element: &HtmlElement, _return_value: &str) {
+        // noop
+    }
+
+    /// Called when a dialog element has closed with the dialog's return value.
+    fn dialog_closed_mut(&mut self, _element: &HtmlElement, _return_value: &str) {
+        // noop
+    }
+
+    /// Called when a dialog element is about to be cancelled. Prevent the event's
+    /// default to keep the dialog open.
+    fn dialog_cancel(&self, _element: &HtmlElement, _event: &Event) {
+        // noop
+    }
+
+    /// Called when a dialog element is about to be cancelled. Prevent the event's
+    /// default to keep the dialog open.
+    fn dialog_cancel_mut(&mut self, _element: &HtmlElement, _event: &Event) {
+        // noop
+    }
+
+    /// Called when focus entered or left an element with `track_focus = true`. Focus
+    /// moving between elements inside of the element doesn't count.
+    fn focus_changed(&self, _element: &HtmlElement, _has_focus: bool) {
+        // noop
+    }
+
+    /// Called when focus entered or left an element with `track_focus = true`. Focus
+    /// moving between elements inside of the element doesn't count.
+    fn focus_changed_mut(&mut self, _element: &HtmlElement, _has_focus: bool) {
+        // noop
+    }
+
+    /// Called when the value of a form control element changed, with what changed it.
+    /// See [ValueSource].
+    fn value_changed(
+        &self,
+        _element: &HtmlElement,
+        _old_value: &str,
+        _new_value: &str,
+        _source: ValueSource,
+    ) {
+        // noop
+    }
+
+    /// Called when the value of a form control element changed, with what changed it.
+    /// See [ValueSource].
+    fn value_changed_mut(
+        &mut self,
+        _element: &HtmlElement,
+        _old_value: &str,
+        _new_value: &str,
+        _source: ValueSource,
+    ) {
+        // noop
+    }
+
     /// Top level event handler for this custom element.
     fn handle_event(&self, _element: &HtmlElement, _event: &Event) {
         // noop
     }
-    
+
     /// Top level event handler for this custom element.
     fn handle_event_mut(&mut self, _element: &HtmlElement, _event: &Event) {
         // noop
     }
+
+    /// Top level event handler with where the event came from, e.g. to tell events from
+    /// slotted content apart from events from the shadow root. Calls `handle_event` by
+    /// default.
+    fn handle_event_with(&self, element: &HtmlElement, event: &Event, _context: &EventContext) {
+        self.handle_event(element, event);
+    }
+
+    /// Top level event handler with where the event came from, e.g. to tell events from
+    /// slotted content apart from events from the shadow root. Calls `handle_event_mut` by
+    /// default.
+    fn handle_event_with_mut(
+        &mut self,
+        element: &HtmlElement,
+        event: &Event,
+        _context: &EventContext,
+    ) {
+        self.handle_event_mut(element, event);
+    }
 }
 
 /// Marker trait used in the generated shims to assert that there are Rust implemtntations
@@ -267,6 +1216,16 @@ pub struct WebComponentHandle {
     pub element_constructor: Function,
 }
 
+impl WebComponentHandle {
+    /// Constructs a new instance of your element by invoking the element constructor
+    /// directly with `new`. Unlike [WebComponentDef::create] this doesn't go through
+    /// `document.createElement` so it works for customized built-ins and before a
+    /// document is available.
+    pub fn construct(&self) -> Result<HtmlElement, JsValue> {
+        Reflect::construct(&self.element_constructor, &Array::new())?.dyn_into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,7 +1272,7 @@ mod tests {
         impl WebComponentBinding for BenchElement {
             fn connected(&self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on connect".into()));
+                node.set_text_content(Some("Added a text node on connect"));
                 element.append_child(&node).unwrap();
             }
 
@@ -324,7 +1283,7 @@ mod tests {
 
             fn adopted(&self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on adopt".into()));
+                node.set_text_content(Some("Added a text node on adopt"));
                 element.append_child(&node).unwrap();
             }
 
@@ -358,6 +1317,68 @@ mod tests {
         }
     }
 
+    #[wasm_bindgen_test]
+    pub fn bench_mark_fragment_appends() {
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let list = document.create_element("ul").unwrap();
+        body.append_child(&list).unwrap();
+        let item = |i: usize| {
+            let item = document.create_element("li").unwrap();
+            item.set_text_content(Some(&i.to_string()));
+            item
+        };
+
+        {
+            let _timer = Timer::new("append-child::timing");
+            for i in 0..10000 {
+                list.append_child(&item(i)).unwrap();
+                // Reading layout between appends is what makes them thrash.
+                let _ = list.client_height();
+            }
+        }
+        {
+            let _timer = Timer::new("replace-children-efficiently::timing");
+            replace_children_efficiently(&list, (0..10000).map(item)).unwrap();
+            let _ = list.client_height();
+        }
+        assert_eq!(list.child_element_count(), 10000);
+        body.remove_child(&list).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    pub fn bench_mark_template_cache() {
+        let document = window().unwrap().document().unwrap();
+        let list = document.create_element("ul").unwrap();
+
+        {
+            let _timer = Timer::new("create-element::timing");
+            for i in 0..10000 {
+                let item = document.create_element("li").unwrap();
+                let name = document.create_element("span").unwrap();
+                name.set_text_content(Some(&i.to_string()));
+                let remove = document.create_element("button").unwrap();
+                remove.set_text_content(Some("x"));
+                item.append_child(&name).unwrap();
+                item.append_child(&remove).unwrap();
+                list.append_child(&item).unwrap();
+            }
+        }
+        let cache =
+            TemplateCache::new("<li><span data-ref='name'></span><button>x</button></li>").unwrap();
+        {
+            let _timer = Timer::new("template-cache::timing");
+            for i in 0..10000 {
+                let item = cache.instantiate().unwrap();
+                item.get("name")
+                    .unwrap()
+                    .set_text_content(Some(&i.to_string()));
+                list.append_child(&item.into_fragment()).unwrap();
+            }
+        }
+        assert_eq!(list.child_element_count(), 20000);
+    }
+
     // NOTE(jwall): We can only construct the web component once and since the lifetime of the component internals is tied
     // to the handle we run this all in one single function.
     #[wasm_bindgen_test]
@@ -365,14 +1386,14 @@ mod tests {
         #[web_component(
             class_name = "MyElement",
             element_name = "my-element",
-            observed_attrs = "['class']",
+            observed_attrs = "['class']"
         )]
         pub struct MyElementImpl {}
 
         impl WebComponentBinding for MyElementImpl {
             fn connected(&self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on connect".into()));
+                node.set_text_content(Some("Added a text node on connect"));
                 element.append_child(&node).unwrap();
             }
 
@@ -383,7 +1404,7 @@ mod tests {
 
             fn adopted(&self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on adopt".into()));
+                node.set_text_content(Some("Added a text node on adopt"));
                 element.append_child(&node).unwrap();
             }
 
@@ -407,6 +1428,11 @@ mod tests {
         let obj = MyElementImpl::define().expect("Failed to define web component");
         let fun = obj.element_constructor.dyn_ref::<Function>().unwrap();
         assert_eq!(fun.name(), MyElementImpl::class_name());
+        let constructed = obj.construct().expect("Failed to construct element");
+        assert_eq!(
+            constructed.tag_name().to_uppercase(),
+            MyElementImpl::element_name().to_uppercase()
+        );
         let element = MyElementImpl::create();
         assert_eq!(
             element.tag_name().to_uppercase(),
@@ -451,14 +1477,14 @@ mod tests {
         #[web_component(
             class_name = "MyElementMut",
             element_name = "my-element-mut",
-            observed_attrs = "['class']",
+            observed_attrs = "['class']"
         )]
         pub struct MyElementMutImpl {}
 
         impl WebComponentBinding for MyElementMutImpl {
             fn connected_mut(&mut self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on connect".into()));
+                node.set_text_content(Some("Added a text node on connect"));
                 element.append_child(&node).unwrap();
             }
 
@@ -469,7 +1495,7 @@ mod tests {
 
             fn adopted_mut(&mut self, element: &HtmlElement) {
                 let node = Text::new().unwrap();
-                node.set_text_content(Some("Added a text node on adopt".into()));
+                node.set_text_content(Some("Added a text node on adopt"));
                 element.append_child(&node).unwrap();
             }
 
@@ -530,10 +1556,2598 @@ mod tests {
                 "Added a text node on adopt"
             );
         } else {
-            assert!(false);
+            panic!("Failed to open a new window");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_builder() {
+        #[web_component(class_name = "BuiltElement")]
+        pub struct BuiltElementImpl {}
+        impl WebComponentBinding for BuiltElementImpl {}
+
+        BuiltElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = BuiltElementImpl::builder()
+            .attr("class", "foo")
+            .prop("config", "bar")
+            .text("some text")
+            .mount(&body)
+            .expect("Failed to build element");
+        assert_eq!(element.get_attribute("class").unwrap(), "foo");
+        assert_eq!(
+            js_sys::Reflect::get(&element, &JsValue::from_str("config"))
+                .unwrap()
+                .as_string()
+                .unwrap(),
+            "bar"
+        );
+        assert_eq!(element.text_content().unwrap(), "some text");
+        assert_eq!(element.parent_node().unwrap(), body.into());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_upgrade_props_and_attrs() {
+        #[web_component(class_name = "UpgradeElement")]
+        pub struct UpgradeElementImpl {}
+        impl WebComponentBinding for UpgradeElementImpl {}
+
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        // Created before the element is defined so it will get upgraded when it gets
+        // connected.
+        let element = UpgradeElementImpl::create();
+        js_sys::Reflect::set(
+            &element,
+            &JsValue::from_str("config"),
+            &JsValue::from_str("foo"),
+        )
+        .unwrap();
+        UpgradeElementImpl::define_once();
+        // The property's setter on the class records what it saw.
+        let class = window()
+            .unwrap()
+            .custom_elements()
+            .get(&UpgradeElementImpl::tag_name());
+        let prototype = js_sys::Reflect::get(&class, &JsValue::from_str("prototype")).unwrap();
+        Function::new_with_args(
+            "proto",
+            "Object.defineProperty(proto, 'config', {
+                get() { return this._config; },
+                set(value) { this._config = value; this._configSet = true; },
+            });",
+        )
+        .call1(&JsValue::NULL, &prototype)
+        .unwrap();
+        body.append_child(&element).unwrap();
+        let get = |name: &str| js_sys::Reflect::get(&element, &JsValue::from_str(name)).unwrap();
+        assert_eq!(get("_configSet"), JsValue::TRUE);
+        assert_eq!(get("config").as_string().unwrap(), "foo");
+        body.remove_child(&element).unwrap();
+
+        let element = UpgradeElementImpl::create_with_attrs(&[("class", "foo"), ("id", "bar")]);
+        assert_eq!(element.get_attribute("class").unwrap(), "foo");
+        assert_eq!(element.get_attribute("id").unwrap(), "bar");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_stateless() {
+        #[web_component(class_name = "StatelessElement", stateless = true)]
+        pub struct StatelessElementImpl {}
+        impl WebComponentBinding for StatelessElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some("connected"));
+            }
         }
+
+        StatelessElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let first = StatelessElementImpl::create();
+        let second = StatelessElementImpl::create();
+        body.append_child(&first).unwrap();
+        body.append_child(&second).unwrap();
+        assert_eq!(first.text_content().unwrap(), "connected");
+        assert_eq!(second.text_content().unwrap(), "connected");
+        let first_impl = js_sys::Reflect::get(&first, &JsValue::from_str("_impl")).unwrap();
+        let second_impl = js_sys::Reflect::get(&second, &JsValue::from_str("_impl")).unwrap();
+        assert!(js_sys::Object::is(&first_impl, &second_impl));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_pooled() {
+        #[web_component(class_name = "PooledElement", pool_size = 2)]
+        pub struct PooledElementImpl {}
+        impl WebComponentBinding for PooledElementImpl {
+            fn reset(&self, element: &HtmlElement) {
+                element.set_text_content(Some("reset"));
+            }
+        }
+
+        PooledElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = PooledElementImpl::create();
+        body.append_child(&element).unwrap();
+        body.remove_child(&element).unwrap();
+        assert_ne!(PooledElementImpl::create(), element);
+        PooledElementImpl::release_to_pool(&element);
+        PooledElementImpl::release_to_pool(&element);
+        let reused = PooledElementImpl::create();
+        assert_eq!(reused, element);
+        assert_eq!(reused.text_content().unwrap(), "reset");
+        let fresh = PooledElementImpl::create();
+        assert_ne!(fresh, element);
+        PooledElementImpl::clear_pool();
     }
-    
+
+    #[wasm_bindgen_test]
+    fn test_component_dispose() {
+        #[web_component(class_name = "DisposedElement")]
+        pub struct DisposedElementImpl {}
+        impl WebComponentBinding for DisposedElementImpl {
+            fn disconnected(&self, element: &HtmlElement) {
+                element.set_text_content(Some("disconnected"));
+            }
+        }
+
+        DisposedElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = DisposedElementImpl::create();
+        body.append_child(&element).unwrap();
+        let hooks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = hooks.clone();
+        on_disconnect(&element, move || counter.set(counter.get() + 1));
+        DisposedElementImpl::dispose(&element).expect("Failed to dispose element");
+        assert_eq!(hooks.get(), 1);
+        assert!(js_sys::Reflect::get(&element, &JsValue::from_str("_impl"))
+            .unwrap()
+            .is_null());
+        body.remove_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "");
+        assert_eq!(hooks.get(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_dispose_while_dispatching() {
+        #[web_component(class_name = "SelfDisposedElement", pool_size = 1)]
+        pub struct SelfDisposedElementImpl {}
+        impl WebComponentBinding for SelfDisposedElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                Self::dispose(element).expect("Failed to dispose element");
+                element.set_text_content(Some("connected"));
+            }
+        }
+
+        SelfDisposedElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = SelfDisposedElementImpl::create();
+        SelfDisposedElementImpl::release_to_pool(&element);
+        body.append_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "connected");
+        assert!(js_sys::Reflect::get(&element, &JsValue::from_str("_impl"))
+            .unwrap()
+            .is_null());
+        body.remove_child(&element).unwrap();
+        assert_ne!(SelfDisposedElementImpl::create(), element);
+    }
+
+    #[cfg(feature = "Snapshot")]
+    #[wasm_bindgen_test]
+    fn test_component_snapshot_and_restore() {
+        #[web_component(class_name = "SnapshotElement", snapshot = true)]
+        pub struct SnapshotElementImpl {
+            count: u32,
+        }
+        impl WebComponentBinding for SnapshotElementImpl {
+            fn connected_mut(&mut self, _element: &HtmlElement) {
+                self.count += 1;
+            }
+        }
+        impl WebComponentSnapshot for SnapshotElementImpl {
+            type State = u32;
+
+            fn snapshot(&self) -> u32 {
+                self.count
+            }
+
+            fn restore(&mut self, element: &HtmlElement, state: u32) {
+                self.count = state;
+                element.set_text_content(Some(&state.to_string()));
+            }
+        }
+
+        SnapshotElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = SnapshotElementImpl::create();
+        element.set_id("snapshotted");
+        body.append_child(&element).unwrap();
+        let snapshot = snapshot_all().expect("Failed to snapshot");
+        assert!(snapshot.contains(r##""snapshot-element":{"#snapshotted":1}"##));
+
+        restore_all(r##"{"snapshot-element":{"#snapshotted":5}}"##).expect("Failed to restore");
+        assert_eq!(element.text_content().unwrap(), "5");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_persisted_attributes() {
+        #[web_component(class_name = "PersistedElement", persist_attrs = "['collapsed']")]
+        pub struct PersistedElementImpl {}
+        impl WebComponentBinding for PersistedElementImpl {}
+
+        PersistedElementImpl::define_once();
+        let storage = window().unwrap().local_storage().unwrap().unwrap();
+        storage
+            .remove_item("persisted-element:panel:collapsed")
+            .unwrap();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = PersistedElementImpl::create_with_attrs(&[("persist-key", "panel")]);
+        body.append_child(&element).unwrap();
+        element.set_attribute("collapsed", "true").unwrap();
+        assert_eq!(
+            storage
+                .get_item("persisted-element:panel:collapsed")
+                .unwrap(),
+            Some("true".to_owned())
+        );
+        body.remove_child(&element).unwrap();
+
+        let restored = PersistedElementImpl::create_with_attrs(&[("persist-key", "panel")]);
+        body.append_child(&restored).unwrap();
+        assert_eq!(restored.get_attribute("collapsed"), Some("true".to_owned()));
+        body.remove_child(&restored).unwrap();
+        storage
+            .remove_item("persisted-element:panel:collapsed")
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_url_attributes() {
+        #[web_component(class_name = "UrlElement", url_attrs = "['q']")]
+        pub struct UrlElementImpl {}
+        impl WebComponentBinding for UrlElementImpl {}
+
+        UrlElementImpl::define_once();
+        let window = window().unwrap();
+        let history = window.history().unwrap();
+        let original = window.location().href().unwrap();
+        history
+            .replace_state_with_url(&JsValue::NULL, "", Some("?q=rust"))
+            .unwrap();
+        let body = window.document().unwrap().body().unwrap();
+        let element = UrlElementImpl::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.get_attribute("q"), Some("rust".to_owned()));
+
+        element.set_attribute("q", "wasm").unwrap();
+        assert_eq!(window.location().search().unwrap(), "?q=wasm");
+
+        body.remove_child(&element).unwrap();
+        history
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original))
+            .unwrap();
+    }
+
+    #[cfg(feature = "LazyLoad")]
+    #[wasm_bindgen_test]
+    fn test_component_lazy_define() {
+        #[web_component(class_name = "LazyElement")]
+        pub struct LazyElementImpl {}
+        impl WebComponentBinding for LazyElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some("loaded"));
+            }
+        }
+
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        // A placeholder that is already in the document loads the element immediately.
+        let placeholder = LazyElementImpl::create();
+        body.append_child(&placeholder).unwrap();
+        assert_eq!(placeholder.text_content().unwrap(), "");
+        let _defined = lazy_define(LazyElementImpl::element_name(), || {
+            LazyElementImpl::define_once();
+            js_sys::Promise::resolve(&JsValue::UNDEFINED)
+        })
+        .unwrap();
+        assert_eq!(placeholder.text_content().unwrap(), "loaded");
+        body.remove_child(&placeholder).unwrap();
+    }
+
+    #[cfg(feature = "LazyLoad")]
+    #[wasm_bindgen_test]
+    async fn test_component_lazy_define_pending() {
+        #[web_component(class_name = "PendingLazyElement")]
+        pub struct PendingLazyElementImpl {}
+        impl WebComponentBinding for PendingLazyElementImpl {}
+
+        let loads = std::rc::Rc::new(std::cell::Cell::new(0));
+        let loader = |loads: std::rc::Rc<std::cell::Cell<u32>>| {
+            move || {
+                loads.set(loads.get() + 1);
+                js_sys::Promise::resolve(&JsValue::UNDEFINED)
+            }
+        };
+        let name = PendingLazyElementImpl::element_name();
+        let defined = lazy_define(name, loader(loads.clone())).unwrap();
+        // The element is already pending so the second loader is dropped.
+        let _ = lazy_define(name, loader(loads.clone())).unwrap();
+        // Defining the element some other way drops the pending loader.
+        PendingLazyElementImpl::define().unwrap();
+        wasm_bindgen_futures::JsFuture::from(defined).await.unwrap();
+        load_now(name);
+        assert_eq!(loads.get(), 0);
+    }
+
+    #[cfg(feature = "DataSource")]
+    #[wasm_bindgen_test]
+    fn test_component_data_source() {
+        #[web_component(class_name = "DataSourceElement", data_src = true)]
+        pub struct DataSourceElementImpl {}
+        impl WebComponentBinding for DataSourceElementImpl {}
+        impl DataSource for DataSourceElementImpl {
+            type Data = Vec<String>;
+
+            fn data_loaded(&mut self, element: &HtmlElement, data: Vec<String>) {
+                element.set_text_content(Some(&data.join(",")));
+            }
+
+            fn data_error(&mut self, element: &HtmlElement, _error: JsValue) {
+                element.set_text_content(Some("error"));
+            }
+        }
+
+        let handle = DataSourceElementImpl::define().unwrap();
+        let observed = js_sys::Reflect::get(
+            &handle.element_constructor,
+            &JsValue::from_str("observedAttributes"),
+        )
+        .unwrap();
+        assert!(js_sys::Array::from(&observed).includes(&JsValue::from_str("data-src"), 0));
+
+        let element: HtmlElement = DataSourceElementImpl::create().unchecked_into();
+        let mut component = DataSourceElementImpl::new();
+        component.data_loaded_impl(&element, r#"["a","b"]"#);
+        assert_eq!(element.text_content().unwrap(), "a,b");
+        component.data_loaded_impl(&element, "not json");
+        assert_eq!(element.text_content().unwrap(), "error");
+    }
+
+    #[cfg(feature = "LiveComponent")]
+    #[wasm_bindgen_test]
+    fn test_component_live() {
+        #[derive(serde::Deserialize)]
+        pub struct Tick {
+            count: u32,
+        }
+        #[web_component(class_name = "LiveElement", live = true)]
+        pub struct LiveElementImpl {}
+        impl WebComponentBinding for LiveElementImpl {}
+        impl LiveComponent for LiveElementImpl {
+            type Message = Tick;
+
+            fn socket_url(&self, _element: &HtmlElement) -> Option<String> {
+                None
+            }
+
+            fn message_received(&mut self, element: &HtmlElement, message: Tick) {
+                element.set_text_content(Some(&message.count.to_string()));
+            }
+        }
+
+        LiveElementImpl::define_once();
+        let element: HtmlElement = LiveElementImpl::create().unchecked_into();
+        let mut component = LiveElementImpl::new();
+        component.live_message_impl(&element, r#"{"count":3}"#);
+        assert_eq!(element.text_content().unwrap(), "3");
+        // Without a socket url the element never opens a socket to send on.
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        body.append_child(&element).unwrap();
+        assert!(!live_send(&element, &3).unwrap());
+        body.remove_child(&element).unwrap();
+    }
+
+    #[cfg(feature = "ViewTransition")]
+    #[wasm_bindgen_test]
+    async fn test_component_view_transition_batch() {
+        #[web_component(
+            class_name = "TransitionElement",
+            observed_attrs = "['first', 'second']",
+            view_transition = true
+        )]
+        pub struct TransitionElementImpl {}
+        impl WebComponentBinding for TransitionElementImpl {
+            fn attribute_changed(
+                &self,
+                element: &HtmlElement,
+                name: JsValue,
+                _old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                let seen = element.text_content().unwrap_or_default();
+                element.set_text_content(Some(&format!(
+                    "{}{}={};",
+                    seen,
+                    name.as_string().unwrap(),
+                    new_value.as_string().unwrap_or_default()
+                )));
+            }
+        }
+
+        TransitionElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = TransitionElementImpl::create();
+        body.append_child(&element).unwrap();
+        element.set_attribute("first", "1").unwrap();
+        element.set_attribute("second", "2").unwrap();
+        element.set_attribute("first", "3").unwrap();
+        if view_transitions_supported() {
+            // The changes wait for the transition's update.
+            assert_eq!(element.text_content().unwrap(), "");
+        }
+        let expected = "first=1;second=2;first=3;";
+        for _ in 0..100 {
+            if element.text_content().unwrap() == expected {
+                break;
+            }
+            let wait = js_sys::Promise::new(&mut |resolve, _| {
+                window()
+                    .unwrap()
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 10)
+                    .unwrap();
+            });
+            wasm_bindgen_futures::JsFuture::from(wait).await.unwrap();
+        }
+        // Every change of the task got delivered in order by a single transition.
+        assert_eq!(element.text_content().unwrap(), expected);
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_default_aria() {
+        #[web_component(
+            class_name = "AriaElement",
+            role = "listbox",
+            aria = "{ariaMultiSelectable: 'true'}"
+        )]
+        pub struct AriaElementImpl {}
+        impl WebComponentBinding for AriaElementImpl {}
+
+        AriaElementImpl::define_once();
+        let element: HtmlElement = AriaElementImpl::create().unchecked_into();
+        assert!(!element.has_attribute("role"));
+        let aria = Aria::of(&element).expect("Missing ElementInternals");
+        assert_eq!(aria.role(), Some("listbox".to_owned()));
+        assert_eq!(aria.get("ariaMultiSelectable"), Some("true".to_owned()));
+        aria.set("ariaMultiSelectable", None).unwrap();
+        assert_eq!(aria.get("ariaMultiSelectable"), None);
+    }
+
+    #[cfg(feature = "Dialog")]
+    #[wasm_bindgen_test]
+    fn test_component_dialog() {
+        #[web_component(class_name = "ModalElement", base_class = "HTMLDialogElement")]
+        pub struct ModalElementImpl {}
+        impl WebComponentBinding for ModalElementImpl {
+            fn dialog_closed(&self, element: &HtmlElement, return_value: &str) {
+                element.set_attribute("data-closed", return_value).unwrap();
+            }
+        }
+
+        ModalElementImpl::define_once();
+        let element = ModalElementImpl::create();
+        assert_eq!(element.tag_name(), "DIALOG");
+        assert_eq!(element.get_attribute("is"), None);
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        body.append_child(&element).unwrap();
+        ModalElementImpl::show_modal(&element).unwrap();
+        assert!(element.has_attribute("open"));
+        ModalElementImpl::close(&element, Some("ok")).unwrap();
+        assert!(!element.has_attribute("open"));
+
+        let mut component = ModalElementImpl::new();
+        component.dialog_closed_impl(element.unchecked_ref(), "ok".to_owned());
+        assert_eq!(element.get_attribute("data-closed"), Some("ok".to_owned()));
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_value_changed() {
+        #[web_component(class_name = "ValueInput", base_class = "HTMLInputElement")]
+        pub struct ValueInputImpl {}
+        impl WebComponentBinding for ValueInputImpl {
+            fn value_changed(
+                &self,
+                element: &HtmlElement,
+                old_value: &str,
+                new_value: &str,
+                source: ValueSource,
+            ) {
+                let seen = element.get_attribute("data-seen").unwrap_or_default();
+                let entry = format!("{:?}:{}>{};", source, old_value, new_value);
+                element
+                    .set_attribute("data-seen", &(seen + &entry))
+                    .unwrap();
+            }
+        }
+
+        ValueInputImpl::define_once();
+        let element = ValueInputImpl::create();
+        assert_eq!(element.tag_name(), "INPUT");
+        assert!(!is_value_dirty(&element));
+        Reflect::set(&element, &"value".into(), &"a".into()).unwrap();
+        // Edits by the user go through the built-in setter.
+        let prototype =
+            js_sys::Object::get_prototype_of(&js_sys::Object::get_prototype_of(&element));
+        let setter: Function = Reflect::get(
+            &js_sys::Object::get_own_property_descriptor(&prototype, &"value".into()),
+            &"set".into(),
+        )
+        .unwrap()
+        .unchecked_into();
+        setter.call1(&element, &"ab".into()).unwrap();
+        element
+            .dispatch_event(&Event::new("input").unwrap())
+            .unwrap();
+        setter.call1(&element, &"abc".into()).unwrap();
+        element
+            .dispatch_event(&Event::new("input").unwrap())
+            .unwrap();
+        element
+            .dispatch_event(&Event::new("change").unwrap())
+            .unwrap();
+        element
+            .dispatch_event(&Event::new("change").unwrap())
+            .unwrap();
+        assert!(is_value_dirty(&element));
+        assert_eq!(
+            element.get_attribute("data-seen").unwrap(),
+            "Property:>a;Input:a>ab;Input:ab>abc;Commit:a>abc;"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_track_focus() {
+        #[web_component(class_name = "FocusTrackingElement", track_focus = true)]
+        pub struct FocusTrackingElementImpl {}
+        impl WebComponentBinding for FocusTrackingElementImpl {
+            fn focus_changed(&self, element: &HtmlElement, has_focus: bool) {
+                let seen = element.get_attribute("data-focus").unwrap_or_default();
+                element
+                    .set_attribute("data-focus", &format!("{}{};", seen, has_focus))
+                    .unwrap();
+            }
+        }
+
+        FocusTrackingElementImpl::define_once();
+        let element: HtmlElement = FocusTrackingElementImpl::create().unchecked_into();
+        FocusTrackingElementImpl::render(&element, "<button>One</button><button>Two</button>")
+            .unwrap();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        body.append_child(&element).unwrap();
+        assert!(focus_first(&element).unwrap());
+        assert!(contains_focus(&element));
+        let second: HtmlElement = element
+            .shadow_root()
+            .unwrap()
+            .last_element_child()
+            .unwrap()
+            .unchecked_into();
+        second.focus().unwrap();
+        second.blur().unwrap();
+        assert!(!contains_focus(&element));
+        focus_first(&element).unwrap();
+        body.remove_child(&element).unwrap();
+        assert_eq!(
+            element.get_attribute("data-focus").unwrap(),
+            "true;false;true;false;"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_animated_remove() {
+        #[web_component(class_name = "AnimatedElement")]
+        pub struct AnimatedElementImpl {}
+        impl WebComponentBinding for AnimatedElementImpl {
+            fn before_remove(&self, element: &HtmlElement) -> Option<Promise> {
+                element.set_attribute("data-hook", "").unwrap();
+                None
+            }
+        }
+
+        AnimatedElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = AnimatedElementImpl::create();
+        body.append_child(&element).unwrap();
+        let _removed = AnimatedElementImpl::remove(&element);
+        assert!(element.has_attribute("data-hook"));
+        assert!(element.has_attribute("data-leaving"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_animate_in() {
+        #[web_component(class_name = "AnimateInElement", animate_in = true)]
+        pub struct AnimateInElementImpl {}
+        impl WebComponentBinding for AnimateInElementImpl {
+            fn animate_in(&self, element: &HtmlElement) {
+                element.set_attribute("data-entered", "").unwrap();
+            }
+        }
+
+        #[web_component(class_name = "StillElement")]
+        pub struct StillElementImpl {}
+        impl WebComponentBinding for StillElementImpl {
+            fn animate_in(&self, element: &HtmlElement) {
+                element.set_attribute("data-entered", "").unwrap();
+            }
+        }
+
+        AnimateInElementImpl::define_once();
+        StillElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let animated = AnimateInElementImpl::create();
+        let still = StillElementImpl::create();
+        body.append_child(&animated).unwrap();
+        body.append_child(&still).unwrap();
+        let reduced = Function::new_no_args(
+            "return !!window.matchMedia && window.matchMedia('(prefers-reduced-motion: reduce)').matches;",
+        )
+        .call0(&JsValue::NULL)
+        .unwrap()
+        .is_truthy();
+        assert_eq!(animated.has_attribute("data-entered"), !reduced);
+        assert!(!still.has_attribute("data-entered"));
+        body.remove_child(&animated).unwrap();
+        body.remove_child(&still).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_media_queries() {
+        #[web_component(class_name = "MediaElement", media_queries = "['all', 'not all']")]
+        pub struct MediaElementImpl {}
+        impl WebComponentBinding for MediaElementImpl {
+            fn media_changed(&self, element: &HtmlElement, query: &str, matches: bool) {
+                let name = format!("data-{}", query.replace(' ', "-"));
+                element.set_attribute(&name, &matches.to_string()).unwrap();
+            }
+        }
+
+        MediaElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = MediaElementImpl::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.get_attribute("data-all"), Some("true".to_owned()));
+        assert_eq!(
+            element.get_attribute("data-not-all"),
+            Some("false".to_owned())
+        );
+        body.remove_child(&element).unwrap();
+    }
+
+    #[cfg(feature = "Theme")]
+    #[wasm_bindgen_test]
+    fn test_component_theme_changed() {
+        #[derive(serde::Serialize)]
+        pub struct CardTheme {
+            background: String,
+        }
+        impl Theme for CardTheme {
+            const PREFIX: &'static str = "card";
+        }
+        #[web_component(class_name = "ThemedElement", themed = true)]
+        pub struct ThemedElementImpl {}
+        impl WebComponentBinding for ThemedElementImpl {
+            fn theme_changed(&self, element: &HtmlElement) {
+                let background = theme_property::<CardTheme>(element, "background");
+                element.set_text_content(background.as_deref());
+            }
+        }
+
+        ThemedElementImpl::define_once();
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let unrelated: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        let element = ThemedElementImpl::create();
+        body.append_child(&unrelated).unwrap();
+        body.append_child(&element).unwrap();
+        let theme = CardTheme {
+            background: "white".to_owned(),
+        };
+        apply_theme(&unrelated, &theme).unwrap();
+        assert_eq!(element.text_content().unwrap(), "");
+        apply_theme(&body, &theme).unwrap();
+        assert_eq!(element.text_content().unwrap(), "white");
+        body.style().remove_property("--card-background").unwrap();
+        body.remove_child(&element).unwrap();
+        body.remove_child(&unrelated).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_parts() {
+        #[web_component(class_name = "PartsElement", parts = "['label', 'icon']")]
+        pub struct PartsElementImpl {}
+        impl WebComponentBinding for PartsElementImpl {
+            fn init(&self, element: &HtmlElement) {
+                self.attach_shadow(element, "<span id='label'></span><i class='icon'></i>");
+            }
+        }
+
+        PartsElementImpl::define_once();
+        assert_eq!(PartsElementImpl::parts(), &["label", "icon"]);
+        let element = PartsElementImpl::create();
+        let root = element.shadow_root().unwrap();
+        let label = root.get_element_by_id("label").unwrap();
+        assert_eq!(label.get_attribute("part"), Some("label".to_owned()));
+        PartsElementImpl::export_parts(&element, Some("inner")).unwrap();
+        assert_eq!(
+            element.get_attribute("exportparts"),
+            Some("label: inner-label, icon: inner-icon".to_owned())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_scoped_style() {
+        #[web_component(
+            class_name = "ScopedElement",
+            scoped_style = "@media (min-width: 1px) { .title, p.body:hover { color: red; } } .note[title='a.b'] { content: '.x'; }"
+        )]
+        pub struct ScopedElementImpl {}
+        impl WebComponentBinding for ScopedElementImpl {}
+
+        ScopedElementImpl::define_once();
+        let scope = ScopedElementImpl::style_scope().unwrap();
+        assert_eq!(
+            ScopedElementImpl::scoped_style().unwrap(),
+            format!(
+                "@media (min-width: 1px) {{ .title-{0}, p.body-{0}:hover {{ color: red; }} }} .note-{0}[title='a.b'] {{ content: '.x'; }}",
+                scope
+            )
+        );
+        let document = window().unwrap().document().unwrap();
+        let style = document
+            .query_selector("style[data-wasm-scope=\"scoped-element\"]")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            style.text_content().as_deref(),
+            ScopedElementImpl::scoped_style()
+        );
+
+        let element = ScopedElementImpl::create();
+        element.set_inner_html("<h2 class='title big'></h2>");
+        ScopedElementImpl::scope_classes(&element).unwrap();
+        ScopedElementImpl::scope_classes(&element).unwrap();
+        let title = element.first_element_child().unwrap();
+        assert_eq!(
+            title.get_attribute("class"),
+            Some(format!("title-{0} big-{0}", scope))
+        );
+        assert_eq!(
+            ScopedElementImpl::scoped_class("title"),
+            format!("title-{}", scope)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_light_dom() {
+        #[web_component(
+            class_name = "LightElement",
+            light_dom = true,
+            observed_events = "['click']",
+            scoped_style = ".frame { display: block; }"
+        )]
+        pub struct LightElementImpl {}
+        impl WebComponentBinding for LightElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                Self::render(
+                    element,
+                    "<div class='frame'><slot name='title'>Untitled</slot><slot></slot><slot name='footer'>None</slot></div>",
+                )
+                .unwrap();
+            }
+        }
+
+        LightElementImpl::define_once();
+        assert_eq!(LightElementImpl::observed_events(), &["click"]);
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let element: HtmlElement = LightElementImpl::create().unchecked_into();
+        element.set_inner_html("<b slot='title' class='title'>Hi</b> text ");
+        body.append_child(&element).unwrap();
+        assert!(element.shadow_root().is_none());
+        let scope = LightElementImpl::style_scope().unwrap();
+        assert_eq!(
+            element.inner_html(),
+            format!(
+                "<div class=\"frame-{}\"><slot name=\"title\"><b slot=\"title\" class=\"title\">Hi</b></slot><slot> text </slot><slot name=\"footer\">None</slot></div>",
+                scope
+            )
+        );
+        assert_eq!(slotted_nodes(&element, Some("title")).len(), 1);
+
+        // Rendering again keeps the projected children.
+        LightElementImpl::render(&element, "<p><slot name='title'></slot></p>").unwrap();
+        assert_eq!(
+            element.inner_html(),
+            "<p><slot name=\"title\"><b slot=\"title\" class=\"title\">Hi</b></slot></p>"
+        );
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_define_conflict() {
+        #[web_component(class_name = "ConflictElement")]
+        pub struct ConflictElementImpl {}
+        impl WebComponentBinding for ConflictElementImpl {}
+
+        ConflictElementImpl::define().unwrap();
+        let conflict = define_conflict("conflict-element").unwrap();
+        assert_eq!(conflict.existing_version.as_deref(), Some(CRATE_VERSION));
+        assert_eq!(conflict.existing_class.as_deref(), Some("ConflictElement"));
+        let err = ConflictElementImpl::define().err().unwrap();
+        assert!(err
+            .dyn_into::<js_sys::Error>()
+            .unwrap()
+            .message()
+            .as_string()
+            .unwrap()
+            .contains("has already been defined by wasm-web-component"));
+
+        set_conflict_policy(ConflictPolicy::Namespace("second".to_owned()));
+        ConflictElementImpl::define().unwrap();
+        set_conflict_policy(ConflictPolicy::Error);
+        assert_eq!(ConflictElementImpl::tag_name(), "second-conflict-element");
+        assert_eq!(
+            ConflictElementImpl::create().tag_name(),
+            "SECOND-CONFLICT-ELEMENT"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_element_prefix() {
+        #[web_component(class_name = "PrefixedElement")]
+        pub struct PrefixedElementImpl {}
+        impl WebComponentBinding for PrefixedElementImpl {}
+
+        set_element_prefix("acme-");
+        assert_eq!(element_prefix().as_deref(), Some("acme"));
+        assert_eq!(PrefixedElementImpl::element_name(), "acme-prefixed-element");
+        PrefixedElementImpl::define_once();
+        let element = PrefixedElementImpl::create();
+        set_element_prefix("");
+        assert_eq!(element.tag_name(), "ACME-PREFIXED-ELEMENT");
+        assert!(window()
+            .unwrap()
+            .custom_elements()
+            .get("acme-prefixed-element")
+            .is_truthy());
+        assert_eq!(PrefixedElementImpl::element_name(), "prefixed-element");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_version() {
+        #[web_component(class_name = "VersionedElement", version = "2", version_alias = true)]
+        pub struct VersionedElementImpl {}
+        impl WebComponentBinding for VersionedElementImpl {}
+
+        assert_eq!(VersionedElementImpl::element_name(), "versioned-element");
+        assert_eq!(
+            VersionedElementImpl::element_name_versioned(),
+            "versioned-element-v2"
+        );
+        VersionedElementImpl::define_once();
+        assert_eq!(
+            VersionedElementImpl::create().tag_name(),
+            "VERSIONED-ELEMENT-V2"
+        );
+        let registry = window().unwrap().custom_elements();
+        assert!(registry.get("versioned-element-v2").is_truthy());
+        assert!(registry.get("versioned-element").is_truthy());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_disabled_features() {
+        #[web_component(
+            class_name = "SealedElement",
+            disabled_features = "['shadow', 'internals']"
+        )]
+        pub struct SealedElementImpl {}
+        impl WebComponentBinding for SealedElementImpl {}
+
+        SealedElementImpl::define_once();
+        assert_eq!(
+            SealedElementImpl::disabled_features(),
+            &["shadow", "internals"]
+        );
+        let element: HtmlElement = SealedElementImpl::create().unchecked_into();
+        assert!(element
+            .attach_shadow(&web_sys::ShadowRootInit::new(ShadowRootMode::Open))
+            .is_err());
+        let class = window().unwrap().custom_elements().get("sealed-element");
+        let disabled =
+            js_sys::Reflect::get(&class, &JsValue::from_str("disabledFeatures")).unwrap();
+        assert_eq!(js_sys::Array::from(&disabled).length(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_constructor() {
+        #[derive(Debug)]
+        pub struct Greeting(&'static str);
+
+        #[web_component(class_name = "GreetingElement", constructor = "with_greeting")]
+        pub struct GreetingElementImpl {
+            greeting: Greeting,
+        }
+        impl GreetingElementImpl {
+            fn with_greeting() -> Self {
+                Self {
+                    greeting: Greeting("Hello"),
+                }
+            }
+        }
+        impl WebComponentBinding for GreetingElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some(self.greeting.0));
+            }
+        }
+
+        #[web_component(class_name = "FactoryElement", constructor = "unused")]
+        pub struct FactoryElementImpl {
+            greeting: Greeting,
+        }
+        impl FactoryElementImpl {
+            fn unused() -> Self {
+                unreachable!("Elements are constructed by the factory")
+            }
+        }
+        impl WebComponentBinding for FactoryElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some(self.greeting.0));
+            }
+        }
+
+        GreetingElementImpl::define_once();
+        let greeting = "Bonjour";
+        FactoryElementImpl::define_with_factory(move || FactoryElementImpl {
+            greeting: Greeting(greeting),
+        })
+        .unwrap();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        for (element, text) in [
+            (GreetingElementImpl::create(), "Hello"),
+            (FactoryElementImpl::create(), "Bonjour"),
+        ] {
+            body.append_child(&element).unwrap();
+            assert_eq!(element.text_content().unwrap(), text);
+            body.remove_child(&element).unwrap();
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_define_with_config() {
+        #[derive(Debug)]
+        pub struct ApiConfig {
+            base_url: &'static str,
+        }
+
+        #[web_component(class_name = "ConfiguredElement", constructor = "unconfigured")]
+        pub struct ConfiguredElementImpl {
+            config: std::rc::Rc<ApiConfig>,
+        }
+        impl ConfiguredElementImpl {
+            fn unconfigured() -> Self {
+                unreachable!("Elements are constructed from the config")
+            }
+        }
+        impl FromConfig<ApiConfig> for ConfiguredElementImpl {
+            fn from_config(config: std::rc::Rc<ApiConfig>) -> Self {
+                Self { config }
+            }
+        }
+        impl WebComponentBinding for ConfiguredElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some(self.config.base_url));
+            }
+        }
+
+        ConfiguredElementImpl::define_with(ApiConfig {
+            base_url: "https://example.com/api",
+        })
+        .unwrap();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = ConfiguredElementImpl::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "https://example.com/api");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_generic() {
+        pub trait Greeter: Default + std::fmt::Debug {
+            fn greeting(&self) -> &'static str;
+        }
+
+        #[derive(Default, Debug)]
+        pub struct EnglishGreeter;
+        impl Greeter for EnglishGreeter {
+            fn greeting(&self) -> &'static str {
+                "Hello"
+            }
+        }
+
+        #[web_component(
+            class_name = "GenericElement",
+            concrete = "GenericElementImpl<EnglishGreeter>"
+        )]
+        pub struct GenericElementImpl<G: Greeter> {
+            greeter: G,
+        }
+        impl<G: Greeter> WebComponentBinding for GenericElementImpl<G> {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_text_content(Some(self.greeter.greeting()));
+            }
+        }
+
+        GenericElementImplEnglishGreeter::define_once();
+        assert_eq!(
+            GenericElementImplEnglishGreeter::element_name(),
+            "generic-element"
+        );
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = GenericElementImplEnglishGreeter::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "Hello");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_keeps_attributes() {
+        /// A component that derives traits itself.
+        #[web_component(class_name = "DerivingElement")]
+        #[derive(Debug, Default, Clone, serde::Serialize)]
+        pub struct DerivingElementImpl {
+            count: u32,
+        }
+        impl WebComponentBinding for DerivingElementImpl {}
+
+        // Never compiled so nothing generated for it may be either.
+        #[cfg(any())]
+        #[web_component(class_name = "DisabledElement")]
+        pub struct DisabledElementImpl {}
+
+        fn assert_serialize<T: serde::Serialize>(_: &T) {}
+        let component = DerivingElementImpl::default().clone();
+        assert_serialize(&component);
+        assert_eq!(component.count, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_mutability() {
+        #[web_component(class_name = "MutElement", mutability = "mut")]
+        pub struct MutElementImpl {
+            connections: u32,
+        }
+        impl WebComponentBinding for MutElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                element.set_attribute("ref", "").unwrap();
+            }
+
+            fn connected_mut(&mut self, element: &HtmlElement) {
+                self.connections += 1;
+                element
+                    .set_attribute("connections", &self.connections.to_string())
+                    .unwrap();
+            }
+        }
+
+        MutElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = MutElementImpl::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.get_attribute("connections").unwrap(), "1");
+        assert!(!element.has_attribute("ref"));
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_cell() {
+        #[web_component(class_name = "CellElement", mutability = "ref")]
+        pub struct CellElementImpl {
+            count: ComponentCell<CellElementImpl, u32>,
+        }
+        impl WebComponentBinding for CellElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                let mut count = self.count.borrow_mut("connected");
+                *count += 1;
+                element.set_text_content(Some(&count.to_string()));
+            }
+        }
+
+        CellElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = CellElementImpl::create();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "1");
+        body.remove_child(&element).unwrap();
+
+        let cell: ComponentCell<CellElementImpl, u32> = ComponentCell::new(1);
+        let count = cell.borrow_mut("attribute_changed");
+        let err = cell.try_borrow("connected").err().unwrap();
+        assert_eq!(
+            err,
+            StateBorrowError {
+                element_name: "cell-element",
+                callback: "connected",
+                holder: Some("attribute_changed"),
+            }
+        );
+        drop(count);
+        assert_eq!(*cell.borrow("connected"), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_reentrant_attribute_changed() {
+        #[web_component(
+            class_name = "ReentrantElement",
+            observed_attrs = "['value', 'mirror']",
+            mutability = "mut"
+        )]
+        pub struct ReentrantElementImpl {
+            changes: Vec<String>,
+        }
+        impl WebComponentBinding for ReentrantElementImpl {
+            fn attribute_changed_mut(
+                &mut self,
+                element: &HtmlElement,
+                name: JsValue,
+                _old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                let name = name.as_string().unwrap();
+                if name == "value" {
+                    // Re-enters the component which is still mutably borrowed.
+                    element
+                        .set_attribute("mirror", &new_value.as_string().unwrap())
+                        .unwrap();
+                }
+                self.changes.push(name);
+                element
+                    .set_attribute("changes", &self.changes.join(","))
+                    .unwrap();
+            }
+        }
+
+        ReentrantElementImpl::define_once();
+        let element = ReentrantElementImpl::create();
+        element.set_attribute("value", "1").unwrap();
+        assert_eq!(element.get_attribute("mirror").unwrap(), "1");
+        assert_eq!(element.get_attribute("changes").unwrap(), "value,mirror");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_init_context() {
+        #[web_component(class_name = "InitContextElement")]
+        pub struct InitContextElementImpl {}
+        impl WebComponentBinding for InitContextElementImpl {
+            fn init_with(&self, element: &HtmlElement, context: &InitContext) {
+                let label = format!(
+                    "{} {} {}",
+                    context.is_upgrade(),
+                    context.has_declarative_shadow_root(),
+                    context.attribute("label").unwrap_or("none"),
+                );
+                // Constructors may not add attributes or children to the element.
+                js_sys::Reflect::set(element, &"initContext".into(), &label.into()).unwrap();
+            }
+        }
+
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let container = document.create_element("div").unwrap();
+        container.set_inner_html("<init-context-element label='parsed'></init-context-element>");
+        body.append_child(&container).unwrap();
+        InitContextElementImpl::define_once();
+        let upgraded = container.first_element_child().unwrap();
+        let init_context =
+            |element: &Element| js_sys::Reflect::get(element, &"initContext".into()).unwrap();
+        assert_eq!(init_context(&upgraded), "true false parsed");
+        assert_eq!(
+            init_context(&InitContextElementImpl::create()),
+            "false false none"
+        );
+        body.remove_child(&container).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_event_context() {
+        #[web_component(class_name = "EventContextElement", observed_events = "['click']")]
+        pub struct EventContextElementImpl {}
+        impl WebComponentBinding for EventContextElementImpl {
+            fn handle_event_with(
+                &self,
+                element: &HtmlElement,
+                _event: &Event,
+                context: &EventContext,
+            ) {
+                let original = context
+                    .original_target
+                    .as_ref()
+                    .and_then(|target| target.dyn_ref::<Element>())
+                    .map(|target| target.tag_name().to_lowercase())
+                    .unwrap_or_default();
+                let retargeted = context.retargeted_target.as_ref() == Some(element.as_ref());
+                let slot = context
+                    .slot
+                    .as_ref()
+                    .map(|slot| slot.name())
+                    .unwrap_or_default();
+                let seen = element.get_attribute("seen").unwrap_or_default();
+                let entry = format!("{:?}:{}:{}:{};", context.origin, original, retargeted, slot);
+                element.set_attribute("seen", &(seen + &entry)).unwrap();
+            }
+        }
+
+        EventContextElementImpl::define_once();
+        let element: HtmlElement = EventContextElementImpl::create().unchecked_into();
+        element.set_inner_html("<b slot='label'>Label</b>");
+        EventContextElementImpl::render(&element, "<button>Go</button><slot name='label'></slot>")
+            .unwrap();
+        let click = |target: &HtmlElement| target.click();
+        click(
+            &element
+                .shadow_root()
+                .unwrap()
+                .query_selector("button")
+                .unwrap()
+                .unwrap()
+                .unchecked_into(),
+        );
+        click(&element.first_element_child().unwrap().unchecked_into());
+        click(&element);
+        assert_eq!(
+            element.get_attribute("seen").unwrap(),
+            "Shadow:button:true:;Light:b:false:label;Host:event-context-element:true:;"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_event_context_deferred() {
+        #[web_component(
+            class_name = "DeferredEventContextElement",
+            observed_attrs = "['go']",
+            observed_events = "['click']"
+        )]
+        pub struct DeferredEventContextElementImpl {}
+        impl WebComponentBinding for DeferredEventContextElementImpl {
+            fn attribute_changed(
+                &self,
+                element: &HtmlElement,
+                _name: JsValue,
+                _old_value: JsValue,
+                _new_value: JsValue,
+            ) {
+                // Handled after this callback, when the dispatch is over.
+                let button: HtmlElement = element
+                    .shadow_root()
+                    .unwrap()
+                    .query_selector("button")
+                    .unwrap()
+                    .unwrap()
+                    .unchecked_into();
+                button.click();
+            }
+
+            fn handle_event_with(
+                &self,
+                element: &HtmlElement,
+                event: &Event,
+                context: &EventContext,
+            ) {
+                let entry = format!(
+                    "{:?}:{}:{}:{}:{}",
+                    context.origin,
+                    event.current_target().is_some(),
+                    context.current_target.is_some(),
+                    context.internal_path(element).len(),
+                    context.is_slotted(),
+                );
+                element.set_attribute("seen", &entry).unwrap();
+            }
+        }
+
+        DeferredEventContextElementImpl::define_once();
+        let element: HtmlElement = DeferredEventContextElementImpl::create().unchecked_into();
+        DeferredEventContextElementImpl::render(&element, "<button>Go</button>").unwrap();
+        element.set_attribute("go", "").unwrap();
+        assert_eq!(
+            element.get_attribute("seen").unwrap(),
+            "Shadow:false:true:2:false"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_listen_on_shadow() {
+        #[web_component(
+            class_name = "ShadowListenerElement",
+            observed_events = "['click']",
+            listen_on = "shadow"
+        )]
+        pub struct ShadowListenerElementImpl {
+            clicks: u32,
+        }
+        impl WebComponentBinding for ShadowListenerElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                Self::render(element, "<button>Click</button>").unwrap();
+            }
+
+            fn handle_event_mut(&mut self, element: &HtmlElement, _event: &Event) {
+                self.clicks += 1;
+                element
+                    .set_attribute("clicks", &self.clicks.to_string())
+                    .unwrap();
+            }
+        }
+
+        ShadowListenerElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element: HtmlElement = ShadowListenerElementImpl::create().unchecked_into();
+        body.append_child(&element).unwrap();
+        element.click();
+        assert!(!element.has_attribute("clicks"));
+        let button: HtmlElement = element
+            .shadow_root()
+            .unwrap()
+            .query_selector("button")
+            .unwrap()
+            .unwrap()
+            .unchecked_into();
+        button.click();
+        assert_eq!(element.get_attribute("clicks").unwrap(), "1");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_runtime_listeners() {
+        #[web_component(class_name = "RuntimeListenerElement")]
+        pub struct RuntimeListenerElementImpl {
+            pings: u32,
+        }
+        impl WebComponentBinding for RuntimeListenerElementImpl {
+            fn connected(&self, element: &HtmlElement) {
+                self.listen(element, "ping", ListenOptions::default())
+                    .unwrap();
+            }
+
+            fn handle_event_mut(&mut self, element: &HtmlElement, event: &Event) {
+                if event.type_() == "ping" {
+                    self.pings += 1;
+                    element
+                        .set_attribute("pings", &self.pings.to_string())
+                        .unwrap();
+                }
+            }
+        }
+
+        RuntimeListenerElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element: HtmlElement = RuntimeListenerElementImpl::create().unchecked_into();
+        let ping = || {
+            element
+                .dispatch_event(&Event::new("ping").unwrap())
+                .unwrap();
+            element.get_attribute("pings")
+        };
+        assert_eq!(ping(), None);
+        body.append_child(&element).unwrap();
+        assert_eq!(ping().unwrap(), "1");
+        body.remove_child(&element).unwrap();
+        // The listener is removed on disconnect.
+        assert_eq!(ping().unwrap(), "1");
+        body.append_child(&element).unwrap();
+        assert_eq!(ping().unwrap(), "2");
+        unlisten(&element, "ping").unwrap();
+        assert_eq!(ping().unwrap(), "2");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[cfg(feature = "DragDrop")]
+    #[wasm_bindgen_test]
+    fn test_component_drag_drop() {
+        #[web_component(class_name = "DropZoneElement", drop_target = true)]
+        pub struct DropZoneElementImpl {
+            drops: u32,
+        }
+        impl WebComponentBinding for DropZoneElementImpl {}
+        impl DropTarget for DropZoneElementImpl {
+            fn accepts(&self, element: &HtmlElement, _data: &DragData) -> bool {
+                !element.has_attribute("disabled")
+            }
+
+            fn dropped(&mut self, element: &HtmlElement, data: DragData) {
+                self.drops += 1;
+                assert!(data.files().is_empty());
+                element
+                    .set_attribute("drops", &self.drops.to_string())
+                    .unwrap();
+            }
+        }
+
+        #[web_component(class_name = "DraggableElement", drag_source = true)]
+        pub struct DraggableElementImpl {}
+        impl WebComponentBinding for DraggableElementImpl {}
+        impl DragSource for DraggableElementImpl {
+            fn drag_started(&mut self, _element: &HtmlElement, data: &DragData) {
+                let _ = data.set_text("dragged");
+            }
+        }
+
+        DropZoneElementImpl::define_once();
+        DraggableElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let source: HtmlElement = DraggableElementImpl::create().unchecked_into();
+        body.append_child(&source).unwrap();
+        assert!(source.draggable());
+        body.remove_child(&source).unwrap();
+
+        let target: HtmlElement = DropZoneElementImpl::create().unchecked_into();
+        let drag = |event_type: &str| {
+            let init = web_sys::EventInit::new();
+            init.set_cancelable(true);
+            let event = Event::new_with_event_init_dict(event_type, &init).unwrap();
+            target.dispatch_event(&event).unwrap();
+            event.default_prevented()
+        };
+        assert!(drag("dragover"));
+        assert!(drag("drop"));
+        assert_eq!(target.get_attribute("drops").unwrap(), "1");
+        target.set_attribute("disabled", "").unwrap();
+        assert!(!drag("dragover"));
+    }
+
+    #[cfg(feature = "I18n")]
+    #[wasm_bindgen_test]
+    fn test_component_localized() {
+        #[web_component(class_name = "LocalizedGreetingElement", localized = true)]
+        pub struct LocalizedGreetingElementImpl {}
+        impl WebComponentBinding for LocalizedGreetingElementImpl {
+            fn locale_changed(&self, element: &HtmlElement, locale: &str) {
+                assert_eq!(element_locale(element), locale);
+                element.set_text_content(Some(&t_for(element, "greeting", &[])));
+            }
+        }
+
+        register_bundle(Bundle::new("en", "greeting = Hello").unwrap());
+        register_bundle(Bundle::new("de", "greeting = Hallo").unwrap());
+        LocalizedGreetingElementImpl::define().unwrap();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = LocalizedGreetingElementImpl::create_with_attrs(&[("lang", "en-US")]);
+        body.append_child(&element).unwrap();
+        assert_eq!(element.text_content().unwrap(), "Hello");
+        body.remove_child(&element).unwrap();
+        // The lang attribute is observed while the element is connected.
+        body.append_child(&element).unwrap();
+        element.set_attribute("lang", "de").unwrap();
+        assert_eq!(t_in("de-AT", "greeting", &[]), "Hallo");
+        assert_eq!(t_in("fr", "missing", &[]), "missing");
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_children_ready() {
+        #[web_component(class_name = "ChildCountingElement", children_ready = true)]
+        pub struct ChildCountingElementImpl {}
+        impl WebComponentBinding for ChildCountingElementImpl {
+            fn children_ready(&self, element: &HtmlElement) {
+                let calls = element.get_attribute("calls").unwrap_or_default();
+                element.set_attribute("calls", &(calls + "x")).unwrap();
+                element
+                    .set_attribute("children", &element.child_element_count().to_string())
+                    .unwrap();
+            }
+        }
+
+        ChildCountingElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = ChildCountingElementImpl::create();
+        element.set_inner_html("<li>one</li><li>two</li>");
+        assert!(!element.has_attribute("calls"));
+        body.append_child(&element).unwrap();
+        assert_eq!(element.get_attribute("children").as_deref(), Some("2"));
+        // Moving the element doesn't parse its children again.
+        body.remove_child(&element).unwrap();
+        body.append_child(&element).unwrap();
+        assert_eq!(element.get_attribute("calls").as_deref(), Some("x"));
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_content_visibility() {
+        #[web_component(class_name = "OffscreenElement", content_visibility = "auto 200px")]
+        pub struct OffscreenElementImpl {}
+        impl WebComponentBinding for OffscreenElementImpl {
+            fn content_visibility_changed(&self, element: &HtmlElement, skipped: bool) {
+                element
+                    .set_attribute("skipped", &skipped.to_string())
+                    .unwrap();
+            }
+        }
+
+        OffscreenElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element = OffscreenElementImpl::create();
+        body.append_child(&element).unwrap();
+        let style = element.get_attribute("style").unwrap_or_default();
+        assert!(style.contains("content-visibility: auto"));
+        assert!(style.contains("contain-intrinsic-size: auto 200px"));
+        let event = Event::new("contentvisibilityautostatechange").unwrap();
+        Reflect::set(&event, &JsValue::from_str("skipped"), &JsValue::TRUE).unwrap();
+        element.dispatch_event(&event).unwrap();
+        assert_eq!(element.get_attribute("skipped").as_deref(), Some("true"));
+        body.remove_child(&element).unwrap();
+    }
+
+    #[cfg(feature = "Keys")]
+    #[wasm_bindgen_test]
+    fn test_component_shortcuts() {
+        use web_sys::{KeyboardEvent, KeyboardEventInit};
+
+        #[web_component(
+            class_name = "SearchableElement",
+            shortcuts = "['Ctrl+K => open_search', 'Escape => close']"
+        )]
+        pub struct SearchableElementImpl {}
+        impl WebComponentBinding for SearchableElementImpl {}
+        impl SearchableElementImpl {
+            fn open_search(&self, element: &HtmlElement, _event: &KeyboardEvent) {
+                element.set_attribute("searching", "").unwrap();
+            }
+
+            fn close(&self, element: &HtmlElement, _event: &KeyboardEvent) {
+                element.remove_attribute("searching").unwrap();
+            }
+        }
+
+        #[web_component(
+            class_name = "GlobalSearchElement",
+            shortcuts = "['Mod+/ => open_search']",
+            shortcuts_scope = "global"
+        )]
+        pub struct GlobalSearchElementImpl {}
+        impl WebComponentBinding for GlobalSearchElementImpl {}
+        impl GlobalSearchElementImpl {
+            fn open_search(&mut self, element: &HtmlElement, _event: &KeyboardEvent) {
+                element.set_attribute("searching", "").unwrap();
+            }
+        }
+
+        let key_event = |key: &str, ctrl: bool, meta: bool| {
+            let init = KeyboardEventInit::new();
+            init.set_key(key);
+            init.set_ctrl_key(ctrl);
+            init.set_meta_key(meta);
+            init.set_bubbles(true);
+            init.set_cancelable(true);
+            KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap()
+        };
+        SearchableElementImpl::define_once();
+        GlobalSearchElementImpl::define_once();
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let element = SearchableElementImpl::create();
+        body.append_child(&element).unwrap();
+        let event = key_event("k", true, false);
+        element.dispatch_event(&event).unwrap();
+        assert!(element.has_attribute("searching"));
+        assert!(event.default_prevented());
+        element
+            .dispatch_event(&key_event("Escape", false, false))
+            .unwrap();
+        assert!(!element.has_attribute("searching"));
+        // Element shortcuts don't fire for keys pressed elsewhere.
+        body.dispatch_event(&key_event("k", true, false)).unwrap();
+        assert!(!element.has_attribute("searching"));
+        body.remove_child(&element).unwrap();
+
+        let global = GlobalSearchElementImpl::create();
+        body.append_child(&global).unwrap();
+        body.dispatch_event(&key_event("/", true, false)).unwrap();
+        body.dispatch_event(&key_event("/", false, true)).unwrap();
+        assert!(global.has_attribute("searching"));
+        global.remove_attribute("searching").unwrap();
+        body.remove_child(&global).unwrap();
+        body.dispatch_event(&key_event("/", true, false)).unwrap();
+        body.dispatch_event(&key_event("/", false, true)).unwrap();
+        assert!(!global.has_attribute("searching"));
+    }
+
+    #[cfg(feature = "IndexedDb")]
+    #[wasm_bindgen_test]
+    fn test_component_persist_idb() {
+        #[web_component(class_name = "DraftElement", persist_idb = "draft")]
+        pub struct DraftElementImpl {
+            text: String,
+        }
+        impl WebComponentBinding for DraftElementImpl {}
+        impl IdbState for DraftElementImpl {
+            type State = String;
+            const VERSION: u32 = 2;
+
+            fn save_state(&self) -> String {
+                self.text.clone()
+            }
+
+            fn load_state(&mut self, _element: &HtmlElement, state: String) {
+                self.text = state;
+            }
+
+            fn migrate(&self, from: u32, state: serde_json::Value) -> Option<serde_json::Value> {
+                // Version 1 saved the text in an object.
+                assert_eq!(from, 1);
+                state.get("text").cloned()
+            }
+        }
+
+        DraftElementImpl::define_once();
+        let mut draft = DraftElementImpl::default();
+        let element: HtmlElement = DraftElementImpl::create().unchecked_into();
+        idb_load(
+            &mut draft,
+            &element,
+            r#"{"version":1,"state":{"text":"hello"}}"#,
+        );
+        assert_eq!(draft.text, "hello");
+        assert_eq!(
+            idb_encode(&draft).unwrap(),
+            r#"{"state":"hello","version":2}"#
+        );
+        // States from newer versions are left alone.
+        idb_load(&mut draft, &element, r#"{"version":3,"state":"newer"}"#);
+        assert_eq!(draft.text, "hello");
+    }
+
+    #[cfg(feature = "IndexedDb")]
+    #[wasm_bindgen_test]
+    async fn test_component_persist_idb_round_trip() {
+        use wasm_bindgen_futures::JsFuture;
+
+        #[web_component(
+            class_name = "CounterIdbElement",
+            observed_attrs = "['bump']",
+            persist_idb = "counter"
+        )]
+        pub struct CounterIdbElementImpl {
+            count: u32,
+        }
+        impl WebComponentBinding for CounterIdbElementImpl {
+            fn attribute_changed_mut(
+                &mut self,
+                element: &HtmlElement,
+                _name: JsValue,
+                _old_value: JsValue,
+                _new_value: JsValue,
+            ) {
+                self.count += 1;
+                // Saving while the impl is still handling the callback.
+                let saved = save_idb_state(element).unwrap();
+                js_sys::Reflect::set(element, &JsValue::from_str("saved"), &saved).unwrap();
+            }
+        }
+        impl IdbState for CounterIdbElementImpl {
+            type State = u32;
+
+            fn save_state(&self) -> u32 {
+                self.count
+            }
+
+            fn load_state(&mut self, element: &HtmlElement, state: u32) {
+                self.count = state;
+                element.set_attribute("count", &state.to_string()).unwrap();
+            }
+
+            fn state_error(&mut self, element: &HtmlElement, _error: JsValue) {
+                element.set_attribute("state-error", "").unwrap();
+            }
+        }
+
+        let property = |element: &Element, name: &str| -> js_sys::Promise {
+            js_sys::Reflect::get(element, &JsValue::from_str(name))
+                .unwrap()
+                .unchecked_into()
+        };
+        CounterIdbElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let first = CounterIdbElementImpl::create_with_attrs(&[("persist-key", "round-trip")]);
+        body.append_child(&first).unwrap();
+        JsFuture::from(property(&first, "_idbLoading"))
+            .await
+            .unwrap();
+        first.set_attribute("bump", "").unwrap();
+        JsFuture::from(property(&first, "saved")).await.unwrap();
+        body.remove_child(&first).unwrap();
+
+        let second = CounterIdbElementImpl::create_with_attrs(&[("persist-key", "round-trip")]);
+        body.append_child(&second).unwrap();
+        JsFuture::from(property(&second, "_idbLoading"))
+            .await
+            .unwrap();
+        assert_eq!(second.get_attribute("count"), Some("1".to_owned()));
+        JsFuture::from(clear_idb_state(&second).unwrap())
+            .await
+            .unwrap();
+        body.remove_child(&second).unwrap();
+
+        // Failing to read the state is reported instead of silently never saving.
+        let failing = CounterIdbElementImpl::create();
+        let reject = Function::new_no_args("return Promise.reject(new Error('blocked'));");
+        js_sys::Reflect::set(&failing, &JsValue::from_str("idbRequest"), &reject).unwrap();
+        body.append_child(&failing).unwrap();
+        JsFuture::from(property(&failing, "_idbLoading"))
+            .await
+            .unwrap();
+        assert!(failing.has_attribute("state-error"));
+        body.remove_child(&failing).unwrap();
+    }
+
+    #[cfg(feature = "Files")]
+    #[wasm_bindgen_test]
+    fn test_component_files() {
+        #[web_component(class_name = "FileDropElement", files = true)]
+        pub struct FileDropElementImpl {
+            chunks: usize,
+        }
+        impl WebComponentBinding for FileDropElementImpl {}
+        impl FileReceiver for FileDropElementImpl {
+            fn file_chunk(&mut self, _element: &HtmlElement, _file: &FileInfo, _chunk: &[u8]) {
+                self.chunks += 1;
+            }
+
+            fn file_read(&mut self, element: &HtmlElement, file: FileInfo, bytes: Vec<u8>) {
+                assert_eq!(file.path, "hello.txt");
+                assert_eq!(bytes, b"hello");
+                element
+                    .set_attribute("read", &self.chunks.to_string())
+                    .unwrap();
+            }
+        }
+
+        FileDropElementImpl::define_once();
+        let element: HtmlElement = FileDropElementImpl::create().unchecked_into();
+        let file =
+            web_sys::File::new_with_str_sequence(&js_sys::Array::of1(&"hello".into()), "hello.txt")
+                .unwrap();
+        // The file is read asynchronously and checked in file_read.
+        assert!(read_files(&element, &[file]).is_ok());
+    }
+
+    #[cfg(feature = "Gestures")]
+    #[wasm_bindgen_test]
+    fn test_component_gestures() {
+        #[web_component(class_name = "SwipeableElement", gestures = true)]
+        pub struct SwipeableElementImpl {}
+        impl WebComponentBinding for SwipeableElementImpl {}
+        impl GestureHandler for SwipeableElementImpl {
+            fn gesture(&mut self, element: &HtmlElement, gesture: Gesture) {
+                if let Gesture::Swipe { direction, .. } = gesture {
+                    element
+                        .set_attribute("swiped", &format!("{:?}", direction))
+                        .unwrap();
+                }
+            }
+        }
+
+        SwipeableElementImpl::define_once();
+        let element: HtmlElement = SwipeableElementImpl::create().unchecked_into();
+        let gesture_timeout = Reflect::get(&element, &JsValue::from_str("gestureTimeout")).unwrap();
+        assert!(gesture_timeout.is_function());
+        // Events that aren't pointer events are ignored.
+        element
+            .dispatch_event(&Event::new("pointerdown").unwrap())
+            .unwrap();
+        assert!(!element.has_attribute("swiped"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_form_associated_labels() {
+        #[web_component(class_name = "LabeledInput", form_associated = true)]
+        pub struct LabeledInputImpl {}
+        impl WebComponentBinding for LabeledInputImpl {
+            fn init(&self, element: &HtmlElement) {
+                let root = element
+                    .attach_shadow(&web_sys::ShadowRootInit::new(web_sys::ShadowRootMode::Open))
+                    .unwrap();
+                root.set_inner_html("<input>");
+            }
+        }
+
+        LabeledInputImpl::define_once();
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let label: HtmlElement = document.create_element("label").unwrap().unchecked_into();
+        label.set_attribute("for", "labeled-input").unwrap();
+        label.set_text_content(Some("Name"));
+        let element: HtmlElement =
+            LabeledInputImpl::create_with_attrs(&[("id", "labeled-input")]).unchecked_into();
+        body.append_child(&label).unwrap();
+        body.append_child(&element).unwrap();
+        assert_eq!(labels(&element), vec![label.clone()]);
+        assert_eq!(label_text(&element).as_deref(), Some("Name"));
+        label.click();
+        let input = element
+            .shadow_root()
+            .unwrap()
+            .first_element_child()
+            .unwrap();
+        assert_eq!(element.shadow_root().unwrap().active_element(), Some(input));
+        body.remove_child(&element).unwrap();
+        body.remove_child(&label).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_validate() {
+        #[web_component(class_name = "RequiredInput", form_associated = true)]
+        pub struct RequiredInputImpl {}
+        impl WebComponentBinding for RequiredInputImpl {
+            fn validate(&self, element: &HtmlElement) -> Option<Validity> {
+                let value = element.get_attribute("value").unwrap_or_default();
+                Some(
+                    Validity::new()
+                        .value_missing(value.is_empty())
+                        .custom_if(value == "admin", "That name is taken."),
+                )
+            }
+        }
+
+        assert!(Validity::new().is_valid());
+        assert_eq!(
+            Validity::new().value_missing(true).validation_message(),
+            "Please fill out this field."
+        );
+
+        RequiredInputImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element: HtmlElement = RequiredInputImpl::create().unchecked_into();
+        let check_validity = || {
+            let internals = aria::internals(&element).unwrap();
+            let check: js_sys::Function =
+                Reflect::get(&internals, &JsValue::from_str("checkValidity"))
+                    .unwrap()
+                    .unchecked_into();
+            check.call0(&internals).unwrap().as_bool().unwrap()
+        };
+        body.append_child(&element).unwrap();
+        assert!(!check_validity());
+        element.set_attribute("value", "admin").unwrap();
+        set_form_value(&element, &JsValue::from_str("admin")).unwrap();
+        assert!(!check_validity());
+        element.set_attribute("value", "ann").unwrap();
+        revalidate(&element).unwrap();
+        assert!(check_validity());
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_behaviors() {
+        struct Tooltip;
+        impl Behavior for Tooltip {
+            const OBSERVED_ATTRIBUTES: &'static [&'static str] = &["tooltip"];
+
+            fn attribute_changed(
+                element: &HtmlElement,
+                _name: &str,
+                _old_value: Option<&str>,
+                new_value: Option<&str>,
+            ) {
+                element.set_title(new_value.unwrap_or_default());
+            }
+        }
+
+        struct Pressable;
+        impl Behavior for Pressable {
+            const OBSERVED_EVENTS: &'static [&'static str] = &["click"];
+
+            fn connected(element: &HtmlElement) {
+                element.set_attribute("pressable", "").unwrap();
+            }
+
+            fn disconnected(element: &HtmlElement) {
+                element.remove_attribute("pressable").unwrap();
+            }
+
+            fn handle_event(element: &HtmlElement, _event: &Event) {
+                element.set_attribute("pressed", "").unwrap();
+            }
+        }
+
+        #[web_component(
+            class_name = "BehavingElement",
+            observed_attrs = "['label']",
+            behaviors = "[Tooltip, Pressable]"
+        )]
+        pub struct BehavingElementImpl {}
+        impl WebComponentBinding for BehavingElementImpl {}
+
+        assert_eq!(BehavingElementImpl::behaviors().len(), 2);
+        let handle = BehavingElementImpl::define().unwrap();
+        let observed = Reflect::get(
+            &handle.element_constructor,
+            &JsValue::from_str("observedAttributes"),
+        )
+        .unwrap();
+        assert_eq!(
+            Array::from(&observed).to_vec(),
+            vec![JsValue::from_str("label"), JsValue::from_str("tooltip")]
+        );
+
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let element: HtmlElement = BehavingElementImpl::create().unchecked_into();
+        body.append_child(&element).unwrap();
+        assert!(element.has_attribute("pressable"));
+        element.set_attribute("tooltip", "Hello").unwrap();
+        assert_eq!(element.title(), "Hello");
+        element.click();
+        assert!(element.has_attribute("pressed"));
+        body.remove_child(&element).unwrap();
+        assert!(!element.has_attribute("pressable"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_delegates() {
+        #[derive(Default, Debug)]
+        struct Selection {
+            selected: Option<String>,
+        }
+        impl WebComponentBinding for Selection {
+            fn attribute_changed_mut(
+                &mut self,
+                _element: &HtmlElement,
+                name: JsValue,
+                _old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                if name.as_string().as_deref() == Some("selected") {
+                    self.selected = new_value.as_string();
+                }
+            }
+        }
+
+        #[derive(Default, Debug)]
+        struct Log {
+            calls: Vec<&'static str>,
+        }
+        impl WebComponentBinding for Log {
+            fn connected_mut(&mut self, _element: &HtmlElement) {
+                self.calls.push("connected");
+            }
+
+            fn disconnected_mut(&mut self, element: &HtmlElement) {
+                self.calls.push("disconnected");
+                element.set_attribute("log", &self.calls.join(",")).unwrap();
+            }
+
+            fn validate(&self, _element: &HtmlElement) -> Option<Validity> {
+                Some(Validity::new().custom("Pick something."))
+            }
+        }
+
+        #[web_component(
+            class_name = "DelegatingElement",
+            observed_attrs = "['selected']",
+            delegates = "[selection, log]"
+        )]
+        pub struct DelegatingElementImpl {
+            selection: Selection,
+            log: Log,
+        }
+        impl WebComponentBinding for DelegatingElementImpl {}
+
+        let mut component = DelegatingElementImpl::default();
+        let element: HtmlElement = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap()
+            .unchecked_into();
+        component.connected_impl(&element);
+        component.attribute_changed_impl(
+            &element,
+            JsValue::from_str("selected"),
+            JsValue::NULL,
+            JsValue::from_str("two"),
+        );
+        assert_eq!(component.selection.selected.as_deref(), Some("two"));
+        assert_eq!(component.log.calls, vec!["connected"]);
+        assert_eq!(
+            component.validate_impl(&element).unwrap_err().as_string(),
+            Some("The element has no internals".to_owned())
+        );
+        component.disconnected_impl(&element);
+        assert_eq!(
+            element.get_attribute("log").as_deref(),
+            Some("connected,disconnected")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_reflect_attribute() {
+        #[web_component(
+            class_name = "ReflectingElement",
+            observed_attrs = "['value', 'empty']"
+        )]
+        pub struct ReflectingElementImpl {
+            changes: u32,
+        }
+        impl WebComponentBinding for ReflectingElementImpl {
+            fn attribute_changed_mut(
+                &mut self,
+                element: &HtmlElement,
+                _name: JsValue,
+                _old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                self.changes += 1;
+                element
+                    .set_attribute("changes", &self.changes.to_string())
+                    .unwrap();
+                // Without the loop protection this would call back into us forever.
+                let empty = new_value.as_string().unwrap_or_default().is_empty();
+                reflect_attribute(element, "empty", empty.then_some("")).unwrap();
+                reflect_attribute(element, "value", new_value.as_string().as_deref()).unwrap();
+            }
+        }
+
+        ReflectingElementImpl::define_once();
+        let element: HtmlElement = ReflectingElementImpl::create().unchecked_into();
+        element.set_attribute("value", "").unwrap();
+        assert!(element.has_attribute("empty"));
+        element.set_attribute("value", "full").unwrap();
+        assert!(!element.has_attribute("empty"));
+        assert_eq!(element.get_attribute("changes").as_deref(), Some("2"));
+        assert!(!reflect_attribute(&element, "value", Some("full")).unwrap());
+        assert!(reflect_attribute(&element, "value", None).unwrap());
+        assert_eq!(element.get_attribute("changes").as_deref(), Some("2"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_set_attribute_silent() {
+        #[web_component(class_name = "SilentElement", observed_attrs = "['state']")]
+        pub struct SilentElementImpl {
+            changes: u32,
+        }
+        impl WebComponentBinding for SilentElementImpl {
+            fn attribute_changed_mut(
+                &mut self,
+                element: &HtmlElement,
+                _name: JsValue,
+                _old_value: JsValue,
+                _new_value: JsValue,
+            ) {
+                self.changes += 1;
+                element.set_text_content(Some(&self.changes.to_string()));
+            }
+        }
+
+        SilentElementImpl::define_once();
+        let element = SilentElementImpl::create();
+        set_attribute_silent(&element, "state", "open").unwrap();
+        set_attribute_silent(&element, "state", "open").unwrap();
+        assert_eq!(element.get_attribute("state").as_deref(), Some("open"));
+        assert_eq!(element.text_content().unwrap(), "");
+        element.set_attribute("state", "closed").unwrap();
+        assert_eq!(element.text_content().unwrap(), "1");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_attribute_aliases() {
+        #[web_component(
+            class_name = "AliasedElement",
+            observed_attrs = "['color', 'constructor']",
+            aliases = "{'colour': 'color'}"
+        )]
+        pub struct AliasedElementImpl {}
+        impl WebComponentBinding for AliasedElementImpl {
+            fn attribute_changed(
+                &self,
+                element: &HtmlElement,
+                name: JsValue,
+                _old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                element.set_text_content(Some(&format!(
+                    "{}={}",
+                    name.as_string().unwrap(),
+                    new_value.as_string().unwrap_or_default()
+                )));
+            }
+        }
+
+        AliasedElementImpl::define_once();
+        let element = AliasedElementImpl::create();
+        element.set_attribute("colour", "red").unwrap();
+        assert_eq!(element.get_attribute("color").as_deref(), Some("red"));
+        assert_eq!(element.text_content().unwrap(), "color=red");
+        element.remove_attribute("colour").unwrap();
+        assert!(!element.has_attribute("color"));
+        assert_eq!(element.text_content().unwrap(), "color=");
+        element.set_attribute("constructor", "x").unwrap();
+        assert_eq!(element.text_content().unwrap(), "constructor=x");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_observed_dataset() {
+        #[web_component(class_name = "DatasetElement", observed_dataset = "['userId', 'x']")]
+        pub struct DatasetElementImpl {}
+        impl WebComponentBinding for DatasetElementImpl {}
+
+        let handle = DatasetElementImpl::define().unwrap();
+        let observed = Reflect::get(
+            &handle.element_constructor,
+            &JsValue::from_str("observedAttributes"),
+        )
+        .unwrap();
+        assert_eq!(
+            Array::from(&observed).to_vec(),
+            vec![
+                JsValue::from_str("data-user-id"),
+                JsValue::from_str("data-x")
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_query() {
+        #[web_component(class_name = "QueryingElement")]
+        pub struct QueryingElementImpl {}
+        impl WebComponentBinding for QueryingElementImpl {}
+
+        QueryingElementImpl::define_once();
+        let component = QueryingElementImpl::default();
+        let element: HtmlElement = QueryingElementImpl::create().unchecked_into();
+        element.set_inner_html("<input class='name'><span></span><span></span>");
+        let input: web_sys::HtmlInputElement = component.query(&element, "input.name").unwrap();
+        assert_eq!(input.class_name(), "name");
+        let spans: Vec<HtmlElement> = component.query_all(&element, "span").unwrap();
+        assert_eq!(spans.len(), 2);
+
+        let error = component
+            .query::<web_sys::HtmlInputElement>(&element, "span")
+            .unwrap_err();
+        assert_eq!(
+            error.kind,
+            QueryErrorKind::WrongType {
+                found: "span".to_owned()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "[querying-element] The selector `span` matched a <span> which isn't a HtmlInputElement"
+        );
+        assert_eq!(
+            component
+                .query::<HtmlElement>(&element, "p")
+                .unwrap_err()
+                .kind,
+            QueryErrorKind::NotFound
+        );
+        assert_eq!(
+            component
+                .query_all::<HtmlElement>(&element, "[")
+                .unwrap_err()
+                .kind,
+            QueryErrorKind::InvalidSelector
+        );
+
+        QueryingElementImpl::render(&element, "<button>Go</button>").unwrap();
+        let button: web_sys::HtmlElement = component.query(&element, "button").unwrap();
+        assert_eq!(button.text_content().unwrap(), "Go");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_slots() {
+        #[web_component(class_name = "SlottingElement")]
+        pub struct SlottingElementImpl {}
+        impl WebComponentBinding for SlottingElementImpl {}
+
+        SlottingElementImpl::define_once();
+        let document = window().unwrap().document().unwrap();
+        let element: HtmlElement = SlottingElementImpl::create().unchecked_into();
+        element.set_inner_html(
+            "<b slot='title'> Hello </b><i slot='title'>world</i>text<p>one</p><p>two</p>",
+        );
+        SlottingElementImpl::render(&element, "<slot name='title'></slot><slot></slot>").unwrap();
+        document.body().unwrap().append_child(&element).unwrap();
+
+        assert_eq!(assigned_text(&element, Some("title")), "Hello world");
+        assert_eq!(assigned_elements(&element, Some("title")).len(), 2);
+        let default = Slotted::of(&element, None);
+        assert_eq!(default.nodes().len(), 3);
+        let paragraphs: Vec<String> = default
+            .iter::<HtmlElement>()
+            .map(|p| p.text_content().unwrap())
+            .collect();
+        assert_eq!(paragraphs, vec!["one", "two"]);
+        assert!(Slotted::flattened(&element, Some("missing")).is_empty());
+        element.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_element_ref() {
+        #[web_component(
+            class_name = "PagingElement",
+            observed_attrs = "['open', 'page-size', 'label']",
+            attr_types = "{open: bool, 'page-size': u32}",
+            emits = "['page-changed']"
+        )]
+        pub struct PagingElementImpl {}
+        impl WebComponentBinding for PagingElementImpl {}
+
+        PagingElementImpl::define_once();
+        let document = window().unwrap().document().unwrap();
+        let pager = PagingElementImplRef::try_from(PagingElementImpl::create()).unwrap();
+        pager.set_open(&true).unwrap();
+        pager.set_page_size(&50).unwrap();
+        pager.set_label(&"Results".to_owned()).unwrap();
+        assert_eq!(pager.open(), Some(true));
+        assert_eq!(pager.page_size(), Some(50));
+        assert_eq!(pager.label().as_deref(), Some("Results"));
+        assert_eq!(pager.get_attribute("page-size").as_deref(), Some("50"));
+
+        let changes = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counted = changes.clone();
+        let subscription = pager
+            .on_page_changed(move |_| counted.set(counted.get() + 1))
+            .unwrap();
+        let event = web_sys::Event::new("page-changed").unwrap();
+        pager.dispatch_event(&event).unwrap();
+        drop(subscription);
+        pager.dispatch_event(&event).unwrap();
+        assert_eq!(changes.get(), 1);
+
+        // Refs don't implement Debug with the Minimal feature so there's no unwrap_err.
+        let error = match PagingElementImplRef::try_from(document.create_element("div").unwrap()) {
+            Ok(_) => panic!("A div isn't a paging-element"),
+            Err(error) => error,
+        };
+        assert_eq!(
+            error,
+            WrongElementError {
+                expected: "paging-element".to_owned(),
+                found: "div".to_owned(),
+            }
+        );
+        let element: HtmlElement = pager.into();
+        assert_eq!(element.tag_name().to_lowercase(), "paging-element");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_upgrade_all() {
+        #[web_component(class_name = "UpgradedElement")]
+        pub struct UpgradedElementImpl {}
+        impl WebComponentBinding for UpgradedElementImpl {}
+
+        let document = window().unwrap().document().unwrap();
+        // Elements that aren't connected when the element gets defined stay un-upgraded.
+        let root = document.create_element("div").unwrap();
+        root.set_inner_html(
+            "<upgraded-element id='a'></upgraded-element><p><upgraded-element id='b'></upgraded-element></p>",
+        );
+        assert!(UpgradedElementImpl::upgrade_all(&root).is_err());
+
+        UpgradedElementImpl::define_once();
+        let upgraded = UpgradedElementImpl::upgrade_all(&root).unwrap();
+        let ids: Vec<String> = upgraded.iter().map(|element| element.id()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        let empty = document.create_element("p").unwrap();
+        assert!(UpgradedElementImpl::upgrade_all(&empty).unwrap().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_custom_elements() {
+        use wasm_web_component_macros::custom_elements;
+        custom_elements!("src/testdata/custom-elements.json");
+
+        let pager = AcmePagerRef::create().unwrap();
+        assert_eq!(AcmePagerRef::TAG_NAME, "acme-pager");
+        pager.set_disabled(&true).unwrap();
+        pager.set_page_size(&25.0).unwrap();
+        pager.set_label(&"Results".to_owned()).unwrap();
+        assert_eq!(pager.get_attribute("page-size").as_deref(), Some("25"));
+        assert_eq!(pager.disabled(), Some(true));
+        assert_eq!(pager.label().as_deref(), Some("Results"));
+        pager.set_sizes(&vec![10.0, 25.0]).unwrap();
+        assert_eq!(pager.sizes(), Some(vec![10.0, 25.0]));
+        assert_eq!(pager.total(), Some(None));
+
+        let pages = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let received = pages.clone();
+        let _subscription = pager
+            .on_page_changed(move |detail: AcmePagerPageChangedDetail, _| {
+                received.borrow_mut().push(detail)
+            })
+            .unwrap();
+        let event = web_sys::CustomEvent::new("page-changed").unwrap();
+        let detail = AcmePagerPageChangedDetail {
+            page: 2.0,
+            previous_page: None,
+        };
+        event.init_custom_event_with_can_bubble_and_cancelable_and_detail(
+            "page-changed",
+            false,
+            false,
+            &detail.property_value(),
+        );
+        pager.dispatch_event(&event).unwrap();
+        assert_eq!(*pages.borrow(), vec![detail]);
+
+        let document = window().unwrap().document().unwrap();
+        assert!(AcmePagerRef::try_from(document.create_element("div").unwrap()).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_define_window() {
+        #[web_component(class_name = "WindowedElement")]
+        pub struct WindowedElementImpl {}
+        impl WebComponentBinding for WindowedElementImpl {}
+
+        assert!(define_window::<WindowedElementImpl>().is_ok());
+        let error = DefineError {
+            element_name: WindowedElementImpl::element_name(),
+            kind: DefineErrorKind::NoRegistry,
+        };
+        assert_eq!(
+            error.to_string(),
+            "[windowed-element] Custom elements can't be defined without a customElements registry"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_define_or_fallback() {
+        #[web_component(class_name = "FallbackElement")]
+        pub struct FallbackElementImpl {}
+        impl WebComponentBinding for FallbackElementImpl {}
+
+        let document = global_document().unwrap();
+        let body = document.body().unwrap();
+        let element = document.create_element("fallback-element").unwrap();
+        body.append_child(&element).unwrap();
+        let fallbacks = std::cell::Cell::new(0);
+        let found = apply_fallback::<FallbackElementImpl, _>(|found| {
+            assert_eq!(found, &element);
+            fallbacks.set(fallbacks.get() + 1);
+        });
+        assert_eq!(found.unwrap(), 1);
+        let handle =
+            FallbackElementImpl::define_or_fallback(|_| fallbacks.set(fallbacks.get() + 1));
+        assert!(handle.unwrap().is_some());
+        assert_eq!(fallbacks.get(), 1);
+        body.remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_define_components() {
+        #[web_component(class_name = "BatchOneElement")]
+        pub struct BatchOneElementImpl {}
+        impl WebComponentBinding for BatchOneElementImpl {}
+
+        #[web_component(class_name = "BatchTwoElement")]
+        pub struct BatchTwoElementImpl {}
+        impl WebComponentBinding for BatchTwoElementImpl {}
+
+        BatchOneElementImpl::define_once();
+        let report = crate::define_components!(BatchOneElementImpl, BatchTwoElementImpl);
+        assert_eq!(report.defined, vec!["batch-two-element"]);
+        assert_eq!(report.already_defined, vec!["batch-one-element"]);
+        assert!(report.failed.is_empty());
+        assert!(is_defined::<BatchTwoElementImpl>());
+        let report = crate::define_components!(BatchTwoElementImpl,);
+        assert_eq!(report.already_defined, vec!["batch-two-element"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_registry() {
+        #[web_component(class_name = "RegisteredElement")]
+        pub struct RegisteredElementImpl {}
+        impl WebComponentBinding for RegisteredElementImpl {}
+
+        assert!(registry_entry("registered-element").is_none());
+        RegisteredElementImpl::define_once();
+        let info = registry_entry("registered-element").unwrap();
+        assert_eq!(info.class_name, "RegisteredElement");
+        assert_eq!(info.crate_version, CRATE_VERSION);
+        assert!(info.defined_at > 0.0);
+        assert!(registry().contains(&info));
+    }
+
+    #[cfg(feature = "Docs")]
+    #[wasm_bindgen_test]
+    fn test_component_docs() {
+        /// A counter.
+        ///
+        /// # Attributes
+        /// * `step` - How much every click adds.
+        ///   Defaults to 1.
+        ///
+        /// # Events
+        /// * `counted`: Dispatched after every click.
+        #[web_component(
+            class_name = "DocumentedElement",
+            observed_attrs = "['step']",
+            emits = "['counted']"
+        )]
+        pub struct DocumentedElementImpl {}
+        impl WebComponentBinding for DocumentedElementImpl {}
+
+        let docs = DocumentedElementImpl::docs();
+        assert_eq!(docs.description, "A counter.");
+        assert_eq!(
+            docs.attribute("step"),
+            Some("How much every click adds. Defaults to 1.")
+        );
+        assert_eq!(docs.event("counted"), Some("Dispatched after every click."));
+        assert_eq!(docs.event("missing"), None);
+        DocumentedElementImpl::define_once();
+        assert_eq!(registry_entry("documented-element").unwrap().docs, docs);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_invalidate_all() {
+        #[web_component(class_name = "InvalidatedElement")]
+        pub struct InvalidatedElementImpl {
+            renders: u32,
+        }
+        impl WebComponentBinding for InvalidatedElementImpl {
+            fn invalidated_mut(&mut self, element: &HtmlElement) {
+                self.renders += 1;
+                element.set_text_content(Some(&self.renders.to_string()));
+            }
+        }
+
+        InvalidatedElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let connected = InvalidatedElementImpl::create();
+        let detached = InvalidatedElementImpl::create();
+        body.append_child(&connected).unwrap();
+        InvalidatedElementImpl::invalidate_all().unwrap();
+        InvalidatedElementImpl::invalidate_all().unwrap();
+        assert_eq!(connected.text_content().unwrap(), "2");
+        assert_eq!(detached.text_content().unwrap(), "");
+        body.remove_child(&connected).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_instances() {
+        #[web_component(class_name = "CountedElement")]
+        pub struct CountedElementImpl {}
+        impl WebComponentBinding for CountedElementImpl {}
+
+        CountedElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let first = CountedElementImpl::create();
+        let second = CountedElementImpl::create();
+        let _detached = CountedElementImpl::create();
+        body.append_child(&first).unwrap();
+        body.append_child(&second).unwrap();
+        let instances: Vec<Element> = CountedElementImpl::instances().map(Into::into).collect();
+        assert_eq!(instances, vec![first.clone(), second.clone()]);
+        let mut instances = CountedElementImpl::instances().map(Element::from);
+        body.remove_child(&first).unwrap();
+        assert_eq!(instances.next(), Some(second.clone()));
+        assert!(instances.next().is_none());
+        body.remove_child(&second).unwrap();
+        assert_eq!(CountedElementImpl::instances().count(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_broadcast() {
+        #[web_component(class_name = "BroadcastElement")]
+        pub struct BroadcastElementImpl {
+            locale: String,
+        }
+        impl WebComponentBinding for BroadcastElementImpl {}
+
+        BroadcastElementImpl::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let first = BroadcastElementImpl::create();
+        let second = BroadcastElementImpl::create();
+        body.append_child(&first).unwrap();
+        body.append_child(&second).unwrap();
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorded = seen.clone();
+        let count = BroadcastElementImpl::broadcast(move |component, element| {
+            component.locale = "de".to_owned();
+            recorded.borrow_mut().push(component.locale.clone());
+            // Disconnecting the next instance skips it.
+            if let Some(next) = element.next_element_sibling() {
+                next.remove();
+            }
+        })
+        .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(*seen.borrow(), vec!["de".to_owned()]);
+        assert!(!second.is_connected());
+        let mut names = Vec::new();
+        broadcast_all(|name, _| names.push(name));
+        assert!(names.contains(&"broadcast-element"));
+        body.remove_child(&first).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_component_attribute_transaction() {
+        #[web_component(
+            class_name = "TransactionElement",
+            observed_attrs = "['min', 'max', 'value']"
+        )]
+        pub struct TransactionElementImpl {
+            notifications: Vec<Vec<AttributeChange>>,
+        }
+        impl WebComponentBinding for TransactionElementImpl {
+            fn attribute_changed_mut(
+                &mut self,
+                _element: &HtmlElement,
+                name: JsValue,
+                old_value: JsValue,
+                new_value: JsValue,
+            ) {
+                self.notifications.push(vec![AttributeChange {
+                    name: name.as_string().unwrap(),
+                    old_value: old_value.as_string(),
+                    new_value: new_value.as_string(),
+                }]);
+            }
+
+            fn attributes_changed_mut(
+                &mut self,
+                element: &HtmlElement,
+                changes: &[AttributeChange],
+            ) {
+                self.notifications.push(changes.to_vec());
+                element.set_text_content(Some(&format!("{:?}", self.notifications)));
+            }
+        }
+
+        TransactionElementImpl::define_once();
+        let element = TransactionElementImpl::create_with_attrs(&[("value", "5")]);
+        element_attrs_txn(&element, |txn| {
+            txn.set("min", "1").set("min", "10").set("max", "20");
+            txn.set("value", "6").set("value", "5");
+            txn.set("unobserved", "x");
+        })
+        .unwrap();
+        assert_eq!(element.get_attribute("min").as_deref(), Some("10"));
+        assert_eq!(element.get_attribute("unobserved").as_deref(), Some("x"));
+        let expected = vec![
+            vec![AttributeChange {
+                name: "value".to_owned(),
+                old_value: None,
+                new_value: Some("5".to_owned()),
+            }],
+            vec![
+                AttributeChange {
+                    name: "min".to_owned(),
+                    old_value: None,
+                    new_value: Some("10".to_owned()),
+                },
+                AttributeChange {
+                    name: "max".to_owned(),
+                    old_value: None,
+                    new_value: Some("20".to_owned()),
+                },
+            ],
+        ];
+        assert_eq!(element.text_content().unwrap(), format!("{:?}", expected));
+
+        // The writes before a failing one still get delivered.
+        assert!(element_attrs_txn(&element, |txn| {
+            txn.set("max", "30").set("not valid", "x").set("min", "0");
+        })
+        .is_err());
+        assert_eq!(element.get_attribute("min").as_deref(), Some("10"));
+        let mut expected = expected;
+        expected.push(vec![AttributeChange {
+            name: "max".to_owned(),
+            old_value: Some("20".to_owned()),
+            new_value: Some("30".to_owned()),
+        }]);
+        assert_eq!(element.text_content().unwrap(), format!("{:?}", expected));
+    }
+
     #[wasm_bindgen_test]
     fn test_component_no_element_name() {
         #[web_component(class_name = "AnElement")]
@@ -584,7 +4198,7 @@ mod tests {
                     .into();
                 let el: HtmlTemplateElement = val.into();
                 el.set_attribute("id", "template-id").unwrap();
-                return el;
+                el
             }
         }
 
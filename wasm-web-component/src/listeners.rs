@@ -0,0 +1,71 @@
+//! Event listeners that components add and remove at runtime.
+//!
+//! Listeners for the `observed_events` of a component are added when the element gets
+//! constructed. Components that only need an expensive listener some of the time,
+//! e.g. `pointermove` while something is being dragged, can [listen] for it when it is
+//! needed instead. Events from these listeners get delivered to the same
+//! `handle_event` callbacks and the listeners are removed when the element gets
+//! disconnected.
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlElement;
+
+use crate::cleanup::on_disconnect;
+
+/// Options for a listener added with [listen].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ListenOptions {
+    /// Listen in the capture phase.
+    pub capture: bool,
+    /// Promise not to call `preventDefault` so the browser doesn't have to wait for
+    /// the listener, e.g. before scrolling.
+    pub passive: bool,
+}
+
+fn call_method(element: &HtmlElement, name: &str, args: &[JsValue]) -> Result<(), JsValue> {
+    let method: Function = Reflect::get(element, &JsValue::from_str(name))?.dyn_into()?;
+    match args {
+        [event_type] => method.call1(element, event_type)?,
+        [event_type, options] => method.call2(element, event_type, options)?,
+        _ => return Err(JsValue::from_str("Unsupported number of arguments")),
+    };
+    Ok(())
+}
+
+/// Has the web component element deliver events of the type to its `handle_event`
+/// callbacks until [unlisten] gets called or the element gets disconnected. Listening
+/// for a type again replaces the options.
+pub fn listen(
+    element: &HtmlElement,
+    event_type: &str,
+    options: ListenOptions,
+) -> Result<(), JsValue> {
+    let js_options = Object::new();
+    Reflect::set(&js_options, &"capture".into(), &options.capture.into())?;
+    Reflect::set(&js_options, &"passive".into(), &options.passive.into())?;
+    call_method(
+        element,
+        "listenFor",
+        &[event_type.into(), js_options.into()],
+    )?;
+    let hook_element = element.clone();
+    let event_type = event_type.to_owned();
+    on_disconnect(element, move || {
+        let _ = unlisten(&hook_element, &event_type);
+    });
+    Ok(())
+}
+
+/// Removes a listener added with [listen].
+pub fn unlisten(element: &HtmlElement, event_type: &str) -> Result<(), JsValue> {
+    call_method(element, "unlistenFor", &[event_type.into()])
+}
+
+/// Whether the element listens for the event type because of [listen].
+pub(crate) fn is_listening(element: &HtmlElement, event_type: &str) -> bool {
+    Reflect::get(element, &"_runtimeListeners".into())
+        .ok()
+        .and_then(|listeners| listeners.dyn_into::<js_sys::Map>().ok())
+        .map(|listeners| listeners.has(&event_type.into()))
+        .unwrap_or(false)
+}
@@ -0,0 +1,61 @@
+//! WebSocket backed live components.
+//!
+//! Components opt in by setting `live = true` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing [LiveComponent].
+//! A socket to [LiveComponent::socket_url] is opened when the element is connected and
+//! closed when it is disconnected. Dropped connections are retried with an exponential
+//! backoff capped at 30 seconds.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlElement;
+
+/// Callbacks for a web component backed by a WebSocket.
+pub trait LiveComponent {
+    /// The type incoming JSON messages get deserialized into.
+    type Message: DeserializeOwned;
+
+    /// Returns the url of the socket to connect to. Returning None leaves the
+    /// component disconnected.
+    fn socket_url(&self, element: &HtmlElement) -> Option<String>;
+
+    /// Called with every message received on the socket.
+    fn message_received(&mut self, element: &HtmlElement, message: Self::Message);
+
+    /// Called when a message could not be deserialized.
+    fn message_error(&mut self, _element: &HtmlElement, _error: JsValue) {
+        // noop
+    }
+
+    /// Called when the socket has been opened.
+    fn socket_opened(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+
+    /// Called when the socket has been closed. A reconnect is scheduled afterwards
+    /// unless the element has been disconnected.
+    fn socket_closed(&mut self, _element: &HtmlElement) {
+        // noop
+    }
+}
+
+/// Sends the message as JSON on the element's socket. Returns false if the socket
+/// is not currently open.
+pub fn live_send<M: Serialize>(element: &HtmlElement, message: &M) -> Result<bool, JsValue> {
+    let message = serde_json::to_string(message).map_err(to_js_error)?;
+    let send: js_sys::Function =
+        js_sys::Reflect::get(element, &JsValue::from_str("liveSend"))?.dyn_into()?;
+    Ok(send
+        .call1(element, &JsValue::from_str(&message))?
+        .is_truthy())
+}
+
+fn to_js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Decodes a received message. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn decode<M: DeserializeOwned>(message: &str) -> Result<M, JsValue> {
+    serde_json::from_str(message).map_err(to_js_error)
+}
@@ -0,0 +1,90 @@
+//! Reactive media query bindings.
+//!
+//! Components can list media queries in the `media_queries` parameter of the
+//! [`#[web_component]`](crate::web_component) attribute to get the
+//! [media_changed](crate::WebComponentBinding::media_changed) callback while they are
+//! connected, or watch a query from Rust with [watch_media_while_connected].
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, MediaQueryList};
+
+use crate::cleanup::on_disconnect;
+
+fn media_query_list(query: &str) -> Option<MediaQueryList> {
+    web_sys::window()?.match_media(query).ok().flatten()
+}
+
+/// Returns true if the media query currently matches.
+pub fn matches_media(query: &str) -> bool {
+    media_query_list(query)
+        .map(|list| list.matches())
+        .unwrap_or(false)
+}
+
+/// Returns true if the user has asked for reduced motion.
+pub fn prefers_reduced_motion() -> bool {
+    matches_media("(prefers-reduced-motion: reduce)")
+}
+
+/// Returns true if the user prefers a dark color scheme.
+pub fn prefers_dark_color_scheme() -> bool {
+    matches_media("(prefers-color-scheme: dark)")
+}
+
+/// Calls the callback with whether the media query matches immediately and then
+/// whenever that changes until the web component element gets disconnected. Call
+/// this from your `connected` callback.
+pub fn watch_media_while_connected<F>(
+    element: &Element,
+    query: &str,
+    mut callback: F,
+) -> Result<(), JsValue>
+where
+    F: FnMut(bool) + 'static,
+{
+    let list =
+        media_query_list(query).ok_or_else(|| JsValue::from_str("Unsupported media query"))?;
+    callback(list.matches());
+    let listener = {
+        let list = list.clone();
+        Closure::<dyn FnMut()>::new(move || callback(list.matches()))
+    };
+    list.add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())?;
+    on_disconnect(element, move || {
+        let _ =
+            list.remove_event_listener_with_callback("change", listener.as_ref().unchecked_ref());
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_watch_media_while_connected() {
+        assert!(matches_media("all"));
+        assert!(!matches_media("not all"));
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        {
+            let seen = seen.clone();
+            watch_media_while_connected(&element, "all", move |matches| {
+                seen.borrow_mut().push(matches)
+            })
+            .unwrap();
+        }
+        assert_eq!(*seen.borrow(), vec![true]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Lifecycle metrics per component.
+//!
+//! With the `Metrics` feature enabled the generated shims count and time the
+//! constructions, connects, renders and event handles of every component. The
+//! aggregated [metrics] can be exported with a [MetricsExporter], either to a
+//! `console.table` with [ConsoleTableExporter], as JSON with [JsonExporter] or to any
+//! closure taking the metrics.
+//!
+//! ```ignore
+//! export_metrics(&ConsoleTableExporter)?;
+//! export_metrics(&JsonExporter(|json| send_to_server(json)))?;
+//! export_metrics(&|metrics: &BTreeMap<String, ComponentMetrics>| {
+//!     if metrics["my-list"].renders.max_ms > 16.0 {
+//!         console::warn_1(&"Slow my-list render".into());
+//!     }
+//!     Ok(())
+//! })?;
+//! ```
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use js_sys::{Object, Reflect};
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+/// The lifecycle phases that get measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Construction,
+    Connect,
+    Render,
+    Event,
+}
+
+/// The count and durations in milliseconds of a phase.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct PhaseStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+impl PhaseStats {
+    fn record(&mut self, duration: f64) {
+        self.count += 1;
+        self.total_ms += duration;
+        self.max_ms = self.max_ms.max(duration);
+    }
+}
+
+/// The metrics of a component.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ComponentMetrics {
+    pub constructions: PhaseStats,
+    pub connects: PhaseStats,
+    pub renders: PhaseStats,
+    pub events: PhaseStats,
+}
+
+impl ComponentMetrics {
+    fn phase_mut(&mut self, phase: Phase) -> &mut PhaseStats {
+        match phase {
+            Phase::Construction => &mut self.constructions,
+            Phase::Connect => &mut self.connects,
+            Phase::Render => &mut self.renders,
+            Phase::Event => &mut self.events,
+        }
+    }
+}
+
+thread_local! {
+    static METRICS: RefCell<BTreeMap<String, ComponentMetrics>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+fn now() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.performance())
+        .map(|p| p.now())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+/// Records the duration of a phase when dropped.
+#[doc(hidden)]
+pub struct MetricsTimer {
+    element_name: &'static str,
+    phase: Phase,
+    start: f64,
+}
+
+impl Drop for MetricsTimer {
+    fn drop(&mut self) {
+        let duration = now() - self.start;
+        METRICS.with(|metrics| {
+            metrics
+                .borrow_mut()
+                .entry(self.element_name.to_owned())
+                .or_default()
+                .phase_mut(self.phase)
+                .record(duration);
+        });
+    }
+}
+
+/// Starts timing a phase of the component.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn metrics_timer(element_name: &'static str, phase: Phase) -> MetricsTimer {
+    MetricsTimer {
+        element_name,
+        phase,
+        start: now(),
+    }
+}
+
+/// Returns the metrics of every component keyed by element name.
+pub fn metrics() -> BTreeMap<String, ComponentMetrics> {
+    METRICS.with(|metrics| metrics.borrow().clone())
+}
+
+/// Clears the metrics.
+pub fn reset_metrics() {
+    METRICS.with(|metrics| metrics.borrow_mut().clear());
+}
+
+/// Returns the metrics of every component as a JSON object keyed by element name.
+pub fn metrics_json() -> Result<String, JsValue> {
+    serde_json::to_string(&metrics()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Exports the metrics somewhere.
+pub trait MetricsExporter {
+    fn export(&self, metrics: &BTreeMap<String, ComponentMetrics>) -> Result<(), JsValue>;
+}
+
+impl<F> MetricsExporter for F
+where
+    F: Fn(&BTreeMap<String, ComponentMetrics>) -> Result<(), JsValue>,
+{
+    fn export(&self, metrics: &BTreeMap<String, ComponentMetrics>) -> Result<(), JsValue> {
+        self(metrics)
+    }
+}
+
+/// Logs the metrics as a `console.table` with a row per component.
+pub struct ConsoleTableExporter;
+
+impl MetricsExporter for ConsoleTableExporter {
+    fn export(&self, metrics: &BTreeMap<String, ComponentMetrics>) -> Result<(), JsValue> {
+        let table = Object::new();
+        for (element_name, metrics) in metrics {
+            let row = Object::new();
+            for (name, stats) in [
+                ("constructions", &metrics.constructions),
+                ("connects", &metrics.connects),
+                ("renders", &metrics.renders),
+                ("events", &metrics.events),
+            ] {
+                Reflect::set(&row, &JsValue::from_str(name), &JsValue::from(stats.count))?;
+                Reflect::set(
+                    &row,
+                    &JsValue::from_str(&format!("{} ms", name)),
+                    &JsValue::from(stats.total_ms),
+                )?;
+            }
+            Reflect::set(&table, &JsValue::from_str(element_name), &row)?;
+        }
+        web_sys::console::table_1(&table);
+        Ok(())
+    }
+}
+
+/// Passes the metrics as JSON to the closure.
+pub struct JsonExporter<F: Fn(String)>(pub F);
+
+impl<F: Fn(String)> MetricsExporter for JsonExporter<F> {
+    fn export(&self, metrics: &BTreeMap<String, ComponentMetrics>) -> Result<(), JsValue> {
+        let json = serde_json::to_string(metrics).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        (self.0)(json);
+        Ok(())
+    }
+}
+
+/// Exports the current metrics with the exporter.
+pub fn export_metrics<E: MetricsExporter>(exporter: &E) -> Result<(), JsValue> {
+    exporter.export(&metrics())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_metrics() {
+        reset_metrics();
+        {
+            let _timer = metrics_timer("metrics-test", Phase::Render);
+        }
+        {
+            let _timer = metrics_timer("metrics-test", Phase::Render);
+        }
+        let _ = metrics_timer("metrics-test", Phase::Event);
+        let current = metrics();
+        assert_eq!(current["metrics-test"].renders.count, 2);
+        assert_eq!(current["metrics-test"].events.count, 1);
+        assert_eq!(current["metrics-test"].connects.count, 0);
+
+        let exported = Cell::new(0);
+        export_metrics(&|metrics: &BTreeMap<String, ComponentMetrics>| {
+            exported.set(metrics.len());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(exported.get(), 1);
+        export_metrics(&ConsoleTableExporter).unwrap();
+        export_metrics(&JsonExporter(|json| assert!(json.contains("\"renders\"")))).unwrap();
+        reset_metrics();
+        assert!(metrics().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Making the rest of the page inert while a modal component is open.
+//!
+//! A [ModalScope] marks the siblings of the element and of each of its ancestors, up to
+//! the document body and across shadow roots, as `inert` so they can't be focused,
+//! clicked or found by assistive technology. Scopes can be nested, e.g. a confirmation
+//! on top of a modal editor. An element stays inert until every scope that marked it is
+//! closed and elements that were inert before keep their `inert` attribute. Scopes are
+//! closed automatically when their component element gets disconnected.
+//!
+//! Components built on `<dialog>` get this from the browser with `show_modal`.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     let scope = ModalScope::open(element).unwrap();
+//!     FocusTrap::activate(element, element).unwrap();
+//!     self.scope.replace(Some(scope));
+//! }
+//! ```
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement, ShadowRoot};
+
+use crate::cleanup::on_disconnect;
+
+/// An element marked inert by open scopes.
+struct InertMark {
+    element: Element,
+    /// How many open scopes marked the element.
+    count: usize,
+    /// Whether the element had the `inert` attribute before the first scope marked it.
+    was_inert: bool,
+}
+
+thread_local! {
+    static MARKS: RefCell<Vec<InertMark>> = const { RefCell::new(Vec::new()) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+fn mark(element: &Element) -> Result<(), JsValue> {
+    MARKS.with(|marks| {
+        let mut marks = marks.borrow_mut();
+        if let Some(mark) = marks
+            .iter_mut()
+            .find(|m| m.element.is_same_node(Some(element)))
+        {
+            mark.count += 1;
+            return Ok(());
+        }
+        let was_inert = element.has_attribute("inert");
+        element.set_attribute("inert", "")?;
+        marks.push(InertMark {
+            element: element.clone(),
+            count: 1,
+            was_inert,
+        });
+        Ok(())
+    })
+}
+
+fn unmark(element: &Element) {
+    MARKS.with(|marks| {
+        let mut marks = marks.borrow_mut();
+        let index = match marks
+            .iter()
+            .position(|m| m.element.is_same_node(Some(element)))
+        {
+            Some(index) => index,
+            None => return,
+        };
+        marks[index].count -= 1;
+        if marks[index].count == 0 {
+            let mark = marks.swap_remove(index);
+            if !mark.was_inert {
+                let _ = mark.element.remove_attribute("inert");
+            }
+        }
+    });
+}
+
+/// Returns the element's parent, or the host for elements at the top of a shadow root.
+fn composed_parent(element: &Element) -> Option<Element> {
+    let parent = element.parent_node()?;
+    match parent.dyn_ref::<ShadowRoot>() {
+        Some(root) => Some(root.host()),
+        None => parent.dyn_into().ok(),
+    }
+}
+
+/// The siblings of the element and of its ancestors up to the body.
+fn outside_elements(element: &Element) -> Vec<Element> {
+    let mut outside = Vec::new();
+    let mut current = element.clone();
+    while current.tag_name() != "BODY" {
+        let siblings = match current.parent_node() {
+            Some(parent) => parent.child_nodes(),
+            None => break,
+        };
+        for index in 0..siblings.length() {
+            if let Some(sibling) = siblings
+                .item(index)
+                .and_then(|n| n.dyn_into::<Element>().ok())
+            {
+                if !sibling.is_same_node(Some(&current)) {
+                    outside.push(sibling);
+                }
+            }
+        }
+        current = match composed_parent(&current) {
+            Some(parent) => parent,
+            None => break,
+        };
+    }
+    outside
+}
+
+struct Inner {
+    marked: Vec<Element>,
+    open: Cell<bool>,
+}
+
+impl Inner {
+    fn close(&self) {
+        if !self.open.replace(false) {
+            return;
+        }
+        for element in &self.marked {
+            unmark(element);
+        }
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// An open modal scope. Cloning returns a handle to the same scope.
+#[derive(Clone)]
+pub struct ModalScope {
+    inner: Rc<Inner>,
+}
+
+impl ModalScope {
+    /// Makes everything outside of the element inert until the scope is closed or the
+    /// element gets disconnected. The element has to be connected.
+    pub fn open(element: &HtmlElement) -> Result<Self, JsValue> {
+        if !element.is_connected() {
+            return Err(JsValue::from_str("Only connected elements can be modal"));
+        }
+        let marked = outside_elements(element);
+        for (index, outside) in marked.iter().enumerate() {
+            if let Err(err) = mark(outside) {
+                marked[..index].iter().for_each(unmark);
+                return Err(err);
+            }
+        }
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        let inner = Rc::new(Inner {
+            marked,
+            open: Cell::new(true),
+        });
+        {
+            let inner = inner.clone();
+            on_disconnect(element, move || inner.close());
+        }
+        Ok(Self { inner })
+    }
+
+    /// Returns true until the scope has been closed.
+    pub fn is_open(&self) -> bool {
+        self.inner.open.get()
+    }
+
+    /// Restores the elements the scope made inert unless another open scope still
+    /// needs them inert.
+    pub fn close(&self) {
+        self.inner.close();
+    }
+}
+
+/// How many modal scopes are open.
+pub fn modal_depth() -> usize {
+    DEPTH.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::environment::document;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_nested_modal_scopes() {
+        let document = document().unwrap();
+        let body = document.body().unwrap();
+        let page = document.create_element("main").unwrap();
+        page.set_inner_html("<p>Page</p><aside inert>Ads</aside><section></section>");
+        body.append_child(&page).unwrap();
+        let editor: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        editor.set_inner_html("<textarea></textarea><div class='confirm'></div>");
+        page.last_element_child()
+            .unwrap()
+            .append_child(&editor)
+            .unwrap();
+        let textarea = editor.first_element_child().unwrap();
+        let confirm: HtmlElement = editor.last_element_child().unwrap().unchecked_into();
+        let paragraph = page.first_element_child().unwrap();
+        let aside = paragraph.next_element_sibling().unwrap();
+
+        let outer = ModalScope::open(&editor).unwrap();
+        assert!(paragraph.has_attribute("inert"));
+        assert!(!textarea.has_attribute("inert"));
+        let inner = ModalScope::open(&confirm).unwrap();
+        assert_eq!(modal_depth(), 2);
+        assert!(textarea.has_attribute("inert"));
+
+        outer.close();
+        assert!(paragraph.has_attribute("inert"));
+        inner.close();
+        assert!(!inner.is_open());
+        assert_eq!(modal_depth(), 0);
+        assert!(!paragraph.has_attribute("inert"));
+        assert!(!textarea.has_attribute("inert"));
+        assert!(aside.has_attribute("inert"));
+        body.remove_child(&page).unwrap();
+    }
+}
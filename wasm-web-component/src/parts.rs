@@ -0,0 +1,107 @@
+//! CSS shadow parts.
+//!
+//! Components declare the parts of their shadow root with the `parts` parameter of the
+//! [`#[web_component]`](crate::web_component) attribute. Elements in a shadow root
+//! attached with the generated `attach_shadow` methods whose id or class matches a
+//! declared part get stamped with the matching `part` attribute automatically.
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DocumentFragment, Element};
+
+/// Adds the declared parts to the `part` attribute of every element in the shadow
+/// root whose id or class matches a part name.
+pub fn stamp_parts(root: &DocumentFragment, parts: &[&str]) -> Result<(), JsValue> {
+    for part in parts {
+        let nodes = root.query_selector_all(&format!("#{0}, .{0}", part))?;
+        for i in 0..nodes.length() {
+            if let Some(element) = nodes.item(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+                add_part(&element, part)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds the part name to the element's `part` attribute.
+pub fn add_part(element: &Element, part: &str) -> Result<(), JsValue> {
+    let current = element.get_attribute("part").unwrap_or_default();
+    if current.split_whitespace().any(|p| p == part) {
+        return Ok(());
+    }
+    let value = if current.trim().is_empty() {
+        part.to_owned()
+    } else {
+        format!("{} {}", current.trim(), part)
+    };
+    element.set_attribute("part", &value)
+}
+
+/// Returns the `exportparts` value forwarding the parts, optionally renamed with a
+/// prefix so they can't collide with the outer component's own parts.
+pub fn exportparts_value(parts: &[&str], prefix: Option<&str>) -> String {
+    parts
+        .iter()
+        .map(|part| match prefix {
+            Some(prefix) => format!("{}: {}-{}", part, prefix, part),
+            None => (*part).to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Forwards the parts of a nested component out of the outer component's shadow root
+/// by setting the nested element's `exportparts` attribute.
+pub fn export_parts(
+    element: &Element,
+    parts: &[&str],
+    prefix: Option<&str>,
+) -> Result<(), JsValue> {
+    element.set_attribute("exportparts", &exportparts_value(parts, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_stamp_and_export_parts() {
+        let document = window().unwrap().document().unwrap();
+        let root = document.create_document_fragment();
+        let template = document.create_element("div").unwrap();
+        template.set_inner_html("<span id='label'></span><i class='icon' part='glyph'></i><b></b>");
+        while let Some(child) = template.first_child() {
+            root.append_child(&child).unwrap();
+        }
+        stamp_parts(&root, &["label", "icon"]).unwrap();
+        assert_eq!(
+            root.query_selector("#label")
+                .unwrap()
+                .unwrap()
+                .get_attribute("part"),
+            Some("label".to_owned())
+        );
+        assert_eq!(
+            root.query_selector(".icon")
+                .unwrap()
+                .unwrap()
+                .get_attribute("part"),
+            Some("glyph icon".to_owned())
+        );
+        assert_eq!(
+            root.query_selector("b")
+                .unwrap()
+                .unwrap()
+                .get_attribute("part"),
+            None
+        );
+
+        assert_eq!(exportparts_value(&["label", "icon"], None), "label, icon");
+        assert_eq!(
+            exportparts_value(&["label"], Some("button")),
+            "label: button-label"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Performance timeline entries for components.
+//!
+//! With the `Performance` feature enabled the generated shims record a
+//! `performance.measure` entry around the `connected` and `attribute_changed`
+//! callbacks and every render pass, named after the component and the phase, e.g.
+//! `my-element::connected`. The browser's performance panel then attributes the time
+//! spent to the individual Rust components. [PerformanceMeasure] records entries for
+//! any other code.
+//!
+//! ```ignore
+//! {
+//!     let _measure = PerformanceMeasure::start("my-app::load-items");
+//!     load_items();
+//! } // The measure ends here.
+//! ```
+#[cfg(feature = "Performance")]
+use std::cell::Cell;
+
+use crate::WebComponentDef;
+
+#[cfg(feature = "Performance")]
+thread_local! {
+    static NEXT_MARK: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Records a `performance.measure` entry from when it was started until it is
+/// dropped.
+#[cfg(feature = "Performance")]
+pub struct PerformanceMeasure {
+    name: String,
+    start_mark: String,
+}
+
+#[cfg(feature = "Performance")]
+impl PerformanceMeasure {
+    /// Starts a measure with the name.
+    pub fn start<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        // Every measure gets its own start mark so nested measures with the same
+        // name don't share one.
+        let start_mark = NEXT_MARK.with(|next| {
+            let id = next.get();
+            next.set(id + 1);
+            format!("{}#{}", name, id)
+        });
+        if let Some(performance) = performance() {
+            let _ = performance.mark(&start_mark);
+        }
+        Self { name, start_mark }
+    }
+}
+
+#[cfg(feature = "Performance")]
+impl Drop for PerformanceMeasure {
+    fn drop(&mut self) {
+        if let Some(performance) = performance() {
+            let _ = performance.measure_with_start_mark(&self.name, &self.start_mark);
+            performance.clear_marks_with_mark_name(&self.start_mark);
+        }
+    }
+}
+
+#[cfg(feature = "Performance")]
+fn performance() -> Option<web_sys::Performance> {
+    web_sys::window()?.performance()
+}
+
+/// Guard for the measure of a component phase.
+#[doc(hidden)]
+pub struct PhaseMeasure {
+    #[cfg(feature = "Performance")]
+    _measure: PerformanceMeasure,
+}
+
+/// Starts the measure for a phase of the component.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "Performance"), allow(unused_variables))]
+pub fn measure_phase<T: WebComponentDef>(phase: &'static str) -> PhaseMeasure {
+    PhaseMeasure {
+        #[cfg(feature = "Performance")]
+        _measure: PerformanceMeasure::start(format!("{}::{}", T::element_name(), phase)),
+    }
+}
+
+#[cfg(all(test, feature = "Performance"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_performance_measure() {
+        let performance = performance().unwrap();
+        {
+            let _outer = PerformanceMeasure::start("perf-test::outer");
+            let _inner = PerformanceMeasure::start("perf-test::outer");
+        }
+        assert_eq!(
+            performance
+                .get_entries_by_name_with_entry_type("perf-test::outer", "measure")
+                .length(),
+            2
+        );
+        assert_eq!(
+            performance
+                .get_entries_by_type("mark")
+                .iter()
+                .filter(|mark| js_sys::Reflect::get(mark, &"name".into())
+                    .ok()
+                    .and_then(|name| name.as_string())
+                    .map(|name| name.starts_with("perf-test::outer#"))
+                    .unwrap_or(false))
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Compatibility with the `@webcomponents/custom-elements` polyfill.
+//!
+//! Older webviews, still common in kiosk and enterprise deployments, only get custom
+//! elements from the polyfill. It constructs elements before their attributes and
+//! children have been parsed, so with the `Polyfill` feature components detect it and
+//! run their `init` callbacks right before the first `attribute_changed` or `connected`
+//! callback instead of in the constructor.
+//!
+//! The engines that need it have WebAssembly but not custom elements, like Firefox 52
+//! to 62 and the EdgeHTML versions of Edge, so the javascript the components generate
+//! sticks to the ES2017 syntax those support.
+//!
+//! The polyfill upgrades the elements in the document every time an element gets
+//! defined. [batch_definitions] defers those upgrades until a whole set of components
+//! has been defined so the document only gets walked once.
+//!
+//! ```ignore
+//! batch_definitions(|| {
+//!     MyList::define_once();
+//!     MyListItem::define_once();
+//! });
+//! ```
+use std::cell::{Cell, RefCell};
+
+use js_sys::{Function, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::environment::custom_elements_registry;
+
+thread_local! {
+    static WRAPPED: Cell<bool> = const { Cell::new(false) };
+    static BATCHING: Cell<bool> = const { Cell::new(false) };
+    static PENDING_FLUSH: RefCell<Option<Function>> = const { RefCell::new(None) };
+}
+
+fn wrap_flush_callback() -> Option<Function> {
+    let registry = custom_elements_registry()?;
+    Reflect::get(&registry, &JsValue::from_str("polyfillWrapFlushCallback"))
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// Whether custom elements come from the `@webcomponents/custom-elements` polyfill.
+pub fn custom_elements_polyfilled() -> bool {
+    wrap_flush_callback().is_some()
+}
+
+/// Wraps the polyfill's flush callback once so flushes can be held back while batching.
+fn install_flush_wrapper(wrap: &Function) -> Result<(), JsValue> {
+    if WRAPPED.with(|wrapped| wrapped.replace(true)) {
+        return Ok(());
+    }
+    let wrapper = Closure::<dyn FnMut(Function)>::new(|flush: Function| {
+        if BATCHING.with(Cell::get) {
+            PENDING_FLUSH.with(|pending| *pending.borrow_mut() = Some(flush));
+        } else {
+            let _ = flush.call0(&JsValue::NULL);
+        }
+    });
+    let registry = custom_elements_registry().ok_or_else(|| JsValue::from_str("No registry"))?;
+    wrap.call1(&registry, wrapper.as_ref())?;
+    // The polyfill keeps the wrapper for the life of the page.
+    wrapper.forget();
+    Ok(())
+}
+
+/// Defines components with the polyfill's upgrades held back until the function
+/// returns. Without the polyfill it just calls the function.
+pub fn batch_definitions<R, F: FnOnce() -> R>(define: F) -> R {
+    let wrap = match wrap_flush_callback() {
+        Some(wrap) => wrap,
+        None => return define(),
+    };
+    if install_flush_wrapper(&wrap).is_err() || BATCHING.with(|batching| batching.replace(true)) {
+        // Nested batches flush with the outermost one.
+        return define();
+    }
+    let result = define();
+    BATCHING.with(|batching| batching.set(false));
+    if let Some(flush) = PENDING_FLUSH.with(|pending| pending.borrow_mut().take()) {
+        let _ = flush.call0(&JsValue::NULL);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_batch_definitions_without_polyfill() {
+        assert!(!custom_elements_polyfilled());
+        assert_eq!(batch_definitions(|| 42), 42);
+    }
+}
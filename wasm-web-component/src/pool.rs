@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+thread_local! {
+    static POOLS: RefCell<HashMap<&'static str, Vec<Element>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns a disconnected element to the pool for the element name if the pool
+/// has room for it. Elements that are already pooled aren't added again.
+pub(crate) fn release(element_name: &'static str, element: &Element, max_size: usize) {
+    POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let pool = pools.entry(element_name).or_default();
+        if pool.len() < max_size && !pool.contains(element) {
+            pool.push(element.clone());
+        }
+    });
+}
+
+/// Takes an element out of the pool for the element name and resets it.
+///
+/// Elements that were reconnected to the DOM after getting released are still
+/// in use and get dropped from the pool instead.
+pub(crate) fn acquire(element_name: &'static str) -> Option<Element> {
+    let element = POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let pool = pools.get_mut(element_name)?;
+        while let Some(element) = pool.pop() {
+            if !element.is_connected() {
+                return Some(element);
+            }
+        }
+        None
+    })?;
+    if let Ok(reset) = Reflect::get(&element, &JsValue::from_str("resetCallback")) {
+        if let Some(reset) = reset.dyn_ref::<Function>() {
+            let _ = reset.call0(&element);
+        }
+    }
+    Some(element)
+}
+
+/// Drops the element from the pool for the element name.
+pub(crate) fn remove(element_name: &'static str, element: &Element) {
+    POOLS.with(|pools| {
+        if let Some(pool) = pools.borrow_mut().get_mut(element_name) {
+            pool.retain(|pooled| pooled != element);
+        }
+    });
+}
+
+/// Drops every pooled element for the element name.
+pub(crate) fn clear(element_name: &'static str) {
+    POOLS.with(|pools| {
+        pools.borrow_mut().remove(element_name);
+    });
+}
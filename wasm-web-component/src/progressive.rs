@@ -0,0 +1,177 @@
+//! Rendering large trees a chunk at a time.
+//!
+//! [render_progressive] runs the first few work items immediately so the element has
+//! its first meaningful paint and runs the rest in [Priority::Background] tasks,
+//! a few milliseconds of work per task, so rendering a long list doesn't block input.
+//! The remaining work is dropped when the element gets disconnected.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     Self::render(element, "<ul></ul>").unwrap();
+//!     let list = element.shadow_root().unwrap().first_element_child().unwrap();
+//!     let rows = self.rows.clone().into_iter().map(move |row| {
+//!         let list = list.clone();
+//!         move || append_row(&list, &row)
+//!     });
+//!     render_progressive(element, 20, rows).unwrap();
+//! }
+//! ```
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::cleanup::on_disconnect;
+use crate::scheduler::{schedule, Priority, Task};
+
+/// How long one background task runs work items before yielding to the browser.
+const CHUNK_BUDGET_MS: f64 = 8.0;
+
+type Work = Box<dyn Iterator<Item = Box<dyn FnOnce()>>>;
+
+#[derive(Default)]
+struct State {
+    work: Option<Work>,
+    task: Option<Task>,
+}
+
+/// The remaining work of a progressive render.
+#[derive(Clone)]
+pub struct ProgressiveRender {
+    state: Rc<RefCell<State>>,
+}
+
+impl ProgressiveRender {
+    /// Whether every work item has run or the render was cancelled.
+    pub fn is_done(&self) -> bool {
+        self.state.borrow().work.is_none()
+    }
+
+    /// Drops the work items that haven't run yet.
+    pub fn cancel(&self) {
+        let task = {
+            let mut state = self.state.borrow_mut();
+            state.work = None;
+            state.task.take()
+        };
+        if let Some(task) = task {
+            task.cancel();
+        }
+    }
+
+    /// Runs the remaining work items now, e.g. before measuring the rendered tree.
+    pub fn finish(&self) {
+        if let Some(task) = self.state.borrow_mut().task.take() {
+            task.cancel();
+        }
+        while let Some(item) = self.next_item() {
+            item();
+        }
+    }
+
+    fn next_item(&self) -> Option<Box<dyn FnOnce()>> {
+        let mut state = self.state.borrow_mut();
+        let item = state.work.as_mut()?.next();
+        if item.is_none() {
+            state.work = None;
+        }
+        item
+    }
+
+    fn run_chunk(&self) {
+        self.state.borrow_mut().task = None;
+        let deadline = js_sys::Date::now() + CHUNK_BUDGET_MS;
+        while js_sys::Date::now() < deadline {
+            match self.next_item() {
+                // Run outside of the borrow so items can cancel or finish the render.
+                Some(item) => item(),
+                None => return,
+            }
+        }
+        let _ = self.schedule_chunk();
+    }
+
+    fn schedule_chunk(&self) -> Result<(), JsValue> {
+        if self.is_done() {
+            return Ok(());
+        }
+        let render = self.clone();
+        let task = schedule(Priority::Background, move || render.run_chunk())?;
+        self.state.borrow_mut().task = Some(task);
+        Ok(())
+    }
+}
+
+/// Runs the first `first_paint` work items now and the rest during idle time until the
+/// web component element gets disconnected.
+pub fn render_progressive<I, F>(
+    element: &Element,
+    first_paint: usize,
+    work: I,
+) -> Result<ProgressiveRender, JsValue>
+where
+    I: IntoIterator<Item = F>,
+    I::IntoIter: 'static,
+    F: FnOnce() + 'static,
+{
+    let work = work
+        .into_iter()
+        .map(|item| Box::new(item) as Box<dyn FnOnce()>);
+    let render = ProgressiveRender {
+        state: Rc::new(RefCell::new(State {
+            work: Some(Box::new(work)),
+            task: None,
+        })),
+    };
+    for _ in 0..first_paint {
+        match render.next_item() {
+            Some(item) => item(),
+            None => break,
+        }
+    }
+    render.schedule_chunk()?;
+    let pending = render.clone();
+    on_disconnect(element, move || pending.cancel());
+    Ok(render)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleanup::run_disconnect_hooks;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_render_progressive() {
+        let document = window().unwrap().document().unwrap();
+        let list = document.create_element("ul").unwrap();
+        let rows = |list: &Element| {
+            let list = list.clone();
+            (0..5).map(move |i| {
+                let list = list.clone();
+                move || {
+                    let row = list.owner_document().unwrap().create_element("li").unwrap();
+                    row.set_text_content(Some(&i.to_string()));
+                    list.append_child(&row).unwrap();
+                }
+            })
+        };
+        let render = render_progressive(&list, 2, rows(&list)).unwrap();
+        assert_eq!(list.child_element_count(), 2);
+        assert!(!render.is_done());
+        render.finish();
+        assert_eq!(list.child_element_count(), 5);
+        assert!(render.is_done());
+
+        let other = document.create_element("ul").unwrap();
+        let render = render_progressive(&other, 1, rows(&other)).unwrap();
+        run_disconnect_hooks(&other);
+        assert!(render.is_done());
+        render.finish();
+        assert_eq!(other.child_element_count(), 1);
+    }
+}
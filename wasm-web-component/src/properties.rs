@@ -0,0 +1,124 @@
+//! Typed access to the javascript properties of elements.
+//!
+//! [PropertyValue] converts between javascript values and Rust types. It is used by the
+//! wrappers that [`custom_elements!`](crate::custom_elements) generates for the
+//! properties and event details of elements described in a custom elements manifest.
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+
+/// A Rust type for a javascript property value.
+pub trait PropertyValue: Sized {
+    /// Converts the javascript value. Returns None if it has the wrong type.
+    fn from_property(value: &JsValue) -> Option<Self>;
+
+    /// The javascript value for the Rust value.
+    fn property_value(&self) -> JsValue;
+}
+
+impl PropertyValue for bool {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn property_value(&self) -> JsValue {
+        JsValue::from_bool(*self)
+    }
+}
+
+impl PropertyValue for f64 {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        value.as_f64()
+    }
+
+    fn property_value(&self) -> JsValue {
+        JsValue::from_f64(*self)
+    }
+}
+
+impl PropertyValue for String {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        value.as_string()
+    }
+
+    fn property_value(&self) -> JsValue {
+        JsValue::from_str(self)
+    }
+}
+
+impl PropertyValue for JsValue {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn property_value(&self) -> JsValue {
+        self.clone()
+    }
+}
+
+/// None for undefined and null.
+impl<T: PropertyValue> PropertyValue for Option<T> {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        if value.is_undefined() || value.is_null() {
+            Some(None)
+        } else {
+            T::from_property(value).map(Some)
+        }
+    }
+
+    fn property_value(&self) -> JsValue {
+        self.as_ref()
+            .map(T::property_value)
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+}
+
+/// An array. None if any of the items has the wrong type.
+impl<T: PropertyValue> PropertyValue for Vec<T> {
+    fn from_property(value: &JsValue) -> Option<Self> {
+        if !Array::is_array(value) {
+            return None;
+        }
+        Array::from(value)
+            .iter()
+            .map(|item| T::from_property(&item))
+            .collect()
+    }
+
+    fn property_value(&self) -> JsValue {
+        self.iter().map(T::property_value).collect::<Array>().into()
+    }
+}
+
+/// Returns the converted value of the target's property.
+pub fn get_prop<T: PropertyValue>(target: &JsValue, name: &str) -> Option<T> {
+    T::from_property(&Reflect::get(target, &JsValue::from_str(name)).ok()?)
+}
+
+/// Sets the target's property to the value.
+pub fn set_prop<T: PropertyValue>(target: &JsValue, name: &str, value: &T) -> Result<(), JsValue> {
+    Reflect::set(target, &JsValue::from_str(name), &value.property_value()).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use js_sys::Object;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_props() {
+        let target: JsValue = Object::new().into();
+        set_prop(&target, "page", &3.0).unwrap();
+        assert_eq!(get_prop::<f64>(&target, "page"), Some(3.0));
+        assert_eq!(get_prop::<String>(&target, "page"), None);
+        assert_eq!(get_prop::<Option<String>>(&target, "missing"), Some(None));
+        set_prop(&target, "tags", &vec!["a".to_owned(), "b".to_owned()]).unwrap();
+        assert_eq!(
+            get_prop::<Vec<String>>(&target, "tags"),
+            Some(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(get_prop::<Vec<bool>>(&target, "tags"), None);
+    }
+}
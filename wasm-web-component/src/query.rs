@@ -0,0 +1,128 @@
+//! Typed queries inside of a component.
+//!
+//! The generated `query` and `query_all` methods search the element's shadow root, or
+//! the element itself for components without one, and cast the matches to the
+//! requested element type. Errors name the component and the selector.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     let name: HtmlInputElement = self.query(element, "input.name").unwrap();
+//!     let options: Vec<HtmlOptionElement> = self.query_all(element, "option").unwrap();
+//! }
+//! ```
+use std::fmt;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement};
+
+use crate::WebComponentDef;
+
+/// Why a query failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryErrorKind {
+    /// Nothing matched the selector.
+    NotFound,
+    /// A matching element isn't of the requested type.
+    WrongType {
+        /// The tag name of the matching element.
+        found: String,
+    },
+    /// The selector isn't a valid CSS selector.
+    InvalidSelector,
+}
+
+/// An error querying inside of a component.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    pub element_name: &'static str,
+    pub selector: String,
+    /// The name of the requested element type.
+    pub expected: &'static str,
+    pub kind: QueryErrorKind,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            QueryErrorKind::NotFound => write!(
+                f,
+                "[{}] Nothing matches the selector `{}`",
+                self.element_name, self.selector
+            ),
+            QueryErrorKind::WrongType { found } => write!(
+                f,
+                "[{}] The selector `{}` matched a <{}> which isn't a {}",
+                self.element_name, self.selector, found, self.expected
+            ),
+            QueryErrorKind::InvalidSelector => write!(
+                f,
+                "[{}] `{}` isn't a valid selector",
+                self.element_name, self.selector
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<QueryError> for JsValue {
+    fn from(error: QueryError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// The last path segment of the type's name, e.g. `HtmlInputElement`.
+pub(crate) fn short_type_name<E>() -> &'static str {
+    let name = std::any::type_name::<E>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn error<T: WebComponentDef, E>(selector: &str, kind: QueryErrorKind) -> QueryError {
+    QueryError {
+        element_name: T::element_name(),
+        selector: selector.to_owned(),
+        expected: short_type_name::<E>(),
+        kind,
+    }
+}
+
+fn cast<T: WebComponentDef, E: JsCast>(selector: &str, found: Element) -> Result<E, QueryError> {
+    let tag_name = found.tag_name().to_lowercase();
+    found
+        .dyn_into()
+        .map_err(|_| error::<T, E>(selector, QueryErrorKind::WrongType { found: tag_name }))
+}
+
+/// Returns the first element matching the selector in the element's shadow root, or
+/// in the element itself if it has no open shadow root, cast to the type. This is
+/// called by the generated `query` method.
+pub fn query<T: WebComponentDef, E: JsCast>(
+    element: &HtmlElement,
+    selector: &str,
+) -> Result<E, QueryError> {
+    let found = match element.shadow_root() {
+        Some(root) => root.query_selector(selector),
+        None => element.query_selector(selector),
+    }
+    .map_err(|_| error::<T, E>(selector, QueryErrorKind::InvalidSelector))?
+    .ok_or_else(|| error::<T, E>(selector, QueryErrorKind::NotFound))?;
+    cast::<T, E>(selector, found)
+}
+
+/// Returns every element matching the selector like [query]. Fails if any of them
+/// isn't of the type. Nothing matching isn't an error. This is called by the generated
+/// `query_all` method.
+pub fn query_all<T: WebComponentDef, E: JsCast>(
+    element: &HtmlElement,
+    selector: &str,
+) -> Result<Vec<E>, QueryError> {
+    let nodes = match element.shadow_root() {
+        Some(root) => root.query_selector_all(selector),
+        None => element.query_selector_all(selector),
+    }
+    .map_err(|_| error::<T, E>(selector, QueryErrorKind::InvalidSelector))?;
+    (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .map(|node| cast::<T, E>(selector, node.unchecked_into()))
+        .collect()
+}
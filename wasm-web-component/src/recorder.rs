@@ -0,0 +1,261 @@
+//! Attribute change and lifecycle recording.
+//!
+//! Once recording has been started with [start_recording] every attribute change and
+//! lifecycle callback of every component instance gets recorded with a timestamp into
+//! a ring buffer per instance. The recordings show how a component got into its
+//! current state and the attribute changes can be replayed onto another element with
+//! [replay_attributes].
+//!
+//! ```ignore
+//! start_recording(100);
+//! // ... use the page ...
+//! console::log_1(&recording_json(&element)?.into());
+//! ```
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use js_sys::WeakMap;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+/// Something that happened to a component instance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// A lifecycle callback was called.
+    Lifecycle { callback: String },
+    /// An observed attribute changed.
+    AttributeChanged {
+        name: String,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    },
+}
+
+/// A recorded event with the time in milliseconds since the epoch it happened at.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Record {
+    pub timestamp: f64,
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+/// The recording of a single component instance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InstanceRecording {
+    pub component: String,
+    pub instance: u32,
+    pub events: VecDeque<Record>,
+}
+
+struct Recorder {
+    capacity: usize,
+    // Maps elements to instance ids without keeping the elements alive.
+    ids: WeakMap,
+    next_id: u32,
+    instances: HashMap<u32, InstanceRecording>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn to_js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Starts recording with a ring buffer that keeps the latest `capacity` events of
+/// every instance. Restarting clears the previous recordings.
+pub fn start_recording(capacity: usize) {
+    RECORDER.with(|recorder| {
+        *recorder.borrow_mut() = Some(Recorder {
+            capacity,
+            ids: WeakMap::new(),
+            next_id: 0,
+            instances: HashMap::new(),
+        })
+    });
+}
+
+/// Stops recording and drops the recordings.
+pub fn stop_recording() {
+    RECORDER.with(|recorder| *recorder.borrow_mut() = None);
+}
+
+/// Returns true while recording.
+pub fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().is_some())
+}
+
+fn record(element_name: &str, element: &Element, event: RecordedEvent) {
+    RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        let recorder = match recorder.as_mut() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        let id = match recorder.ids.get(element).as_f64() {
+            Some(id) => id as u32,
+            None => {
+                let id = recorder.next_id;
+                recorder.next_id += 1;
+                recorder.ids.set(element, &JsValue::from(id));
+                id
+            }
+        };
+        let capacity = recorder.capacity;
+        let recording = recorder
+            .instances
+            .entry(id)
+            .or_insert_with(|| InstanceRecording {
+                component: element_name.to_owned(),
+                instance: id,
+                events: VecDeque::new(),
+            });
+        if capacity == 0 {
+            return;
+        }
+        while recording.events.len() >= capacity {
+            recording.events.pop_front();
+        }
+        recording.events.push_back(Record {
+            timestamp: js_sys::Date::now(),
+            event,
+        });
+    });
+}
+
+/// Records a lifecycle callback.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn record_lifecycle(element_name: &str, callback: &str, element: &Element) {
+    record(
+        element_name,
+        element,
+        RecordedEvent::Lifecycle {
+            callback: callback.to_owned(),
+        },
+    );
+}
+
+/// Records an attribute change.
+///
+/// This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn record_attribute_change(
+    element_name: &str,
+    element: &Element,
+    name: &JsValue,
+    old_value: &JsValue,
+    new_value: &JsValue,
+) {
+    record(
+        element_name,
+        element,
+        RecordedEvent::AttributeChanged {
+            name: name.as_string().unwrap_or_default(),
+            old_value: old_value.as_string(),
+            new_value: new_value.as_string(),
+        },
+    );
+}
+
+/// Returns the recording of the element if it has one.
+pub fn recording(element: &Element) -> Option<InstanceRecording> {
+    RECORDER.with(|recorder| {
+        let recorder = recorder.borrow();
+        let recorder = recorder.as_ref()?;
+        let id = recorder.ids.get(element).as_f64()? as u32;
+        recorder.instances.get(&id).cloned()
+    })
+}
+
+/// Returns the recording of the element as JSON. The recording is `null` if the
+/// element doesn't have one.
+pub fn recording_json(element: &Element) -> Result<String, JsValue> {
+    serde_json::to_string(&recording(element)).map_err(to_js_error)
+}
+
+/// Returns the recordings of every instance as a JSON array.
+pub fn recordings_json() -> Result<String, JsValue> {
+    RECORDER.with(|recorder| {
+        let recorder = recorder.borrow();
+        let mut recordings: Vec<&InstanceRecording> = recorder
+            .as_ref()
+            .map(|recorder| recorder.instances.values().collect())
+            .unwrap_or_default();
+        recordings.sort_by_key(|recording| recording.instance);
+        serde_json::to_string(&recordings).map_err(to_js_error)
+    })
+}
+
+/// Applies the recorded attribute changes to the element in order.
+pub fn replay_attributes(element: &Element, records: &[Record]) -> Result<(), JsValue> {
+    for record in records {
+        if let RecordedEvent::AttributeChanged {
+            name, new_value, ..
+        } = &record.event
+        {
+            match new_value {
+                Some(value) => element.set_attribute(name, value)?,
+                None => element.remove_attribute(name)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_record_and_replay() {
+        let document = window().unwrap().document().unwrap();
+        let element = document.create_element("div").unwrap();
+        record_lifecycle("recorder-test", "connected", &element);
+        assert_eq!(recording(&element), None);
+
+        start_recording(2);
+        record_lifecycle("recorder-test", "connected", &element);
+        record_attribute_change(
+            "recorder-test",
+            &element,
+            &"title".into(),
+            &JsValue::NULL,
+            &"a".into(),
+        );
+        record_attribute_change(
+            "recorder-test",
+            &element,
+            &"title".into(),
+            &"a".into(),
+            &"b".into(),
+        );
+        let recorded = recording(&element).unwrap();
+        assert_eq!(recorded.component, "recorder-test");
+        // The ring buffer only keeps the latest two events.
+        assert_eq!(recorded.events.len(), 2);
+        assert!(matches!(
+            &recorded.events[0].event,
+            RecordedEvent::AttributeChanged { new_value: Some(v), .. } if v == "a"
+        ));
+
+        let json = recording_json(&element).unwrap();
+        let decoded: InstanceRecording = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, recorded);
+
+        let replayed = document.create_element("div").unwrap();
+        let events: Vec<Record> = decoded.events.into_iter().collect();
+        replay_attributes(&replayed, &events).unwrap();
+        assert_eq!(replayed.get_attribute("title"), Some("b".to_owned()));
+        stop_recording();
+        assert!(!is_recording());
+    }
+}
@@ -0,0 +1,154 @@
+//! Rendering into the shadow DOM or the light DOM.
+//!
+//! Components render with [WebComponentDef::render](crate::WebComponentDef::render)
+//! which uses a shadow root unless the component has `light_dom = true` in the
+//! [`#[web_component]`](crate::web_component) attribute. Both modes support the same
+//! features: observed events, scoped styles and slots. Shadow parts only apply to
+//! shadow roots.
+//!
+//! In the light DOM `<slot>` elements are emulated. The children the element had
+//! before its first render get projected into the slot with the matching name, or the
+//! unnamed slot for children without a `slot` attribute, and slots without any
+//! projected children keep their fallback content.
+//!
+//! Large trees can be rendered a chunk at a time during idle time with
+//! [render_progressive](crate::render_progressive).
+//!
+//! ```ignore
+//! #[web_component(light_dom = true)]
+//! pub struct MyCard {}
+//!
+//! impl WebComponentBinding for MyCard {
+//!     fn connected(&self, element: &HtmlElement) {
+//!         Self::render(element, "<h2><slot name='title'>Untitled</slot></h2><slot></slot>")
+//!             .unwrap();
+//!     }
+//! }
+//! ```
+use js_sys::{Array, Function, Reflect, WeakMap};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement, Node, ShadowRoot, ShadowRootInit, ShadowRootMode};
+
+use crate::scoped_css::scope_fragment_classes;
+use crate::{measure_phase, scope_classes, set_inner_html, stamp_parts, WebComponentDef};
+
+thread_local! {
+    static LIGHT_CHILDREN: WeakMap = WeakMap::new();
+}
+
+/// Has the component's JS class listen for its observed events on the root.
+fn listen_on(element: &HtmlElement, root: &JsValue) -> Result<(), JsValue> {
+    let listen: Function = Reflect::get(element, &JsValue::from_str("listenOn"))?.dyn_into()?;
+    listen.call1(element, root)?;
+    Ok(())
+}
+
+/// Renders the html into the element's shadow root, attaching one with the mode if
+/// the element doesn't have an open shadow root yet.
+pub fn render_shadow<T: WebComponentDef>(
+    element: &HtmlElement,
+    html: &str,
+    mode: ShadowRootMode,
+) -> Result<ShadowRoot, JsValue> {
+    let _measure = measure_phase::<T>("render");
+    #[cfg(feature = "Metrics")]
+    let _metrics = crate::metrics_timer(T::element_name(), crate::Phase::Render);
+    let root = match element.shadow_root() {
+        Some(root) => root,
+        None => element.attach_shadow(&ShadowRootInit::new(mode))?,
+    };
+    set_inner_html(&root, html)?;
+    stamp_parts(&root, T::parts())?;
+    if let (Some(css), Some(scope)) = (T::scoped_style(), T::style_scope()) {
+        scope_fragment_classes(&root, scope)?;
+        // Styles in the document head don't reach into shadow roots.
+        let document = element
+            .owner_document()
+            .ok_or_else(|| JsValue::from_str("No document"))?;
+        let style = document.create_element("style")?;
+        style.set_text_content(Some(css));
+        root.insert_before(&style, root.first_child().as_ref())?;
+    }
+    listen_on(element, &root)?;
+    Ok(root)
+}
+
+fn light_children(element: &Element) -> Array {
+    LIGHT_CHILDREN.with(|children| {
+        let existing = children.get(element);
+        if existing.is_undefined() {
+            let nodes = Array::from(&element.child_nodes());
+            children.set(element, &nodes);
+            nodes
+        } else {
+            existing.unchecked_into()
+        }
+    })
+}
+
+fn slot_name(node: &Node) -> Option<String> {
+    match node.dyn_ref::<Element>() {
+        Some(element) => Some(element.get_attribute("slot").unwrap_or_default()),
+        // Whitespace between elements doesn't get projected.
+        None if node.node_type() == Node::TEXT_NODE => node
+            .text_content()
+            .filter(|text| !text.trim().is_empty())
+            .map(|_| String::new()),
+        None => None,
+    }
+}
+
+/// Returns the light DOM children that get projected into the slot with the name,
+/// or the unnamed slot if there is no name.
+pub fn slotted_nodes(element: &Element, name: Option<&str>) -> Vec<Node> {
+    let name = name.unwrap_or_default();
+    light_children(element)
+        .iter()
+        .map(|node| node.unchecked_into::<Node>())
+        .filter(|node| slot_name(node).as_deref() == Some(name))
+        .collect()
+}
+
+/// Renders the html into the element's light DOM projecting the element's original
+/// children into the `<slot>` elements of the html.
+pub fn render_light<T: WebComponentDef>(element: &HtmlElement, html: &str) -> Result<(), JsValue> {
+    let _measure = measure_phase::<T>("render");
+    #[cfg(feature = "Metrics")]
+    let _metrics = crate::metrics_timer(T::element_name(), crate::Phase::Render);
+    let document = element
+        .owner_document()
+        .ok_or_else(|| JsValue::from_str("No document"))?;
+    // Remember the children before the first render replaces them.
+    light_children(element);
+    let container = document.create_element("div")?;
+    set_inner_html(&container, html)?;
+    if let Some(scope) = T::style_scope() {
+        scope_classes(&container, scope)?;
+    }
+    let slots = container.query_selector_all("slot")?;
+    let mut projected = Vec::new();
+    for i in 0..slots.length() {
+        let slot = match slots.item(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            Some(slot) => slot,
+            None => continue,
+        };
+        let name = slot.get_attribute("name").unwrap_or_default();
+        // Like in the shadow DOM the first slot with a name gets the children.
+        if projected.contains(&name) {
+            continue;
+        }
+        let nodes = slotted_nodes(element, Some(&name));
+        if !nodes.is_empty() {
+            slot.set_text_content(None);
+            for node in nodes {
+                slot.append_child(&node)?;
+            }
+        }
+        projected.push(name);
+    }
+    element.set_text_content(None);
+    while let Some(child) = container.first_child() {
+        element.append_child(&child)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,497 @@
+//! A client side router built on the History API.
+//!
+//! Routes can be declared in markup with the `<wasm-router>` and `<wasm-route>`
+//! elements or registered from Rust with [Router::register]. Either way navigation
+//! goes through [Router::navigate] which runs the navigation guards, updates the
+//! history and then renders every connected router.
+//!
+//! ```html
+//! <wasm-router>
+//!   <wasm-route path="/">Home</wasm-route>
+//!   <wasm-route path="/users/:id">A user</wasm-route>
+//!   <wasm-route path="*">Not found</wasm-route>
+//! </wasm-router>
+//! ```
+//!
+//! The first `<wasm-route>` matching the current path is shown and the others get
+//! the `hidden` attribute. Path params are set on the matching route as `data-param-*`
+//! attributes.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, HtmlAnchorElement, HtmlElement, MouseEvent};
+
+use crate::instances::connected_instances;
+use crate::{web_component, WebComponentBinding};
+
+const PARAM_PREFIX: &str = "data-param-";
+const INDEX_KEY: &str = "routerIndex";
+
+/// A matched route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMatch {
+    /// The path that got matched.
+    pub path: String,
+    /// The route pattern that matched the path.
+    pub pattern: String,
+    /// The path params captured by the pattern.
+    pub params: HashMap<String, String>,
+}
+
+/// Matches a path against a route pattern.
+///
+/// Pattern segments starting with `:` capture the path segment as a param and a
+/// `*` segment matches the rest of the path. Captured params are percent-decoded.
+pub fn match_path(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let mut params = HashMap::new();
+    let mut path_segments = path.split('/').filter(|s| !s.is_empty());
+    for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+        if segment == "*" {
+            return Some(params);
+        }
+        let path_segment = path_segments.next()?;
+        if let Some(name) = segment.strip_prefix(':') {
+            params.insert(name.to_owned(), decode_segment(path_segment));
+        } else if segment != path_segment {
+            return None;
+        }
+    }
+    if path_segments.next().is_some() {
+        return None;
+    }
+    Some(params)
+}
+
+/// Percent-decodes a path segment. Malformed escapes are kept as they are.
+fn decode_segment(segment: &str) -> String {
+    js_sys::decode_uri_component(segment)
+        .map(String::from)
+        .unwrap_or_else(|_| segment.to_owned())
+}
+
+type Handler = Rc<dyn Fn(&RouteMatch)>;
+type Guard = Rc<dyn Fn(&str, &str) -> bool>;
+
+/// Identifies a navigation guard added with [Router::add_guard].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardId(u32);
+
+thread_local! {
+    static ROUTES: RefCell<Vec<(String, Handler)>> = const { RefCell::new(Vec::new()) };
+    static GUARDS: RefCell<Vec<(GuardId, Guard)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_GUARD: Cell<u32> = const { Cell::new(0) };
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+    // The position of the current entry in the history, stored in the entries' state
+    // so a cancelled back or forward navigation knows how far to go to get back.
+    static CURRENT_INDEX: Cell<i32> = const { Cell::new(0) };
+    // Set while the router undoes a cancelled back or forward navigation.
+    static RESTORING: Cell<bool> = const { Cell::new(false) };
+    static POPSTATE_INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn location_path() -> String {
+    web_sys::window()
+        .and_then(|w| w.location().pathname().ok())
+        .unwrap_or_else(|| "/".to_owned())
+}
+
+/// The history index stored in the state of the current history entry.
+fn history_index(history: &web_sys::History) -> Option<i32> {
+    let state = history.state().ok()?;
+    if !state.is_object() {
+        return None;
+    }
+    Reflect::get(&state, &JsValue::from_str(INDEX_KEY))
+        .ok()?
+        .as_f64()
+        .map(|index| index as i32)
+}
+
+fn index_state(index: i32) -> JsValue {
+    let state = Object::new();
+    let _ = Reflect::set(&state, &JsValue::from_str(INDEX_KEY), &JsValue::from(index));
+    state.into()
+}
+
+/// The router. All of its state is shared by every router element on the page.
+pub struct Router;
+
+impl Router {
+    /// Defines the `<wasm-router>` and `<wasm-route>` elements.
+    pub fn define_elements() {
+        RouterElement::define_once();
+        RouteElement::define_once();
+    }
+
+    /// Registers a handler that gets called when a navigation ends on a path
+    /// matching the pattern.
+    pub fn register<F: Fn(&RouteMatch) + 'static>(pattern: &str, handler: F) {
+        Self::install_popstate_listener();
+        ROUTES.with(|routes| {
+            routes
+                .borrow_mut()
+                .push((pattern.to_owned(), Rc::new(handler)))
+        });
+    }
+
+    /// Removes the handlers registered for the pattern.
+    pub fn unregister(pattern: &str) {
+        ROUTES.with(|routes| routes.borrow_mut().retain(|(p, _)| p != pattern));
+    }
+
+    /// Adds a navigation guard. Guards get called with the current and the new
+    /// path before every navigation and can cancel it by returning false. A cancelled
+    /// back or forward navigation returns to the history entry it started from.
+    pub fn add_guard<F: Fn(&str, &str) -> bool + 'static>(guard: F) -> GuardId {
+        Self::install_popstate_listener();
+        let id = GuardId(NEXT_GUARD.with(|next| next.replace(next.get() + 1)));
+        GUARDS.with(|guards| guards.borrow_mut().push((id, Rc::new(guard))));
+        id
+    }
+
+    /// Removes a navigation guard added with [Router::add_guard].
+    pub fn remove_guard(id: GuardId) {
+        GUARDS.with(|guards| guards.borrow_mut().retain(|(guard, _)| *guard != id));
+    }
+
+    /// Returns the current path.
+    pub fn current_path() -> String {
+        CURRENT
+            .with(|current| current.borrow().clone())
+            .unwrap_or_else(location_path)
+    }
+
+    /// Navigates to the path. Returns false if a guard cancelled the navigation.
+    pub fn navigate(path: &str) -> Result<bool, JsValue> {
+        if !Self::guards_allow(&Self::current_path(), path) {
+            return Ok(false);
+        }
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+        let index = CURRENT_INDEX.with(|current| current.get()) + 1;
+        window
+            .history()?
+            .push_state_with_url(&index_state(index), "", Some(path))?;
+        CURRENT_INDEX.with(|current| current.set(index));
+        Self::route();
+        Ok(true)
+    }
+
+    fn guards_allow(from: &str, to: &str) -> bool {
+        let guards: Vec<Guard> = GUARDS.with(|guards| {
+            guards
+                .borrow()
+                .iter()
+                .map(|(_, guard)| guard.clone())
+                .collect()
+        });
+        guards.iter().all(|guard| guard(from, to))
+    }
+
+    /// Renders the routers and calls the handlers for the current location.
+    fn route() {
+        let path = location_path();
+        CURRENT.with(|current| *current.borrow_mut() = Some(path.clone()));
+        let handler = ROUTES.with(|routes| {
+            routes.borrow().iter().find_map(|(pattern, handler)| {
+                match_path(pattern, &path).map(|params| {
+                    (
+                        RouteMatch {
+                            path: path.clone(),
+                            pattern: pattern.clone(),
+                            params,
+                        },
+                        handler.clone(),
+                    )
+                })
+            })
+        });
+        for router in connected_instances(RouterElement::element_name()) {
+            render_router(&router, &path);
+        }
+        if let Some((route_match, handler)) = handler {
+            handler(&route_match);
+        }
+    }
+
+    fn install_popstate_listener() {
+        if POPSTATE_INSTALLED.with(|installed| installed.replace(true)) {
+            return;
+        }
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        let history = match window.history() {
+            Ok(history) => history,
+            Err(_) => return,
+        };
+        match history_index(&history) {
+            Some(index) => CURRENT_INDEX.with(|current| current.set(index)),
+            None => {
+                if history.state().map(|s| s.is_null()).unwrap_or(false) {
+                    let _ = history.replace_state(&index_state(0), "");
+                }
+            }
+        }
+        let on_popstate = Closure::<dyn FnMut()>::new(move || {
+            let history = match web_sys::window().unwrap().history() {
+                Ok(history) => history,
+                Err(_) => return,
+            };
+            let index = history_index(&history).unwrap_or(0);
+            if RESTORING.with(|restoring| restoring.replace(false)) {
+                return;
+            }
+            let from = Router::current_path();
+            let to = location_path();
+            if Router::guards_allow(&from, &to) {
+                CURRENT_INDEX.with(|current| current.set(index));
+                Router::route();
+            } else {
+                // The browser has already moved so go back to the entry it came from.
+                let delta = CURRENT_INDEX.with(|current| current.get()) - index;
+                if delta != 0 {
+                    RESTORING.with(|restoring| restoring.set(true));
+                    let _ = history.go_with_delta(delta);
+                }
+            }
+        });
+        let _ = window
+            .add_event_listener_with_callback("popstate", on_popstate.as_ref().unchecked_ref());
+        // The listener lives as long as the page does.
+        on_popstate.forget();
+    }
+}
+
+fn render_router(router: &Element, path: &str) {
+    let mut matched = false;
+    let mut child = router.first_element_child();
+    while let Some(route) = child {
+        child = route.next_element_sibling();
+        if !route
+            .tag_name()
+            .eq_ignore_ascii_case(&RouteElement::tag_name())
+        {
+            continue;
+        }
+        let names = route.get_attribute_names();
+        for name in names.iter().filter_map(|n| n.as_string()) {
+            if name.starts_with(PARAM_PREFIX) {
+                let _ = route.remove_attribute(&name);
+            }
+        }
+        let params = if matched {
+            None
+        } else {
+            route
+                .get_attribute("path")
+                .and_then(|pattern| match_path(&pattern, path))
+        };
+        match params {
+            Some(params) => {
+                matched = true;
+                let _ = route.remove_attribute("hidden");
+                for (name, value) in params {
+                    let _ = route.set_attribute(&format!("{}{}", PARAM_PREFIX, name), &value);
+                }
+            }
+            None => {
+                let _ = route.set_attribute("hidden", "");
+            }
+        }
+    }
+}
+
+/// The `<wasm-router>` element. It renders its `<wasm-route>` children and turns
+/// clicks on same origin links into navigations.
+#[web_component(
+    class_name = "WasmRouter",
+    element_name = "wasm-router",
+    observed_events = "['click']",
+    stateless = true
+)]
+pub struct RouterElement {}
+
+impl WebComponentBinding for RouterElement {
+    fn connected(&self, element: &HtmlElement) {
+        Router::install_popstate_listener();
+        render_router(element, &Router::current_path());
+    }
+
+    fn handle_event(&self, _element: &HtmlElement, event: &Event) {
+        if event.default_prevented() {
+            return;
+        }
+        if let Some(mouse) = event.dyn_ref::<MouseEvent>() {
+            if mouse.button() != 0
+                || mouse.ctrl_key()
+                || mouse.meta_key()
+                || mouse.shift_key()
+                || mouse.alt_key()
+            {
+                return;
+            }
+        }
+        let anchor = event
+            .target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+            .and_then(|el| el.closest("a[href]").ok().flatten())
+            .and_then(|el| el.dyn_into::<HtmlAnchorElement>().ok());
+        let anchor = match anchor {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        if !anchor.target().is_empty() || anchor.has_attribute("download") {
+            return;
+        }
+        let location = match web_sys::window() {
+            Some(window) => window.location(),
+            None => return,
+        };
+        if Some(anchor.origin()) != location.origin().ok() {
+            return;
+        }
+        // Links to a fragment of the current page are left to the browser.
+        if !anchor.hash().is_empty()
+            && Some(anchor.pathname()) == location.pathname().ok()
+            && Some(anchor.search()) == location.search().ok()
+        {
+            return;
+        }
+        event.prevent_default();
+        let path = format!("{}{}{}", anchor.pathname(), anchor.search(), anchor.hash());
+        let _ = Router::navigate(&path);
+    }
+}
+
+/// The `<wasm-route path="/users/:id">` element. It is shown by its parent
+/// `<wasm-router>` when its path matches.
+#[web_component(
+    class_name = "WasmRoute",
+    element_name = "wasm-route",
+    observed_attrs = "['path']",
+    stateless = true
+)]
+pub struct RouteElement {}
+
+impl WebComponentBinding for RouteElement {
+    fn attribute_changed(
+        &self,
+        element: &HtmlElement,
+        _name: JsValue,
+        _old_value: JsValue,
+        _new_value: JsValue,
+    ) {
+        if let Some(router) = element.parent_element() {
+            if router
+                .tag_name()
+                .eq_ignore_ascii_case(&RouterElement::tag_name())
+            {
+                render_router(&router, &Router::current_path());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::WebComponentDef;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_match_path() {
+        assert_eq!(match_path("/", "/"), Some(HashMap::new()));
+        assert_eq!(match_path("/users", "/posts"), None);
+        assert_eq!(match_path("/users", "/users/1"), None);
+        let params = match_path("/users/:id/posts/:post", "/users/1/posts/2").unwrap();
+        assert_eq!(params.get("id").unwrap(), "1");
+        assert_eq!(params.get("post").unwrap(), "2");
+        assert_eq!(match_path("/files/*", "/files/a/b/c"), Some(HashMap::new()));
+        let params = match_path("/users/:id", "/users/j%C3%BCrgen%20k").unwrap();
+        assert_eq!(params.get("id").unwrap(), "jürgen k");
+        let params = match_path("/users/:id", "/users/100%").unwrap();
+        assert_eq!(params.get("id").unwrap(), "100%");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_router_navigation() {
+        Router::define_elements();
+        let original = location_path();
+        let body = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .body()
+            .unwrap();
+        let router = RouterElement::builder()
+            .child(RouteElement::create_with_attrs(&[("path", "/users/:id")]))
+            .child(RouteElement::create_with_attrs(&[("path", "*")]))
+            .mount(&body)
+            .unwrap();
+        let seen = Rc::new(RefCell::new(None));
+        {
+            let seen = seen.clone();
+            Router::register("/users/:id", move |route| {
+                *seen.borrow_mut() = route.params.get("id").cloned();
+            });
+        }
+        let guard = Router::add_guard(|_, to| to != "/forbidden");
+
+        assert!(Router::navigate("/users/42").unwrap());
+        assert_eq!(*seen.borrow(), Some("42".to_owned()));
+        let user_route = router.first_element_child().unwrap();
+        let fallback_route = user_route.next_element_sibling().unwrap();
+        assert!(!user_route.has_attribute("hidden"));
+        assert_eq!(
+            user_route.get_attribute("data-param-id"),
+            Some("42".to_owned())
+        );
+        assert!(fallback_route.has_attribute("hidden"));
+
+        assert!(!Router::navigate("/forbidden").unwrap());
+        assert_eq!(Router::current_path(), "/users/42");
+
+        body.remove_child(&router).unwrap();
+        Router::remove_guard(guard);
+        Router::unregister("/users/:id");
+        assert!(Router::navigate("/forbidden").unwrap());
+        Router::navigate(&original).unwrap();
+        assert_eq!(*seen.borrow(), Some("42".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_router_leaves_fragment_and_download_links() {
+        Router::define_elements();
+        let document = web_sys::window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let original = location_path();
+        let router = RouterElement::builder().mount(&body).unwrap();
+        let link = |href: &str, download: bool| {
+            let anchor: HtmlElement = document.create_element("a").unwrap().unchecked_into();
+            anchor.set_attribute("href", href).unwrap();
+            if download {
+                anchor.set_attribute("download", "").unwrap();
+            }
+            router.append_child(&anchor).unwrap();
+            anchor
+        };
+        // Keep the browser from following the links the router leaves alone.
+        let prevent = Closure::<dyn FnMut(Event)>::new(|event: Event| event.prevent_default());
+        body.add_event_listener_with_callback("click", prevent.as_ref().unchecked_ref())
+            .unwrap();
+        link("#section", false).click();
+        link("/download/file.txt", true).click();
+        body.remove_event_listener_with_callback("click", prevent.as_ref().unchecked_ref())
+            .unwrap();
+        assert_eq!(Router::current_path(), original);
+        body.remove_child(&router).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+//! The javascript runtime shared by every web component.
+//!
+//! The element class logic lives in a single javascript module that gets embedded once
+//! per binary. Defining a component only creates a thin subclass of the shared base
+//! class which reads its settings from a static config the
+//! [`#[web_component]`](crate::web_component) macro generates.
+use js_sys::{Array, Function, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+
+use crate::{behavior_events, WebComponentDef, CRATE_VERSION};
+
+#[wasm_web_component_macros::component_runtime]
+#[cfg_attr(feature = "DataSource", runtime(data_src))]
+#[cfg_attr(not(feature = "Minimal"), runtime(diagnostics))]
+#[cfg_attr(feature = "Dialog", runtime(dialog))]
+#[cfg_attr(feature = "DragDrop", runtime(drag_drop))]
+#[cfg_attr(feature = "Files", runtime(files))]
+#[cfg_attr(feature = "Gestures", runtime(gestures))]
+#[cfg_attr(feature = "LiveComponent", runtime(live))]
+#[cfg_attr(feature = "IndexedDb", runtime(persist_idb))]
+#[cfg_attr(feature = "Polyfill", runtime(polyfill))]
+#[cfg_attr(feature = "Keys", runtime(shortcuts))]
+extern "C" {
+    #[wasm_bindgen(js_name = defineComponent, catch)]
+    fn define_component_class(config: &JsValue) -> Result<JsValue, JsValue>;
+}
+
+fn set(config: &JsValue, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    Reflect::set(config, &JsValue::from_str(key), value)?;
+    Ok(())
+}
+
+fn push_all<'a>(
+    config: &JsValue,
+    key: &str,
+    items: impl Iterator<Item = &'a str>,
+) -> Result<(), JsValue> {
+    let list: Array = Reflect::get(config, &JsValue::from_str(key))?.dyn_into()?;
+    for item in items {
+        list.push(&JsValue::from_str(item));
+    }
+    Ok(())
+}
+
+/// Defines the element class of the component under the tag name with every instance
+/// backed by the Rust value the constructor returns.
+#[doc(hidden)]
+pub fn define_component<T: WebComponentDef>(
+    config: &str,
+    tag_name: &str,
+    constructor: &Function,
+) -> Result<JsValue, JsValue> {
+    let config = JSON::parse(config)?;
+    set(&config, "className", &JsValue::from_str(T::class_name()))?;
+    set(&config, "elementName", &JsValue::from_str(tag_name))?;
+    let alias = if T::version_alias() {
+        T::element_name()
+    } else {
+        ""
+    };
+    set(&config, "alias", &JsValue::from_str(alias))?;
+    let extends = T::extends().map(JsValue::from_str).unwrap_or(JsValue::NULL);
+    set(&config, "extends", &extends)?;
+    set(&config, "crateVersion", &JsValue::from_str(CRATE_VERSION))?;
+    set(
+        &config,
+        "diagnostics",
+        &JsValue::from_bool(!cfg!(feature = "Minimal") && crate::diagnostics_enabled()),
+    )?;
+    set(&config, "impl", constructor)?;
+    push_all(
+        &config,
+        "observedAttributes",
+        T::behaviors()
+            .iter()
+            .flat_map(|behavior| behavior.observed_attributes.iter().copied()),
+    )?;
+    push_all(
+        &config,
+        "observedEvents",
+        behavior_events::<T>().into_iter(),
+    )?;
+    define_component_class(&config)
+}
@@ -0,0 +1,57 @@
+//! Sanitized HTML for rendering untrusted content.
+//!
+//! [set_sanitized_html] strips scripts, event handler attributes and other ways to
+//! run code from the HTML before inserting it. It uses the browser's built in
+//! Sanitizer API (`setHTML`) where it is available and falls back to sanitizing the
+//! HTML in Rust with [ammonia](https://docs.rs/ammonia) otherwise.
+//! Both are behind the `Sanitize` feature so only the apps rendering untrusted content
+//! pull in ammonia.
+//!
+//! ```ignore
+//! let comment = element.shadow_root().unwrap().get_element_by_id("comment").unwrap();
+//! set_sanitized_html(&comment, &user_submitted_html)?;
+//! ```
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::set_inner_html;
+
+/// Returns the HTML with everything that could run code removed.
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::clean(html)
+}
+
+/// Replaces the content of an element or shadow root with the sanitized HTML.
+pub fn set_sanitized_html(node: &JsValue, html: &str) -> Result<(), JsValue> {
+    let set_html = Reflect::get(node, &JsValue::from_str("setHTML"))?;
+    match set_html.dyn_into::<Function>() {
+        Ok(set_html) => set_html.call1(node, &JsValue::from_str(html)).map(|_| ()),
+        Err(_) => set_inner_html(node, &sanitize_html(html)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_set_sanitized_html() {
+        let unsafe_html = "<img src='x' onerror='alert(1)'><script>alert(2)</script><b>ok</b>";
+        assert!(!sanitize_html(unsafe_html).contains("onerror"));
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        set_sanitized_html(&element, unsafe_html).unwrap();
+        let html = element.inner_html();
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("script"));
+        assert!(html.contains("<b>ok</b>"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Deferring non-urgent work to the browser's task scheduler.
+//!
+//! [schedule] posts a task with `scheduler.postTask` where the browser supports it so
+//! work like analytics, prefetching or warming caches doesn't block interaction. Other
+//! browsers run [Priority::Background] tasks with `requestIdleCallback` and everything
+//! else with a timeout.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     let src = self.next_page_url();
+//!     schedule_while_connected(element, Priority::Background, move || prefetch(&src))
+//!         .unwrap();
+//! }
+//! ```
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Function;
+use wasm_bindgen::prelude::{wasm_bindgen, Closure};
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::cleanup::on_disconnect;
+
+#[wasm_bindgen(inline_js = "export function postTask(callback, priority) {
+    if (self.scheduler && scheduler.postTask) {
+        const controller = new AbortController();
+        scheduler.postTask(callback, { priority: priority, signal: controller.signal })
+            .catch(function(err) { if (!controller.signal.aborted) { throw err; } });
+        return function() { controller.abort(); };
+    }
+    if (priority === \"background\" && self.requestIdleCallback) {
+        const id = requestIdleCallback(function() { callback(); });
+        return function() { cancelIdleCallback(id); };
+    }
+    const id = setTimeout(callback, 0);
+    return function() { clearTimeout(id); };
+}")]
+extern "C" {
+    #[wasm_bindgen(js_name = postTask, catch)]
+    fn post_task(callback: &JsValue, priority: &str) -> Result<Function, JsValue>;
+}
+
+/// How urgent a scheduled task is. These are the `postTask` priorities.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Work the user is waiting on, like responding to input.
+    UserBlocking,
+    /// Work the user will see soon but isn't waiting on.
+    #[default]
+    UserVisible,
+    /// Work the user won't notice, run when the browser is idle.
+    Background,
+}
+
+impl Priority {
+    /// The `postTask` name of the priority.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::UserBlocking => "user-blocking",
+            Priority::UserVisible => "user-visible",
+            Priority::Background => "background",
+        }
+    }
+}
+
+type Callback = Rc<RefCell<Option<Box<dyn FnOnce()>>>>;
+
+/// A scheduled task that can be cancelled until it runs.
+#[derive(Clone)]
+pub struct Task {
+    callback: Callback,
+    cancel: Function,
+}
+
+impl Task {
+    /// Whether the task hasn't run or been cancelled yet.
+    pub fn is_pending(&self) -> bool {
+        self.callback.borrow().is_some()
+    }
+
+    /// Keeps the task from running. Does nothing if it already ran.
+    pub fn cancel(&self) {
+        if self.callback.borrow_mut().take().is_some() {
+            let _ = self.cancel.call0(&JsValue::NULL);
+        }
+    }
+}
+
+/// Runs the task later with the priority.
+pub fn schedule<F: FnOnce() + 'static>(priority: Priority, task: F) -> Result<Task, JsValue> {
+    let callback: Callback = Rc::new(RefCell::new(Some(Box::new(task))));
+    let run = {
+        let callback = callback.clone();
+        Closure::once_into_js(move || {
+            // Take the task out first so it can schedule more tasks.
+            let task = callback.borrow_mut().take();
+            if let Some(task) = task {
+                task();
+            }
+        })
+    };
+    let cancel = post_task(&run, priority.as_str())?;
+    Ok(Task { callback, cancel })
+}
+
+/// Runs the task later with the priority unless the web component element gets
+/// disconnected from the DOM first.
+pub fn schedule_while_connected<F: FnOnce() + 'static>(
+    element: &Element,
+    priority: Priority,
+    task: F,
+) -> Result<Task, JsValue> {
+    let task = schedule(priority, task)?;
+    let pending = task.clone();
+    on_disconnect(element, move || pending.cancel());
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleanup::run_disconnect_hooks;
+    use std::cell::Cell;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_schedule_cancel() {
+        let ran = Rc::new(Cell::new(false));
+        let task = {
+            let ran = ran.clone();
+            schedule(Priority::Background, move || ran.set(true)).unwrap()
+        };
+        assert!(task.is_pending());
+        task.cancel();
+        assert!(!task.is_pending());
+        assert!(!ran.get());
+
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        let task = schedule_while_connected(&element, Priority::UserVisible, || {}).unwrap();
+        assert!(task.is_pending());
+        run_disconnect_hooks(&element);
+        assert!(!task.is_pending());
+    }
+}
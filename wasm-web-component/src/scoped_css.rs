@@ -0,0 +1,94 @@
+//! Scoped CSS for components rendering into the light DOM.
+//!
+//! The `scoped_style` parameter of the [`#[web_component]`](crate::web_component)
+//! attribute takes a stylesheet whose class selectors get a suffix unique to the
+//! component at compile time. The stylesheet is added to the document head once when
+//! the component is defined and the component renders its markup through
+//! [WebComponentDef::scope_classes](crate::WebComponentDef::scope_classes) or
+//! [WebComponentDef::scoped_class](crate::WebComponentDef::scoped_class) so its
+//! classes match.
+//!
+//! ```ignore
+//! #[web_component(scoped_style = ".title { font-weight: bold; }")]
+//! pub struct MyCard {}
+//!
+//! impl WebComponentBinding for MyCard {
+//!     fn connected(&self, element: &HtmlElement) {
+//!         element.set_inner_html("<h2 class='title'>Hello</h2>");
+//!         // The h2 now has the class `title-s1a2b3c`.
+//!         Self::scope_classes(element);
+//!     }
+//! }
+//! ```
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DocumentFragment, Element};
+
+use crate::environment::document;
+
+const SCOPE_ATTRIBUTE: &str = "data-wasm-scope";
+
+/// Returns the class name with the scope's suffix.
+pub fn scoped_class(name: &str, scope: &str) -> String {
+    format!("{}-{}", name, scope)
+}
+
+fn scope_element_classes(element: &Element, scope: &str) -> Result<(), JsValue> {
+    let suffix = format!("-{}", scope);
+    if let Some(classes) = element.get_attribute("class") {
+        let scoped = classes
+            .split_whitespace()
+            .map(|class| {
+                if class.ends_with(&suffix) {
+                    class.to_owned()
+                } else {
+                    scoped_class(class, scope)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        element.set_attribute("class", &scoped)?;
+    }
+    Ok(())
+}
+
+/// Adds the scope's suffix to the classes of the element and all of its descendants.
+/// Classes that already have the suffix are left alone.
+pub fn scope_classes(root: &Element, scope: &str) -> Result<(), JsValue> {
+    scope_element_classes(root, scope)?;
+    let nodes = root.query_selector_all("[class]")?;
+    for i in 0..nodes.length() {
+        if let Some(element) = nodes.item(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            scope_element_classes(&element, scope)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the scope's suffix to the classes of every element in the fragment.
+pub(crate) fn scope_fragment_classes(root: &DocumentFragment, scope: &str) -> Result<(), JsValue> {
+    let nodes = root.query_selector_all("[class]")?;
+    for i in 0..nodes.length() {
+        if let Some(element) = nodes.item(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            scope_element_classes(&element, scope)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the stylesheet to the document head unless a stylesheet was already added
+/// under the same key.
+pub fn inject_style(key: &str, css: &str) -> Result<(), JsValue> {
+    let document = document()?;
+    let head = document
+        .query_selector("head")?
+        .ok_or_else(|| JsValue::from_str("No document head"))?;
+    let selector = format!("style[{}=\"{}\"]", SCOPE_ATTRIBUTE, key);
+    if head.query_selector(&selector)?.is_some() {
+        return Ok(());
+    }
+    let style = document.create_element("style")?;
+    style.set_attribute(SCOPE_ATTRIBUTE, key)?;
+    style.set_text_content(Some(css));
+    head.append_child(&style)?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+//! Scroll locking for overlays and keeping scroll positions across renders.
+//!
+//! A [ScrollLock] stops the page from scrolling behind an overlay by hiding the body's
+//! overflow. The body gets padding in place of the scrollbar that disappears so the
+//! page doesn't shift sideways. Locks can overlap, the body is restored when the last
+//! one is released. [lock_scroll_while_connected] holds a lock for as long as a
+//! component element is connected.
+//!
+//! Rendering a shadow root again replaces its scroll containers and resets their
+//! scroll positions. [preserve_scroll] saves the positions of the elements marked with
+//! a `data-scroll-key` attribute before a render and restores them on the elements
+//! with the same key afterwards.
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     lock_scroll_while_connected(element).unwrap();
+//! }
+//!
+//! fn invalidated(&self, element: &HtmlElement) {
+//!     preserve_scroll(element, || Self::render(element, &self.html())).unwrap();
+//! }
+//! ```
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement, Node};
+
+use crate::cleanup::on_disconnect;
+use crate::environment::document;
+
+/// The attribute marking scroll containers whose positions [preserve_scroll] keeps.
+pub const SCROLL_KEY_ATTRIBUTE: &str = "data-scroll-key";
+
+/// The body's inline styles from before the first lock.
+struct BodyStyle {
+    overflow: String,
+    padding_right: String,
+}
+
+thread_local! {
+    static LOCKS: Cell<usize> = const { Cell::new(0) };
+    static SAVED: RefCell<Option<BodyStyle>> = const { RefCell::new(None) };
+}
+
+fn body() -> Result<HtmlElement, JsValue> {
+    document()?
+        .body()
+        .ok_or_else(|| JsValue::from_str("The document has no body"))
+}
+
+/// The width of the window's vertical scrollbar.
+fn scrollbar_width() -> f64 {
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return 0.0,
+    };
+    let inner_width = window
+        .inner_width()
+        .ok()
+        .and_then(|width| width.as_f64())
+        .unwrap_or(0.0);
+    let client_width = window
+        .document()
+        .and_then(|document| document.document_element())
+        .map(|root| root.client_width() as f64)
+        .unwrap_or(inner_width);
+    (inner_width - client_width).max(0.0)
+}
+
+fn lock_body() -> Result<(), JsValue> {
+    let body = body()?;
+    let style = body.style();
+    let width = scrollbar_width();
+    let computed_padding = web_sys::window()
+        .and_then(|window| window.get_computed_style(&body).ok().flatten())
+        .and_then(|computed| computed.get_property_value("padding-right").ok())
+        .and_then(|padding| padding.trim_end_matches("px").parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let saved = BodyStyle {
+        overflow: style.get_property_value("overflow")?,
+        padding_right: style.get_property_value("padding-right")?,
+    };
+    style.set_property("overflow", "hidden")?;
+    if width > 0.0 {
+        style.set_property("padding-right", &format!("{}px", computed_padding + width))?;
+    }
+    SAVED.with(|slot| *slot.borrow_mut() = Some(saved));
+    Ok(())
+}
+
+fn unlock_body() {
+    let saved = match SAVED.with(|slot| slot.borrow_mut().take()) {
+        Some(saved) => saved,
+        None => return,
+    };
+    if let Ok(body) = body() {
+        let style = body.style();
+        let _ = style.set_property("overflow", &saved.overflow);
+        let _ = style.set_property("padding-right", &saved.padding_right);
+    }
+}
+
+/// A held scroll lock. Cloning returns a handle to the same lock.
+#[derive(Clone, Debug)]
+pub struct ScrollLock {
+    held: Rc<Cell<bool>>,
+}
+
+impl ScrollLock {
+    /// Stops the page from scrolling until the lock is released.
+    pub fn acquire() -> Result<Self, JsValue> {
+        if LOCKS.with(Cell::get) == 0 {
+            lock_body()?;
+        }
+        LOCKS.with(|locks| locks.set(locks.get() + 1));
+        Ok(Self {
+            held: Rc::new(Cell::new(true)),
+        })
+    }
+
+    /// Returns true until the lock has been released.
+    pub fn is_held(&self) -> bool {
+        self.held.get()
+    }
+
+    /// Releases the lock. The page scrolls again once every lock is released.
+    pub fn release(&self) {
+        if !self.held.replace(false) {
+            return;
+        }
+        let locks = LOCKS.with(|locks| {
+            locks.set(locks.get() - 1);
+            locks.get()
+        });
+        if locks == 0 {
+            unlock_body();
+        }
+    }
+}
+
+/// Whether any scroll lock is held.
+pub fn scroll_locked() -> bool {
+    LOCKS.with(Cell::get) > 0
+}
+
+/// Holds a scroll lock until the element gets disconnected.
+pub fn lock_scroll_while_connected(element: &Element) -> Result<ScrollLock, JsValue> {
+    let lock = ScrollLock::acquire()?;
+    {
+        let lock = lock.clone();
+        on_disconnect(element, move || lock.release());
+    }
+    Ok(lock)
+}
+
+/// Scroll positions of the marked scroll containers in a tree.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScrollPositions {
+    positions: Vec<(String, i32, i32)>,
+}
+
+impl ScrollPositions {
+    /// Saves the positions of the elements with a `data-scroll-key` attribute in the
+    /// element's shadow root and light DOM.
+    pub fn save(element: &HtmlElement) -> Result<Self, JsValue> {
+        let mut positions = Vec::new();
+        for root in scroll_roots(element) {
+            let marked = marked_elements(&root)?;
+            for scroller in marked {
+                if let Some(key) = scroller.get_attribute(SCROLL_KEY_ATTRIBUTE) {
+                    positions.push((key, scroller.scroll_top(), scroller.scroll_left()));
+                }
+            }
+        }
+        Ok(Self { positions })
+    }
+
+    /// Scrolls the elements with the saved keys back to their positions.
+    pub fn restore(&self, element: &HtmlElement) -> Result<(), JsValue> {
+        for root in scroll_roots(element) {
+            for scroller in marked_elements(&root)? {
+                let key = scroller.get_attribute(SCROLL_KEY_ATTRIBUTE);
+                if let Some((_, top, left)) = self
+                    .positions
+                    .iter()
+                    .find(|(saved, _, _)| Some(saved) == key.as_ref())
+                {
+                    scroller.set_scroll_top(*top);
+                    scroller.set_scroll_left(*left);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the saved position of the key as `(top, left)`.
+    pub fn get(&self, key: &str) -> Option<(i32, i32)> {
+        self.positions
+            .iter()
+            .find(|(saved, _, _)| saved == key)
+            .map(|(_, top, left)| (*top, *left))
+    }
+}
+
+fn scroll_roots(element: &HtmlElement) -> Vec<Node> {
+    let mut roots: Vec<Node> = element.shadow_root().into_iter().map(Into::into).collect();
+    roots.push(element.clone().into());
+    roots
+}
+
+fn marked_elements(root: &Node) -> Result<Vec<Element>, JsValue> {
+    let selector = format!("[{}]", SCROLL_KEY_ATTRIBUTE);
+    let nodes = match root.dyn_ref::<Element>() {
+        Some(element) => element.query_selector_all(&selector)?,
+        None => root
+            .unchecked_ref::<web_sys::DocumentFragment>()
+            .query_selector_all(&selector)?,
+    };
+    Ok((0..nodes.length())
+        .filter_map(|index| nodes.item(index))
+        .filter_map(|node| node.dyn_into().ok())
+        .collect())
+}
+
+/// Runs the render and keeps the scroll positions of the element's marked scroll
+/// containers across it.
+pub fn preserve_scroll<R, F: FnOnce() -> R>(
+    element: &HtmlElement,
+    render: F,
+) -> Result<R, JsValue> {
+    let positions = ScrollPositions::save(element)?;
+    let result = render();
+    positions.restore(element)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_scroll_lock() {
+        let body = body().unwrap();
+        body.style().set_property("overflow", "auto").unwrap();
+        let first = ScrollLock::acquire().unwrap();
+        let second = ScrollLock::acquire().unwrap();
+        assert!(scroll_locked());
+        assert_eq!(
+            body.style().get_property_value("overflow").unwrap(),
+            "hidden"
+        );
+        first.release();
+        first.release();
+        assert!(scroll_locked());
+        second.release();
+        assert!(!scroll_locked());
+        assert!(!second.is_held());
+        assert_eq!(body.style().get_property_value("overflow").unwrap(), "auto");
+        body.style().remove_property("overflow").unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_preserve_scroll() {
+        let document = document().unwrap();
+        let body = document.body().unwrap();
+        let element: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        let html = "<div data-scroll-key='list' style='height: 50px; overflow: auto'>\
+            <div style='height: 500px'></div></div>";
+        element.set_inner_html(html);
+        body.append_child(&element).unwrap();
+        element.first_element_child().unwrap().set_scroll_top(120);
+
+        preserve_scroll(&element, || element.set_inner_html(html)).unwrap();
+        assert_eq!(element.first_element_child().unwrap().scroll_top(), 120);
+        body.remove_child(&element).unwrap();
+    }
+}
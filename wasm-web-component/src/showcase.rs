@@ -0,0 +1,281 @@
+//! A playground for the components defined through this crate.
+//!
+//! The `<wasm-component-showcase>` element lists every component in the
+//! [registry](crate::registry). Picking one instantiates it with an input for each of
+//! its observed attributes and logs the events it emits, i.e. the ones from `emits` and
+//! the `# Events` docs. With the `Docs` feature the component's description and the
+//! docs of its attributes are shown too.
+//!
+//! ```ignore
+//! MyButton::define_once();
+//! MyCard::define_once();
+//! Showcase::define_once();
+//! // <wasm-component-showcase></wasm-component-showcase>
+//! ```
+//!
+//! The showcase renders into its shadow root and exposes the `picker`, `description`,
+//! `attributes`, `stage` and `log` parts for styling.
+use js_sys::{Array, Reflect, JSON};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomEvent, Element, Event, HtmlElement, HtmlInputElement, HtmlSelectElement};
+
+use crate::environment::registry as custom_elements;
+use crate::introspection::{registry, registry_entry, ComponentInfo};
+use crate::{web_component, EventSubscription, WebComponentBinding, WebComponentDef};
+
+/// The attribute of the inputs holding the attribute they set.
+const ATTRIBUTE_INPUT: &str = "data-attribute";
+
+const SHELL: &str = "<select part='picker'><option value=''>Pick a component</option></select>\
+    <p part='description'></p>\
+    <fieldset part='attributes'></fieldset>\
+    <div part='stage'></div>\
+    <ol part='log'></ol>";
+
+/// The `<wasm-component-showcase>` element.
+#[web_component(
+    class_name = "WasmComponentShowcase",
+    element_name = "wasm-component-showcase",
+    observed_events = "['change', 'input']",
+    listen_on = "shadow"
+)]
+pub struct Showcase {
+    /// The instance of the picked component.
+    instance: Option<Element>,
+    /// The listeners for the events of the instance.
+    subscriptions: Vec<EventSubscription>,
+}
+
+fn part(element: &HtmlElement, name: &str) -> Option<Element> {
+    element
+        .shadow_root()?
+        .query_selector(&format!("[part='{}']", name))
+        .ok()
+        .flatten()
+}
+
+/// The components to pick from, every registered one but the showcase itself.
+fn showcased_components() -> Vec<ComponentInfo> {
+    let own = Showcase::tag_name();
+    registry()
+        .into_iter()
+        .filter(|info| info.element_name != own)
+        .collect()
+}
+
+/// Reads the observed attributes off the element's class.
+fn observed_attributes(element_name: &str) -> Vec<String> {
+    custom_elements()
+        .map(|registry| registry.get(element_name))
+        .and_then(|class| Reflect::get(&class, &JsValue::from_str("observedAttributes")))
+        .ok()
+        .filter(Array::is_array)
+        .map(|attributes| {
+            Array::from(&attributes)
+                .iter()
+                .filter_map(|name| name.as_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The event types the component emits or documents.
+fn event_types(info: &ComponentInfo) -> Vec<&'static str> {
+    let mut types = info.events.to_vec();
+    for (event_type, _) in info.docs.events {
+        if !types.contains(event_type) {
+            types.push(event_type);
+        }
+    }
+    types
+}
+
+fn log_event(log: &Element, event: &Event) -> Result<(), JsValue> {
+    let detail = event
+        .dyn_ref::<CustomEvent>()
+        .map(CustomEvent::detail)
+        .filter(|detail| !detail.is_undefined())
+        .and_then(|detail| JSON::stringify(&detail).ok())
+        .and_then(|detail| detail.as_string());
+    let entry = log.owner_document().unwrap().create_element("li")?;
+    entry.set_text_content(Some(&match detail {
+        Some(detail) => format!("{} {}", event.type_(), detail),
+        None => event.type_(),
+    }));
+    log.append_child(&entry)?;
+    Ok(())
+}
+
+impl Showcase {
+    fn render_picker(element: &HtmlElement) -> Result<(), JsValue> {
+        let picker = part(element, "picker").unwrap();
+        let document = element.owner_document().unwrap();
+        for info in showcased_components() {
+            let option = document.create_element("option")?;
+            option.set_attribute("value", &info.element_name)?;
+            option.set_text_content(Some(&format!(
+                "<{}> {}",
+                info.element_name, info.class_name
+            )));
+            picker.append_child(&option)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the showcased instance with a new instance of the element name.
+    fn show(&mut self, element: &HtmlElement, element_name: &str) -> Result<(), JsValue> {
+        self.subscriptions.clear();
+        self.instance = None;
+        let (description, attributes, stage, log) = match (
+            part(element, "description"),
+            part(element, "attributes"),
+            part(element, "stage"),
+            part(element, "log"),
+        ) {
+            (Some(description), Some(attributes), Some(stage), Some(log)) => {
+                (description, attributes, stage, log)
+            }
+            _ => return Ok(()),
+        };
+        for part in [&description, &attributes, &stage, &log] {
+            part.set_text_content(None);
+        }
+        let info = match registry_entry(element_name) {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        let document = element.owner_document().unwrap();
+        let instance = match info.extends {
+            Some(tag) => document.create_element_with_str(tag, element_name)?,
+            None => document.create_element(element_name)?,
+        };
+        description.set_text_content(Some(info.docs.description));
+        for name in observed_attributes(element_name) {
+            let label = document.create_element("label")?;
+            label.set_text_content(Some(&name));
+            if let Some(doc) = info.docs.attribute(&name) {
+                label.set_attribute("title", doc)?;
+            }
+            let input = document.create_element("input")?;
+            input.set_attribute(ATTRIBUTE_INPUT, &name)?;
+            label.append_child(&input)?;
+            attributes.append_child(&label)?;
+        }
+        for event_type in event_types(&info) {
+            let log = log.clone();
+            self.subscriptions.push(EventSubscription::listen(
+                &instance,
+                event_type,
+                move |event| {
+                    let _ = log_event(&log, &event);
+                },
+            )?);
+        }
+        stage.append_child(&instance)?;
+        self.instance = Some(instance);
+        Ok(())
+    }
+
+    /// Sets the attribute of the input on the instance, or removes it if the input is
+    /// empty.
+    fn set_attribute(&self, input: &HtmlInputElement) -> Result<(), JsValue> {
+        let (instance, name) = match (&self.instance, input.get_attribute(ATTRIBUTE_INPUT)) {
+            (Some(instance), Some(name)) => (instance, name),
+            _ => return Ok(()),
+        };
+        let value = input.value();
+        if value.is_empty() {
+            instance.remove_attribute(&name)
+        } else {
+            instance.set_attribute(&name, &value)
+        }
+    }
+}
+
+impl WebComponentBinding for Showcase {
+    fn connected(&self, element: &HtmlElement) {
+        Self::render(element, SHELL).unwrap();
+        Self::render_picker(element).unwrap();
+    }
+
+    fn disconnected_mut(&mut self, _element: &HtmlElement) {
+        self.subscriptions.clear();
+        self.instance = None;
+    }
+
+    fn handle_event_mut(&mut self, element: &HtmlElement, event: &Event) {
+        let target = event.target();
+        if let Some(picker) = target
+            .as_ref()
+            .and_then(|t| t.dyn_ref::<HtmlSelectElement>())
+        {
+            let _ = self.show(element, &picker.value());
+        } else if let Some(input) = target
+            .as_ref()
+            .and_then(|t| t.dyn_ref::<HtmlInputElement>())
+        {
+            let _ = self.set_attribute(input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::{window, CustomEventInit, EventInit};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[web_component(
+        class_name = "ShowcasedElement",
+        observed_attrs = "['label']",
+        emits = "['pressed']"
+    )]
+    pub struct ShowcasedElement {}
+    impl WebComponentBinding for ShowcasedElement {}
+
+    fn bubbling(event_type: &str) -> Event {
+        let init = EventInit::new();
+        init.set_bubbles(true);
+        Event::new_with_event_init_dict(event_type, &init).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_showcase() {
+        ShowcasedElement::define_once();
+        Showcase::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let showcase: HtmlElement = Showcase::create().unchecked_into();
+        body.append_child(&showcase).unwrap();
+
+        let picker: HtmlSelectElement = part(&showcase, "picker").unwrap().unchecked_into();
+        picker.set_value("showcased-element");
+        picker.dispatch_event(&bubbling("change")).unwrap();
+        let stage = part(&showcase, "stage").unwrap();
+        let instance = stage.first_element_child().unwrap();
+        assert_eq!(instance.tag_name(), "SHOWCASED-ELEMENT");
+
+        let input: HtmlInputElement = part(&showcase, "attributes")
+            .unwrap()
+            .query_selector("input")
+            .unwrap()
+            .unwrap()
+            .unchecked_into();
+        assert_eq!(
+            input.get_attribute(ATTRIBUTE_INPUT).as_deref(),
+            Some("label")
+        );
+        input.set_value("Press me");
+        input.dispatch_event(&bubbling("input")).unwrap();
+        assert_eq!(instance.get_attribute("label").as_deref(), Some("Press me"));
+
+        let init = CustomEventInit::new();
+        init.set_detail(&JsValue::from_f64(1.0));
+        let pressed = CustomEvent::new_with_event_init_dict("pressed", &init).unwrap();
+        instance.dispatch_event(&pressed).unwrap();
+        let log = part(&showcase, "log").unwrap();
+        assert_eq!(log.text_content().as_deref(), Some("pressed 1"));
+        body.remove_child(&showcase).unwrap();
+    }
+}
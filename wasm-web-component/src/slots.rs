@@ -0,0 +1,111 @@
+//! Inspecting the light DOM content slotted into a component.
+//!
+//! In a shadow root these are the nodes assigned to the `<slot>` with the name, or the
+//! unnamed slot if there is no name. Components rendering into the light DOM get the
+//! children projected into their emulated slots instead. See
+//! [render_light](crate::render_light).
+//!
+//! ```ignore
+//! fn connected(&self, element: &HtmlElement) {
+//!     let title = assigned_text(element, Some("title"));
+//!     for item in Slotted::of(element, None).iter::<HtmlLiElement>() {
+//!         // ...
+//!     }
+//! }
+//! ```
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement, Node};
+
+use crate::render::slotted_nodes;
+
+fn find_slot(root: &web_sys::ShadowRoot, name: &str) -> Option<Element> {
+    let slots = root.query_selector_all("slot").ok()?;
+    // Like the browser the first slot with the name gets the nodes.
+    (0..slots.length())
+        .filter_map(|i| slots.item(i))
+        .map(|node| node.unchecked_into::<Element>())
+        .find(|slot| slot.get_attribute("name").unwrap_or_default() == name)
+}
+
+fn assigned(slot: &Element, flatten: bool) -> Result<Vec<Node>, JsValue> {
+    let assigned_nodes: Function =
+        Reflect::get(slot, &JsValue::from_str("assignedNodes"))?.dyn_into()?;
+    let options = Object::new();
+    Reflect::set(
+        &options,
+        &JsValue::from_str("flatten"),
+        &JsValue::from_bool(flatten),
+    )?;
+    let nodes: Array = assigned_nodes.call1(slot, &options)?.dyn_into()?;
+    Ok(nodes.iter().map(|node| node.unchecked_into()).collect())
+}
+
+/// The nodes slotted into one of a component's slots.
+#[derive(Clone, Debug, Default)]
+pub struct Slotted {
+    nodes: Vec<Node>,
+}
+
+impl Slotted {
+    /// The nodes assigned to the slot with the name, or the unnamed slot if there is no
+    /// name.
+    pub fn of(element: &HtmlElement, slot_name: Option<&str>) -> Self {
+        Self::collect(element, slot_name, false)
+    }
+
+    /// Like [of](Self::of) but slots that are themselves slotted, e.g. by a component
+    /// wrapping this one, are replaced with the nodes assigned to them.
+    pub fn flattened(element: &HtmlElement, slot_name: Option<&str>) -> Self {
+        Self::collect(element, slot_name, true)
+    }
+
+    fn collect(element: &HtmlElement, slot_name: Option<&str>, flatten: bool) -> Self {
+        let name = slot_name.unwrap_or_default();
+        let nodes = match element.shadow_root() {
+            Some(root) => find_slot(&root, name)
+                .and_then(|slot| assigned(&slot, flatten).ok())
+                .unwrap_or_default(),
+            None => slotted_nodes(element, slot_name),
+        };
+        Self { nodes }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Iterates over the slotted nodes of the type, e.g. `Element` or
+    /// `HtmlImageElement`, skipping every other node.
+    pub fn iter<T: JsCast>(&self) -> impl Iterator<Item = T> + '_ {
+        self.nodes
+            .iter()
+            .filter_map(|node| node.clone().dyn_into::<T>().ok())
+    }
+
+    /// The combined text of the slotted nodes with surrounding whitespace trimmed.
+    pub fn text(&self) -> String {
+        self.nodes
+            .iter()
+            .filter_map(Node::text_content)
+            .collect::<String>()
+            .trim()
+            .to_owned()
+    }
+}
+
+/// The elements assigned to the slot with the name, or the unnamed slot if there is
+/// no name.
+pub fn assigned_elements(element: &HtmlElement, slot_name: Option<&str>) -> Vec<Element> {
+    Slotted::of(element, slot_name).iter().collect()
+}
+
+/// The text of the nodes assigned to the slot with the name, or the unnamed slot if
+/// there is no name.
+pub fn assigned_text(element: &HtmlElement, slot_name: Option<&str>) -> String {
+    Slotted::of(element, slot_name).text()
+}
@@ -0,0 +1,103 @@
+//! Serializing and restoring the state of every connected web component.
+//!
+//! Components opt in by setting `snapshot = true` in the
+//! [`#[web_component]`](crate::web_component) attribute and implementing
+//! [WebComponentSnapshot].
+use js_sys::{Function, Reflect};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement};
+
+use crate::instances::{connected_element_names, connected_instances};
+
+/// Snapshot and restore hooks for a web component's state.
+pub trait WebComponentSnapshot {
+    /// The serializable state of the component.
+    type State: Serialize + DeserializeOwned;
+
+    /// Returns the current state of the component.
+    fn snapshot(&self) -> Self::State;
+
+    /// Restores the component to a previously snapshotted state.
+    fn restore(&mut self, element: &HtmlElement, state: Self::State);
+}
+
+fn to_js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Encodes a component state. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn encode<S: Serialize>(state: &S) -> Result<String, JsValue> {
+    serde_json::to_string(state).map_err(to_js_error)
+}
+
+/// Decodes a component state. This is called by the generated shims for you.
+#[doc(hidden)]
+pub fn decode<S: DeserializeOwned>(state: &str) -> Result<S, JsValue> {
+    serde_json::from_str(state).map_err(to_js_error)
+}
+
+/// Elements are identified by their id attribute or else by the order they got connected in.
+fn instance_key(element: &Element, index: usize) -> String {
+    let id = element.id();
+    if id.is_empty() {
+        index.to_string()
+    } else {
+        format!("#{}", id)
+    }
+}
+
+fn call_method(element: &Element, name: &str, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let method: Function = Reflect::get(element, &JsValue::from_str(name))?.dyn_into()?;
+    match args {
+        [] => method.call0(element),
+        [arg] => method.call1(element, arg),
+        _ => Err(JsValue::from_str("Unsupported number of arguments")),
+    }
+}
+
+/// Serializes the state of every connected component that supports snapshots.
+///
+/// The result is a JSON object keyed by element name and then by the element's id
+/// or, for elements without an id, the order the element got connected in.
+pub fn snapshot_all() -> Result<String, JsValue> {
+    let mut snapshot = Map::new();
+    for element_name in connected_element_names() {
+        let mut states = Map::new();
+        for (index, element) in connected_instances(element_name).iter().enumerate() {
+            let state = call_method(element, "snapshotState", &[])?;
+            if let Some(state) = state.as_string() {
+                states.insert(instance_key(element, index), decode::<Value>(&state)?);
+            }
+        }
+        if !states.is_empty() {
+            snapshot.insert(element_name.to_owned(), Value::Object(states));
+        }
+    }
+    encode(&snapshot)
+}
+
+/// Restores a snapshot created by [snapshot_all] to the connected components.
+/// Components without a matching state in the snapshot are left alone.
+pub fn restore_all(snapshot: &str) -> Result<(), JsValue> {
+    let snapshot: Map<String, Value> = decode(snapshot)?;
+    for element_name in connected_element_names() {
+        let states = match snapshot.get(element_name) {
+            Some(Value::Object(states)) => states,
+            _ => continue,
+        };
+        for (index, element) in connected_instances(element_name).iter().enumerate() {
+            if let Some(state) = states.get(&instance_key(element, index)) {
+                call_method(
+                    element,
+                    "restoreState",
+                    &[JsValue::from_str(&encode(state)?)],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+//! A shared application store that components can subscribe to.
+//!
+//! Updates to the store are batched. Subscribers are notified once in a microtask
+//! after one or more updates and only when the part of the state they selected
+//! has changed.
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use crate::cleanup::on_disconnect;
+
+trait Subscriber<T> {
+    /// Returns true if the selected state has changed since the last call.
+    fn changed(&mut self, state: &T) -> bool;
+    /// Calls the subscriber callback with the current selected state.
+    fn notify(&mut self);
+}
+
+struct Selector<R, S, F> {
+    selector: S,
+    callback: F,
+    last: Option<R>,
+}
+
+impl<T, R, S, F> Subscriber<T> for Selector<R, S, F>
+where
+    R: PartialEq,
+    S: Fn(&T) -> R,
+    F: FnMut(&R),
+{
+    fn changed(&mut self, state: &T) -> bool {
+        let selected = (self.selector)(state);
+        if self.last.as_ref() == Some(&selected) {
+            return false;
+        }
+        self.last = Some(selected);
+        true
+    }
+
+    fn notify(&mut self) {
+        if let Some(selected) = &self.last {
+            (self.callback)(selected);
+        }
+    }
+}
+
+type SubscriberRef<T> = Rc<RefCell<dyn Subscriber<T>>>;
+
+struct StoreInner<T> {
+    state: RefCell<T>,
+    subscribers: RefCell<Vec<(u64, SubscriberRef<T>)>>,
+    next_id: Cell<u64>,
+    scheduled: Cell<bool>,
+}
+
+/// A shared piece of application state. Cloning a store returns a handle to
+/// the same state.
+pub struct Store<T> {
+    inner: Rc<StoreInner<T>>,
+}
+
+impl<T> Clone for Store<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: 'static> Store<T> {
+    /// Creates a new store with the initial state.
+    pub fn new(state: T) -> Self {
+        Self {
+            inner: Rc::new(StoreInner {
+                state: RefCell::new(state),
+                subscribers: RefCell::new(Vec::new()),
+                next_id: Cell::new(0),
+                scheduled: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Returns a value selected from the current state.
+    pub fn select<R, S: FnOnce(&T) -> R>(&self, selector: S) -> R {
+        selector(&self.inner.state.borrow())
+    }
+
+    /// Updates the state and schedules notification of the subscribers.
+    pub fn update<F: FnOnce(&mut T)>(&self, updater: F) {
+        updater(&mut self.inner.state.borrow_mut());
+        self.schedule();
+    }
+
+    /// Notifies the subscribers of any pending updates immediately instead of
+    /// waiting for the scheduled notification.
+    pub fn flush(&self) {
+        self.inner.scheduled.set(false);
+        let subscribers: Vec<SubscriberRef<T>> = self
+            .inner
+            .subscribers
+            .borrow()
+            .iter()
+            .map(|(_, s)| s.clone())
+            .collect();
+        for subscriber in subscribers {
+            let changed = subscriber.borrow_mut().changed(&self.inner.state.borrow());
+            if changed {
+                subscriber.borrow_mut().notify();
+            }
+        }
+    }
+
+    fn schedule(&self) {
+        if self.inner.scheduled.replace(true) {
+            return;
+        }
+        let weak = Rc::downgrade(&self.inner);
+        let flush = Closure::once_into_js(move || {
+            if let Some(inner) = weak.upgrade() {
+                let store = Store { inner };
+                if store.inner.scheduled.get() {
+                    store.flush();
+                }
+            }
+        });
+        if let Some(window) = web_sys::window() {
+            window.queue_microtask(flush.unchecked_ref());
+        }
+    }
+
+    /// Subscribes to the part of the state returned by the selector. The callback
+    /// gets called with the current selection immediately and then whenever the
+    /// selection changes. The subscription lasts until the returned
+    /// [StoreSubscription] is dropped.
+    pub fn subscribe<R, S, F>(&self, selector: S, callback: F) -> StoreSubscription<T>
+    where
+        R: PartialEq + 'static,
+        S: Fn(&T) -> R + 'static,
+        F: FnMut(&R) + 'static,
+    {
+        let subscriber = Rc::new(RefCell::new(Selector {
+            selector,
+            callback,
+            last: None,
+        }));
+        let id = self.inner.next_id.get();
+        self.inner.next_id.set(id + 1);
+        self.inner
+            .subscribers
+            .borrow_mut()
+            .push((id, subscriber.clone()));
+        subscriber.borrow_mut().changed(&self.inner.state.borrow());
+        subscriber.borrow_mut().notify();
+        StoreSubscription {
+            store: Rc::downgrade(&self.inner),
+            id,
+        }
+    }
+
+    /// Subscribes to the part of the state returned by the selector until the web
+    /// component element gets disconnected. Call this from your `connected` callback.
+    pub fn subscribe_while_connected<R, S, F>(&self, element: &Element, selector: S, callback: F)
+    where
+        R: PartialEq + 'static,
+        S: Fn(&T) -> R + 'static,
+        F: FnMut(&R) + 'static,
+    {
+        let subscription = self.subscribe(selector, callback);
+        on_disconnect(element, move || drop(subscription));
+    }
+}
+
+/// A subscription to a [Store]. Dropping it unsubscribes.
+pub struct StoreSubscription<T> {
+    store: Weak<StoreInner<T>>,
+    id: u64,
+}
+
+impl<T> Drop for StoreSubscription<T> {
+    fn drop(&mut self) {
+        if let Some(store) = self.store.upgrade() {
+            store
+                .subscribers
+                .borrow_mut()
+                .retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Default)]
+    struct AppState {
+        user: String,
+        clicks: u32,
+    }
+
+    #[wasm_bindgen_test]
+    fn test_store_notifies_on_selected_changes() {
+        let store = Store::new(AppState::default());
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let subscription = {
+            let seen = seen.clone();
+            store.subscribe(
+                |state: &AppState| state.user.clone(),
+                move |user: &String| seen.borrow_mut().push(user.clone()),
+            )
+        };
+        assert_eq!(*seen.borrow(), vec!["".to_owned()]);
+
+        store.update(|state| state.clicks += 1);
+        store.flush();
+        assert_eq!(seen.borrow().len(), 1);
+
+        store.update(|state| state.user = "jwall".to_owned());
+        store.update(|state| state.clicks += 1);
+        store.flush();
+        assert_eq!(*seen.borrow(), vec!["".to_owned(), "jwall".to_owned()]);
+        assert_eq!(store.select(|state| state.clicks), 2);
+
+        drop(subscription);
+        store.update(|state| state.user = "nobody".to_owned());
+        store.flush();
+        assert_eq!(seen.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! Cached template instantiation for repeated markup like list rows.
+//!
+//! A [TemplateCache] parses its html once into a prototype fragment. Every
+//! [TemplateCache::instantiate] deep clones the prototype, which is much faster than
+//! building the same tree with `createElement` calls or parsing the html again, and
+//! finds the elements marked with a `data-ref` attribute in the clone by their
+//! position in the tree instead of querying for them.
+//!
+//! ```ignore
+//! thread_local! {
+//!     static ROW: TemplateCache = TemplateCache::new(
+//!         "<li><span data-ref='name'></span><button data-ref='remove'>x</button></li>",
+//!     ).unwrap();
+//! }
+//!
+//! let row = ROW.with(|row| row.instantiate())?;
+//! row.get("name").unwrap().set_text_content(Some(&item.name));
+//! list.append_child(&row.into_fragment())?;
+//! ```
+use js_sys::Reflect;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DocumentFragment, Element, Node};
+
+use crate::environment::document;
+use crate::set_inner_html;
+
+/// The attribute marking elements to find in every instance.
+pub const REF_ATTRIBUTE: &str = "data-ref";
+
+/// A prototype fragment to clone and the positions of its refs.
+#[derive(Clone, Debug)]
+pub struct TemplateCache {
+    prototype: DocumentFragment,
+    /// The ref names with the child indexes leading to them from the fragment.
+    refs: Vec<(String, Vec<u32>)>,
+}
+
+/// A clone of a cached template with its refs resolved.
+#[derive(Clone, Debug)]
+pub struct TemplateInstance {
+    fragment: DocumentFragment,
+    refs: Vec<(String, Element)>,
+}
+
+fn collect_refs(node: &Node, path: &mut Vec<u32>, refs: &mut Vec<(String, Vec<u32>)>) {
+    if let Some(element) = node.dyn_ref::<Element>() {
+        if let Some(name) = element.get_attribute(REF_ATTRIBUTE) {
+            refs.push((name, path.clone()));
+            // Instances don't need the marker once the position is known.
+            let _ = element.remove_attribute(REF_ATTRIBUTE);
+        }
+    }
+    let children = node.child_nodes();
+    for index in 0..children.length() {
+        if let Some(child) = children.item(index) {
+            path.push(index);
+            collect_refs(&child, path, refs);
+            path.pop();
+        }
+    }
+}
+
+fn resolve_ref(fragment: &DocumentFragment, path: &[u32]) -> Result<Element, JsValue> {
+    let mut node: Node = fragment.clone().into();
+    for index in path {
+        node = node
+            .child_nodes()
+            .item(*index)
+            .ok_or_else(|| JsValue::from_str("The template changed after caching"))?;
+    }
+    node.dyn_into().map_err(Into::into)
+}
+
+impl TemplateCache {
+    /// Parses the html into the prototype.
+    pub fn new(html: &str) -> Result<Self, JsValue> {
+        let template = document()?.create_element("template")?;
+        set_inner_html(&template, html)?;
+        let content = Reflect::get(&template, &JsValue::from_str("content"))?;
+        Ok(Self::from_fragment(content.dyn_into()?))
+    }
+
+    /// Uses the fragment as the prototype. The fragment shouldn't be changed
+    /// afterwards.
+    pub fn from_fragment(prototype: DocumentFragment) -> Self {
+        let mut refs = Vec::new();
+        collect_refs(&prototype, &mut Vec::new(), &mut refs);
+        Self { prototype, refs }
+    }
+
+    /// Clones the prototype and resolves its refs.
+    pub fn instantiate(&self) -> Result<TemplateInstance, JsValue> {
+        let fragment: DocumentFragment = self.prototype.clone_node_with_deep(true)?.dyn_into()?;
+        let refs = self
+            .refs
+            .iter()
+            .map(|(name, path)| Ok((name.clone(), resolve_ref(&fragment, path)?)))
+            .collect::<Result<_, JsValue>>()?;
+        Ok(TemplateInstance { fragment, refs })
+    }
+}
+
+impl TemplateInstance {
+    /// Returns the element with the `data-ref` name.
+    pub fn get(&self, name: &str) -> Option<&Element> {
+        self.refs
+            .iter()
+            .find(|(ref_name, _)| ref_name == name)
+            .map(|(_, element)| element)
+    }
+
+    /// The cloned nodes. Appending the fragment moves them out of it.
+    pub fn fragment(&self) -> &DocumentFragment {
+        &self.fragment
+    }
+
+    /// Returns the cloned nodes to insert them.
+    pub fn into_fragment(self) -> DocumentFragment {
+        self.fragment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_instantiate() {
+        let cache = TemplateCache::new(
+            "<li><span data-ref='name'></span> <button data-ref='remove'>x</button></li>",
+        )
+        .unwrap();
+        let first = cache.instantiate().unwrap();
+        let second = cache.instantiate().unwrap();
+        first.get("name").unwrap().set_text_content(Some("one"));
+        assert_eq!(first.get("remove").unwrap().tag_name(), "BUTTON");
+        assert!(first.get("missing").is_none());
+        assert_eq!(
+            second.get("name").unwrap().text_content().as_deref(),
+            Some("")
+        );
+        assert!(!first.get("name").unwrap().has_attribute(REF_ATTRIBUTE));
+        assert_eq!(
+            first
+                .fragment()
+                .first_child()
+                .unwrap()
+                .text_content()
+                .as_deref(),
+            Some("one x")
+        );
+    }
+}
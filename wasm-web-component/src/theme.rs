@@ -0,0 +1,187 @@
+//! Typed themes over CSS custom properties.
+//!
+//! A theme is a serializable struct. Every field becomes a CSS custom property named
+//! after the theme's prefix and the field, with nested structs joined by dashes.
+//! Custom properties are inherited through shadow roots so a theme applied to the
+//! document root or a component host styles everything below it.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct AppTheme {
+//!     primary_color: String,
+//!     radius: String,
+//! }
+//!
+//! impl Theme for AppTheme {
+//!     const PREFIX: &'static str = "app";
+//! }
+//!
+//! // Sets --app-primary-color and --app-radius on the document element.
+//! apply_root_theme(&AppTheme { primary_color: "#336".into(), radius: "4px".into() })?;
+//! ```
+//!
+//! Components with `themed = true` in the [`#[web_component]`](crate::web_component)
+//! attribute get the [theme_changed](crate::WebComponentBinding::theme_changed)
+//! callback whenever a theme gets applied to them or one of their ancestors.
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CustomEvent, CustomEventInit, HtmlElement};
+
+/// The event dispatched on an element when a theme has been applied to it.
+pub const THEME_CHANGED_EVENT: &str = "wasm-theme-changed";
+
+/// A set of CSS custom properties.
+pub trait Theme: Serialize {
+    /// The prefix of the custom property names.
+    const PREFIX: &'static str;
+
+    /// Returns the custom property name for a field of the theme.
+    fn property_name(field: &str) -> String {
+        property_name(Self::PREFIX, field)
+    }
+}
+
+fn property_name(prefix: &str, field: &str) -> String {
+    let field = field.replace('_', "-");
+    if prefix.is_empty() {
+        format!("--{}", field)
+    } else {
+        format!("--{}-{}", prefix, field)
+    }
+}
+
+fn flatten(name: String, value: &Value, properties: &mut Vec<(String, Option<String>)>) {
+    match value {
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                flatten(
+                    format!("{}-{}", name, field.replace('_', "-")),
+                    value,
+                    properties,
+                );
+            }
+        }
+        Value::Null => properties.push((name, None)),
+        Value::String(s) => properties.push((name, Some(s.clone()))),
+        other => properties.push((name, Some(other.to_string()))),
+    }
+}
+
+/// Returns the custom properties of the theme. Properties for fields that
+/// serialize to null have no value.
+pub fn custom_properties<T: Theme>(theme: &T) -> Result<Vec<(String, Option<String>)>, JsValue> {
+    let value = serde_json::to_value(theme).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut properties = Vec::new();
+    match &value {
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                flatten(T::property_name(field), value, &mut properties);
+            }
+        }
+        _ => return Err(JsValue::from_str("A theme has to serialize to an object")),
+    }
+    Ok(properties)
+}
+
+/// Applies the theme to the element and everything below it, including shadow roots,
+/// and notifies the themed components affected.
+pub fn apply_theme<T: Theme>(element: &HtmlElement, theme: &T) -> Result<(), JsValue> {
+    let style = element.style();
+    for (name, value) in custom_properties(theme)? {
+        match value {
+            Some(value) => style.set_property(&name, &value)?,
+            None => {
+                style.remove_property(&name)?;
+            }
+        }
+    }
+    let init = CustomEventInit::new();
+    init.set_bubbles(true);
+    init.set_composed(true);
+    init.set_detail(&JsValue::from_str(T::PREFIX));
+    let event = CustomEvent::new_with_event_init_dict(THEME_CHANGED_EVENT, &init)?;
+    element.dispatch_event(&event)?;
+    Ok(())
+}
+
+/// Applies the theme to the whole document.
+pub fn apply_root_theme<T: Theme>(theme: &T) -> Result<(), JsValue> {
+    let root = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.document_element())
+        .ok_or_else(|| JsValue::from_str("No document element"))?;
+    apply_theme(root.unchecked_ref(), theme)
+}
+
+/// Returns the computed value of a theme's custom property for the element.
+pub fn theme_property<T: Theme>(element: &HtmlElement, field: &str) -> Option<String> {
+    web_sys::window()?
+        .get_computed_style(element)
+        .ok()
+        .flatten()?
+        .get_property_value(&T::property_name(field))
+        .ok()
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Serialize)]
+    struct Palette {
+        primary: String,
+    }
+
+    #[derive(Serialize)]
+    struct AppTheme {
+        font_size: u32,
+        accent: Option<String>,
+        palette: Palette,
+    }
+
+    impl Theme for AppTheme {
+        const PREFIX: &'static str = "app";
+    }
+
+    #[wasm_bindgen_test]
+    fn test_apply_theme() {
+        let theme = AppTheme {
+            font_size: 14,
+            accent: None,
+            palette: Palette {
+                primary: "red".to_owned(),
+            },
+        };
+        let mut properties = custom_properties(&theme).unwrap();
+        properties.sort();
+        assert_eq!(
+            properties,
+            vec![
+                ("--app-accent".to_owned(), None),
+                ("--app-font-size".to_owned(), Some("14".to_owned())),
+                ("--app-palette-primary".to_owned(), Some("red".to_owned())),
+            ]
+        );
+
+        let document = window().unwrap().document().unwrap();
+        let body = document.body().unwrap();
+        let parent: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        let child: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        parent.append_child(&child).unwrap();
+        body.append_child(&parent).unwrap();
+        apply_theme(&parent, &theme).unwrap();
+        assert_eq!(
+            theme_property::<AppTheme>(&child, "palette_primary"),
+            Some("red".to_owned())
+        );
+        assert_eq!(theme_property::<AppTheme>(&child, "accent"), None);
+        body.remove_child(&parent).unwrap();
+    }
+}
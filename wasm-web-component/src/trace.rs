@@ -0,0 +1,262 @@
+//! Tracing spans for lifecycle callbacks.
+//!
+//! With the `tracing` feature enabled the generated shims enter a
+//! [tracing](https://docs.rs/tracing) span around every lifecycle callback and event
+//! dispatch. The spans record the component's element name, the callback and the id
+//! of the element so the order callbacks ran in across components can be followed
+//! in the log. [init_console_tracing] installs a subscriber that logs to the browser
+//! console.
+//!
+//! ```ignore
+//! #[wasm_bindgen(start)]
+//! pub fn start() {
+//!     init_console_tracing(tracing::Level::DEBUG).unwrap();
+//! }
+//! ```
+//!
+//! Without the feature the spans compile to nothing. The same guards feed the
+//! [recorder](crate::start_recording) and the lifecycle metrics when the `Recorder` or
+//! `Metrics` features are enabled.
+#[cfg(feature = "tracing")]
+use std::io;
+
+#[cfg(feature = "tracing")]
+use tracing::{debug_span, span::EnteredSpan, Level, Metadata};
+#[cfg(feature = "tracing")]
+use tracing_subscriber::fmt::MakeWriter;
+use wasm_bindgen::JsValue;
+#[cfg(feature = "tracing")]
+use web_sys::console;
+use web_sys::{Event, HtmlElement};
+
+use crate::WebComponentDef;
+
+/// Guard for a lifecycle span. The span is exited when the guard is dropped.
+#[doc(hidden)]
+pub struct LifecycleSpan {
+    #[cfg(feature = "Metrics")]
+    _metrics: Option<crate::MetricsTimer>,
+    #[cfg(feature = "tracing")]
+    _span: EnteredSpan,
+}
+
+/// Records the lifecycle callback of the component if recording and enters its span.
+#[doc(hidden)]
+#[cfg_attr(
+    not(any(feature = "tracing", feature = "Recorder")),
+    allow(unused_variables)
+)]
+pub fn lifecycle_span<T: WebComponentDef>(
+    callback: &'static str,
+    element: &HtmlElement,
+) -> LifecycleSpan {
+    #[cfg(feature = "Recorder")]
+    crate::record_lifecycle(T::element_name(), callback, element);
+    LifecycleSpan {
+        #[cfg(feature = "Metrics")]
+        _metrics: match callback {
+            "init" => Some(crate::metrics_timer(
+                T::element_name(),
+                crate::Phase::Construction,
+            )),
+            "connected" => Some(crate::metrics_timer(
+                T::element_name(),
+                crate::Phase::Connect,
+            )),
+            _ => None,
+        },
+        #[cfg(feature = "tracing")]
+        _span: debug_span!(
+            "lifecycle",
+            component = T::element_name(),
+            callback,
+            element_id = %element.id()
+        )
+        .entered(),
+    }
+}
+
+/// Records the attribute change of the component if recording and enters the span
+/// for its `attribute_changed` callback.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "Recorder"), allow(unused_variables))]
+pub fn attribute_span<T: WebComponentDef>(
+    element: &HtmlElement,
+    name: &JsValue,
+    old_value: &JsValue,
+    new_value: &JsValue,
+) -> LifecycleSpan {
+    #[cfg(feature = "Recorder")]
+    crate::record_attribute_change(T::element_name(), element, name, old_value, new_value);
+    LifecycleSpan {
+        #[cfg(feature = "Metrics")]
+        _metrics: None,
+        #[cfg(feature = "tracing")]
+        _span: debug_span!(
+            "lifecycle",
+            component = T::element_name(),
+            callback = "attribute_changed",
+            attribute = %name.as_string().unwrap_or_default(),
+            element_id = %element.id()
+        )
+        .entered(),
+    }
+}
+
+/// Enters the span for an event dispatched to the component.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn event_span<T: WebComponentDef>(element: &HtmlElement, event: &Event) -> LifecycleSpan {
+    LifecycleSpan {
+        #[cfg(feature = "Metrics")]
+        _metrics: Some(crate::metrics_timer(T::element_name(), crate::Phase::Event)),
+        #[cfg(feature = "tracing")]
+        _span: debug_span!(
+            "event",
+            component = T::element_name(),
+            event_type = %event.type_(),
+            element_id = %element.id()
+        )
+        .entered(),
+    }
+}
+
+/// Writes a formatted log line to the console method for its level.
+#[cfg(feature = "tracing")]
+pub struct ConsoleWriter {
+    level: Level,
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "tracing")]
+impl io::Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for ConsoleWriter {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.buffer);
+        let line = line.trim_end();
+        if line.is_empty() {
+            return;
+        }
+        match self.level {
+            Level::ERROR => console::error_1(&line.into()),
+            Level::WARN => console::warn_1(&line.into()),
+            Level::INFO => console::info_1(&line.into()),
+            _ => console::debug_1(&line.into()),
+        }
+    }
+}
+
+/// Creates a [ConsoleWriter] for every log line.
+#[cfg(feature = "tracing")]
+pub struct MakeConsoleWriter;
+
+#[cfg(feature = "tracing")]
+impl<'a> MakeWriter<'a> for MakeConsoleWriter {
+    type Writer = ConsoleWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ConsoleWriter {
+            level: Level::INFO,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        ConsoleWriter {
+            level: *meta.level(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Installs a global subscriber that logs events at the level and above to the
+/// browser console. Fails if a global subscriber has already been installed.
+#[cfg(feature = "tracing")]
+pub fn init_console_tracing(level: Level) -> Result<(), JsValue> {
+    tracing_subscriber::fmt()
+        .with_writer(MakeConsoleWriter)
+        .with_max_level(level)
+        .with_ansi(false)
+        .without_time()
+        .try_init()
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::environment::document;
+    use crate::{web_component, WebComponentBinding};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Collects the formatted log lines.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[web_component(class_name = "TracedElement", observed_events = "['click']")]
+    pub struct TracedElement {}
+    impl WebComponentBinding for TracedElement {}
+
+    #[wasm_bindgen_test]
+    fn test_spans() {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .with_max_level(Level::DEBUG)
+            .with_ansi(false)
+            .without_time()
+            .with_span_events(FmtSpan::ENTER)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            TracedElement::define_once();
+            let element: HtmlElement = TracedElement::create().unchecked_into();
+            element.set_id("traced");
+            let body = document().unwrap().body().unwrap();
+            body.append_child(&element).unwrap();
+            element.click();
+            body.remove_child(&element).unwrap();
+        });
+        let log = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        let has = |span: &str, field: &str| {
+            lines
+                .iter()
+                .any(|line| line.contains(span) && line.contains(field) && line.contains("traced"))
+        };
+        assert!(has("lifecycle{", "connected"), "{}", log);
+        assert!(has("event{", "event_type=click"), "{}", log);
+        assert!(has("lifecycle{", "disconnected"), "{}", log);
+    }
+}
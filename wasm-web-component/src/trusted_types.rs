@@ -0,0 +1,114 @@
+//! Trusted Types support for HTML written by the library.
+//!
+//! Pages with a `require-trusted-types-for 'script'` Content Security Policy reject
+//! plain strings assigned to `innerHTML`. Every HTML write the library does, like
+//! rendering a component with [WebComponentDef::render](crate::WebComponentDef::render)
+//! or `attach_shadow`, goes through [set_inner_html] which wraps the HTML in a
+//! `TrustedHTML` value created by a policy.
+//!
+//! The library doesn't create a policy of its own. A policy that passes every string
+//! through would let any HTML past the page's Content Security Policy, which defeats
+//! the point of enforcing Trusted Types. Pages that enforce them have to provide a
+//! policy with [set_trusted_types_policy] before the first HTML write, otherwise the
+//! browser rejects the write and the error is returned. The policy decides how much
+//! it trusts the HTML. Sanitizing everything, e.g. with `sanitize_html` from the
+//! `Sanitize` feature, is the safest but strips markup of the components' own
+//! templates as well, like `<style>` and `<slot>` elements.
+//!
+//! ```ignore
+//! // A policy the page created with trustedTypes.createPolicy and exposed as appPolicy.
+//! let policy = js_sys::Reflect::get(&window, &JsValue::from_str("appPolicy"))?;
+//! set_trusted_types_policy(Some(policy));
+//! ```
+//!
+//! The library never evaluates javascript from strings. The element classes and the
+//! other javascript helpers are embedded as modules with `wasm_bindgen(inline_js)` so
+//! defining components needs neither `'unsafe-eval'` nor a `createScript` in the
+//! policy. The only script url the library loads is the worker script of
+//! `ComputeWorker`, which goes through the policy's `createScriptURL` with
+//! [trusted_script_url].
+use std::cell::RefCell;
+
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+thread_local! {
+    static POLICY: RefCell<Option<JsValue>> = const { RefCell::new(None) };
+}
+
+/// Sets the Trusted Types policy used for the library's HTML writes. `None` goes
+/// back to writing plain strings.
+pub fn set_trusted_types_policy(policy: Option<JsValue>) {
+    POLICY.with(|current| *current.borrow_mut() = policy);
+}
+
+fn create_trusted(method: &str, value: &str) -> Result<JsValue, JsValue> {
+    let policy = match POLICY.with(|policy| policy.borrow().clone()) {
+        Some(policy) => policy,
+        None => return Ok(JsValue::from_str(value)),
+    };
+    let create: Function = Reflect::get(&policy, &JsValue::from_str(method))?.dyn_into()?;
+    create.call1(&policy, &JsValue::from_str(value))
+}
+
+/// Returns the HTML as a `TrustedHTML` created by the current policy or as a plain
+/// string if no policy has been set.
+pub fn trusted_html(html: &str) -> Result<JsValue, JsValue> {
+    create_trusted("createHTML", html)
+}
+
+/// Returns the url as a `TrustedScriptURL` created by the current policy or as a
+/// plain string if no policy has been set.
+pub fn trusted_script_url(url: &str) -> Result<JsValue, JsValue> {
+    create_trusted("createScriptURL", url)
+}
+
+/// Sets the `innerHTML` of an element or shadow root through the Trusted Types
+/// policy.
+pub fn set_inner_html(target: &JsValue, html: &str) -> Result<(), JsValue> {
+    if !Reflect::set(
+        target,
+        &JsValue::from_str("innerHTML"),
+        &trusted_html(html)?,
+    )? {
+        return Err(JsValue::from_str("Failed to set innerHTML"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_set_inner_html() {
+        let element = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap();
+        set_inner_html(&element, "<b>bold</b>").unwrap();
+        assert_eq!(element.inner_html(), "<b>bold</b>");
+        assert_eq!(
+            trusted_html("<i></i>").unwrap().as_string().unwrap(),
+            "<i></i>"
+        );
+        assert!(set_inner_html(&js_sys::Object::freeze(&js_sys::Object::new()), "").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_set_trusted_types_policy() {
+        let policy = js_sys::Object::new();
+        let create_html = Function::new_with_args("html", "return html.toUpperCase();");
+        Reflect::set(&policy, &JsValue::from_str("createHTML"), &create_html).unwrap();
+        set_trusted_types_policy(Some(policy.into()));
+        let html = trusted_html("<b></b>");
+        set_trusted_types_policy(None);
+        assert_eq!(html.unwrap().as_string().unwrap(), "<B></B>");
+    }
+}
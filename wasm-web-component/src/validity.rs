@@ -0,0 +1,194 @@
+//! Constraint validation for form-associated web components.
+//!
+//! [WebComponentBinding::validate](crate::WebComponentBinding::validate) returns the
+//! [Validity] of the element's value. It is called when the element gets connected,
+//! when its value is set with [set_form_value], when its form gets reset and when the
+//! user tries to submit its form, and the result is set on the element's internals so
+//! the browser blocks submitting invalid forms and shows the message anchored at the
+//! control inside of the element.
+//!
+//! ```ignore
+//! fn validate(&self, element: &HtmlElement) -> Option<Validity> {
+//!     let input = element.shadow_root()?.query_selector("input").ok()??;
+//!     Some(
+//!         Validity::new()
+//!             .value_missing(element.has_attribute("required") && self.value.is_empty())
+//!             .custom_if(self.value == "admin", "That name is taken.")
+//!             .anchor(&input),
+//!     )
+//! }
+//! ```
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement};
+
+use crate::aria::internals;
+
+const FLAGS: [(&str, &str); 9] = [
+    ("valueMissing", "Please fill out this field."),
+    ("typeMismatch", "Please enter a valid value."),
+    ("patternMismatch", "Please match the requested format."),
+    ("tooLong", "Please shorten this text."),
+    ("tooShort", "Please lengthen this text."),
+    ("rangeUnderflow", "Value is too low."),
+    ("rangeOverflow", "Value is too high."),
+    ("stepMismatch", "Please enter a valid value."),
+    ("badInput", "Please enter a valid value."),
+];
+
+/// The validity of a form-associated element's value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Validity {
+    flags: Vec<&'static str>,
+    custom: bool,
+    message: Option<String>,
+    anchor: Option<Element>,
+}
+
+impl Validity {
+    /// A valid validity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn flag(mut self, flag: &'static str, cond: bool) -> Self {
+        if cond && !self.flags.contains(&flag) {
+            self.flags.push(flag);
+        }
+        self
+    }
+
+    /// A required value is missing.
+    pub fn value_missing(self, cond: bool) -> Self {
+        self.flag("valueMissing", cond)
+    }
+
+    /// The value isn't of the required type, e.g. an email address.
+    pub fn type_mismatch(self, cond: bool) -> Self {
+        self.flag("typeMismatch", cond)
+    }
+
+    /// The value doesn't match the required pattern.
+    pub fn pattern_mismatch(self, cond: bool) -> Self {
+        self.flag("patternMismatch", cond)
+    }
+
+    pub fn too_long(self, cond: bool) -> Self {
+        self.flag("tooLong", cond)
+    }
+
+    pub fn too_short(self, cond: bool) -> Self {
+        self.flag("tooShort", cond)
+    }
+
+    pub fn range_underflow(self, cond: bool) -> Self {
+        self.flag("rangeUnderflow", cond)
+    }
+
+    pub fn range_overflow(self, cond: bool) -> Self {
+        self.flag("rangeOverflow", cond)
+    }
+
+    pub fn step_mismatch(self, cond: bool) -> Self {
+        self.flag("stepMismatch", cond)
+    }
+
+    /// The user's input can't be converted to a value.
+    pub fn bad_input(self, cond: bool) -> Self {
+        self.flag("badInput", cond)
+    }
+
+    /// The value is invalid for a reason of the component's own.
+    pub fn custom(mut self, message: &str) -> Self {
+        self.custom = true;
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    /// Like [custom](Self::custom) if the condition holds.
+    pub fn custom_if(self, cond: bool, message: &str) -> Self {
+        if cond {
+            self.custom(message)
+        } else {
+            self
+        }
+    }
+
+    /// Sets the message shown for an invalid value. Defaults to a generic message for
+    /// the first failed constraint.
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    /// Sets the element the browser anchors the validation message at, usually the
+    /// control inside of the element's shadow root.
+    pub fn anchor(mut self, anchor: &Element) -> Self {
+        self.anchor = Some(anchor.clone());
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.flags.is_empty() && !self.custom
+    }
+
+    /// The message shown for an invalid value. Empty for a valid one.
+    pub fn validation_message(&self) -> String {
+        if self.is_valid() {
+            return String::new();
+        }
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        FLAGS
+            .iter()
+            .find(|(flag, _)| self.flags.contains(flag))
+            .map(|(_, message)| (*message).to_owned())
+            .unwrap_or_default()
+    }
+
+    /// Sets the validity on the form-associated element's internals.
+    pub fn apply(&self, element: &HtmlElement) -> Result<(), JsValue> {
+        let internals =
+            internals(element).ok_or_else(|| JsValue::from_str("The element has no internals"))?;
+        let set_validity: Function = Reflect::get(&internals, &JsValue::from_str("setValidity"))?
+            .dyn_into()
+            .map_err(|_| JsValue::from_str("The element isn't form-associated"))?;
+        let flags = Object::new();
+        for flag in &self.flags {
+            Reflect::set(&flags, &JsValue::from_str(flag), &JsValue::TRUE)?;
+        }
+        if self.custom {
+            Reflect::set(&flags, &JsValue::from_str("customError"), &JsValue::TRUE)?;
+        }
+        let message = JsValue::from_str(&self.validation_message());
+        match &self.anchor {
+            Some(anchor) if !self.is_valid() => {
+                set_validity.call3(&internals, &flags, &message, anchor)?
+            }
+            _ => set_validity.call2(&internals, &flags, &message)?,
+        };
+        Ok(())
+    }
+}
+
+/// Sets the value the form-associated element submits with its form and validates it.
+pub fn set_form_value(element: &HtmlElement, value: &JsValue) -> Result<(), JsValue> {
+    let internals =
+        internals(element).ok_or_else(|| JsValue::from_str("The element has no internals"))?;
+    let set_form_value: Function = Reflect::get(&internals, &JsValue::from_str("setFormValue"))?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("The element isn't form-associated"))?;
+    set_form_value.call1(&internals, value)?;
+    revalidate(element)
+}
+
+/// Calls the element's [validate](crate::WebComponentBinding::validate) callback, e.g.
+/// after something other than its value changed what is valid.
+pub fn revalidate(element: &HtmlElement) -> Result<(), JsValue> {
+    let run: Function = Reflect::get(element, &JsValue::from_str("runValidation"))?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("The element isn't a web component"))?;
+    run.call0(element)?;
+    Ok(())
+}
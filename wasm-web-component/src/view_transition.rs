@@ -0,0 +1,96 @@
+//! View Transitions API integration.
+//!
+//! DOM updates wrapped in [with_view_transition] get animated by the browser with
+//! `document.startViewTransition` where it is supported and are applied immediately
+//! everywhere else. Components with `view_transition = true` in the
+//! [`#[web_component]`](crate::web_component) attribute run their `attribute_changed`
+//! callbacks inside of a view transition and get a unique `view-transition-name`
+//! while connected so they animate independently of the rest of the page.
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlElement;
+
+const VIEW_TRANSITION_NAME: &str = "view-transition-name";
+
+/// Returns true if the browser supports view transitions.
+pub fn view_transitions_supported() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| Reflect::get(&d, &JsValue::from_str("startViewTransition")).ok())
+        .map(|f| f.is_function())
+        .unwrap_or(false)
+}
+
+/// Applies the DOM updates inside of a view transition. Browsers without view
+/// transitions apply the updates immediately. Browsers with them call the update
+/// asynchronously once the current state of the page has been captured.
+///
+/// Returns a promise that resolves once the updates have been applied.
+pub fn with_view_transition<F: FnOnce() + 'static>(update: F) -> Promise {
+    let document = match web_sys::window().and_then(|w| w.document()) {
+        Some(document) => document,
+        None => {
+            update();
+            return Promise::resolve(&JsValue::UNDEFINED);
+        }
+    };
+    let start = Reflect::get(&document, &JsValue::from_str("startViewTransition"))
+        .ok()
+        .and_then(|f| f.dyn_into::<Function>().ok());
+    let start = match start {
+        Some(start) => start,
+        None => {
+            update();
+            return Promise::resolve(&JsValue::UNDEFINED);
+        }
+    };
+    let update = Closure::once_into_js(update);
+    match start.call1(&document, &update) {
+        Ok(transition) => Reflect::get(&transition, &JsValue::from_str("updateCallbackDone"))
+            .map(|done| done.unchecked_into())
+            .unwrap_or_else(|e| Promise::reject(&e)),
+        Err(e) => Promise::reject(&e),
+    }
+}
+
+/// Sets or clears the element's `view-transition-name`. Names have to be unique in
+/// the document for the transition to run.
+pub fn set_view_transition_name(element: &HtmlElement, name: Option<&str>) -> Result<(), JsValue> {
+    let style = element.style();
+    match name {
+        Some(name) => style.set_property(VIEW_TRANSITION_NAME, name),
+        None => style.remove_property(VIEW_TRANSITION_NAME).map(|_| ()),
+    }
+}
+
+/// Returns the element's `view-transition-name` if it has one.
+pub fn view_transition_name(element: &HtmlElement) -> Option<String> {
+    element
+        .style()
+        .get_property_value(VIEW_TRANSITION_NAME)
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_view_transition_name() {
+        let document = window().unwrap().document().unwrap();
+        let element: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+        assert_eq!(view_transition_name(&element), None);
+        set_view_transition_name(&element, Some("card-1")).unwrap();
+        if view_transitions_supported() {
+            assert_eq!(view_transition_name(&element), Some("card-1".to_owned()));
+        }
+        set_view_transition_name(&element, None).unwrap();
+        assert_eq!(view_transition_name(&element), None);
+    }
+}
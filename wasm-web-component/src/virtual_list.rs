@@ -0,0 +1,466 @@
+//! A virtual scrolling list element built with this crate's own macros.
+//!
+//! Only the rows that are visible in the list's viewport exist in the DOM. Rows that
+//! scroll out of view get recycled for the rows scrolling into view and rows are keyed
+//! so that a row which stays visible is never rendered twice.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::Reflect;
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, HtmlElement, ResizeObserver};
+
+use crate::{web_component, WebComponentBinding};
+
+const DEFAULT_ITEM_HEIGHT: i32 = 24;
+const ID_PROPERTY: &str = "virtualListId";
+
+/// Supplies the rows for a [VirtualList].
+pub trait ItemRenderer {
+    /// The total number of items in the list.
+    fn item_count(&self) -> usize;
+
+    /// A stable key for the item at index. Rows for keys that stay visible
+    /// across renders are not rendered again. Defaults to the index.
+    fn key(&self, index: usize) -> String {
+        index.to_string()
+    }
+
+    /// Creates a new empty row element. Rows get recycled so this is only
+    /// called when there is no spare row available.
+    fn create_row(&self) -> Element {
+        web_sys::window()
+            .expect("Failed to get window")
+            .document()
+            .expect("Failed to get document")
+            .create_element("div")
+            .expect("Failed to create row")
+    }
+
+    /// Renders the item at index into the row.
+    fn render(&self, index: usize, row: &Element);
+}
+
+#[derive(Default)]
+struct ListState {
+    renderer: Option<Box<dyn ItemRenderer>>,
+    element: Option<HtmlElement>,
+    spacer: Option<HtmlElement>,
+    rows: HashMap<String, HtmlElement>,
+    spare_rows: Vec<HtmlElement>,
+    item_height: i32,
+    observer: Option<(ResizeObserver, Closure<dyn FnMut()>)>,
+    rendering: bool,
+    // Set when the list is updated while it renders, with whether every row has to
+    // be rendered again.
+    pending: Option<bool>,
+}
+
+impl std::fmt::Debug for ListState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ListState")
+            .field("visible_rows", &self.rows.len())
+            .field("spare_rows", &self.spare_rows.len())
+            .field("item_height", &self.item_height)
+            .finish()
+    }
+}
+
+impl ListState {
+    fn item_height(&self) -> i32 {
+        if self.item_height > 0 {
+            self.item_height
+        } else {
+            DEFAULT_ITEM_HEIGHT
+        }
+    }
+
+    /// Recycles every visible row so the next render renders all of them again.
+    fn invalidate(&mut self) {
+        for (_, row) in self.rows.drain() {
+            let _ = row.style().set_property("display", "none");
+            self.spare_rows.push(row);
+        }
+    }
+}
+
+/// Renders the visible rows of the list, every one of them again with invalidate.
+///
+/// The renderer is called without the state borrowed so it can update the list
+/// itself. Those updates are rendered once the current render is done.
+fn update(state: &Rc<RefCell<ListState>>, invalidate: bool) -> Result<(), JsValue> {
+    {
+        let mut state = state.borrow_mut();
+        if state.rendering {
+            state.pending = Some(invalidate || state.pending.unwrap_or(false));
+            return Ok(());
+        }
+        state.rendering = true;
+    }
+    let mut invalidate = invalidate;
+    loop {
+        if invalidate {
+            state.borrow_mut().invalidate();
+        }
+        let result = render(state);
+        let mut state = state.borrow_mut();
+        match state.pending.take() {
+            Some(pending) if result.is_ok() => invalidate = pending,
+            _ => {
+                state.rendering = false;
+                return result;
+            }
+        }
+    }
+}
+
+fn render(state: &Rc<RefCell<ListState>>) -> Result<(), JsValue> {
+    let (element, renderer, item_height) = {
+        let mut state = state.borrow_mut();
+        match (state.element.clone(), state.renderer.take()) {
+            (Some(element), Some(renderer)) => (element, renderer, state.item_height()),
+            (_, renderer) => {
+                state.renderer = renderer;
+                return Ok(());
+            }
+        }
+    };
+    let result = render_rows(state, &element, renderer.as_ref(), item_height);
+    let mut state = state.borrow_mut();
+    // The renderer may have been replaced while it was rendering.
+    if state.renderer.is_none() {
+        state.renderer = Some(renderer);
+    }
+    result
+}
+
+fn render_rows(
+    state: &Rc<RefCell<ListState>>,
+    element: &HtmlElement,
+    renderer: &dyn ItemRenderer,
+    item_height: i32,
+) -> Result<(), JsValue> {
+    let count = renderer.item_count();
+    // Long lists of tall rows are taller than an i32 of pixels.
+    let offset = |index: usize| index as f64 * item_height as f64;
+    let spacer = state.borrow().spacer.clone();
+    if let Some(spacer) = spacer {
+        spacer
+            .style()
+            .set_property("height", &format!("{}px", offset(count)))?;
+    }
+    let scroll_top = element.scroll_top().max(0);
+    let first = (scroll_top / item_height) as usize;
+    let visible = (element.client_height() / item_height) as usize + 2;
+    let last = first.saturating_add(visible).min(count);
+
+    let mut rows = HashMap::with_capacity(last.saturating_sub(first));
+    for index in first..last {
+        let key = renderer.key(index);
+        let existing = state.borrow_mut().rows.remove(&key);
+        let row = match existing {
+            Some(row) => row,
+            None => {
+                let spare = state.borrow_mut().spare_rows.pop();
+                let row = match spare {
+                    Some(row) => row,
+                    None => {
+                        let row: HtmlElement = renderer.create_row().dyn_into()?;
+                        let style = row.style();
+                        style.set_property("position", "absolute")?;
+                        style.set_property("left", "0")?;
+                        style.set_property("right", "0")?;
+                        element.append_child(&row)?;
+                        row
+                    }
+                };
+                renderer.render(index, &row);
+                row
+            }
+        };
+        let style = row.style();
+        style.set_property("top", &format!("{}px", offset(index)))?;
+        style.set_property("height", &format!("{}px", item_height))?;
+        style.remove_property("display")?;
+        rows.insert(key, row);
+    }
+    let mut state = state.borrow_mut();
+    let state = &mut *state;
+    for (_, row) in state.rows.drain() {
+        row.style().set_property("display", "none")?;
+        state.spare_rows.push(row);
+    }
+    state.rows = rows;
+    Ok(())
+}
+
+thread_local! {
+    static LISTS: RefCell<HashMap<u32, Rc<RefCell<ListState>>>> = RefCell::new(HashMap::new());
+    static NEXT_ID: RefCell<u32> = const { RefCell::new(0) };
+}
+
+fn state_for(element: &Element) -> Result<Rc<RefCell<ListState>>, JsValue> {
+    let id = Reflect::get(element, &JsValue::from_str(ID_PROPERTY))?
+        .as_f64()
+        .ok_or_else(|| JsValue::from_str("Element is not a wasm-virtual-list"))?
+        as u32;
+    LISTS
+        .with(|lists| lists.borrow().get(&id).cloned())
+        .ok_or_else(|| JsValue::from_str("wasm-virtual-list is disconnected or disposed"))
+}
+
+/// A `<wasm-virtual-list>` element that renders only the visible rows of a
+/// potentially very long list.
+///
+/// Every row has the same height which is set with the `item-height` attribute
+/// in pixels. Supply the rows with [VirtualList::set_renderer]. A list that got
+/// disconnected can't be updated until it is connected again.
+///
+/// ```ignore
+/// use wasm_web_component::{ItemRenderer, VirtualList};
+///
+/// struct Numbers(usize);
+/// impl ItemRenderer for Numbers {
+///     fn item_count(&self) -> usize {
+///         self.0
+///     }
+///
+///     fn render(&self, index: usize, row: &web_sys::Element) {
+///         row.set_text_content(Some(&index.to_string()));
+///     }
+/// }
+///
+/// VirtualList::define_once();
+/// let list = VirtualList::create_with_attrs(&[("item-height", "20")]);
+/// VirtualList::set_renderer(&list, Numbers(100_000)).unwrap();
+/// ```
+#[web_component(
+    class_name = "WasmVirtualList",
+    element_name = "wasm-virtual-list",
+    observed_attrs = "['item-height']",
+    observed_events = "['scroll']"
+)]
+pub struct VirtualList {
+    id: u32,
+    state: Rc<RefCell<ListState>>,
+}
+
+impl VirtualList {
+    /// Sets the renderer for the list element and renders it.
+    pub fn set_renderer<R: ItemRenderer + 'static>(
+        element: &Element,
+        renderer: R,
+    ) -> Result<(), JsValue> {
+        let state = state_for(element)?;
+        state.borrow_mut().renderer = Some(Box::new(renderer));
+        update(&state, true)
+    }
+
+    /// Renders every visible row of the list element again. Call this after the
+    /// items of the renderer have changed.
+    pub fn refresh(element: &Element) -> Result<(), JsValue> {
+        update(&state_for(element)?, true)
+    }
+}
+
+impl Drop for VirtualList {
+    fn drop(&mut self) {
+        LISTS.with(|lists| lists.borrow_mut().remove(&self.id));
+    }
+}
+
+impl WebComponentBinding for VirtualList {
+    fn init_mut(&mut self, element: &HtmlElement) {
+        self.id = NEXT_ID.with(|id| {
+            let mut id = id.borrow_mut();
+            *id += 1;
+            *id
+        });
+        let _ = Reflect::set(
+            element,
+            &JsValue::from_str(ID_PROPERTY),
+            &JsValue::from(self.id),
+        );
+        LISTS.with(|lists| lists.borrow_mut().insert(self.id, self.state.clone()));
+    }
+
+    fn connected(&self, element: &HtmlElement) {
+        LISTS.with(|lists| lists.borrow_mut().insert(self.id, self.state.clone()));
+        let mut state = self.state.borrow_mut();
+        if state.spacer.is_none() {
+            let style = element.style();
+            let _ = style.set_property("display", "block");
+            let _ = style.set_property("position", "relative");
+            let _ = style.set_property("overflow-y", "auto");
+            let spacer: HtmlElement = element
+                .owner_document()
+                .expect("Failed to get document")
+                .create_element("div")
+                .expect("Failed to create spacer")
+                .unchecked_into();
+            element
+                .append_child(&spacer)
+                .expect("Failed to append spacer");
+            state.spacer = Some(spacer);
+        }
+        state.element = Some(element.clone());
+        let weak_state = Rc::downgrade(&self.state);
+        let on_resize = Closure::<dyn FnMut()>::new(move || {
+            if let Some(state) = weak_state.upgrade() {
+                let _ = update(&state, false);
+            }
+        });
+        if let Ok(observer) = ResizeObserver::new(on_resize.as_ref().unchecked_ref()) {
+            observer.observe(element);
+            state.observer = Some((observer, on_resize));
+        }
+        drop(state);
+        let _ = update(&self.state, false);
+    }
+
+    fn disconnected(&self, _element: &HtmlElement) {
+        // Nothing may keep a disconnected list alive so it can get garbage collected.
+        let mut state = self.state.borrow_mut();
+        if let Some((observer, _)) = state.observer.take() {
+            observer.disconnect();
+        }
+        state.element = None;
+        LISTS.with(|lists| lists.borrow_mut().remove(&self.id));
+    }
+
+    fn attribute_changed(
+        &self,
+        _element: &HtmlElement,
+        _name: JsValue,
+        _old_value: JsValue,
+        new_value: JsValue,
+    ) {
+        self.state.borrow_mut().item_height = new_value
+            .as_string()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ITEM_HEIGHT);
+        let _ = update(&self.state, true);
+    }
+
+    fn handle_event(&self, _element: &HtmlElement, _event: &Event) {
+        let _ = update(&self.state, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use web_sys::window;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    struct Numbers(usize);
+
+    impl ItemRenderer for Numbers {
+        fn item_count(&self) -> usize {
+            self.0
+        }
+
+        fn render(&self, index: usize, row: &Element) {
+            row.set_text_content(Some(&index.to_string()));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_virtual_list_renders_visible_rows() {
+        VirtualList::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let list = VirtualList::builder()
+            .attr("item-height", "20")
+            .attr("style", "height: 100px")
+            .mount(&body)
+            .expect("Failed to mount list");
+        VirtualList::set_renderer(&list, Numbers(100_000)).expect("Failed to set renderer");
+        // The spacer plus a handful of rows rather than 100k rows.
+        let children = list.child_element_count();
+        assert!(children > 1 && children < 10);
+        assert_eq!(
+            list.first_element_child()
+                .unwrap()
+                .next_element_sibling()
+                .unwrap()
+                .text_content()
+                .unwrap(),
+            "0"
+        );
+
+        list.set_scroll_top(2000);
+        VirtualList::refresh(&list).expect("Failed to refresh list");
+        assert_eq!(list.child_element_count(), children);
+        body.remove_child(&list).unwrap();
+        assert!(VirtualList::refresh(&list).is_err());
+        body.append_child(&list).unwrap();
+        VirtualList::refresh(&list).expect("Failed to refresh reconnected list");
+        body.remove_child(&list).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_virtual_list_tall_lists() {
+        VirtualList::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let list = VirtualList::builder()
+            .attr("item-height", "20")
+            .attr("style", "height: 100px")
+            .mount(&body)
+            .expect("Failed to mount list");
+        VirtualList::set_renderer(&list, Numbers(200_000_000)).expect("Failed to set renderer");
+        let spacer: HtmlElement = list.first_element_child().unwrap().unchecked_into();
+        assert_eq!(
+            spacer.style().get_property_value("height").unwrap(),
+            "4000000000px"
+        );
+        body.remove_child(&list).unwrap();
+    }
+
+    struct Refreshing {
+        list: Element,
+        refreshed: std::cell::Cell<bool>,
+    }
+
+    impl ItemRenderer for Refreshing {
+        fn item_count(&self) -> usize {
+            10
+        }
+
+        fn render(&self, index: usize, row: &Element) {
+            if !self.refreshed.replace(true) {
+                VirtualList::refresh(&self.list).expect("Failed to refresh list");
+            }
+            row.set_text_content(Some(&index.to_string()));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_virtual_list_refresh_while_rendering() {
+        VirtualList::define_once();
+        let body = window().unwrap().document().unwrap().body().unwrap();
+        let list = VirtualList::builder()
+            .attr("item-height", "20")
+            .attr("style", "height: 100px")
+            .mount(&body)
+            .expect("Failed to mount list");
+        let renderer = Refreshing {
+            list: list.clone(),
+            refreshed: Default::default(),
+        };
+        VirtualList::set_renderer(&list, renderer).expect("Failed to set renderer");
+        assert_eq!(
+            list.first_element_child()
+                .unwrap()
+                .next_element_sibling()
+                .unwrap()
+                .text_content()
+                .unwrap(),
+            "0"
+        );
+        body.remove_child(&list).unwrap();
+    }
+}
@@ -0,0 +1,369 @@
+//! Offloading heavy computation to a dedicated web worker.
+//!
+//! The worker runs the same wasm module as the page. Export an entry point from your
+//! crate that registers your [ComputeHandler]s and calls [serve]:
+//!
+//! ```ignore
+//! #[wasm_bindgen]
+//! pub fn worker_main() {
+//!     register_handler::<Primes>();
+//!     serve().unwrap();
+//! }
+//! ```
+//!
+//! Then spawn the worker on the page with the url of your wasm-pack generated module
+//! and send it work with [ComputeWorker::compute].
+//!
+//! ```ignore
+//! let worker = ComputeWorker::spawn("./pkg/my_app.js", "worker_main")?;
+//! worker.compute_for::<Primes, _>(&element, &10_000, |element, primes| {
+//!     // render the result
+//! })?;
+//! ```
+//!
+//! If the worker fails to load the module or to start, every pending and later
+//! computation gets called back with the error.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Blob, BlobPropertyBag, DedicatedWorkerGlobalScope, Element, Event, MessageEvent, Url, Worker,
+    WorkerOptions, WorkerType,
+};
+
+/// A computation that can run in a [ComputeWorker].
+pub trait ComputeHandler: 'static {
+    /// The name identifying the handler in messages to the worker.
+    const NAME: &'static str;
+
+    /// The input to the computation.
+    type Input: Serialize + DeserializeOwned;
+
+    /// The result of the computation.
+    type Output: Serialize + DeserializeOwned;
+
+    /// Runs the computation. This is called inside of the worker.
+    fn compute(input: Self::Input) -> Self::Output;
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    id: u32,
+    handler: String,
+    input: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+    id: u32,
+    #[serde(default)]
+    output: Option<Value>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+type Handler = Box<dyn Fn(Value) -> Result<Value, String>>;
+
+thread_local! {
+    static HANDLERS: RefCell<HashMap<&'static str, Handler>> = RefCell::new(HashMap::new());
+}
+
+/// Registers a compute handler in the worker.
+pub fn register_handler<H: ComputeHandler>() {
+    let handler: Handler = Box::new(|input| {
+        let input: H::Input = serde_json::from_value(input).map_err(|e| e.to_string())?;
+        serde_json::to_value(H::compute(input)).map_err(|e| e.to_string())
+    });
+    HANDLERS.with(|handlers| handlers.borrow_mut().insert(H::NAME, handler));
+}
+
+fn handle_request(request: &str) -> String {
+    let response = match serde_json::from_str::<Request>(request) {
+        Ok(request) => {
+            let result =
+                HANDLERS.with(
+                    |handlers| match handlers.borrow().get(request.handler.as_str()) {
+                        Some(handler) => handler(request.input),
+                        None => Err(format!("No compute handler named {}", request.handler)),
+                    },
+                );
+            match result {
+                Ok(output) => Response {
+                    id: request.id,
+                    output: Some(output),
+                    error: None,
+                },
+                Err(error) => Response {
+                    id: request.id,
+                    output: None,
+                    error: Some(error),
+                },
+            }
+        }
+        Err(e) => Response {
+            id: 0,
+            output: None,
+            error: Some(e.to_string()),
+        },
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// Starts answering compute requests. Call this from your worker entry point after
+/// registering your handlers.
+pub fn serve() -> Result<(), JsValue> {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().dyn_into()?;
+    let on_message = {
+        let scope = scope.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Some(request) = event.data().as_string() {
+                let _ = scope.post_message(&JsValue::from_str(&handle_request(&request)));
+            }
+        })
+    };
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The handler lives as long as the worker does.
+    on_message.forget();
+    Ok(())
+}
+
+type Pending = Rc<RefCell<HashMap<u32, Box<dyn FnOnce(Result<Value, String>)>>>>;
+
+/// Calls every pending callback with the error.
+fn fail_pending(pending: &Pending, error: &str) {
+    let callbacks: Vec<_> = pending.borrow_mut().drain().collect();
+    for (_, callback) in callbacks {
+        callback(Err(error.to_owned()));
+    }
+}
+
+/// A dedicated worker running your wasm module.
+///
+/// The worker is terminated when this is dropped.
+pub struct ComputeWorker {
+    worker: Worker,
+    pending: Pending,
+    next_id: Cell<u32>,
+    /// Why the worker failed to start, if it did.
+    failure: Rc<RefCell<Option<String>>>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(Event)>,
+}
+
+impl ComputeWorker {
+    /// Spawns a worker that loads the wasm-pack generated module at the url and calls
+    /// the exported entry function. Messages sent before the module has loaded are
+    /// queued.
+    pub fn spawn(module_url: &str, entry: &str) -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+        // The worker script is loaded from a blob url so the module url has to be absolute.
+        let module_url = Url::new_with_base(module_url, &window.location().href()?)?.href();
+        // Requests that arrive after a failed start are answered with the error.
+        let script = format!(
+            "var queue = [];
+self.onmessage = function(e) {{ queue.push(e); }};
+import({module_url:?}).then(async function(wasm) {{
+    await wasm.default();
+    wasm[{entry:?}]();
+}}).catch(function(error) {{
+    var message = \"The compute worker failed to start: \" + error;
+    self.onmessage = function(e) {{
+        var id = 0;
+        try {{
+            id = JSON.parse(e.data).id;
+        }} catch (_) {{}}
+        self.postMessage(JSON.stringify({{ id: id, error: message }}));
+    }};
+}}).then(function() {{
+    for (const e of queue) {{
+        self.onmessage(e);
+    }}
+}});",
+        );
+        let parts = js_sys::Array::of1(&JsValue::from_str(&script));
+        let options = BlobPropertyBag::new();
+        options.set_type("text/javascript");
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let script_url = Url::create_object_url_with_blob(&blob)?;
+        let worker_options = WorkerOptions::new();
+        worker_options.set_type(WorkerType::Module);
+        // The Worker constructor is a Trusted Types sink so the url goes through the policy.
+        let worker = crate::trusted_script_url(&script_url).and_then(|url| {
+            let class: js_sys::Function =
+                js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("Worker"))?
+                    .dyn_into()?;
+            js_sys::Reflect::construct(&class, &js_sys::Array::of2(&url, &worker_options))
+        });
+        Url::revoke_object_url(&script_url)?;
+        let worker: Worker = worker?.dyn_into()?;
+
+        let pending: Pending = Rc::new(RefCell::new(HashMap::new()));
+        let on_message = {
+            let pending = pending.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let response = match event
+                    .data()
+                    .as_string()
+                    .and_then(|data| serde_json::from_str::<Response>(&data).ok())
+                {
+                    Some(response) => response,
+                    None => return,
+                };
+                let callback = pending.borrow_mut().remove(&response.id);
+                if let Some(callback) = callback {
+                    match (response.output, response.error) {
+                        (_, Some(error)) => callback(Err(error)),
+                        (Some(output), None) => callback(Ok(output)),
+                        (None, None) => callback(Ok(Value::Null)),
+                    }
+                }
+            })
+        };
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        // Errors of the worker script itself, e.g. browsers without module workers.
+        let failure = Rc::new(RefCell::new(None));
+        let on_error = {
+            let pending = pending.clone();
+            let failure = failure.clone();
+            Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+                event.prevent_default();
+                let message = js_sys::Reflect::get(&event, &JsValue::from_str("message"))
+                    .ok()
+                    .and_then(|message| message.as_string())
+                    .unwrap_or_else(|| "unknown error".to_owned());
+                let error = format!("The compute worker failed: {}", message);
+                fail_pending(&pending, &error);
+                *failure.borrow_mut() = Some(error);
+            })
+        };
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        Ok(Self {
+            worker,
+            pending,
+            next_id: Cell::new(1),
+            failure,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    /// Runs the compute handler in the worker and calls the callback with the result.
+    pub fn compute<H, F>(&self, input: &H::Input, callback: F) -> Result<(), JsValue>
+    where
+        H: ComputeHandler,
+        F: FnOnce(Result<H::Output, String>) + 'static,
+    {
+        if let Some(error) = self.failure.borrow().clone() {
+            callback(Err(error));
+            return Ok(());
+        }
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1).max(1));
+        let request = Request {
+            id,
+            handler: H::NAME.to_owned(),
+            input: serde_json::to_value(input).map_err(|e| JsValue::from_str(&e.to_string()))?,
+        };
+        let request =
+            serde_json::to_string(&request).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.pending.borrow_mut().insert(
+            id,
+            Box::new(move |result: Result<Value, String>| {
+                callback(
+                    result.and_then(|output| {
+                        serde_json::from_value(output).map_err(|e| e.to_string())
+                    }),
+                )
+            }),
+        );
+        self.worker.post_message(&JsValue::from_str(&request))
+    }
+
+    /// Runs the compute handler in the worker and delivers the result to the element.
+    /// Results for elements that have been disconnected in the meantime are dropped.
+    pub fn compute_for<H, F>(
+        &self,
+        element: &Element,
+        input: &H::Input,
+        callback: F,
+    ) -> Result<(), JsValue>
+    where
+        H: ComputeHandler,
+        F: FnOnce(&Element, Result<H::Output, String>) + 'static,
+    {
+        let element = element.clone();
+        self.compute::<H, _>(input, move |result| {
+            if element.is_connected() {
+                callback(&element, result);
+            }
+        })
+    }
+}
+
+impl Drop for ComputeWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    struct Sum;
+
+    impl ComputeHandler for Sum {
+        const NAME: &'static str = "sum";
+        type Input = Vec<u32>;
+        type Output = u32;
+
+        fn compute(input: Vec<u32>) -> u32 {
+            input.iter().sum()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_handle_request() {
+        register_handler::<Sum>();
+        let response: Response = serde_json::from_str(&handle_request(
+            r#"{"id":7,"handler":"sum","input":[1,2,3]}"#,
+        ))
+        .unwrap();
+        assert_eq!(response.id, 7);
+        assert_eq!(response.output, Some(Value::from(6)));
+
+        let response: Response =
+            serde_json::from_str(&handle_request(r#"{"id":8,"handler":"nope","input":null}"#))
+                .unwrap();
+        assert_eq!(response.id, 8);
+        assert!(response.error.is_some());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_fail_pending() {
+        let pending: Pending = Rc::new(RefCell::new(HashMap::new()));
+        let failed = Rc::new(Cell::new(0));
+        for id in 1..=2 {
+            let failed = failed.clone();
+            pending.borrow_mut().insert(
+                id,
+                Box::new(move |result: Result<Value, String>| {
+                    assert_eq!(result, Err("gone".to_owned()));
+                    failed.set(failed.get() + 1);
+                }),
+            );
+        }
+        fail_pending(&pending, "gone");
+        assert_eq!(failed.get(), 2);
+        assert!(pending.borrow().is_empty());
+    }
+}